@@ -1,6 +1,25 @@
 pub mod args;
+pub mod atomic_write;
+pub mod budget;
+pub mod cloudflare;
 pub mod config;
+pub mod connectivity;
 pub mod error;
+pub mod history;
+pub mod http;
+pub mod http_debug;
+pub mod launchd;
+pub mod metrics;
+pub mod netns;
+pub mod network;
+pub mod notification;
+pub mod resolve;
+pub mod scheduler;
+pub mod secret;
 pub mod source;
+pub mod state;
+pub mod stats;
+pub mod status;
+pub mod supervisor;
+pub mod ttl;
 pub mod updater;
-pub mod scheduler;
@@ -1,6 +1,14 @@
+/// 测试通知子命令名称
+pub const TEST_NOTIFICATION: &str = "test-notification";
+/// 生成 launchd 任务配置子命令名称
+pub const LAUNCHD_PLIST: &str = "launchd-plist";
+
 /// 获取运行时环境变量及输入参数
 ///
 /// - `-c | --config`: 配置文件路径
+/// - `--dry-run`: 模拟运行，不修改任何 DNS 记录
+/// - `test-notification`: 向所有已配置的通知渠道发送测试消息
+/// - `launchd-plist`: 输出 macOS launchd 任务配置
 pub fn arguments() -> clap::ArgMatches<'static> {
     clap::App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
@@ -13,7 +21,33 @@ pub fn arguments() -> clap::ArgMatches<'static> {
                 .value_name("FILE")
                 .help("配置文件路径")
                 .takes_value(true)
-                .required(false),
+                .required(false)
+                .global(true),
+        )
+        .arg(
+            clap::Arg::with_name("dry-run").long("dry-run").help(
+                "模拟运行，仅获取 IP 地址及记录详情并输出将要进行的更新，不修改任何 DNS 记录",
+            ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name(TEST_NOTIFICATION)
+                .about("向所有已配置的通知渠道发送测试消息"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name(LAUNCHD_PLIST)
+                .about("输出 macOS launchd 任务配置")
+                .arg(
+                    clap::Arg::with_name("agent")
+                        .long("agent")
+                        .help("生成用户级 LaunchAgent 配置，默认生成系统级 LaunchDaemon 配置"),
+                )
+                .arg(
+                    clap::Arg::with_name("label")
+                        .long("label")
+                        .value_name("LABEL")
+                        .help("launchd 任务标签")
+                        .takes_value(true),
+                ),
         )
         .get_matches()
 }
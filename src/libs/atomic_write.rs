@@ -0,0 +1,50 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// 先写入同目录下的临时文件，再重命名至目标路径，保证读取方不会读到写了一半的内容
+///
+/// 临时文件名包含进程 id，避免多个进程同时写入同一路径时相互覆盖；写入或重命名失败时删除临时文件。
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let mut temp = OsString::from(path.as_os_str());
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+
+    fs::write(&temp, contents)
+        .and_then(|_| fs::rename(&temp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::write_atomic;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = std::env::temp_dir().join(format!("ddns4cf-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ddns4cf.prom");
+
+        write_atomic(&path, "first\n").unwrap();
+        write_atomic(&path, b"second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second\n");
+        // 不应残留临时文件
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert!(write_atomic(&dir.join("missing").join("ddns4cf.prom"), "").is_err());
+
+        // 重命名失败时删除临时文件
+        let occupied = dir.join("occupied");
+        fs::create_dir_all(occupied.join("child")).unwrap();
+        assert!(write_atomic(&occupied, "").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+use std::{sync::Mutex, time::Duration};
+
+use log::{info, warn};
+use tokio::time::Instant;
+
+/// 默认每小时允许的重试次数
+pub const DEFAULT_RETRIES_PER_HOUR: u32 = 60;
+
+/// 令牌桶状态
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// 是否处于限流状态，用于仅在状态变化时输出日志
+    throttled: bool,
+}
+
+/// 全局重试预算
+///
+/// 所有域名共享的令牌桶，仅由失败后的重试消耗，定时检查不受影响。
+/// 预算耗尽后重试将被推迟至预算恢复，避免 Cloudflare 长时间故障期间产生大量注定失败的请求。
+#[derive(Debug)]
+pub struct RetryBudget {
+    capacity: f64,
+    /// 每秒恢复的令牌数量
+    refill_rate: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RetryBudget {
+    /// 创建每小时允许 `per_hour` 次重试的预算，初始预算已满
+    pub fn new(per_hour: u32) -> Self {
+        Self::new_at(per_hour, Instant::now())
+    }
+
+    fn new_at(per_hour: u32, now: Instant) -> Self {
+        let capacity = per_hour.max(1) as f64;
+        Self {
+            capacity,
+            refill_rate: capacity / 3600.0,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                last_refill: now,
+                throttled: false,
+            }),
+        }
+    }
+
+    /// 尝试消耗一次重试预算，预算不足时返回恢复一次重试所需的等待时间
+    fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * self.refill_rate).min(self.capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            if bucket.throttled {
+                bucket.throttled = false;
+                info!("重试预算已恢复，恢复正常重试");
+            }
+            Ok(())
+        } else {
+            if !bucket.throttled {
+                bucket.throttled = true;
+                warn!(
+                    "重试预算已耗尽（每小时 {} 次），后续重试将推迟至预算恢复",
+                    self.capacity
+                );
+            }
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_rate,
+            ))
+        }
+    }
+
+    /// 消耗一次重试预算，预算不足时等待预算恢复
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire_at(Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use tokio::time::Instant;
+
+    use super::RetryBudget;
+
+    #[test]
+    fn test_refill() {
+        let start = Instant::now();
+        let budget = RetryBudget::new_at(60, start);
+
+        (0..60).for_each(|_| assert!(budget.try_acquire_at(start).is_ok()));
+        assert_eq!(budget.try_acquire_at(start), Err(Duration::from_secs(60)));
+        assert!(budget.bucket.lock().unwrap().throttled);
+
+        // 每分钟恢复一次重试
+        let later = start + Duration::from_secs(30);
+        assert_eq!(budget.try_acquire_at(later), Err(Duration::from_secs(30)));
+        let later = start + Duration::from_secs(60);
+        assert!(budget.try_acquire_at(later).is_ok());
+        assert!(!budget.bucket.lock().unwrap().throttled);
+
+        // 恢复量不超过容量
+        let later = start + Duration::from_secs(24 * 3600);
+        (0..60).for_each(|_| assert!(budget.try_acquire_at(later).is_ok()));
+        assert!(budget.try_acquire_at(later).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_consumers() {
+        let start = Instant::now();
+        let budget = Arc::new(RetryBudget::new_at(100, start));
+
+        let handles = (0..8)
+            .map(|_| {
+                let budget = budget.clone();
+                thread::spawn(move || {
+                    (0..50)
+                        .filter(|_| budget.try_acquire_at(start).is_ok())
+                        .count()
+                })
+            })
+            .collect::<Vec<_>>();
+        let acquired = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum::<usize>();
+        assert_eq!(acquired, 100);
+    }
+}
@@ -0,0 +1,1117 @@
+use std::{
+    fmt::Debug,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bytes::Buf;
+use chrono::{DateTime, Local};
+use log::{debug, info, warn};
+use reqwest::{
+    header::{self, HeaderMap},
+    Client, Request, StatusCode,
+};
+use serde::de::DeserializeOwned;
+use tokio::{sync::OnceCell, time::sleep};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    error::{Error, Phase},
+    http_debug::{self, HttpDebug},
+    metrics::Metrics,
+    secret::Secret,
+};
+
+/// Cloudflare API 地址
+const CLOUDFLARE_API_URL: &str = "https://api.cloudflare.com/client/v4";
+/// Cloudflare 响应中的 Ray ID 请求头
+const CF_RAY_HEADER: &str = "cf-ray";
+
+/// Cloudflare API 响应
+#[derive(serde::Deserialize, Debug)]
+struct CloudflareResponse<T> {
+    success: bool,
+    errors: Option<Vec<CloudflareMessage>>,
+    result: Option<T>,
+}
+
+/// Cloudflare API 消息
+#[derive(serde::Deserialize, Debug)]
+struct CloudflareMessage {
+    code: u32,
+    message: String,
+}
+
+impl CloudflareMessage {
+    /// 拆分为响应代码列表及消息列表
+    fn split(errors: Option<Vec<CloudflareMessage>>) -> (Vec<u32>, Vec<String>) {
+        errors
+            .unwrap_or_default()
+            .into_iter()
+            .map(|error| (error.code, error.message))
+            .unzip()
+    }
+}
+
+/// Cloudflare API 域名详情
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+pub struct CloudflareRecordDetails {
+    pub r#type: String,
+    pub name: String,
+    pub content: IpAddr,
+    pub ttl: usize,
+    pub proxied: bool,
+    /// 记录最近一次修改的时间，Cloudflare 未返回时为空
+    #[serde(default)]
+    pub modified_on: Option<DateTime<Local>>,
+}
+
+/// Cloudflare API 按名称查询的 DNS 记录
+#[derive(serde::Deserialize, Debug)]
+struct CloudflareRecord {
+    id: String,
+    #[serde(flatten)]
+    details: CloudflareRecordDetails,
+}
+
+/// 通过地址更新的 DNS 记录类型
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    A,
+    #[serde(rename = "AAAA")]
+    Aaaa,
+}
+
+impl RecordType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::Aaaa => "AAAA",
+        }
+    }
+
+    /// IP 地址对应的记录类型
+    pub fn of(ip: &IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(_) => RecordType::A,
+            IpAddr::V6(_) => RecordType::Aaaa,
+        }
+    }
+}
+
+/// 记录不存在时创建记录使用的参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordTemplate {
+    pub name: Arc<str>,
+    /// 记录类型，为 `None` 时根据创建时的 IP 地址决定
+    pub r#type: Option<RecordType>,
+    pub ttl: usize,
+    pub proxied: bool,
+}
+
+/// 需要更新的 DNS 记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordRef {
+    /// 通过 Cloudflare id 指定
+    Id(Arc<str>),
+    /// 通过记录名称及类型指定，初始化时查询对应的 id
+    Name { name: Arc<str>, r#type: RecordType },
+}
+
+/// Cloudflare API 按名称查询的 zone
+#[derive(serde::Deserialize, Debug)]
+struct CloudflareZone {
+    id: String,
+}
+
+/// 按名称查询的 zone，同一账号下使用相同名称的记录共享同一查询结果
+#[derive(Debug)]
+pub struct ZoneLookup {
+    name: Arc<str>,
+    id: OnceCell<Arc<str>>,
+}
+
+impl ZoneLookup {
+    pub fn new(name: Arc<str>) -> Self {
+        Self {
+            name,
+            id: OnceCell::new(),
+        }
+    }
+}
+
+/// 记录所在的 zone
+#[derive(Debug, Clone)]
+pub enum ZoneRef {
+    /// 通过 zone id 指定
+    Id(Arc<str>),
+    /// 通过 zone 名称指定，首次使用时查询对应的 id
+    Name(Arc<ZoneLookup>),
+}
+
+/// Cloudflare API token 验证结果
+#[derive(serde::Deserialize, Debug)]
+struct CloudflareToken {
+    status: String,
+}
+
+/// 同一账号的 API token 验证，账号下所有记录共享同一验证结果
+///
+/// 仅缓存 Cloudflare 明确给出的结果，即 token 可用、未激活或认证失败，限流、网络错误等无法验证的情况不缓存。
+#[derive(Debug, Default)]
+pub struct TokenCheck {
+    /// 验证结果，token 可用时为 `None`
+    invalid: OnceCell<Option<Error>>,
+}
+
+/// 从按名称查询的结果中选出唯一的 zone
+fn select_zone(name: &str, zones: Vec<CloudflareZone>) -> Result<Arc<str>, Error> {
+    match &zones[..] {
+        [] => Err(Error::config(format!(
+            "Cloudflare 中不存在名称为 {} 的 zone，或当前 token 无权访问该 zone，请检查 zone_name 及 token 权限",
+            name
+        ))),
+        [zone] => Ok(Arc::from(zone.id.as_str())),
+        zones => Err(Error::config(format!(
+            "名称为 {} 的 zone 共有 {} 个（id：{}），无法确定记录所在的 zone，请改用 zone_id 指定",
+            name,
+            zones.len(),
+            zones
+                .iter()
+                .map(|zone| zone.id.as_str())
+                .collect::<Vec<_>>()
+                .join("、")
+        ))),
+    }
+}
+
+/// 从按名称查询的结果中选出唯一的记录
+fn select_record(
+    name: &str,
+    r#type: RecordType,
+    mut records: Vec<CloudflareRecord>,
+) -> Result<CloudflareRecord, Error> {
+    match records.len() {
+        0 => Err(Error::cloudflare_record_missing(format!(
+            "Cloudflare 中不存在名称为 {} 的 {} 记录，请检查 name、type 及 zone_id（或 zone_name）是否正确",
+            name,
+            r#type.as_str()
+        ))),
+        1 => Ok(records.remove(0)),
+        count => Err(Error::config(format!(
+            "Cloudflare 中名称为 {} 的 {} 记录共有 {} 条（id：{}），无法确定需要更新的记录，请改用 id 指定",
+            name,
+            r#type.as_str(),
+            count,
+            records
+                .iter()
+                .map(|record| record.id.as_str())
+                .collect::<Vec<_>>()
+                .join("、")
+        ))),
+    }
+}
+
+/// Cloudflare API 更新域名发送的消息负载
+///
+/// 通过 PATCH 仅发送需要修改的字段，记录的备注（comment）及标签（tags）等其余字段保持不变。
+#[derive(serde::Serialize, Debug)]
+struct CloudflareUpdateDNSBody<'a> {
+    content: &'a IpAddr,
+    ttl: usize,
+    proxied: bool,
+}
+
+/// Cloudflare API 创建域名发送的消息负载
+#[derive(serde::Serialize, Debug)]
+struct CloudflareCreateDNSBody<'a> {
+    r#type: &'a str,
+    ttl: usize,
+    name: &'a str,
+    content: &'a IpAddr,
+    proxied: bool,
+}
+
+/// 单条 DNS 记录的 Cloudflare API 操作
+///
+/// 生产环境使用 [`HttpCfApi`]，测试中可替换为返回预设结果的实现，无需访问网络。
+#[async_trait]
+pub trait CfApi: Debug + Send + Sync {
+    /// 获取 DNS 记录详情
+    async fn get_record(&self) -> Result<CloudflareRecordDetails, Error>;
+
+    /// 以 `details` 为基础将 DNS 记录内容更新为 `new_ip`，返回更新后的记录详情
+    async fn update_record(
+        &self,
+        details: &CloudflareRecordDetails,
+        new_ip: IpAddr,
+    ) -> Result<CloudflareRecordDetails, Error>;
+
+    /// 创建内容为 `ip` 的 DNS 记录，此后的操作均针对新创建的记录，返回创建后的记录详情
+    async fn create_record(&self, ip: IpAddr) -> Result<CloudflareRecordDetails, Error>;
+
+    /// 验证 API token，仅在 Cloudflare 明确表示 token 无效或不可用时返回错误
+    async fn verify_token(&self) -> Result<(), Error>;
+
+    /// 记录在状态文件中的标识，配置中的 zone 或记录变化后标识随之变化
+    fn state_key(&self) -> String;
+
+    /// 已确定的 zone id 及记录 id，通过名称指定且尚未查询时返回 `None`
+    fn resolved_ids(&self) -> Option<(Arc<str>, Arc<str>)>;
+
+    /// 恢复状态文件中保存的 zone id 及记录 id，此后不再查询，通过名称指定的记录不存在时除外
+    fn restore_ids(&self, zone_id: Arc<str>, record_id: Arc<str>);
+}
+
+/// 通过 HTTP 访问 Cloudflare API
+#[derive(Debug)]
+pub struct HttpCfApi {
+    client: Client,
+    nickname: Arc<str>,
+    token: Secret,
+    zone: ZoneRef,
+    record: RecordRef,
+    /// 通过名称指定记录时查询到的 id，首次获取记录详情时查询，记录不存在时清除，创建记录后替换为新记录的 id
+    id: RwLock<Option<Arc<str>>>,
+    /// 记录不存在时创建记录使用的参数
+    template: Option<RecordTemplate>,
+    /// 同一账号共享的 token 验证，为 `None` 时不验证
+    token_check: Option<Arc<TokenCheck>>,
+    /// Cloudflare API 地址，测试中可替换为本地服务器
+    api_url: Arc<str>,
+    metrics: Option<Arc<Metrics>>,
+    http_debug: Option<HttpDebug>,
+}
+
+impl HttpCfApi {
+    pub fn new(
+        client: Client,
+        nickname: Arc<str>,
+        token: Secret,
+        zone: ZoneRef,
+        record: RecordRef,
+        metrics: Option<Arc<Metrics>>,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            client,
+            nickname,
+            token,
+            zone,
+            record,
+            id: RwLock::new(None),
+            template: None,
+            token_check: None,
+            api_url: Arc::from(CLOUDFLARE_API_URL),
+            metrics,
+            http_debug,
+        }
+    }
+
+    /// 设置记录不存在时创建记录使用的参数
+    pub fn with_template(mut self, template: Option<RecordTemplate>) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// 设置同一账号共享的 token 验证
+    pub fn with_token_check(mut self, token_check: Option<Arc<TokenCheck>>) -> Self {
+        self.token_check = token_check;
+        self
+    }
+
+    /// 设置 Cloudflare API 地址
+    #[cfg(test)]
+    pub fn with_api_url(mut self, api_url: &str) -> Self {
+        self.api_url = Arc::from(api_url);
+        self
+    }
+
+    /// 获取 API token
+    #[cfg(test)]
+    pub fn token(&self) -> &Secret {
+        &self.token
+    }
+
+    fn record_url(&self, zone_id: &str, id: &str) -> String {
+        format!("{}/zones/{}/dns_records/{}", self.api_url, zone_id, id)
+    }
+
+    /// 获取 zone id，通过名称指定的 zone 首次使用时查询
+    ///
+    /// 同一账号下使用相同名称的记录共享查询结果，仅发送一次查询请求。
+    async fn zone_id(&self) -> Result<&Arc<str>, Error> {
+        match &self.zone {
+            ZoneRef::Id(id) => Ok(id),
+            ZoneRef::Name(lookup) => {
+                lookup
+                    .id
+                    .get_or_try_init(|| self.lookup_zone(&lookup.name))
+                    .await
+            }
+        }
+    }
+
+    /// 按名称查询 zone，要求结果唯一
+    async fn lookup_zone(&self, name: &str) -> Result<Arc<str>, Error> {
+        let request = self
+            .client
+            .get(format!("{}/zones", self.api_url))
+            .query(&[("name", name)])
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.token.expose()),
+            )
+            .build()
+            .map_err(Error::cloudflare_network_failure)?;
+        let zones: Vec<CloudflareZone> = self
+            .send(request, Phase::Fetch, Error::cloudflare_zone_failure)
+            .await?;
+
+        let id =
+            select_zone(name, zones).map_err(|err| err.context(&self.nickname, Phase::Fetch))?;
+        info!("[{}] 已查询到 zone {} 的 id：{}", self.nickname, name, id);
+        Ok(id)
+    }
+
+    /// 请求 Cloudflare 验证 token，token 无效或不可用时返回 `Some`
+    ///
+    /// 仅认证失败（HTTP 401、403 或对应响应代码）说明 token 无效，
+    /// 限流、网络错误、Cloudflare 服务器错误等其他错误直接返回且不缓存。
+    async fn request_token_check(&self) -> Result<Option<Error>, Error> {
+        let request = self
+            .client
+            .get(format!("{}/user/tokens/verify", self.api_url))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.token.expose()),
+            )
+            .build()
+            .map_err(Error::cloudflare_network_failure)?;
+        let result: Result<CloudflareToken, Error> = self
+            .send(request, Phase::Fetch, Error::cloudflare_token_failure)
+            .await;
+
+        match result {
+            Ok(token) if token.status == "active" => {
+                info!("[{}] Cloudflare API token 验证通过", self.nickname);
+                Ok(None)
+            }
+            Ok(token) => Ok(Some(
+                Error::cloudflare_token_inactive(&token.status)
+                    .context(&self.nickname, Phase::Fetch),
+            )),
+            Err(err) if err.is_auth_failure() => Ok(Some(err)),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// 获取记录 id，通过名称指定的记录尚未查询时返回 `None`
+    fn id(&self) -> Option<Arc<str>> {
+        self.id.read().unwrap().clone().or(match &self.record {
+            RecordRef::Id(id) => Some(id.clone()),
+            RecordRef::Name { .. } => None,
+        })
+    }
+
+    /// 保存查询到或新创建的记录 id，此后的操作均针对该记录
+    fn set_id(&self, id: Arc<str>) {
+        *self.id.write().unwrap() = Some(id);
+    }
+
+    /// 通过名称指定的记录不存在时清除保存的 id，下次获取记录详情时重新按名称查询
+    ///
+    /// 记录可能已在 Cloudflare 中被删除后重新创建，新记录的 id 与原先不同。
+    /// 返回是否已清除。
+    fn forget_missing_id(&self, err: &Error) -> bool {
+        if !matches!(self.record, RecordRef::Name { .. }) || !err.is_record_missing() {
+            return false;
+        }
+        let Some(id) = self.id.write().unwrap().take() else {
+            return false;
+        };
+        warn!(
+            "[{}] Cloudflare 中已不存在 id 为 {} 的记录，将重新按名称查询",
+            self.nickname, id
+        );
+        true
+    }
+
+    /// 按名称及类型查询记录，要求结果唯一，并保存查询到的 id
+    async fn lookup_record(
+        &self,
+        name: &str,
+        r#type: RecordType,
+    ) -> Result<CloudflareRecordDetails, Error> {
+        let request = self
+            .client
+            .get(format!(
+                "{}/zones/{}/dns_records",
+                self.api_url,
+                self.zone_id().await?
+            ))
+            .query(&[("name", name), ("type", r#type.as_str())])
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.token.expose()),
+            )
+            .build()
+            .map_err(Error::cloudflare_network_failure)?;
+        let records: Vec<CloudflareRecord> = self
+            .send(request, Phase::Fetch, Error::cloudflare_record_failure)
+            .await?;
+
+        let record = select_record(name, r#type, records)
+            .map_err(|err| err.context(&self.nickname, Phase::Fetch))?;
+        info!(
+            "[{}] 已查询到 {} 记录 {} 的 id：{}",
+            self.nickname,
+            r#type.as_str(),
+            name,
+            record.id
+        );
+        self.set_id(Arc::from(record.id));
+        Ok(record.details)
+    }
+
+    /// 解析响应前检查 Cloudflare API 状态码
+    ///
+    /// 5xx 及 429 响应通常为 HTML 或不完整的 JSON，直接返回对应错误而不尝试解析。
+    /// 429 响应附带 `Retry-After` 响应头时，按其要求的时间等待后再重试。
+    fn check_status(&self, status: StatusCode, headers: &HeaderMap) -> Result<(), Error> {
+        if let Some(metrics) = &self.metrics {
+            metrics.cloudflare_error(&self.nickname, status);
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = headers
+                .get(header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok());
+            Err(Error::cloudflare_rate_limited(retry_after))
+        } else if status.is_server_error() {
+            let ray_id = headers
+                .get(CF_RAY_HEADER)
+                .and_then(|value| value.to_str().ok());
+            Err(Error::cloudflare_server_failure(status, ray_id))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 发送 Cloudflare API 请求并解析响应结果
+    ///
+    /// `failure` 用于根据响应中的错误代码及消息创建错误，解析失败的错误均附加 HTTP 状态码。
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: Request,
+        phase: Phase,
+        failure: fn(Vec<u32>, Vec<String>) -> Error,
+    ) -> Result<T, Error> {
+        let start = Instant::now();
+        let (status, headers, bytes) =
+            http_debug::execute(&self.client, request, self.http_debug.as_ref())
+                .await
+                .map_err(Error::cloudflare_network_failure)?;
+        // 复用连接时耗时明显低于重新握手，可用于确认连接保活是否生效
+        debug!(
+            "[{}] Cloudflare API 请求耗时 {} 毫秒",
+            self.nickname,
+            start.elapsed().as_millis()
+        );
+        self.check_status(status, &headers)?;
+
+        let details: CloudflareResponse<T> =
+            simd_json::from_reader(bytes.reader()).map_err(|err| {
+                Error::cloudflare_deserialized_failure(err)
+                    .context(&self.nickname, phase)
+                    .with_http_status(status)
+            })?;
+
+        match (details.success, details.result) {
+            (true, Some(details)) => Ok(details),
+            (false, _) | (true, None) => {
+                let (codes, messages) = CloudflareMessage::split(details.errors);
+                Err(failure(codes, messages)
+                    .context(&self.nickname, phase)
+                    .with_http_status(status))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CfApi for HttpCfApi {
+    async fn get_record(&self) -> Result<CloudflareRecordDetails, Error> {
+        // 通过名称指定的记录首次获取详情或原有记录已不存在时查询 id，查询结果即为记录详情
+        let id = match (self.id(), &self.record) {
+            (Some(id), _) => id,
+            (None, RecordRef::Name { name, r#type }) => {
+                return self.lookup_record(name, *r#type).await
+            }
+            (None, RecordRef::Id(_)) => return Err(Error::uninitialized()),
+        };
+
+        // 访问 Cloudflare 获取当前 DNS 记录配置
+        let request = self
+            .client
+            .get(self.record_url(self.zone_id().await?, &id))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.token.expose()),
+            )
+            .build()
+            .map_err(Error::cloudflare_network_failure)?;
+        match self
+            .send(request, Phase::Fetch, Error::cloudflare_record_failure)
+            .await
+        {
+            Err(err) if self.forget_missing_id(&err) => match &self.record {
+                RecordRef::Name { name, r#type } => self.lookup_record(name, *r#type).await,
+                RecordRef::Id(_) => Err(err),
+            },
+            result => result,
+        }
+    }
+
+    async fn update_record(
+        &self,
+        details: &CloudflareRecordDetails,
+        new_ip: IpAddr,
+    ) -> Result<CloudflareRecordDetails, Error> {
+        // 访问 Cloudflare 更新当前 DNS 记录配置
+        let body = CloudflareUpdateDNSBody {
+            content: &new_ip,
+            ttl: details.ttl,
+            proxied: details.proxied,
+        };
+
+        let request = self
+            .client
+            .patch(self.record_url(
+                self.zone_id().await?,
+                &self.id().ok_or_else(Error::uninitialized)?,
+            ))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.token.expose()),
+            )
+            // 由于需要序列化，所以此处使用 body
+            .body(simd_json::to_string::<CloudflareUpdateDNSBody>(&body).unwrap())
+            .build()
+            .map_err(Error::cloudflare_network_failure)?;
+        self.send(request, Phase::Update, Error::cloudflare_update_failure)
+            .await
+            .inspect_err(|err| {
+                self.forget_missing_id(err);
+            })
+    }
+
+    async fn create_record(&self, ip: IpAddr) -> Result<CloudflareRecordDetails, Error> {
+        let Some(template) = &self.template else {
+            return Err(Error::config(format!(
+                "域名 {} 未启用 create_if_missing，不会创建记录",
+                self.nickname
+            )));
+        };
+        let r#type = template.r#type.unwrap_or_else(|| RecordType::of(&ip));
+        let body = CloudflareCreateDNSBody {
+            r#type: r#type.as_str(),
+            ttl: template.ttl,
+            name: &template.name,
+            content: &ip,
+            proxied: template.proxied,
+        };
+
+        let request = self
+            .client
+            .post(format!(
+                "{}/zones/{}/dns_records",
+                self.api_url,
+                self.zone_id().await?
+            ))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(
+                header::AUTHORIZATION,
+                format!("Bearer {}", self.token.expose()),
+            )
+            .body(simd_json::to_string::<CloudflareCreateDNSBody>(&body).unwrap())
+            .build()
+            .map_err(Error::cloudflare_network_failure)?;
+        let record: CloudflareRecord = self
+            .send(request, Phase::Fetch, Error::cloudflare_create_failure)
+            .await?;
+
+        warn!(
+            "[{}] Cloudflare 中不存在需要更新的记录，已按 create_if_missing 配置创建 {} 记录 {}（id：{}），内容为 {}",
+            self.nickname, r#type.as_str(), template.name, record.id, ip
+        );
+        if let RecordRef::Id(id) = &self.record {
+            warn!(
+                "[{}] 配置中的记录 id {} 已失效，请将其替换为新记录的 id {}",
+                self.nickname, id, record.id
+            );
+        }
+        self.set_id(Arc::from(record.id));
+        Ok(record.details)
+    }
+
+    fn state_key(&self) -> String {
+        let zone = match &self.zone {
+            ZoneRef::Id(id) => format!("zone_id:{}", id),
+            ZoneRef::Name(lookup) => format!("zone_name:{}", lookup.name),
+        };
+        match &self.record {
+            RecordRef::Id(id) => format!("{}|id:{}", zone, id),
+            RecordRef::Name { name, r#type } => {
+                format!("{}|name:{}/{}", zone, name, r#type.as_str())
+            }
+        }
+    }
+
+    fn resolved_ids(&self) -> Option<(Arc<str>, Arc<str>)> {
+        let zone_id = match &self.zone {
+            ZoneRef::Id(id) => id,
+            ZoneRef::Name(lookup) => lookup.id.get()?,
+        };
+        Some((zone_id.clone(), self.id()?))
+    }
+
+    fn restore_ids(&self, zone_id: Arc<str>, record_id: Arc<str>) {
+        if let ZoneRef::Name(lookup) = &self.zone {
+            let _ = lookup.id.set(zone_id);
+        }
+        // 按 id 指定的记录可能已按 create_if_missing 重新创建，同样以保存的 id 为准
+        self.set_id(record_id);
+    }
+
+    async fn verify_token(&self) -> Result<(), Error> {
+        let Some(token_check) = &self.token_check else {
+            return Ok(());
+        };
+        match token_check
+            .invalid
+            .get_or_try_init(|| self.request_token_check())
+            .await
+        {
+            Ok(None) => Ok(()),
+            Ok(Some(err)) => Err(err.clone()),
+            Err(err) => {
+                warn!(
+                    "[{}] 暂时无法验证 API token，跳过验证：{}",
+                    self.nickname, err
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Cloudflare API 连接保活
+///
+/// 定时向 Cloudflare API 发送 HEAD 请求，使连接池中的连接保持可用，
+/// 避免高延迟网络中每次更新均重新进行 TCP 及 TLS 握手。
+#[derive(Debug, Clone)]
+pub struct CfKeepalive {
+    client: Client,
+    interval: Duration,
+}
+
+impl CfKeepalive {
+    /// `client` 须与更新器使用同一客户端，才能共享连接池
+    pub fn new(client: Client, interval: Duration) -> Self {
+        Self { client, interval }
+    }
+
+    /// 定时发送保活请求，直至收到终止消息
+    pub async fn run(self, termination: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = termination.cancelled() => break,
+                _ = sleep(self.interval) => {}
+            }
+
+            let start = Instant::now();
+            match self.client.head(CLOUDFLARE_API_URL).send().await {
+                Ok(response) => debug!(
+                    "Cloudflare API 连接保活请求完成（HTTP {}），耗时 {} 毫秒",
+                    response.status().as_u16(),
+                    start.elapsed().as_millis()
+                ),
+                Err(err) => debug!("Cloudflare API 连接保活请求失败：{}", err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use bytes::Buf;
+    use reqwest::Client;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        task::JoinHandle,
+    };
+
+    use super::{
+        select_record, select_zone, CfApi, CloudflareRecord, CloudflareResponse, CloudflareZone,
+        HttpCfApi, RecordRef, RecordTemplate, RecordType, TokenCheck, ZoneRef,
+    };
+    use crate::libs::{error::ErrorKind, secret::Secret};
+
+    /// 接受一次请求并以 `status` 及 `body` 响应的服务器，返回 API 地址及完整的请求内容
+    ///
+    /// `status` 可附带额外的响应头，如 `"429 Too Many Requests\r\nRetry-After: 30"`。
+    async fn serve_once(status: &'static str, body: &'static str) -> (String, JoinHandle<String>) {
+        let (url, server) = serve(vec![(status, body)]).await;
+        (
+            url,
+            tokio::spawn(async move { server.await.unwrap().remove(0) }),
+        )
+    }
+
+    /// 按顺序以 `responses` 响应各次请求的服务器，返回 API 地址及所有请求内容
+    ///
+    /// 每次响应后关闭连接，后续请求使用新的连接。
+    async fn serve(
+        responses: Vec<(&'static str, &'static str)>,
+    ) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                // 读取请求头后按 Content-Length 读取请求体
+                let header_end = loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                        break end + 4;
+                    }
+                };
+                let content_length = String::from_utf8_lossy(&request[..header_end])
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                while request.len() < header_end + content_length {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(String::from_utf8(request).unwrap());
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    fn records(json: &str) -> Vec<CloudflareRecord> {
+        let response: CloudflareResponse<Vec<CloudflareRecord>> =
+            simd_json::from_reader(json.as_bytes().reader()).unwrap();
+        response.result.unwrap()
+    }
+
+    #[test]
+    fn test_select_record() {
+        let single = records(
+            r#"{"success":true,"errors":[],"result":[{"id":"372e67954025e0ba6aaa6d586b9e0b59","zone_id":"023e105f4ecef8ad9ca31a8372d0c353","name":"home.example.com","type":"A","content":"198.51.100.4","proxied":false,"ttl":3600,"modified_on":"2024-05-01T08:00:00.12345Z"}]}"#,
+        );
+        let record = select_record("home.example.com", RecordType::A, single).unwrap();
+        assert_eq!(record.id, "372e67954025e0ba6aaa6d586b9e0b59");
+        assert_eq!(record.details.name, "home.example.com");
+        assert_eq!(record.details.content.to_string(), "198.51.100.4");
+        assert_eq!(record.details.ttl, 3600);
+        assert_eq!(
+            record.details.modified_on.map(|time| time.timestamp()),
+            Some(1714550400)
+        );
+
+        let err = select_record(
+            "home.example.com",
+            RecordType::Aaaa,
+            records(r#"{"success":true,"errors":[],"result":[]}"#),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("home.example.com 的 AAAA 记录"), "{}", err);
+
+        let err = select_record(
+            "home.example.com",
+            RecordType::A,
+            records(
+                r#"{"success":true,"errors":[],"result":[{"id":"1","name":"home.example.com","type":"A","content":"198.51.100.4","proxied":false,"ttl":1},{"id":"2","name":"home.example.com","type":"A","content":"198.51.100.5","proxied":true,"ttl":1}]}"#,
+            ),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("共有 2 条（id：1、2）"), "{}", err);
+    }
+
+    #[test]
+    fn test_select_zone() {
+        let zones = |json: &str| {
+            let response: CloudflareResponse<Vec<CloudflareZone>> =
+                simd_json::from_reader(json.as_bytes().reader()).unwrap();
+            response.result.unwrap()
+        };
+
+        assert_eq!(
+            &*select_zone(
+                "example.com",
+                zones(r#"{"success":true,"errors":[],"result":[{"id":"023e105f4ecef8ad9ca31a8372d0c353","name":"example.com","status":"active"}]}"#)
+            )
+            .unwrap(),
+            "023e105f4ecef8ad9ca31a8372d0c353"
+        );
+        let err = select_zone(
+            "example.com",
+            zones(r#"{"success":true,"errors":[],"result":[]}"#),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("example.com") && err.contains("无权访问"),
+            "{}",
+            err
+        );
+        assert!(select_zone(
+            "example.com",
+            zones(r#"{"success":true,"errors":[],"result":[{"id":"1"},{"id":"2"}]}"#)
+        )
+        .is_err());
+    }
+
+    fn api(url: &str) -> HttpCfApi {
+        HttpCfApi::new(
+            Client::new(),
+            Arc::from("home"),
+            Secret::from("secret-token".to_string()),
+            ZoneRef::Id(Arc::from("z")),
+            RecordRef::Id(Arc::from("1")),
+            None,
+            None,
+        )
+        .with_api_url(url)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        // 429 响应体不是 Cloudflare API 响应，不尝试解析
+        let (url, server) = serve_once(
+            "429 Too Many Requests\r\nRetry-After: 30",
+            "<html>rate limited</html>",
+        )
+        .await;
+        let err = api(&url).get_record().await.unwrap_err();
+        server.await.unwrap();
+        assert_eq!(err.kind(), ErrorKind::RateLimited);
+        assert_eq!(err.retry_after(), Some(30));
+
+        let (url, server) = serve_once("429 Too Many Requests", "").await;
+        let err = api(&url).get_record().await.unwrap_err();
+        server.await.unwrap();
+        assert_eq!(err.kind(), ErrorKind::RateLimited);
+        assert_eq!(err.retry_after(), None);
+    }
+
+    #[tokio::test]
+    async fn test_token_check() {
+        let token_check = Arc::new(TokenCheck::default());
+
+        // 限流时无法验证 token，跳过验证且不缓存结果
+        let (url, server) = serve_once(
+            "429 Too Many Requests",
+            r#"{"success":false,"errors":[{"code":971,"message":"Please wait and consider throttling your request speed"}],"result":null}"#,
+        )
+        .await;
+        let cf_api = api(&url).with_token_check(Some(token_check.clone()));
+        cf_api.verify_token().await.unwrap();
+        server.await.unwrap();
+        assert!(token_check.invalid.get().is_none());
+
+        // 认证失败时缓存结果，再次验证不再请求 Cloudflare
+        let (url, server) = serve_once(
+            "403 Forbidden",
+            r#"{"success":false,"errors":[{"code":9109,"message":"Invalid access token"}],"result":null}"#,
+        )
+        .await;
+        let cf_api = api(&url).with_token_check(Some(token_check.clone()));
+        let err = cf_api.verify_token().await.unwrap_err();
+        server.await.unwrap();
+        assert!(err.is_auth_failure());
+        let cached = cf_api.verify_token().await.unwrap_err();
+        assert_eq!(cached.to_string(), err.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_recreate_record() {
+        const MISSING: &str = r#"{"success":false,"errors":[{"code":81044,"message":"Record does not exist."}],"result":null}"#;
+        let (url, server) = serve(vec![
+            ("404 Not Found", MISSING),
+            ("200 OK", r#"{"success":true,"errors":[],"result":{"id":"2","type":"A","name":"home.example.com","content":"2.2.2.2","ttl":1,"proxied":false}}"#),
+            ("404 Not Found", MISSING),
+            ("200 OK", r#"{"success":true,"errors":[],"result":{"id":"3","type":"A","name":"home.example.com","content":"3.3.3.3","ttl":1,"proxied":false}}"#),
+            ("200 OK", r#"{"success":true,"errors":[],"result":{"id":"3","type":"A","name":"home.example.com","content":"4.4.4.4","ttl":1,"proxied":false}}"#),
+        ])
+        .await;
+        let api = api(&url).with_template(Some(RecordTemplate {
+            name: Arc::from("home.example.com"),
+            r#type: None,
+            ttl: 1,
+            proxied: false,
+        }));
+
+        // 记录多次被删除后均以新创建记录的 id 替换原有 id
+        let mut created = None;
+        for ip in ["2.2.2.2", "3.3.3.3"] {
+            let err = api.get_record().await.unwrap_err();
+            assert!(err.is_record_missing());
+            let details = api.create_record(ip.parse().unwrap()).await.unwrap();
+            assert_eq!(details.content.to_string(), ip);
+            created = Some(details);
+        }
+        assert_eq!(api.resolved_ids(), Some((Arc::from("z"), Arc::from("3"))));
+
+        // 此后的更新针对最近一次创建的记录
+        let details = api
+            .update_record(&created.unwrap(), "4.4.4.4".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(details.content.to_string(), "4.4.4.4");
+
+        let requests = server.await.unwrap();
+        let lines = requests
+            .iter()
+            .map(|request| request.lines().next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "GET /zones/z/dns_records/1 HTTP/1.1",
+                "POST /zones/z/dns_records HTTP/1.1",
+                "GET /zones/z/dns_records/2 HTTP/1.1",
+                "POST /zones/z/dns_records HTTP/1.1",
+                "PATCH /zones/z/dns_records/3 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_after_missing() {
+        const MISSING: &str = r#"{"success":false,"errors":[{"code":81044,"message":"Record does not exist."}],"result":null}"#;
+        let (url, server) = serve(vec![
+            ("200 OK", r#"{"success":true,"errors":[],"result":[{"id":"1","type":"A","name":"home.example.com","content":"1.1.1.1","ttl":1,"proxied":false}]}"#),
+            ("404 Not Found", MISSING),
+            ("200 OK", r#"{"success":true,"errors":[],"result":[{"id":"2","type":"A","name":"home.example.com","content":"1.1.1.1","ttl":1,"proxied":false}]}"#),
+            ("404 Not Found", MISSING),
+            ("200 OK", r#"{"success":true,"errors":[],"result":[{"id":"3","type":"A","name":"home.example.com","content":"1.1.1.1","ttl":1,"proxied":false}]}"#),
+        ])
+        .await;
+        let api = HttpCfApi::new(
+            Client::new(),
+            Arc::from("home"),
+            Secret::from("secret-token".to_string()),
+            ZoneRef::Id(Arc::from("z")),
+            RecordRef::Name {
+                name: Arc::from("home.example.com"),
+                r#type: RecordType::A,
+            },
+            None,
+            None,
+        )
+        .with_api_url(&url);
+
+        let details = api.get_record().await.unwrap();
+        assert_eq!(api.resolved_ids(), Some((Arc::from("z"), Arc::from("1"))));
+
+        // 记录在 Cloudflare 中被删除后重新创建，按保存的 id 获取失败时重新按名称查询
+        api.get_record().await.unwrap();
+        assert_eq!(api.resolved_ids(), Some((Arc::from("z"), Arc::from("2"))));
+
+        // 更新时记录不存在，清除保存的 id，下次获取记录详情时重新按名称查询
+        let err = api
+            .update_record(&details, "2.2.2.2".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert!(err.is_record_missing());
+        assert_eq!(api.resolved_ids(), None);
+        api.get_record().await.unwrap();
+        assert_eq!(api.resolved_ids(), Some((Arc::from("z"), Arc::from("3"))));
+
+        let requests = server.await.unwrap();
+        let lines = requests
+            .iter()
+            .map(|request| request.lines().next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "GET /zones/z/dns_records?name=home.example.com&type=A HTTP/1.1",
+                "GET /zones/z/dns_records/1 HTTP/1.1",
+                "GET /zones/z/dns_records?name=home.example.com&type=A HTTP/1.1",
+                "PATCH /zones/z/dns_records/2 HTTP/1.1",
+                "GET /zones/z/dns_records?name=home.example.com&type=A HTTP/1.1",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_preserves_fields() {
+        let (url, server) = serve_once(
+            "200 OK",
+            r#"{"success":true,"errors":[],"result":{"id":"1","type":"A","name":"home.example.com","content":"2.2.2.2","ttl":300,"proxied":true,"comment":"home router","tags":["owner:ops"]}}"#,
+        )
+        .await;
+        let api = api(&url);
+        let old = records(
+            r#"{"success":true,"errors":[],"result":[{"id":"1","type":"A","name":"home.example.com","content":"1.1.1.1","ttl":300,"proxied":true}]}"#,
+        )
+        .remove(0)
+        .details;
+
+        let new = api
+            .update_record(&old, "2.2.2.2".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(new.content.to_string(), "2.2.2.2");
+
+        // 使用 PATCH 且仅发送需要修改的字段，避免清空记录的备注、标签等字段
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("PATCH /zones/z/dns_records/1 HTTP/1.1"),
+            "{}",
+            request
+        );
+        let (_, body) = request.split_once("\r\n\r\n").unwrap();
+        let mut body = body.as_bytes().to_vec();
+        let fields: BTreeMap<String, simd_json::OwnedValue> =
+            simd_json::from_slice(&mut body).unwrap();
+        assert_eq!(
+            fields.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["content", "proxied", "ttl"]
+        );
+    }
+}
@@ -1,32 +1,115 @@
-use std::{borrow::Cow, env, fs, net::IpAddr, path::Path, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{hash_map::Entry, HashMap},
+    env, fs,
+    net::{IpAddr, Ipv6Addr},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
-use reqwest::{Client, Url};
-use serde::{
-    de::{self, Visitor},
-    Deserialize,
+use log::warn;
+use regex::Regex;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+    Client, Method, StatusCode, Url,
 };
+use serde::{de, Deserialize};
 use smallvec::SmallVec;
-use tokio::sync::Mutex;
 
 use super::{
-    args,
+    budget::{RetryBudget, DEFAULT_RETRIES_PER_HOUR},
+    cloudflare::{
+        CfApi, CfKeepalive, HttpCfApi, RecordRef, RecordTemplate, RecordType, TokenCheck,
+        ZoneLookup, ZoneRef,
+    },
+    connectivity::{ConnectivityProbe, DEFAULT_CONNECTIVITY_CACHE, DEFAULT_CONNECTIVITY_CHECK_URL},
     error::Error,
-    source::{standalone::Standalone, IpSource},
-    updater::Updater,
+    history::DEFAULT_HISTORY_DEPTH,
+    http::{CaCert, ClientKey, ClientRegistry, PoolOptions, Proxy, ProxySetting, TlsOptions},
+    http_debug::{self, HttpDebug},
+    metrics::{Metrics, MetricsConfig},
+    netns::Netns,
+    network::{NetworkGate, NetworkMode, DEFAULT_WAIT_FOR_NETWORK_SECONDS},
+    notification::{NotificationConfig, NotificationDispatcher},
+    resolve::{CfResolve, CLOUDFLARE_API_HOST},
+    secret::Secret,
+    source::{
+        bind_family,
+        cached::CachedSource,
+        cf_trace::CfTrace,
+        chain::Chain,
+        command::Command,
+        consensus::Consensus,
+        dns::{self, DnsQuery, DEFAULT_DNS_QUERY_NAME, DEFAULT_DNS_RESOLVER},
+        doh_whoami::{DohWhoami, DEFAULT_DOH_WHOAMI_URL},
+        fritzbox::{FritzBox, DEFAULT_FRITZBOX_URL},
+        http_json::{self, HttpJson},
+        http_regex::{self, HttpRegex},
+        icanhazip::Icanhazip,
+        ipify::Ipify,
+        prefix_compose::{self, PrefixCompose},
+        routeros::{RouterOs, RouterOsAuth},
+        standalone::Standalone,
+        stun::{self, Stun, DEFAULT_STUN_SERVERS},
+        ubus::{Ubus, UbusTransport},
+        IpFamily, IpSource,
+    },
+    state::{StateFile, DEFAULT_STATE_MAX_AGE_SECONDS},
+    ttl::{
+        AutoTtl, Ttl, AUTO_TTL, DEFAULT_HIGH_TTL, DEFAULT_LOW_TTL, DEFAULT_STABLE_AFTER_SECONDS,
+        MAX_TTL, MIN_TTL,
+    },
+    updater::{Backoff, Updater},
 };
 
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+use super::source::local_ipv6::AddressRules;
+
 /// 默认刷新间隔
 const DEFAULT_FRESH_INTERVAL_SECONDS: u64 = 15 * 60;
+/// 最短刷新间隔
+const MIN_FRESH_INTERVAL_SECONDS: u64 = 30;
 /// 默认全局出现错误时重试间隔
 const DEFAULT_RETRY_INTERVAL_SECONDS: u64 = 5 * 60;
+/// 最短重试间隔
+const MIN_RETRY_INTERVAL_SECONDS: u64 = 10;
+/// 刷新间隔及重试间隔的上限
+const MAX_INTERVAL_SECONDS: u64 = 7 * 24 * 60 * 60;
+/// 默认获取 IP 地址超时时间
+const DEFAULT_SOURCE_TIMEOUT_SECONDS: u64 = 15;
+/// 默认获取 IP 地址快速重试次数
+const DEFAULT_SOURCE_RETRIES: u32 = 2;
+/// 默认获取 IP 地址快速重试间隔
+const DEFAULT_SOURCE_RETRY_DELAY_SECONDS: u64 = 5;
+/// 默认同时初始化的域名数量上限
+const DEFAULT_INIT_CONCURRENCY: usize = 8;
+/// 默认触发消息静默等待时间
+const DEFAULT_TRIGGER_QUIESCENCE_SECONDS: u64 = 2;
+/// 低内存模式下的空闲连接过期时间
+const LOW_MEMORY_POOL_IDLE_TIMEOUT_SECONDS: u64 = 5;
+/// 低内存模式下每个主机保留的空闲连接数量上限
+const LOW_MEMORY_POOL_MAX_IDLE_PER_HOST: usize = 1;
+/// 多线程运行时的最少工作线程数量
+const MIN_RUNTIME_THREADS: usize = 2;
+/// 每个工作线程负责的域名数量
+const DOMAINS_PER_RUNTIME_THREAD: usize = 4;
+/// 单次更新最长时间的上限
+const MAX_UPDATE_DEADLINE_SECONDS: u64 = 120;
+/// 默认指数退避首次重试的等待时间，单位秒
+const DEFAULT_BACKOFF_BASE_SECONDS: u64 = 5;
+/// 默认重新获取记录详情的间隔，单位秒
+const DEFAULT_DETAILS_REFRESH_SECONDS: u64 = 24 * 60 * 60;
 
 /// 配置内容数据结构
 ///
-/// 包含全局参数及需要刷新的域名列表。
-#[derive(serde::Deserialize, Debug, Clone)]
+/// 包含全局参数及需要刷新的域名列表。解析后通过 [`Arc`] 共享，不再复制。
+#[derive(serde::Deserialize, Debug)]
 pub struct Configuration {
     /// 绑定的本地 IP 地址，可选
     bind_address: Option<IpAddr>,
+    /// 网络地址族模式，应用于所有 HTTP 客户端。默认为 `dual`
+    network: Option<NetworkMode>,
     /// 全局刷新间隔，单位秒。默认为 900 秒。
     ///
     /// 若通过 [`Domain`] 为单独的域名设置 `fresh_interval` 属性，该属性将不会被使用。
@@ -45,6 +128,102 @@ pub struct Configuration {
     accounts: Vec<Account>,
     /// Cloudflare 访问代理，可选。默认使用当前系统配置的全局代理
     proxy: Option<Proxy>,
+    /// Cloudflare API 域名解析方式，可选。默认使用系统 DNS
+    cf_resolve: Option<CfResolve>,
+    /// Cloudflare API 连接保活间隔，单位秒，可选。默认不保活
+    ///
+    /// 配置后 Cloudflare API 空闲连接不再过期，并定时发送轻量请求维持连接，避免每次更新重新握手。
+    cf_keepalive: Option<u64>,
+    /// HTTP 客户端配置，可选
+    http: Option<Http>,
+    /// 事件触发更新前的网络连通性检查，可选。默认不检查
+    connectivity_check: Option<ConnectivityCheck>,
+    /// 根据 IP 地址变化频率自动调整记录 TTL，可选。默认不调整
+    ///
+    /// 若通过 [`Domain`] 为单独的域名设置 `auto_ttl` 属性，该属性将不会被使用。
+    auto_ttl: Option<AutoTtlConfig>,
+    /// 记录的固定 TTL，单位秒，为 `1` 时由 Cloudflare 自动决定，可选。默认保持记录原有的 TTL
+    ///
+    /// 若通过 [`Domain`] 为单独的域名设置 `ttl` 属性，该属性将不会被使用。
+    ttl: Option<Ttl>,
+    /// 定期重新获取记录详情的间隔，单位秒，用于发现在 Cloudflare 中对记录的修改。
+    /// 为 `0` 时不定期重新获取。默认为 24 小时
+    ///
+    /// 若通过 [`Domain`] 为单独的域名设置 `details_refresh_interval` 属性，该属性将不会被使用。
+    details_refresh_interval: Option<u64>,
+    /// 通知配置，可选
+    notifications: Option<NotificationConfig>,
+    /// 指标配置，可选
+    metrics: Option<MetricsConfig>,
+    /// 获取 IP 地址的超时时间，单位秒。默认为 15 秒
+    source_timeout: Option<u64>,
+    /// HTTP 请求的 User-Agent，应用于 Cloudflare API 及所有基于 HTTP 的 IP 地址来源。默认使用 reqwest 的默认值
+    ///
+    /// IP 地址来源可通过 `user_agent` 单独指定。
+    user_agent: Option<Arc<str>>,
+    /// IP 地址来源的访问代理，为 `false` 时不使用任何代理。默认使用系统代理
+    ///
+    /// IP 地址来源可通过 `proxy` 单独指定。
+    source_proxy: Option<ProxySetting>,
+    /// IP 地址来源获取结果的缓存时间，单位秒，可选。默认不缓存
+    ///
+    /// 使用同一 IP 来源配置的域名共享缓存，有效期内不再重复请求来源。
+    source_cache_ttl: Option<u64>,
+    /// 获取 IP 地址失败后的快速重试次数，用尽后才等待 `retry_interval`。默认为 2 次
+    source_retries: Option<u32>,
+    /// 获取 IP 地址快速重试的间隔，单位秒。默认为 5 秒
+    source_retry_delay: Option<u64>,
+    /// 初始化时是否先尝试一次获取 IP 地址以校验来源配置。默认为 `true`
+    validate_sources: Option<bool>,
+    /// 状态文件路径，可选。默认不保存状态
+    ///
+    /// 保存各域名的 zone id、记录 id 及记录详情，重启后直接使用未过期的状态，跳过获取记录详情。
+    state_path: Option<PathBuf>,
+    /// 状态文件中状态的有效期，单位秒。默认为 3600 秒
+    state_max_age: Option<u64>,
+    /// 初始化失败后的指数退避，可选。默认从 5 秒开始翻倍，最长为重试间隔，并加入随机抖动
+    backoff: Option<BackoffConfig>,
+    /// 初始化时是否验证各账号的 API token，同一账号仅验证一次。默认为 `true`
+    verify_tokens: Option<bool>,
+    /// API token 无效时是否停止更新该账号下的域名，为 `false` 时仍按原方式重试。默认为 `true`
+    stop_on_invalid_token: Option<bool>,
+    /// 连续失败次数上限，达到后永久停止更新该域名，为 `0` 时不限制。默认不限制
+    ///
+    /// 若通过 [`Domain`] 为单独的域名设置 `max_consecutive_failures` 属性，该属性将不会被使用。
+    max_consecutive_failures: Option<u32>,
+    /// 强制更新周期，IP 地址未变化时也至多每隔该时长更新一次记录，为 `0` 时不强制更新。默认不强制更新
+    ///
+    /// 若通过 [`Domain`] 为单独的域名设置 `force_update_every` 属性，该属性将不会被使用。
+    force_update_every: Option<Period>,
+    /// 首次初始化前等待网络就绪的最长时间，单位秒，为 `false` 时不等待。默认为 120 秒
+    wait_for_network: Option<WaitForNetwork>,
+    /// 是否启用严格模式。默认为 `false`
+    ///
+    /// 刷新间隔或重试间隔低于下限时，默认输出警告并使用下限值，严格模式下直接报错。
+    strict: Option<bool>,
+    /// 是否模拟运行，仅输出将要进行的更新，不修改任何 DNS 记录。默认为 `false`
+    ///
+    /// 使用命令行参数 `--dry-run` 时总是启用。
+    dry_run: Option<bool>,
+    /// 同时初始化的域名数量上限。默认为 8 个
+    init_concurrency: Option<usize>,
+    /// 运行时工作线程数量，为 `1` 时使用单线程运行时。默认根据域名数量及 CPU 核心数计算
+    runtime_threads: Option<usize>,
+    /// 收到触发消息后的静默等待时间，单位秒，期间收到的触发消息合并为一次更新。默认为 2 秒
+    trigger_quiescence: Option<u64>,
+    /// 所有域名共享的每小时重试次数上限，仅限制失败后的重试。默认为 60 次
+    retry_budget: Option<u32>,
+    /// 每个域名保留的 IP 地址变化记录数量。默认为 50 条
+    history_depth: Option<usize>,
+    /// 是否输出 HTTP 请求及响应调试日志。默认为 `false`
+    ///
+    /// 日志中的 Authorization 请求头及所有账号 token 均会被脱敏。
+    debug_http: Option<bool>,
+    /// HTTP 调试日志中请求体及响应体的最大输出长度，单位字节。默认为 4096 字节
+    debug_http_body_limit: Option<usize>,
+    /// HTTP 客户端注册表，首次使用时创建
+    #[serde(skip)]
+    clients: OnceLock<ClientRegistry>,
     // /// 日志
     // log: Option<Log>,
 }
@@ -55,6 +234,11 @@ impl Configuration {
         self.bind_address
     }
 
+    /// 获取网络地址族模式。默认为 `dual`
+    pub fn network(&self) -> NetworkMode {
+        self.network.unwrap_or_default()
+    }
+
     /// 获取全局刷新间隔，单位秒。默认为 900 秒。
     pub fn fresh_interval(&self) -> u64 {
         self.fresh_interval
@@ -71,46 +255,378 @@ impl Configuration {
         self.ip_source.as_ref()
     }
 
+    /// 获取 HTTP 客户端注册表，所有 HTTP 客户端均通过该注册表创建
+    fn clients(&self) -> &ClientRegistry {
+        self.clients.get_or_init(|| self.create_client_registry())
+    }
+
+    /// 创建新的 HTTP 客户端注册表，网络命名空间中的域名使用各命名空间专用的注册表
+    fn create_client_registry(&self) -> ClientRegistry {
+        ClientRegistry::new(
+            self.cf_resolve.clone(),
+            self.cf_keepalive(),
+            self.http
+                .as_ref()
+                .map(Http::pool_options)
+                .unwrap_or_default(),
+            self.network(),
+        )
+    }
+
     // 创建 Cloudflare HTTP reqwest client.
-    fn create_cf_http_client(&self) -> Client {
-        let mut builder = reqwest::ClientBuilder::new().local_address(self.bind_address);
-        if let Some(proxy) = self.proxy() {
-            builder = builder.proxy(proxy);
+    fn create_cf_http_client(&self) -> Result<Client, Error> {
+        self.cf_http_client(self.clients())
+    }
+
+    /// 通过 `clients` 获取 Cloudflare HTTP 客户端
+    fn cf_http_client(&self, clients: &ClientRegistry) -> Result<Client, Error> {
+        clients.client(ClientKey {
+            bind_address: self.bind_address,
+            timeout: None,
+            proxy: self
+                .proxy
+                .clone()
+                .map(ProxySetting::Proxy)
+                .unwrap_or_default(),
+            cf_resolve: true,
+            keepalive: true,
+            user_agent: self.user_agent.clone(),
+            tls: TlsOptions::default(),
+        })
+    }
+
+    /// 获取 Cloudflare API 连接保活间隔，未配置或为 `0` 时不保活
+    pub fn cf_keepalive(&self) -> Option<Duration> {
+        self.cf_keepalive
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// 创建 Cloudflare API 连接保活任务，与更新器共享同一 HTTP 客户端。未配置时返回 `None`
+    pub fn create_cf_keepalive(&self) -> Result<Option<CfKeepalive>, Error> {
+        let Some(interval) = self.cf_keepalive() else {
+            return Ok(None);
+        };
+        Ok(Some(CfKeepalive::new(
+            self.create_cf_http_client()?,
+            interval,
+        )))
+    }
+
+    /// 获取通知配置
+    pub fn notifications(&self) -> Option<&NotificationConfig> {
+        self.notifications.as_ref()
+    }
+
+    /// 创建通知分发器，未配置任何通知渠道时返回 `None`
+    pub fn create_notification_dispatcher(
+        &self,
+    ) -> Result<Option<Arc<NotificationDispatcher>>, Error> {
+        let Some(notifications) = self.notifications() else {
+            return Ok(None);
         };
 
-        builder.build().unwrap()
+        let dispatcher = notifications.create_dispatcher()?;
+        if dispatcher.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Arc::new(dispatcher)))
+        }
+    }
+
+    /// 获取指标配置
+    pub fn metrics(&self) -> Option<&MetricsConfig> {
+        self.metrics.as_ref()
+    }
+
+    /// 创建指标注册表，未配置指标时返回 `None`
+    fn create_metrics(&self) -> Result<Option<Arc<Metrics>>, Error> {
+        self.metrics()
+            .map(|metrics| metrics.create_metrics().map(Arc::new))
+            .transpose()
+    }
+
+    /// 创建 HTTP 调试日志，未启用时返回 `None`
+    fn create_http_debug(&self) -> Option<HttpDebug> {
+        if !self.debug_http.unwrap_or(false) {
+            return None;
+        }
+
+        Some(HttpDebug::new(
+            self.debug_http_body_limit
+                .unwrap_or(http_debug::DEFAULT_BODY_LIMIT),
+            self.accounts()
+                .iter()
+                .map(|account| account.token().expose().to_string()),
+        ))
+    }
+
+    /// 获取域名所在的 zone
+    ///
+    /// 通过名称指定时，同一账号下相同名称的 zone 共享 `zones` 中的同一查询。
+    fn resolve_zone(
+        zones: &mut HashMap<(*const Account, Arc<str>), Arc<ZoneLookup>>,
+        account: &Account,
+        domain: &Domain,
+    ) -> Result<ZoneRef, Error> {
+        match (&domain.zone_id, &domain.zone_name) {
+            (Some(_), Some(_)) => Err(Error::config(format!(
+                "域名 {} 的 zone_id 与 zone_name 不能同时指定",
+                domain.nickname
+            ))),
+            (Some(zone_id), None) => Ok(ZoneRef::Id(zone_id.clone())),
+            (None, zone_name) => {
+                let Some(zone_name) = zone_name.as_ref().or(account.zone_name.as_ref()) else {
+                    return Err(Error::config(format!(
+                        "域名 {} 必须指定 zone_id 或 zone_name",
+                        domain.nickname
+                    )));
+                };
+                let lookup = zones
+                    .entry((account as *const _, zone_name.clone()))
+                    .or_insert_with(|| Arc::new(ZoneLookup::new(zone_name.clone())));
+                Ok(ZoneRef::Name(lookup.clone()))
+            }
+        }
+    }
+
+    /// 创建单条 DNS 记录的 Cloudflare API，同一账号下的域名共享 token 存储
+    fn create_cf_api(
+        account: &Account,
+        zone: ZoneRef,
+        record: &DomainRecord,
+        client: &Client,
+        metrics: &Option<Arc<Metrics>>,
+        http_debug: &Option<HttpDebug>,
+    ) -> HttpCfApi {
+        HttpCfApi::new(
+            client.clone(),
+            record.nickname.clone(),
+            account.token().clone(),
+            zone,
+            record.record.clone(),
+            metrics.clone(),
+            http_debug.clone(),
+        )
+        .with_template(record.template.clone())
     }
 
     /// 通过当前配置内容创建 [`Updater`] 列表
-    pub fn create_updaters(&self) -> Result<SmallVec<[Arc<Mutex<Updater>>; 4]>, Error> {
-        let cf_http_client = self.create_cf_http_client();
+    pub fn create_updaters(&self) -> Result<SmallVec<[Updater; 4]>, Error> {
+        let clients = self.clients();
+        let cf_http_client = self.create_cf_http_client()?;
+        let notifications = self.create_notification_dispatcher()?;
+        let metrics = self.create_metrics()?;
+        let http_debug = self.create_http_debug();
+        let retry_budget = Arc::new(RetryBudget::new(
+            self.retry_budget.unwrap_or(DEFAULT_RETRIES_PER_HOUR),
+        ));
 
+        // 同一 IP 来源配置、绑定地址及网络命名空间仅创建一个来源实例，由使用该配置的所有域名共享
+        let mut ip_sources: HashMap<IpSourceKey, Arc<dyn IpSource>> = HashMap::new();
+        // 同一网络命名空间仅打开一次，命名空间中的域名使用专用的 HTTP 客户端，不与其他命名空间共享连接
+        let mut namespaces: HashMap<&str, (Arc<Netns>, ClientRegistry, Client)> = HashMap::new();
+        // 同一账号下相同名称的 zone 仅查询一次
+        let mut zones = HashMap::new();
+        let backoff = self
+            .backoff
+            .as_ref()
+            .unwrap_or(&BackoffConfig::default())
+            .to_backoff()?;
+        let state = self.state_path.as_ref().map(|path| {
+            Arc::new(StateFile::load(
+                path.clone(),
+                Duration::from_secs(self.state_max_age.unwrap_or(DEFAULT_STATE_MAX_AGE_SECONDS)),
+            ))
+        });
         let mut updaters = SmallVec::new();
         self.accounts().iter().try_for_each(|account| {
+            let token_check = self
+                .verify_tokens
+                .unwrap_or(true)
+                .then(|| Arc::new(TokenCheck::default()));
             account.domains().iter().try_for_each(|domain| {
+                domain.validate_bind_interface()?;
+                let (netns, clients, cf_http_client) = match domain.netns.as_deref() {
+                    Some(name) => {
+                        let (netns, clients, cf_http_client) = match namespaces.entry(name) {
+                            Entry::Occupied(entry) => entry.into_mut(),
+                            Entry::Vacant(entry) => {
+                                let netns = Netns::open(name).map_err(|err| {
+                                    err.with_prefix(&format!("域名 {} ", domain.nickname()))
+                                })?;
+                                let clients = self.create_client_registry();
+                                let cf_http_client = self.cf_http_client(&clients)?;
+                                entry.insert((Arc::new(netns), clients, cf_http_client))
+                            }
+                        };
+                        (Some(netns.clone()), &*clients, &*cf_http_client)
+                    }
+                    None => (None, clients, &cf_http_client),
+                };
                 let bind_address = domain.bind_address().or(self.bind_address());
-                let ip_source = domain
-                    .ip_source_type()
-                    .or(self.ip_source_type())
-                    .ok_or(Error::new_string(format!(
-                        "域名 {} 未指定 IP 来源方式",
-                        domain.nickname
-                    )))?
-                    .to_ip_source(&bind_address)?;
-
-                let updater = Updater::new(
-                    bind_address,
-                    ip_source,
+                let fresh_interval = validate_interval(
                     domain.nickname(),
-                    account.token(),
-                    domain.id(),
-                    domain.zone_id(),
+                    "fresh_interval",
                     domain.fresh_interval().unwrap_or(self.fresh_interval()),
+                    MIN_FRESH_INTERVAL_SECONDS,
+                    self.strict(),
+                )?;
+                let retry_interval = validate_interval(
+                    domain.nickname(),
+                    "retry_interval",
                     domain.retry_interval().unwrap_or(self.retry_interval()),
-                    cf_http_client.clone(),
-                );
+                    MIN_RETRY_INTERVAL_SECONDS,
+                    self.strict(),
+                )?;
+                let update_deadline = domain
+                    .update_deadline()
+                    .unwrap_or(fresh_interval.min(MAX_UPDATE_DEADLINE_SECONDS));
+
+                let auto_ttl = domain
+                    .auto_ttl
+                    .as_ref()
+                    .or(self.auto_ttl.as_ref())
+                    .map(|auto_ttl| auto_ttl.to_auto_ttl(domain.nickname()))
+                    .transpose()?;
+                let ttl = domain.ttl.or(self.ttl).map(|ttl| ttl.seconds());
+                let details_refresh = match domain
+                    .details_refresh_interval
+                    .or(self.details_refresh_interval)
+                    .unwrap_or(DEFAULT_DETAILS_REFRESH_SECONDS)
+                {
+                    0 => None,
+                    seconds => Some(Duration::from_secs(seconds)),
+                };
+                let max_consecutive_failures = domain
+                    .max_consecutive_failures
+                    .or(self.max_consecutive_failures)
+                    .filter(|max| *max > 0);
+                let force_update = domain
+                    .force_update_every
+                    .or(self.force_update_every)
+                    .map(|period| period.duration())
+                    .filter(|duration| !duration.is_zero());
+                if auto_ttl.is_some() && ttl.is_some() {
+                    return Err(Error::config(format!(
+                        "域名 {} 的 ttl 与 auto_ttl 不能同时使用",
+                        domain.nickname()
+                    )));
+                }
+
+                for record in domain.records()? {
+                    let ip_source_type =
+                        record
+                            .ip_source
+                            .or(self.ip_source_type())
+                            .ok_or(Error::config(format!(
+                                "域名 {} 未指定 IP 来源方式",
+                                record.nickname
+                            )))?;
+                    validate_network(
+                        &record.nickname,
+                        self.network(),
+                        bind_address,
+                        ip_source_type,
+                    )?;
+                    let source_timeout = ip_source_type.timeout().unwrap_or(self.source_timeout());
+                    let ip_source = match ip_sources.entry((
+                        ip_source_type as *const _,
+                        bind_address,
+                        domain.netns.as_deref(),
+                    )) {
+                        Entry::Occupied(entry) => entry.get().clone(),
+                        Entry::Vacant(entry) => {
+                            let ip_source: Arc<dyn IpSource> = ip_source_type
+                                .to_ip_source(
+                                    &bind_address,
+                                    source_timeout,
+                                    &SourceHttpOptions {
+                                        user_agent: self.user_agent.clone(),
+                                        proxy: self.source_proxy.clone().unwrap_or_default(),
+                                    },
+                                    &http_debug,
+                                    clients,
+                                )?
+                                .into();
+                            let ip_source = match self.source_cache_ttl() {
+                                Some(ttl) => Arc::new(CachedSource::new(ip_source, ttl)),
+                                None => ip_source,
+                            };
+                            entry.insert(ip_source).clone()
+                        }
+                    };
+
+                    let zone = Self::resolve_zone(&mut zones, account, domain)?;
+                    let cf_api = Self::create_cf_api(
+                        account,
+                        zone.clone(),
+                        &record,
+                        cf_http_client,
+                        &metrics,
+                        &http_debug,
+                    )
+                    .with_token_check(token_check.clone());
+                    let extra_cf_apis = record
+                        .extra_records
+                        .iter()
+                        .map(|extra| {
+                            let extra = DomainRecord {
+                                nickname: record.nickname.clone(),
+                                record: extra.clone(),
+                                ip_source: record.ip_source,
+                                template: None,
+                                extra_records: Vec::new(),
+                            };
+                            Box::new(Self::create_cf_api(
+                                account,
+                                zone.clone(),
+                                &extra,
+                                cf_http_client,
+                                &metrics,
+                                &http_debug,
+                            )) as Box<dyn CfApi>
+                        })
+                        .collect();
+                    let updater = Updater::new(
+                        bind_address,
+                        Box::new(ip_source),
+                        record.nickname,
+                        Box::new(cf_api),
+                        fresh_interval,
+                        retry_interval,
+                        notifications.clone(),
+                        metrics.clone(),
+                        self.history_depth.unwrap_or(DEFAULT_HISTORY_DEPTH),
+                        source_timeout,
+                        Duration::from_secs(update_deadline),
+                        retry_budget.clone(),
+                    )
+                    .with_records(extra_cf_apis)
+                    .with_details_refresh(details_refresh)
+                    .with_state(state.clone())
+                    .with_backoff(backoff)
+                    .with_auto_ttl(auto_ttl)
+                    .with_ttl(ttl)
+                    .with_proxied(domain.proxied)
+                    .with_private_addresses(domain.allow_private.unwrap_or(false))
+                    .with_source_retries(
+                        self.source_retries.unwrap_or(DEFAULT_SOURCE_RETRIES),
+                        Duration::from_secs(
+                            self.source_retry_delay
+                                .unwrap_or(DEFAULT_SOURCE_RETRY_DELAY_SECONDS),
+                        ),
+                    )
+                    .with_source_validation(self.validate_sources.unwrap_or(true))
+                    .with_invalid_token_stop(self.stop_on_invalid_token.unwrap_or(true))
+                    .with_max_consecutive_failures(max_consecutive_failures)
+                    .with_dry_run(self.dry_run())
+                    .with_netns(netns.clone())
+                    .with_force_update(force_update)
+                    .with_record_creation(record.template.is_some());
 
-                updaters.push(Arc::new(Mutex::new(updater)));
+                    updaters.push(updater);
+                }
 
                 Ok::<(), Error>(())
             })?;
@@ -121,21 +637,155 @@ impl Configuration {
         Ok(updaters)
     }
 
-    /// 获取全局出现错误时重试间隔，单位秒。默认为 300 秒后。
-    pub fn retry_interval(&self) -> u64 {
-        self.retry_interval
-            .unwrap_or(DEFAULT_RETRY_INTERVAL_SECONDS)
+    /// 创建网络就绪检查，未启用时返回 `None`
+    ///
+    /// 检查目标为 Cloudflare API 及所有独立服务器 IP 地址来源。
+    pub fn create_network_gate(&self) -> Option<NetworkGate> {
+        let limit = match self.wait_for_network {
+            Some(WaitForNetwork::Enabled(false)) => return None,
+            Some(WaitForNetwork::Seconds(seconds)) => seconds,
+            Some(WaitForNetwork::Enabled(true)) | None => DEFAULT_WAIT_FOR_NETWORK_SECONDS,
+        };
+
+        let mut targets = match &self.cf_resolve {
+            Some(CfResolve::Pin(addresses)) => addresses
+                .iter()
+                .map(|ip| std::net::SocketAddr::new(*ip, 443).to_string())
+                .collect(),
+            _ => vec![format!("{}:443", CLOUDFLARE_API_HOST)],
+        };
+        self.ip_source_type()
+            .into_iter()
+            .chain(
+                self.accounts()
+                    .iter()
+                    .flat_map(|account| account.domains())
+                    .flat_map(|domain| {
+                        [
+                            domain.ip_source_type(),
+                            domain.ip_source_v4.as_ref(),
+                            domain.ip_source_v6.as_ref(),
+                        ]
+                    })
+                    .flatten(),
+            )
+            .flat_map(|ip_source| ip_source.probe_targets())
+            .for_each(|target| {
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            });
+
+        Some(NetworkGate::new(
+            targets,
+            self.bind_address,
+            self.network(),
+            Duration::from_secs(limit),
+        ))
+    }
+
+    /// 创建网络连通性检查，未配置时返回 `None`
+    pub fn create_connectivity_probe(&self) -> Result<Option<Arc<ConnectivityProbe>>, Error> {
+        let Some(check) = &self.connectivity_check else {
+            return Ok(None);
+        };
+
+        let url = check
+            .url
+            .as_deref()
+            .unwrap_or(DEFAULT_CONNECTIVITY_CHECK_URL);
+        let url = url.parse::<Url>().map_err(|_| {
+            Error::config(format!("connectivity_check.url 不是合法的地址：{}", url))
+        })?;
+        let status = check.status.unwrap_or(StatusCode::NO_CONTENT.as_u16());
+        let status = StatusCode::from_u16(status).map_err(|_| {
+            Error::config(format!(
+                "connectivity_check.status {} 不是合法的 HTTP 状态码",
+                status
+            ))
+        })?;
+        let client = self.clients().client(ClientKey {
+            bind_address: self.bind_address,
+            timeout: Some(self.source_timeout()),
+            proxy: ProxySetting::System,
+            cf_resolve: false,
+            keepalive: false,
+            user_agent: self.user_agent.clone(),
+            tls: TlsOptions::default(),
+        })?;
+
+        Ok(Some(Arc::new(ConnectivityProbe::new(
+            client,
+            url,
+            status,
+            check.body.clone().unwrap_or_default(),
+            check
+                .cache
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_CONNECTIVITY_CACHE),
+        ))))
+    }
+
+    /// 获取同时初始化的域名数量上限。默认为 8 个
+    pub fn init_concurrency(&self) -> usize {
+        self.init_concurrency
+            .unwrap_or(DEFAULT_INIT_CONCURRENCY)
+            .max(1)
+    }
+
+    /// 获取收到触发消息后的静默等待时间。默认为 2 秒
+    pub fn trigger_quiescence(&self) -> Duration {
+        Duration::from_secs(
+            self.trigger_quiescence
+                .unwrap_or(DEFAULT_TRIGGER_QUIESCENCE_SECONDS),
+        )
+    }
+
+    /// 获取运行时工作线程数量，返回 `1` 时应使用单线程运行时
+    ///
+    /// 未配置时仅有一个域名使用单线程运行时，否则每 4 个域名使用一个工作线程，
+    /// 最少 2 个，最多不超过 CPU 核心数。
+    pub fn runtime_threads(&self, domains: usize) -> usize {
+        match self.runtime_threads {
+            Some(threads) => threads.max(1),
+            None => {
+                let cpus = std::thread::available_parallelism()
+                    .map(|cpus| cpus.get())
+                    .unwrap_or(1);
+                default_runtime_threads(domains, cpus)
+            }
+        }
+    }
+
+    /// 是否启用严格模式
+    pub fn strict(&self) -> bool {
+        self.strict.unwrap_or(false)
+    }
+
+    /// 是否模拟运行
+    pub fn dry_run(&self) -> bool {
+        self.dry_run.unwrap_or(false)
     }
 
-    /// 获取 Cloudflare 访问代理配置
-    pub fn proxy(&self) -> Option<reqwest::Proxy> {
-        // let Some(proxy) = &self.proxy else {
-        //     return None;
-        // };
+    /// 获取 IP 地址的超时时间。默认为 15 秒
+    pub fn source_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.source_timeout
+                .unwrap_or(DEFAULT_SOURCE_TIMEOUT_SECONDS),
+        )
+    }
 
-        // let proxy = reqwest::Proxy::https(proxy.url.as_str()).
+    /// 获取 IP 地址来源获取结果的缓存时间，未配置或为 `0` 时不缓存
+    pub fn source_cache_ttl(&self) -> Option<Duration> {
+        self.source_cache_ttl
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
 
-        self.proxy.as_ref().and_then(|proxy| Some(proxy.0.clone()))
+    /// 获取全局出现错误时重试间隔，单位秒。默认为 300 秒后。
+    pub fn retry_interval(&self) -> u64 {
+        self.retry_interval
+            .unwrap_or(DEFAULT_RETRY_INTERVAL_SECONDS)
     }
 
     // /// 获取日志参数
@@ -144,31 +794,734 @@ impl Configuration {
     // }
 }
 
+/// 等待网络就绪配置，可为布尔值或最长等待时间
+#[derive(serde::Deserialize, Debug, Clone, Copy)]
+#[serde(untagged)]
+pub enum WaitForNetwork {
+    Enabled(bool),
+    Seconds(u64),
+}
+
+/// 时间长度，可为秒数或带单位的字符串，如 `90`、`30m`、`12h`、`7d`
+///
+/// 支持的单位为 `s`（秒）、`m`（分钟）、`h`（小时）及 `d`（天）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period(Duration);
+
+impl Period {
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl std::str::FromStr for Period {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let split = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+        let number = number
+            .parse::<u64>()
+            .map_err(|_| format!("无法解析时间长度 {}", value))?;
+        let unit = match unit.trim() {
+            "" | "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            unit => {
+                return Err(format!(
+                    "时间长度 {} 的单位 {} 无效，可用单位为 s、m、h、d",
+                    value, unit
+                ))
+            }
+        };
+        number
+            .checked_mul(unit)
+            .map(|seconds| Self(Duration::from_secs(seconds)))
+            .ok_or(format!("时间长度 {} 过长", value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Period {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Seconds(u64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Seconds(seconds) => Ok(Self(Duration::from_secs(seconds))),
+            Raw::Text(text) => text.parse().map_err(de::Error::custom),
+        }
+    }
+}
+
+/// 校验域名 `nickname` 的间隔配置 `name`，单位秒
+///
+/// 超过上限时报错；低于下限时严格模式下报错，否则输出警告并使用下限值。
+fn validate_interval(
+    nickname: &str,
+    name: &str,
+    value: u64,
+    min: u64,
+    strict: bool,
+) -> Result<u64, Error> {
+    if value > MAX_INTERVAL_SECONDS {
+        Err(Error::config(format!(
+            "域名 {} 的 {} 为 {} 秒，超过上限 {} 秒",
+            nickname, name, value, MAX_INTERVAL_SECONDS
+        )))
+    } else if value >= min {
+        Ok(value)
+    } else if strict {
+        Err(Error::config(format!(
+            "域名 {} 的 {} 为 {} 秒，低于下限 {} 秒",
+            nickname, name, value, min
+        )))
+    } else {
+        warn!(
+            "域名 {} 的 {} 为 {} 秒，低于下限 {} 秒，已使用 {} 秒",
+            nickname, name, value, min, min
+        );
+        Ok(min)
+    }
+}
+
+/// 校验域名 `nickname` 的绑定地址及 IP 地址来源能否在网络地址族模式 `network` 下使用
+fn validate_network(
+    nickname: &str,
+    network: NetworkMode,
+    bind_address: Option<IpAddr>,
+    ip_source: &IpSourceType,
+) -> Result<(), Error> {
+    if let Some(bind_address) = bind_address {
+        if !network.allows(&bind_address) {
+            return Err(Error::config(format!(
+                "域名 {} 绑定的本地地址 {} 无法在 {} 模式下使用",
+                nickname,
+                bind_address,
+                network.as_str()
+            )));
+        }
+    }
+
+    let available = match ip_source {
+        IpSourceType::Standalone {
+            url,
+            family: Some(family),
+            ..
+        }
+        | IpSourceType::HttpJson {
+            url,
+            family: Some(family),
+            ..
+        }
+        | IpSourceType::HttpRegex {
+            url,
+            family: Some(family),
+            ..
+        } => {
+            if let Some(bind_address) = bind_address {
+                if !family.matches(&bind_address) {
+                    return Err(Error::config(format!(
+                        "域名 {} 绑定的本地地址 {} 与 IP 地址来源的地址族 {} 不一致",
+                        nickname,
+                        bind_address,
+                        family.as_str()
+                    )));
+                }
+            }
+            if let Some(ip) = host_ip(url) {
+                if !family.matches(&ip) {
+                    return Err(Error::config(format!(
+                        "域名 {} 的 IP 地址来源 {} 与地址族 {} 不一致",
+                        nickname,
+                        url,
+                        family.as_str()
+                    )));
+                }
+            }
+            match family {
+                IpFamily::V4 => network != NetworkMode::Ipv6Only,
+                IpFamily::V6 => network != NetworkMode::Ipv4Only,
+            }
+        }
+        // 使用 IP 地址访问的服务器不经过域名解析，需检查地址族
+        IpSourceType::Standalone { url, .. }
+        | IpSourceType::HttpJson { url, .. }
+        | IpSourceType::HttpRegex { url, .. }
+        | IpSourceType::RouterOs { url, .. } => host_ip(url).is_none_or(|ip| network.allows(&ip)),
+        IpSourceType::Ipify(family)
+        | IpSourceType::Icanhazip(family)
+        | IpSourceType::CfTrace(family)
+        | IpSourceType::DohWhoami { family, .. }
+        | IpSourceType::Ubus { family, .. }
+        | IpSourceType::FritzBox { family, .. }
+        | IpSourceType::Dns { family, .. }
+        | IpSourceType::Stun { family, .. } => match family {
+            IpFamily::V4 => network != NetworkMode::Ipv6Only,
+            IpFamily::V6 => network != NetworkMode::Ipv4Only,
+        },
+        // 命令由用户自行决定访问方式
+        IpSourceType::Command { .. } => true,
+        IpSourceType::Chain(sources) | IpSourceType::Consensus { sources, .. } => {
+            for source in sources {
+                validate_network(nickname, network, bind_address, source)?;
+            }
+            true
+        }
+        IpSourceType::WithTimeout { source, .. }
+        | IpSourceType::WithUserAgent { source, .. }
+        | IpSourceType::WithProxy { source, .. } => {
+            return validate_network(nickname, network, bind_address, source)
+        }
+        IpSourceType::PrefixCompose { source, .. } => {
+            validate_network(nickname, network, bind_address, source)?;
+            network != NetworkMode::Ipv4Only
+        }
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        IpSourceType::LocalIPv6 { .. } => network != NetworkMode::Ipv4Only,
+        #[cfg(target_os = "linux")]
+        IpSourceType::WanIpv4 { .. } => network != NetworkMode::Ipv6Only,
+    };
+    if available {
+        Ok(())
+    } else {
+        Err(Error::config(format!(
+            "域名 {} 的 IP 地址来源无法在 {} 模式下使用",
+            nickname,
+            network.as_str()
+        )))
+    }
+}
+
+/// 基于 HTTP 的 IP 地址来源使用的客户端参数
+///
+/// 来源可单独指定，多来源中未单独指定的成员沿用多来源的参数。
+#[derive(Debug, Clone, Default)]
+struct SourceHttpOptions {
+    /// 请求的 User-Agent，为 `None` 时使用 reqwest 的默认值
+    user_agent: Option<Arc<str>>,
+    proxy: ProxySetting,
+}
+
+/// 共享 IP 来源实例的依据：来源配置、绑定地址及网络命名空间名称
+type IpSourceKey<'a> = (*const IpSourceType, Option<IpAddr>, Option<&'a str>);
+
+/// URL 主机部分为 IP 地址时返回该地址
+fn host_ip(url: &Url) -> Option<IpAddr> {
+    url.host_str().and_then(|host| {
+        host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .ok()
+    })
+}
+
 /// 可用的 IP 地址来源方式
 ///
 /// - `0`：IpIp(废弃，已移除)
 /// - `1`：独立服务器
 /// - `2`：本地 IPv6 查询
+/// - `3`：ipify
+/// - `4`：icanhazip
+/// - `5`：Cloudflare trace
+/// - `6`：DNS 查询
+/// - `7`：STUN
+/// - `8`：自定义命令
+/// - `9`：HTTP JSON 接口
+/// - `10`：HTTP 正则提取
+/// - `11`：按顺序尝试多个来源
+/// - `12`：同时查询多个来源，取多数一致的结果
+/// - `13`：通过 DoH 查询 `whoami.cloudflare`
+/// - `14`：读取 WAN 口网卡的 IPv4 地址，仅 Linux 可用
+/// - `15`：以其他来源获取的 IPv6 前缀拼接固定后缀
+/// - `16`：MikroTik RouterOS REST API
+/// - `17`：OpenWrt ubus 接口状态
+/// - `18`：FRITZ!Box TR-064 外部地址
+///
+/// 除数值外，也可使用名称指定来源方式，见 [`source_type_code`]。
 #[derive(Debug, Clone)]
 pub enum IpSourceType {
     // IpIp,
-    Standalone(Url),
-    #[cfg(any(target_os = "linux", target_os = "windows"))]
-    LocalIPv6(Option<String>),
+    Standalone {
+        url: Url,
+        /// 请求方法，仅支持 `GET`、`POST` 及 `PUT`
+        method: Method,
+        headers: HeaderMap,
+        /// 固定的请求体，仅用于 `POST` 及 `PUT`
+        body: Option<String>,
+        /// 请求签名使用的共享密钥
+        secret: Option<Secret>,
+        /// 期望的地址族，为 `None` 时接受任意地址族
+        family: Option<IpFamily>,
+        tls: TlsOptions,
+    },
+    Ipify(IpFamily),
+    Icanhazip(IpFamily),
+    CfTrace(IpFamily),
+    Dns {
+        /// DNS 服务器地址，`host:port` 形式
+        resolver: String,
+        /// 查询域名
+        name: String,
+        family: IpFamily,
+        /// 是否查询 TXT 记录，否则按地址族查询 A 或 AAAA 记录
+        txt: bool,
+    },
+    Stun {
+        /// STUN 服务器列表，`host:port` 形式，按顺序尝试
+        servers: Vec<String>,
+        family: IpFamily,
+    },
+    Command {
+        program: String,
+        args: Vec<String>,
+        /// 命令最长执行时间，为空时使用 IP 地址来源的超时时间
+        timeout: Option<Duration>,
+    },
+    HttpJson {
+        url: Url,
+        /// 字段路径
+        path: Vec<String>,
+        headers: HeaderMap,
+        /// 期望的地址族，为 `None` 时接受任意地址族
+        family: Option<IpFamily>,
+    },
+    HttpRegex {
+        url: Url,
+        /// 至少包含一个捕获组的正则表达式
+        regex: Regex,
+        headers: HeaderMap,
+        /// 期望的地址族，为 `None` 时接受任意地址族
+        family: Option<IpFamily>,
+    },
+    Chain(Vec<IpSourceType>),
+    Consensus {
+        sources: Vec<IpSourceType>,
+        /// 至少需要多少个来源给出相同的地址
+        quorum: usize,
+    },
+    DohWhoami {
+        /// DoH 服务地址
+        url: Url,
+        family: IpFamily,
+    },
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    LocalIPv6 {
+        /// 网卡接口名称，为空时使用首个符合规则的网卡
+        interface: Option<String>,
+        rules: AddressRules,
+    },
+    #[cfg(target_os = "linux")]
+    WanIpv4 {
+        /// 网卡接口名称，如 `ppp0`
+        interface: String,
+        /// 是否允许使用运营商级 NAT 地址
+        allow_cgnat: bool,
+    },
+    RouterOs {
+        /// 路由器地址
+        url: Url,
+        /// 网卡接口名称，如 `pppoe-out1`
+        interface: String,
+        auth: RouterOsAuth,
+        tls: TlsOptions,
+    },
+    Ubus {
+        /// OpenWrt 逻辑接口名称，如 `wan`、`wan6`
+        interface: String,
+        family: IpFamily,
+        /// 远程调用的 JSON-RPC 接口地址及会话 ID，为 `None` 时在本机执行 `ubus` 命令
+        remote: Option<(Url, Secret)>,
+    },
+    FritzBox {
+        /// 路由器地址
+        url: Url,
+        family: IpFamily,
+    },
+    PrefixCompose {
+        /// 提供前缀的来源
+        source: Box<IpSourceType>,
+        /// 前缀长度，单位位
+        prefix_len: u8,
+        /// 后缀，仅低 `128 - prefix_len` 位有效
+        suffix: Ipv6Addr,
+    },
+    /// 单独指定超时时间的来源，超时时间同样用于多来源中未指定超时时间的来源
+    WithTimeout {
+        source: Box<IpSourceType>,
+        timeout: Duration,
+    },
+    /// 单独指定 User-Agent 的来源，同样用于多来源中未指定 User-Agent 的来源
+    WithUserAgent {
+        source: Box<IpSourceType>,
+        user_agent: Arc<str>,
+    },
+    /// 单独指定访问代理的来源，同样用于多来源中未指定访问代理的来源
+    WithProxy {
+        source: Box<IpSourceType>,
+        proxy: ProxySetting,
+    },
 }
 
 impl IpSourceType {
-    fn to_ip_source(&self, bind_address: &Option<IpAddr>) -> Result<Box<dyn IpSource>, Error> {
+    /// 等待网络就绪时需探测的 `host:port` 地址
+    fn probe_targets(&self) -> Vec<String> {
+        let target = match self {
+            IpSourceType::Standalone { url, .. }
+            | IpSourceType::HttpJson { url, .. }
+            | IpSourceType::HttpRegex { url, .. }
+            | IpSourceType::DohWhoami { url, .. }
+            | IpSourceType::RouterOs { url, .. }
+            | IpSourceType::FritzBox { url, .. }
+            | IpSourceType::Ubus {
+                remote: Some((url, _)),
+                ..
+            } => url
+                .host_str()
+                .zip(url.port_or_known_default())
+                .map(|(host, port)| format!("{}:{}", host, port)),
+            IpSourceType::Ipify(family) => Some(format!("{}:443", Ipify::host(*family))),
+            IpSourceType::Icanhazip(family) => Some(format!("{}:443", Icanhazip::host(*family))),
+            IpSourceType::CfTrace(family) => Some(format!("{}:443", CfTrace::host(*family))),
+            IpSourceType::Chain(sources) | IpSourceType::Consensus { sources, .. } => {
+                return sources
+                    .iter()
+                    .flat_map(|source| source.probe_targets())
+                    .collect()
+            }
+            IpSourceType::WithTimeout { source, .. }
+            | IpSourceType::WithUserAgent { source, .. }
+            | IpSourceType::WithProxy { source, .. }
+            | IpSourceType::PrefixCompose { source, .. } => return source.probe_targets(),
+            _ => None,
+        };
+        target.into_iter().collect()
+    }
+
+    /// 单独指定的超时时间，未指定时使用全局的 `source_timeout`
+    fn timeout(&self) -> Option<Duration> {
+        match self {
+            IpSourceType::WithTimeout { timeout, .. } => Some(*timeout),
+            IpSourceType::Command { timeout, .. } => *timeout,
+            _ => None,
+        }
+    }
+
+    fn to_ip_source(
+        &self,
+        bind_address: &Option<IpAddr>,
+        timeout: Duration,
+        http: &SourceHttpOptions,
+        http_debug: &Option<HttpDebug>,
+        clients: &ClientRegistry,
+    ) -> Result<Box<dyn IpSource>, Error> {
         let ip_source: Box<dyn IpSource> = match self {
-            IpSourceType::Standalone(url) => {
-                Box::new(Standalone::new(url.clone(), bind_address.clone())?)
+            IpSourceType::Standalone {
+                url,
+                method,
+                headers,
+                body,
+                secret,
+                family,
+                tls,
+            } => {
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, *family),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: tls.clone(),
+                })?;
+                Box::new(
+                    Standalone::new(
+                        url.clone(),
+                        method.clone(),
+                        headers.clone(),
+                        body.clone(),
+                        client,
+                        timeout,
+                        http_debug.clone(),
+                    )
+                    .with_secret(secret.clone())
+                    .with_family(*family),
+                )
+            }
+            IpSourceType::Ipify(family) => {
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, Some(*family)),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(Ipify::new(*family, client, timeout, http_debug.clone()))
+            }
+            IpSourceType::Icanhazip(family) => {
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, Some(*family)),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(Icanhazip::new(*family, client, timeout, http_debug.clone()))
+            }
+            IpSourceType::CfTrace(family) => {
+                // 与其他基于 HTTP 的来源一致使用来源的访问代理（source_proxy 或来源的 proxy），
+                // 而非 Cloudflare 访问代理；经代理访问时获取到的将是代理服务器的地址
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, Some(*family)),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(CfTrace::new(*family, client, timeout, http_debug.clone()))
+            }
+            IpSourceType::DohWhoami { url, family } => {
+                // 解析器返回的是请求方地址，未绑定本地地址时绑定对应地址族的任意地址
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, Some(*family)),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(DohWhoami::new(
+                    url.clone(),
+                    *family,
+                    client,
+                    timeout,
+                    http_debug.clone(),
+                ))
+            }
+            IpSourceType::Dns {
+                resolver,
+                name,
+                family,
+                txt,
+            } => Box::new(DnsQuery::new(
+                resolver.clone(),
+                name.clone(),
+                *family,
+                *txt,
+                *bind_address,
+                timeout,
+            )),
+            IpSourceType::Stun { servers, family } => {
+                Box::new(Stun::new(servers.clone(), *family, *bind_address, timeout))
+            }
+            IpSourceType::Command {
+                program,
+                args,
+                timeout: command_timeout,
+            } => Box::new(Command::new(
+                program.clone(),
+                args.clone(),
+                command_timeout.unwrap_or(timeout),
+            )),
+            IpSourceType::HttpJson {
+                url,
+                path,
+                headers,
+                family,
+            } => {
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, *family),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(
+                    HttpJson::new(
+                        url.clone(),
+                        path.clone(),
+                        headers.clone(),
+                        client,
+                        timeout,
+                        http_debug.clone(),
+                    )
+                    .with_family(*family),
+                )
+            }
+            IpSourceType::HttpRegex {
+                url,
+                regex,
+                headers,
+                family,
+            } => {
+                let client = clients.client(ClientKey {
+                    bind_address: bind_family(*bind_address, *family),
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(
+                    HttpRegex::new(
+                        url.clone(),
+                        regex.clone(),
+                        headers.clone(),
+                        client,
+                        timeout,
+                        http_debug.clone(),
+                    )
+                    .with_family(*family),
+                )
+            }
+            IpSourceType::WithTimeout { source, timeout } => {
+                return source.to_ip_source(bind_address, *timeout, http, http_debug, clients)
+            }
+            IpSourceType::WithUserAgent { source, user_agent } => {
+                let http = SourceHttpOptions {
+                    user_agent: Some(user_agent.clone()),
+                    ..http.clone()
+                };
+                return source.to_ip_source(bind_address, timeout, &http, http_debug, clients);
+            }
+            IpSourceType::WithProxy { source, proxy } => {
+                let http = SourceHttpOptions {
+                    proxy: proxy.clone(),
+                    ..http.clone()
+                };
+                return source.to_ip_source(bind_address, timeout, &http, http_debug, clients);
+            }
+            IpSourceType::RouterOs {
+                url,
+                interface,
+                auth,
+                tls,
+            } => {
+                let client = clients.client(ClientKey {
+                    bind_address: *bind_address,
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: tls.clone(),
+                })?;
+                Box::new(RouterOs::new(
+                    url.clone(),
+                    interface.clone(),
+                    auth.clone(),
+                    client,
+                    timeout,
+                    http_debug.clone(),
+                ))
+            }
+            IpSourceType::Ubus {
+                interface,
+                family,
+                remote,
+            } => {
+                let transport = match remote {
+                    None => UbusTransport::Local,
+                    Some((url, session)) => UbusTransport::Remote {
+                        url: url.clone(),
+                        session: session.clone(),
+                        client: clients.client(ClientKey {
+                            bind_address: *bind_address,
+                            timeout: Some(timeout),
+                            proxy: http.proxy.clone(),
+                            cf_resolve: false,
+                            keepalive: false,
+                            user_agent: http.user_agent.clone(),
+                            tls: TlsOptions::default(),
+                        })?,
+                        // 会话 ID 位于请求体中，需额外脱敏
+                        http_debug: http_debug
+                            .as_ref()
+                            .map(|http_debug| http_debug.with_secret(session.expose())),
+                    },
+                };
+                Box::new(Ubus::new(interface.clone(), *family, transport, timeout))
+            }
+            IpSourceType::FritzBox { url, family } => {
+                let client = clients.client(ClientKey {
+                    bind_address: *bind_address,
+                    timeout: Some(timeout),
+                    proxy: http.proxy.clone(),
+                    cf_resolve: false,
+                    keepalive: false,
+                    user_agent: http.user_agent.clone(),
+                    tls: TlsOptions::default(),
+                })?;
+                Box::new(FritzBox::new(
+                    url.clone(),
+                    *family,
+                    client,
+                    timeout,
+                    http_debug.clone(),
+                ))
             }
-            #[cfg(any(target_os = "linux", target_os = "windows"))]
-            IpSourceType::LocalIPv6(interface_name) => {
+            IpSourceType::PrefixCompose {
+                source,
+                prefix_len,
+                suffix,
+            } => Box::new(PrefixCompose::new(
+                source.to_ip_source(bind_address, timeout, http, http_debug, clients)?,
+                *prefix_len,
+                *suffix,
+            )),
+            IpSourceType::Chain(sources) => Box::new(Chain::new(
+                sources
+                    .iter()
+                    .map(|source| {
+                        source.to_ip_source(bind_address, timeout, http, http_debug, clients)
+                    })
+                    .collect::<Result<_, _>>()?,
+            )),
+            IpSourceType::Consensus { sources, quorum } => Box::new(Consensus::new(
+                sources
+                    .iter()
+                    .map(|source| {
+                        source.to_ip_source(bind_address, timeout, http, http_debug, clients)
+                    })
+                    .collect::<Result<_, _>>()?,
+                *quorum,
+            )),
+            #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+            IpSourceType::LocalIPv6 { interface, rules } => {
                 Box::new(super::source::local_ipv6::LocalIPv6::new(
-                    interface_name.clone().map(|name| Cow::Owned(name)),
+                    interface.clone().map(Cow::Owned),
+                    *rules,
                 ))
             }
+            #[cfg(target_os = "linux")]
+            IpSourceType::WanIpv4 {
+                interface,
+                allow_cgnat,
+            } => Box::new(super::source::wan_ipv4::WanIpv4::new(
+                interface.clone(),
+                *allow_cgnat,
+                timeout,
+            )),
         };
 
         Ok(ip_source)
@@ -185,10 +1538,14 @@ impl<'de> Deserialize<'de> for IpSourceType {
             type Value = IpSourceType;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                #[cfg(any(target_os = "linux", target_os = "windows"))]
-                formatter.write_str("可用的 IP 地址来源方式为：1(独立服务器) 或 2(Local IPv6)")?;
-                #[cfg(not(any(target_os = "linux", target_os = "windows")))]
-                formatter.write_str("可用的 IP 地址来源方式为：1(独立服务器)")?;
+                #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+                formatter.write_str(
+                    "可用的 IP 地址来源方式为：1(独立服务器)、2(Local IPv6)、3(ipify)、4(icanhazip)、5(Cloudflare trace)、6(DNS 查询)、7(STUN)、8(自定义命令)、9(HTTP JSON 接口)、10(HTTP 正则提取)、11(多来源)、12(多数一致)、13(DoH whoami)、14(WAN 口 IPv4，仅 Linux)、15(前缀拼接)、16(RouterOS)、17(ubus) 或 18(FRITZ!Box)",
+                )?;
+                #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+                formatter.write_str(
+                    "可用的 IP 地址来源方式为：1(独立服务器)、3(ipify)、4(icanhazip)、5(Cloudflare trace)、6(DNS 查询)、7(STUN)、8(自定义命令)、9(HTTP JSON 接口)、10(HTTP 正则提取)、11(多来源)、12(多数一致)、13(DoH whoami)、15(前缀拼接)、16(RouterOS)、17(ubus) 或 18(FRITZ!Box)",
+                )?;
 
                 Ok(())
             }
@@ -202,12 +1559,68 @@ impl<'de> Deserialize<'de> for IpSourceType {
                     1 => Err(E::custom(
                         "IP 来源方式 1(独立服务器) 必须指定服务器访问地址",
                     )),
-                    #[cfg(any(target_os = "linux", target_os = "windows"))]
-                    2 => Ok(IpSourceType::LocalIPv6(None)),
+                    8 => Err(E::custom("IP 来源方式 8(自定义命令) 必须指定命令")),
+                    9 => Err(E::custom(
+                        "IP 来源方式 9(HTTP JSON 接口) 必须指定接口地址及字段路径",
+                    )),
+                    10 => Err(E::custom(
+                        "IP 来源方式 10(HTTP 正则提取) 必须指定页面地址及正则表达式",
+                    )),
+                    11 => Err(E::custom("IP 来源方式 11(多来源) 必须指定来源列表")),
+                    12 => Err(E::custom("IP 来源方式 12(多数一致) 必须指定来源列表")),
+                    15 => Err(E::custom("IP 来源方式 15(前缀拼接) 必须指定后缀")),
+                    16 => Err(E::custom(
+                        "IP 来源方式 16(RouterOS) 必须指定路由器地址、网卡接口名称及认证信息",
+                    )),
+                    #[cfg(target_os = "linux")]
+                    14 => Err(E::custom(
+                        "IP 来源方式 14(WAN 口 IPv4) 必须指定网卡接口名称",
+                    )),
+                    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+                    2 => Ok(IpSourceType::LocalIPv6 {
+                        interface: None,
+                        rules: AddressRules::default(),
+                    }),
+                    3 => Ok(IpSourceType::Ipify(IpFamily::default())),
+                    18 => Ok(IpSourceType::FritzBox {
+                        url: DEFAULT_FRITZBOX_URL.parse().unwrap(),
+                        family: IpFamily::default(),
+                    }),
+                    17 => Ok(IpSourceType::Ubus {
+                        interface: "wan".to_string(),
+                        family: IpFamily::default(),
+                        remote: None,
+                    }),
+                    4 => Ok(IpSourceType::Icanhazip(IpFamily::default())),
+                    5 => Ok(IpSourceType::CfTrace(IpFamily::default())),
+                    6 => Ok(IpSourceType::Dns {
+                        resolver: DEFAULT_DNS_RESOLVER.to_string(),
+                        name: DEFAULT_DNS_QUERY_NAME.to_string(),
+                        family: IpFamily::default(),
+                        txt: false,
+                    }),
+                    7 => Ok(IpSourceType::Stun {
+                        servers: DEFAULT_STUN_SERVERS.map(String::from).to_vec(),
+                        family: IpFamily::default(),
+                    }),
+                    13 => Ok(IpSourceType::DohWhoami {
+                        url: DEFAULT_DOH_WHOAMI_URL.parse().unwrap(),
+                        family: IpFamily::default(),
+                    }),
                     _ => Err(E::custom(format!("不支持的 IP 来源方式：{}", v))),
                 }
             }
 
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                match source_type_code(v) {
+                    Some(code) => self.visit_i64(code),
+                    None => Err(E::custom(format!("不支持的 IP 来源方式：{}", v))),
+                }
+            }
+
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
             where
                 A: de::MapAccess<'de>,
@@ -215,12 +1628,99 @@ impl<'de> Deserialize<'de> for IpSourceType {
                 let mut r#type = None;
                 let mut server = None;
                 let mut interface = None;
+                let mut family = None;
+                let mut resolver = None;
+                let mut name = None;
+                let mut txt = None;
+                let mut servers = None;
+                let mut program = None;
+                let mut args = None;
+                let mut timeout = None;
+                let mut url = None;
+                let mut path = None;
+                let mut headers = None;
+                let mut secret = None;
+                let mut ca_cert = None;
+                let mut accept_invalid_certs = None;
+                let mut regex = None;
+                let mut sources = None;
+                let mut quorum = None;
+                let mut user_agent = None;
+                let mut proxy = None;
+                let mut token = None;
+                let mut method = None;
+                let mut body = None;
+                let mut content_type = None;
+                let mut require_dynamic = None;
+                let mut require_mngtmpaddr = None;
+                let mut require_noprefixroute = None;
+                let mut allow_temporary = None;
+                let mut allow_cgnat = None;
+                let mut source = None;
+                let mut prefix_len = None;
+                let mut suffix = None;
+                let mut suffix_len = None;
+                let mut username = None;
+                let mut password = None;
+                let mut verify_tls = None;
+                let mut session = None;
 
                 while let Some(key) = map.next_key::<Cow<'_, str>>()? {
                     match &*key {
-                        "type" => r#type = Some(map.next_value::<i64>()?),
+                        "type" => {
+                            r#type = Some(match map.next_value::<SourceTypeKey>()? {
+                                SourceTypeKey::Code(code) => code,
+                                SourceTypeKey::Name(name) => {
+                                    source_type_code(&name).ok_or_else(|| {
+                                        de::Error::custom(format!("不支持的 IP 来源方式：{}", name))
+                                    })?
+                                }
+                            })
+                        }
                         "server" => server = Some(map.next_value::<Cow<'_, str>>()?),
                         "interface" => interface = Some(map.next_value::<Cow<'_, str>>()?),
+                        "family" => family = Some(map.next_value::<FamilyKey>()?),
+                        "resolver" => resolver = Some(map.next_value::<Cow<'_, str>>()?),
+                        "name" => name = Some(map.next_value::<Cow<'_, str>>()?),
+                        "txt" => txt = Some(map.next_value::<bool>()?),
+                        "servers" => servers = Some(map.next_value::<Vec<String>>()?),
+                        "program" => program = Some(map.next_value::<String>()?),
+                        "args" => args = Some(map.next_value::<Vec<String>>()?),
+                        "timeout" => timeout = Some(map.next_value::<u64>()?),
+                        "url" => url = Some(map.next_value::<String>()?),
+                        "path" => path = Some(map.next_value::<String>()?),
+                        "regex" => regex = Some(map.next_value::<String>()?),
+                        "sources" => sources = Some(map.next_value::<Vec<IpSourceType>>()?),
+                        "quorum" => quorum = Some(map.next_value::<usize>()?),
+                        "user_agent" => user_agent = Some(map.next_value::<Arc<str>>()?),
+                        "proxy" => proxy = Some(map.next_value::<ProxySetting>()?),
+                        "token" => token = Some(map.next_value::<String>()?),
+                        "method" => method = Some(map.next_value::<String>()?),
+                        "body" => body = Some(map.next_value::<String>()?),
+                        "content_type" => content_type = Some(map.next_value::<String>()?),
+                        "secret" => secret = Some(map.next_value::<Secret>()?),
+                        "ca_cert" => ca_cert = Some(map.next_value::<PathBuf>()?),
+                        "accept_invalid_certs" => {
+                            accept_invalid_certs = Some(map.next_value::<bool>()?)
+                        }
+                        "headers" => headers = Some(map.next_value::<HashMap<String, String>>()?),
+                        "require_dynamic" => require_dynamic = Some(map.next_value::<bool>()?),
+                        "require_mngtmpaddr" => {
+                            require_mngtmpaddr = Some(map.next_value::<bool>()?)
+                        }
+                        "require_noprefixroute" => {
+                            require_noprefixroute = Some(map.next_value::<bool>()?)
+                        }
+                        "allow_temporary" => allow_temporary = Some(map.next_value::<bool>()?),
+                        "allow_cgnat" => allow_cgnat = Some(map.next_value::<bool>()?),
+                        "source" => source = Some(map.next_value::<IpSourceType>()?),
+                        "prefix_len" => prefix_len = Some(map.next_value::<u8>()?),
+                        "suffix" => suffix = Some(map.next_value::<String>()?),
+                        "suffix_len" => suffix_len = Some(map.next_value::<u8>()?),
+                        "username" => username = Some(map.next_value::<String>()?),
+                        "password" => password = Some(map.next_value::<Secret>()?),
+                        "verify_tls" => verify_tls = Some(map.next_value::<bool>()?),
+                        "session" => session = Some(map.next_value::<Secret>()?),
                         _ => {}
                     }
                 }
@@ -228,8 +1728,18 @@ impl<'de> Deserialize<'de> for IpSourceType {
                 let Some(r#type) = r#type else {
                     return Err(de::Error::missing_field("type"));
                 };
+                let family = match family {
+                    Some(FamilyKey::Any) if !matches!(r#type, 1 | 9 | 10) => {
+                        return Err(de::Error::custom(
+                            "仅 IP 来源方式 1(独立服务器)、9(HTTP JSON 接口) 及 10(HTTP 正则提取) 支持地址族 any",
+                        ))
+                    }
+                    Some(FamilyKey::V4) => Some(IpFamily::V4),
+                    Some(FamilyKey::V6) => Some(IpFamily::V6),
+                    Some(FamilyKey::Any) | None => None,
+                };
 
-                match r#type {
+                let ip_source_type = match r#type {
                     0 => Err(de::Error::custom(
                         "IP 来源方式 0(IpIp) 已废弃，请使用其他地址来源",
                     )),
@@ -241,20 +1751,408 @@ impl<'de> Deserialize<'de> for IpSourceType {
                                     server
                                 )));
                             };
-                            Ok(IpSourceType::Standalone(server))
+                            let mut headers = parse_headers(headers.unwrap_or_default())
+                                .map_err(de::Error::custom)?;
+                            if let Some(token) = token {
+                                if headers.contains_key(AUTHORIZATION) {
+                                    return Err(de::Error::custom(
+                                        "IP 来源方式 1(独立服务器) 不能同时指定 token 及 Authorization 请求头",
+                                    ));
+                                }
+                                let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+                                    .map_err(|_| de::Error::custom("独立服务器 token 无效"))?;
+                                value.set_sensitive(true);
+                                headers.insert(AUTHORIZATION, value);
+                            }
+
+                            let method = match method.as_deref().map(str::to_ascii_uppercase) {
+                                None => Method::GET,
+                                Some(method) => match method.as_str() {
+                                    "GET" => Method::GET,
+                                    "POST" => Method::POST,
+                                    "PUT" => Method::PUT,
+                                    _ => {
+                                        return Err(de::Error::custom(format!(
+                                            "IP 来源方式 1(独立服务器) 不支持请求方法 {}，仅支持 GET、POST 及 PUT",
+                                            method
+                                        )))
+                                    }
+                                },
+                            };
+                            if method == Method::GET && body.is_some() {
+                                return Err(de::Error::custom(
+                                    "IP 来源方式 1(独立服务器) 使用 GET 请求时不能指定请求体",
+                                ));
+                            }
+                            if let Some(content_type) = content_type {
+                                if headers.contains_key(CONTENT_TYPE) {
+                                    return Err(de::Error::custom(
+                                        "IP 来源方式 1(独立服务器) 不能同时指定 content_type 及 Content-Type 请求头",
+                                    ));
+                                }
+                                let value = HeaderValue::from_str(&content_type).map_err(|_| {
+                                    de::Error::custom(format!(
+                                        "无效 Content-Type：{}",
+                                        content_type
+                                    ))
+                                })?;
+                                headers.insert(CONTENT_TYPE, value);
+                            }
+                            if accept_invalid_certs == Some(true) {
+                                warn!(
+                                    "独立服务器 {} 已跳过证书校验，请优先使用 ca_cert 指定 CA 证书",
+                                    server
+                                );
+                            }
+
+                            Ok(IpSourceType::Standalone {
+                                url: server,
+                                method,
+                                headers,
+                                body,
+                                secret,
+                                family,
+                                tls: TlsOptions {
+                                    ca_cert: ca_cert
+                                        .map(|path| CaCert::load(&path))
+                                        .transpose()
+                                        .map_err(de::Error::custom)?,
+                                    accept_invalid_certs: accept_invalid_certs.unwrap_or(false),
+                                },
+                            })
                         }
                         None => Err(de::Error::custom(
                             "IP 来源方式 1(独立服务器) 必须指定服务器访问地址",
                         )),
                     },
-                    #[cfg(any(target_os = "linux", target_os = "windows"))]
-                    2 => Ok(IpSourceType::LocalIPv6(
-                        interface.map(|name| name.to_string()),
-                    )),
+                    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+                    2 => {
+                        let defaults = AddressRules::default();
+                        Ok(IpSourceType::LocalIPv6 {
+                            interface: interface.map(|name| name.to_string()),
+                            rules: AddressRules {
+                                require_dynamic: require_dynamic
+                                    .unwrap_or(defaults.require_dynamic),
+                                require_mngtmpaddr: require_mngtmpaddr
+                                    .unwrap_or(defaults.require_mngtmpaddr),
+                                require_noprefixroute: require_noprefixroute
+                                    .unwrap_or(defaults.require_noprefixroute),
+                                allow_temporary: allow_temporary
+                                    .unwrap_or(defaults.allow_temporary),
+                            },
+                        })
+                    }
+                    #[cfg(target_os = "linux")]
+                    14 => match interface {
+                        Some(interface) => Ok(IpSourceType::WanIpv4 {
+                            interface: interface.to_string(),
+                            allow_cgnat: allow_cgnat.unwrap_or(false),
+                        }),
+                        None => Err(de::Error::custom(
+                            "IP 来源方式 14(WAN 口 IPv4) 必须指定网卡接口名称",
+                        )),
+                    },
+                    3 => Ok(IpSourceType::Ipify(family.unwrap_or_default())),
+                    4 => Ok(IpSourceType::Icanhazip(family.unwrap_or_default())),
+                    5 => Ok(IpSourceType::CfTrace(family.unwrap_or_default())),
+                    6 => Ok(IpSourceType::Dns {
+                        resolver: resolver
+                            .map(|resolver| dns::resolver_address(&resolver))
+                            .unwrap_or_else(|| DEFAULT_DNS_RESOLVER.to_string()),
+                        name: name
+                            .map(|name| name.to_string())
+                            .unwrap_or_else(|| DEFAULT_DNS_QUERY_NAME.to_string()),
+                        family: family.unwrap_or_default(),
+                        txt: txt.unwrap_or(false),
+                    }),
+                    7 => match servers {
+                        Some(servers) if servers.is_empty() => Err(de::Error::custom(
+                            "IP 来源方式 7(STUN) 的服务器列表不能为空",
+                        )),
+                        servers => Ok(IpSourceType::Stun {
+                            servers: servers
+                                .map(|servers| {
+                                    servers
+                                        .iter()
+                                        .map(|server| stun::server_address(server))
+                                        .collect()
+                                })
+                                .unwrap_or_else(|| DEFAULT_STUN_SERVERS.map(String::from).to_vec()),
+                            family: family.unwrap_or_default(),
+                        }),
+                    },
+                    8 => match program {
+                        Some(program) if !program.is_empty() => {
+                            if timeout == Some(0) {
+                                return Err(de::Error::custom(
+                                    "IP 来源方式 8(自定义命令) 的超时时间必须大于 0",
+                                ));
+                            }
+                            Ok(IpSourceType::Command {
+                                program,
+                                args: args.unwrap_or_default(),
+                                timeout: timeout.map(Duration::from_secs),
+                            })
+                        }
+                        _ => Err(de::Error::custom("IP 来源方式 8(自定义命令) 必须指定命令")),
+                    },
+                    9 => {
+                        let (Some(url), Some(path)) = (url, path) else {
+                            return Err(de::Error::custom(
+                                "IP 来源方式 9(HTTP JSON 接口) 必须指定接口地址及字段路径",
+                            ));
+                        };
+                        let Ok(url) = url.parse::<Url>() else {
+                            return Err(de::Error::custom(format!("无效接口地址：{}", url)));
+                        };
+                        let Some(path) = http_json::parse_path(&path) else {
+                            return Err(de::Error::custom(format!("无效字段路径：{}", path)));
+                        };
+                        Ok(IpSourceType::HttpJson {
+                            url,
+                            path,
+                            headers: parse_headers(headers.unwrap_or_default())
+                                .map_err(de::Error::custom)?,
+                            family,
+                        })
+                    }
+                    10 => {
+                        let (Some(url), Some(regex)) = (url, regex) else {
+                            return Err(de::Error::custom(
+                                "IP 来源方式 10(HTTP 正则提取) 必须指定页面地址及正则表达式",
+                            ));
+                        };
+                        let Ok(url) = url.parse::<Url>() else {
+                            return Err(de::Error::custom(format!("无效页面地址：{}", url)));
+                        };
+                        Ok(IpSourceType::HttpRegex {
+                            url,
+                            regex: http_regex::compile(&regex).map_err(de::Error::custom)?,
+                            headers: parse_headers(headers.unwrap_or_default())
+                                .map_err(de::Error::custom)?,
+                            family,
+                        })
+                    }
+                    11 => match sources {
+                        Some(sources) if !sources.is_empty() => Ok(IpSourceType::Chain(sources)),
+                        _ => Err(de::Error::custom("IP 来源方式 11(多来源) 必须指定来源列表")),
+                    },
+                    12 => match sources {
+                        Some(sources) if !sources.is_empty() => {
+                            let quorum =
+                                quorum.unwrap_or_else(|| Consensus::default_quorum(sources.len()));
+                            if quorum == 0 || quorum > sources.len() {
+                                return Err(de::Error::custom(format!(
+                                    "IP 来源方式 12(多数一致) 的法定数量 {} 必须介于 1 与来源数量 {} 之间",
+                                    quorum,
+                                    sources.len()
+                                )));
+                            }
+                            Ok(IpSourceType::Consensus { sources, quorum })
+                        }
+                        _ => Err(de::Error::custom(
+                            "IP 来源方式 12(多数一致) 必须指定来源列表",
+                        )),
+                    },
+                    16 => {
+                        let (Some(url), Some(interface)) = (url, interface) else {
+                            return Err(de::Error::custom(
+                                "IP 来源方式 16(RouterOS) 必须指定路由器地址及网卡接口名称",
+                            ));
+                        };
+                        let Ok(url) = url.parse::<Url>() else {
+                            return Err(de::Error::custom(format!("无效路由器地址：{}", url)));
+                        };
+                        // 认证信息不能写在地址中，否则将随地址出现在日志中
+                        if !url.username().is_empty() || url.password().is_some() {
+                            return Err(de::Error::custom(
+                                "RouterOS 路由器地址中不能包含认证信息，请使用 username 及 password",
+                            ));
+                        }
+                        let auth = match (username, password, token) {
+                            (Some(username), Some(password), None) => {
+                                RouterOsAuth::Basic { username, password }
+                            }
+                            (None, None, Some(token)) => RouterOsAuth::Token(Secret::from(token)),
+                            _ => {
+                                return Err(de::Error::custom(
+                                    "IP 来源方式 16(RouterOS) 必须指定 username 及 password，或仅指定 token",
+                                ))
+                            }
+                        };
+                        let verify_tls = verify_tls.unwrap_or(true);
+                        if !verify_tls {
+                            warn!(
+                                "RouterOS {} 已跳过证书校验，请优先使用 ca_cert 指定 CA 证书",
+                                url
+                            );
+                        }
+                        Ok(IpSourceType::RouterOs {
+                            url,
+                            interface: interface.to_string(),
+                            auth,
+                            tls: TlsOptions {
+                                ca_cert: ca_cert
+                                    .map(|path| CaCert::load(&path))
+                                    .transpose()
+                                    .map_err(de::Error::custom)?,
+                                accept_invalid_certs: !verify_tls,
+                            },
+                        })
+                    }
+                    17 => {
+                        let remote =
+                            match (url, session) {
+                                (None, None) => None,
+                                (Some(url), Some(session)) => {
+                                    let Ok(url) = url.parse::<Url>() else {
+                                        return Err(de::Error::custom(format!(
+                                            "无效 ubus 接口地址：{}",
+                                            url
+                                        )));
+                                    };
+                                    Some((url, session))
+                                }
+                                _ => return Err(de::Error::custom(
+                                    "IP 来源方式 17(ubus) 远程调用时必须同时指定 url 及 session",
+                                )),
+                            };
+                        Ok(IpSourceType::Ubus {
+                            interface: interface
+                                .map(|name| name.to_string())
+                                .unwrap_or_else(|| "wan".to_string()),
+                            family: family.unwrap_or_default(),
+                            remote,
+                        })
+                    }
+                    18 => {
+                        let url = url.as_deref().unwrap_or(DEFAULT_FRITZBOX_URL);
+                        let Ok(url) = url.parse::<Url>() else {
+                            return Err(de::Error::custom(format!("无效 FRITZ!Box 地址：{}", url)));
+                        };
+                        Ok(IpSourceType::FritzBox {
+                            url,
+                            family: family.unwrap_or_default(),
+                        })
+                    }
+                    15 => {
+                        let Some(suffix) = suffix else {
+                            return Err(de::Error::custom("IP 来源方式 15(前缀拼接) 必须指定后缀"));
+                        };
+                        let Ok(suffix) = suffix.parse::<Ipv6Addr>() else {
+                            return Err(de::Error::custom(format!(
+                                "无效后缀：{}，后缀须为 IPv6 地址形式，如 ::1234:5678",
+                                suffix
+                            )));
+                        };
+                        let prefix_len = prefix_len.unwrap_or(64);
+                        if prefix_len > 128 {
+                            return Err(de::Error::custom(format!(
+                                "前缀长度 {} 不能大于 128",
+                                prefix_len
+                            )));
+                        }
+                        if let Some(suffix_len) = suffix_len {
+                            if u16::from(prefix_len) + u16::from(suffix_len) != 128 {
+                                return Err(de::Error::custom(format!(
+                                    "前缀长度 {} 与后缀长度 {} 之和必须为 128",
+                                    prefix_len, suffix_len
+                                )));
+                            }
+                        }
+                        if !prefix_compose::suffix_fits(prefix_len, &suffix) {
+                            return Err(de::Error::custom(format!(
+                                "后缀 {} 超出 {} 位，与 /{} 前缀重叠",
+                                suffix,
+                                128 - u16::from(prefix_len),
+                                prefix_len
+                            )));
+                        }
+                        // 未指定来源时读取本机网卡的 IPv6 地址
+                        #[cfg(any(
+                            target_os = "linux",
+                            target_os = "macos",
+                            target_os = "windows"
+                        ))]
+                        let source = source.unwrap_or(IpSourceType::LocalIPv6 {
+                            interface: None,
+                            rules: AddressRules::default(),
+                        });
+                        #[cfg(not(any(
+                            target_os = "linux",
+                            target_os = "macos",
+                            target_os = "windows"
+                        )))]
+                        let Some(source) = source
+                        else {
+                            return Err(de::Error::custom(
+                                "IP 来源方式 15(前缀拼接) 必须指定前缀来源",
+                            ));
+                        };
+                        Ok(IpSourceType::PrefixCompose {
+                            source: Box::new(source),
+                            prefix_len,
+                            suffix,
+                        })
+                    }
+                    13 => {
+                        let url = url.as_deref().unwrap_or(DEFAULT_DOH_WHOAMI_URL);
+                        let Ok(url) = url.parse::<Url>() else {
+                            return Err(de::Error::custom(format!("无效 DoH 服务地址：{}", url)));
+                        };
+                        Ok(IpSourceType::DohWhoami {
+                            url,
+                            family: family.unwrap_or_default(),
+                        })
+                    }
                     _ => Err(de::Error::custom(format!(
                         "不支持的 IP 来源方式：{}",
                         r#type
                     ))),
+                }?;
+
+                // User-Agent 及访问代理仅用于基于 HTTP 的来源
+                let http = matches!(
+                    r#type,
+                    1 | 3 | 4 | 5 | 9 | 10 | 11 | 12 | 13 | 15 | 16 | 17 | 18
+                );
+                for (name, specified) in [
+                    ("user_agent", user_agent.is_some()),
+                    ("proxy", proxy.is_some()),
+                ] {
+                    if specified && !http {
+                        return Err(de::Error::custom(format!(
+                            "IP 来源方式 {} 不通过 HTTP 获取，不支持 {}",
+                            r#type, name
+                        )));
+                    }
+                }
+                let ip_source_type = match user_agent {
+                    Some(user_agent) => IpSourceType::WithUserAgent {
+                        source: Box::new(ip_source_type),
+                        user_agent,
+                    },
+                    None => ip_source_type,
+                };
+                let ip_source_type = match proxy {
+                    Some(proxy) => IpSourceType::WithProxy {
+                        source: Box::new(ip_source_type),
+                        proxy,
+                    },
+                    None => ip_source_type,
+                };
+
+                // 自定义命令的超时时间为命令最长执行时间，已在上方处理
+                match timeout {
+                    Some(0) => Err(de::Error::custom(format!(
+                        "IP 来源方式 {} 的超时时间必须大于 0",
+                        r#type
+                    ))),
+                    Some(timeout) if r#type != 8 => Ok(IpSourceType::WithTimeout {
+                        source: Box::new(ip_source_type),
+                        timeout: Duration::from_secs(timeout),
+                    }),
+                    _ => Ok(ip_source_type),
                 }
             }
         }
@@ -263,32 +2161,265 @@ impl<'de> Deserialize<'de> for IpSourceType {
     }
 }
 
-/// Cloudflare 账号数据
-#[derive(serde::Deserialize, Debug, Clone)]
-pub struct Account {
-    /// Cloudflare 账号 API token
-    token: String,
-    /// Cloudflare 中需要刷新的域名列表
-    domains: Vec<Domain>,
+/// 解析 IP 地址来源的自定义请求头，请求头的值均视为敏感信息
+fn parse_headers(headers: HashMap<String, String>) -> Result<HeaderMap, String> {
+    headers
+        .into_iter()
+        .map(|(name, value)| {
+            let name = name
+                .parse::<HeaderName>()
+                .map_err(|_| format!("无效请求头名称：{}", name))?;
+            let mut value =
+                HeaderValue::from_str(&value).map_err(|_| format!("请求头 {} 的值无效", name))?;
+            value.set_sensitive(true);
+            Ok((name, value))
+        })
+        .collect()
 }
 
-impl Account {
-    /// 获取 Cloudflare 账号 token
-    pub fn token(&self) -> &str {
-        self.token.as_ref()
-    }
+/// IP 地址来源方式的数值或名称
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum SourceTypeKey {
+    Code(i64),
+    Name(String),
+}
 
-    /// 获取 Cloudflare 中需要刷新的域名列表
-    pub fn domains(&self) -> &[Domain] {
+/// IP 地址来源的 `family` 字段，`any` 仅独立服务器可用
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FamilyKey {
+    V4,
+    V6,
+    Any,
+}
+
+/// 将 IP 地址来源方式名称转换为对应数值
+fn source_type_code(name: &str) -> Option<i64> {
+    match name {
+        "standalone" => Some(1),
+        "local_ipv6" => Some(2),
+        "ipify" => Some(3),
+        "icanhazip" => Some(4),
+        "cf_trace" => Some(5),
+        "dns" => Some(6),
+        "stun" => Some(7),
+        "command" => Some(8),
+        "http_json" => Some(9),
+        "http_regex" => Some(10),
+        "chain" => Some(11),
+        "consensus" => Some(12),
+        "doh_whoami" => Some(13),
+        "wan_ipv4" => Some(14),
+        "prefix_compose" => Some(15),
+        "routeros" => Some(16),
+        "ubus" => Some(17),
+        "fritzbox" => Some(18),
+        _ => None,
+    }
+}
+
+/// HTTP 客户端配置
+///
+/// 进程绝大部分时间处于休眠，空闲连接池及其缓冲区在内存较小的设备上占比可观。
+/// 缩短空闲连接过期时间可减少内存占用，代价是每次请求需重新握手。
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct Http {
+    /// 空闲连接过期时间，单位秒。默认为 90 秒
+    pool_idle_timeout: Option<u64>,
+    /// 每个主机保留的空闲连接数量上限。默认不限制
+    pool_max_idle_per_host: Option<usize>,
+    /// 是否启用低内存模式，空闲连接 5 秒后过期且每个主机仅保留一个空闲连接。默认为 `false`
+    ///
+    /// 单独配置的 `pool_idle_timeout` 及 `pool_max_idle_per_host` 优先于低内存模式。
+    low_memory: Option<bool>,
+}
+
+impl Http {
+    /// 获取连接池参数
+    pub fn pool_options(&self) -> PoolOptions {
+        let mut pool = PoolOptions::default();
+        if self.low_memory.unwrap_or(false) {
+            pool.idle_timeout = Some(Duration::from_secs(LOW_MEMORY_POOL_IDLE_TIMEOUT_SECONDS));
+            pool.max_idle_per_host = LOW_MEMORY_POOL_MAX_IDLE_PER_HOST;
+        }
+        if let Some(idle_timeout) = self.pool_idle_timeout {
+            pool.idle_timeout = Some(Duration::from_secs(idle_timeout));
+        }
+        if let Some(max_idle_per_host) = self.pool_max_idle_per_host {
+            pool.max_idle_per_host = max_idle_per_host;
+        }
+        pool
+    }
+}
+
+/// 网络连通性检查配置
+///
+/// 仅在系统唤醒等事件触发的更新前检查，检查地址返回的状态码及响应体与预期不一致时跳过本次更新。
+#[derive(serde::Deserialize, Debug)]
+pub struct ConnectivityCheck {
+    /// 检查地址。默认为 `http://cp.cloudflare.com/generate_204`
+    url: Option<String>,
+    /// 预期的 HTTP 状态码。默认为 204
+    status: Option<u16>,
+    /// 预期的响应体。默认为空
+    body: Option<String>,
+    /// 检查结果缓存时间，单位秒。默认为 30 秒
+    cache: Option<u64>,
+}
+
+/// 自动 TTL 配置
+#[derive(serde::Deserialize, Debug)]
+pub struct AutoTtlConfig {
+    /// IP 地址频繁变化时使用的 TTL，单位秒。默认为 60 秒
+    low: Option<usize>,
+    /// IP 地址稳定时使用的 TTL，单位秒。默认为 3600 秒
+    high: Option<usize>,
+    /// IP 地址保持不变多久后视为稳定，单位秒。默认为 7 天
+    stable_after: Option<u64>,
+}
+
+impl AutoTtlConfig {
+    /// 校验域名 `nickname` 的自动 TTL 配置
+    fn to_auto_ttl(&self, nickname: &str) -> Result<AutoTtl, Error> {
+        let low = self.low.unwrap_or(DEFAULT_LOW_TTL);
+        let high = self.high.unwrap_or(DEFAULT_HIGH_TTL);
+        if low < MIN_TTL || high > MAX_TTL || low >= high {
+            return Err(Error::config(format!(
+                "域名 {} 的 auto_ttl 配置无效：要求 {} <= low < high <= {}，当前 low 为 {}，high 为 {}",
+                nickname, MIN_TTL, MAX_TTL, low, high
+            )));
+        }
+
+        let stable_after = self.stable_after.unwrap_or(DEFAULT_STABLE_AFTER_SECONDS);
+        Ok(AutoTtl::new(
+            low,
+            high,
+            chrono::Duration::seconds(stable_after.min(i64::MAX as u64) as i64),
+        ))
+    }
+}
+
+/// 初始化失败后的指数退避配置
+#[derive(serde::Deserialize, Debug, Default)]
+pub struct BackoffConfig {
+    /// 是否启用指数退避，为 `false` 时固定按重试间隔重试。默认为 `true`
+    enabled: Option<bool>,
+    /// 首次重试的等待时间，单位秒。默认为 5 秒
+    base: Option<u64>,
+    /// 是否加入随机抖动。默认为 `true`
+    jitter: Option<bool>,
+}
+
+impl BackoffConfig {
+    /// 转换为更新器使用的指数退避，未启用时返回 `None`
+    fn to_backoff(&self) -> Result<Option<Backoff>, Error> {
+        if !self.enabled.unwrap_or(true) {
+            return Ok(None);
+        }
+        let base = self.base.unwrap_or(DEFAULT_BACKOFF_BASE_SECONDS);
+        if base == 0 {
+            return Err(Error::config("backoff 的 base 必须大于 0"));
+        }
+        Ok(Some(Backoff {
+            base,
+            jitter: self.jitter.unwrap_or(true),
+        }))
+    }
+}
+
+/// 记录不存在时创建记录的配置
+#[derive(serde::Deserialize, Debug)]
+pub struct CreateIfMissing {
+    /// 记录名称，通过 `id` 指定记录时必填，通过 `name` 指定记录时使用该名称
+    name: Option<Arc<str>>,
+    /// 记录类型，通过 `id` 指定单条记录时可选。默认根据获取的 IP 地址决定
+    r#type: Option<RecordType>,
+    /// 记录 TTL，单位秒，为 `1` 时由 Cloudflare 自动决定。默认为 `1`
+    ttl: Option<Ttl>,
+    /// 是否启用 Cloudflare 代理。默认为 `false`
+    proxied: Option<bool>,
+}
+
+impl CreateIfMissing {
+    /// 校验域名 `nickname` 的创建配置，`name` 及 `r#type` 为域名配置中已确定的记录名称及类型
+    fn to_template(
+        &self,
+        nickname: &str,
+        name: Option<&Arc<str>>,
+        r#type: Option<RecordType>,
+    ) -> Result<RecordTemplate, Error> {
+        let name = match (name, &self.name) {
+            (Some(_), Some(_)) => {
+                return Err(Error::config(format!(
+                    "域名 {} 的 create_if_missing.name 仅在通过 id 指定记录时可用",
+                    nickname
+                )))
+            }
+            (Some(name), None) | (None, Some(name)) => name.clone(),
+            (None, None) => {
+                return Err(Error::config(format!(
+                    "域名 {} 通过 id 指定记录时 create_if_missing 必须指定 name",
+                    nickname
+                )))
+            }
+        };
+        let r#type = match (r#type, self.r#type) {
+            (Some(r#type), Some(other)) if r#type != other => {
+                return Err(Error::config(format!(
+                    "域名 {} 的 create_if_missing.type {} 与记录类型 {} 不一致",
+                    nickname,
+                    other.as_str(),
+                    r#type.as_str()
+                )))
+            }
+            (r#type, other) => r#type.or(other),
+        };
+        Ok(RecordTemplate {
+            name,
+            r#type,
+            ttl: self.ttl.map_or(AUTO_TTL, |ttl| ttl.seconds()),
+            proxied: self.proxied.unwrap_or(false),
+        })
+    }
+}
+
+/// Cloudflare 账号数据
+#[derive(serde::Deserialize, Debug)]
+pub struct Account {
+    /// Cloudflare 账号 API token，由该账号下所有域名的更新器共享
+    token: Secret,
+    /// Cloudflare 中需要刷新的域名列表
+    domains: Vec<Domain>,
+    /// 域名未指定 `zone_id` 及 `zone_name` 时使用的 zone 名称，可选
+    zone_name: Option<Arc<str>>,
+}
+
+impl Account {
+    /// 获取 Cloudflare 账号 token
+    pub fn token(&self) -> &Secret {
+        &self.token
+    }
+
+    /// 获取 Cloudflare 中需要刷新的域名列表
+    pub fn domains(&self) -> &[Domain] {
         self.domains.as_ref()
     }
 }
 
 /// Cloudflare 域名数据
-#[derive(serde::Deserialize, Debug, Clone)]
+#[derive(serde::Deserialize, Debug)]
 pub struct Domain {
     /// 绑定的本地 IP 地址，可选
     bind_address: Option<IpAddr>,
+    /// 执行网络操作的网络命名空间名称，对应 `/var/run/netns/<netns>`，可选（仅限 linux 系统）
+    ///
+    /// 该域名的 IP 来源及 Cloudflare API 请求均在该命名空间中进行。
+    netns: Option<String>,
+    /// 绑定的网卡名称（如 VRF 设备），可选
+    ///
+    /// 当前使用的 HTTP 实现无法将连接绑定至网卡，配置该项时报错。
+    bind_interface: Option<String>,
     /// 刷新间隔，单位秒。
     ///
     /// 若未配置该项，则会使用 [`Configuration`] 中 `fresh_interval` 属性。
@@ -297,6 +2428,18 @@ pub struct Domain {
     ///
     /// 若未配置该项，则会使用 [`Configuration`] 中 `retry_interval` 属性。
     retry_interval: Option<u64>,
+    /// 连续失败次数上限，达到后永久停止更新，为 `0` 时不限制。
+    ///
+    /// 若未配置该项，则会使用 [`Configuration`] 中 `max_consecutive_failures` 属性。
+    max_consecutive_failures: Option<u32>,
+    /// 强制更新周期，为 `0` 时不强制更新。
+    ///
+    /// 若未配置该项，则会使用 [`Configuration`] 中 `force_update_every` 属性。
+    force_update_every: Option<Period>,
+    /// 单次更新的最长时间，单位秒，超时后放弃本次更新并记为失败。
+    ///
+    /// 默认为刷新间隔与 120 秒中的较小值。
+    update_deadline: Option<u64>,
     /// 当前机器运行环境的 IP 地址来源。
     ///
     /// - `0`：IpIp(废弃，已移除)
@@ -305,12 +2448,95 @@ pub struct Domain {
     ///
     /// 若未配置该项，则会使用 [`Configuration`] 中 `ip_source` 属性。
     ip_source: Option<IpSourceType>,
+    /// A 记录的 IP 地址来源，仅在指定 `id_v6` 或启用 `dual_stack` 时可用。
+    ///
+    /// 若未配置该项，则会使用 `ip_source` 属性。
+    ip_source_v4: Option<IpSourceType>,
+    /// AAAA 记录的 IP 地址来源，仅在指定 `id_v6` 或启用 `dual_stack` 时可用。
+    ///
+    /// 若未配置该项，则会使用 `ip_source` 属性。
+    ip_source_v6: Option<IpSourceType>,
+    /// 根据 IP 地址变化频率自动调整记录 TTL。
+    ///
+    /// 若未配置该项，则会使用 [`Configuration`] 中 `auto_ttl` 属性。
+    auto_ttl: Option<AutoTtlConfig>,
+    /// 记录的固定 TTL，单位秒，与记录当前 TTL 不一致时即使 IP 地址未变化也会更新记录。
+    ///
+    /// 若未配置该项，则会使用 [`Configuration`] 中 `ttl` 属性。
+    ttl: Option<Ttl>,
+    /// 定期重新获取记录详情的间隔，单位秒，为 `0` 时不定期重新获取。
+    ///
+    /// 若未配置该项，则会使用 [`Configuration`] 中 `details_refresh_interval` 属性。
+    details_refresh_interval: Option<u64>,
+    /// 固定的 Cloudflare 代理状态，与记录当前状态不一致时即使 IP 地址未变化也会更新记录。
+    ///
+    /// 默认保持记录原有的代理状态。
+    proxied: Option<bool>,
+    /// 是否允许发布私有地址、环回地址、链路本地地址、唯一本地地址及运营商级 NAT 地址。
+    ///
+    /// 默认为 `false`，获取到此类地址时本次更新失败，用于内外网使用不同解析的场景。
+    allow_private: Option<bool>,
     /// 域名昵称，用于输出日志
-    nickname: String,
-    /// 域名 Cloudflare id
-    id: String,
-    /// 域名 Cloudflare zone id
-    zone_id: String,
+    nickname: Arc<str>,
+    /// 域名 Cloudflare id，同时指定 `id_v6` 时为 A 记录的 id
+    ///
+    /// 与 `name` 二选一。指定为列表时由同一更新器管理同一 zone 下的多条记录，
+    /// 每次更新仅获取一次 IP 地址。
+    id: Option<RecordIds>,
+    /// 记录名称，如 `home.example.com`，需同时指定 `type`
+    ///
+    /// 初始化时通过名称及类型查询记录 id，与 `id` 二选一。
+    name: Option<Arc<str>>,
+    /// 通过 `name` 查询的记录类型
+    r#type: Option<RecordType>,
+    /// 记录不存在时以获取的 IP 地址创建记录，可选。默认不创建
+    create_if_missing: Option<CreateIfMissing>,
+    /// 同一域名 AAAA 记录的 Cloudflare id，可选
+    ///
+    /// 指定后由同一配置项同时管理 A 及 AAAA 记录，两条记录分别使用独立的更新器。
+    id_v6: Option<Arc<str>>,
+    /// 是否同时管理 `name` 对应的 A 及 AAAA 记录，可选。默认为 `false`
+    ///
+    /// 启用后无需指定 `type`，两条记录与指定 `id_v6` 时相同，分别使用独立的更新器，
+    /// 一条记录更新失败不影响另一条记录。
+    dual_stack: Option<bool>,
+    /// 域名 Cloudflare zone id，与 `zone_name` 二选一
+    zone_id: Option<Arc<str>>,
+    /// 域名所在 zone 的名称，如 `example.com`
+    ///
+    /// 初始化时通过名称查询 zone id，与 `zone_id` 二选一。均未指定时使用账号的 `zone_name`。
+    zone_name: Option<Arc<str>>,
+}
+
+/// 域名配置项的 `id`，可为单个 id 或 id 列表
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum RecordIds {
+    One(Arc<str>),
+    Many(Vec<Arc<str>>),
+}
+
+impl RecordIds {
+    fn as_slice(&self) -> &[Arc<str>] {
+        match self {
+            RecordIds::One(id) => std::slice::from_ref(id),
+            RecordIds::Many(ids) => ids,
+        }
+    }
+}
+
+/// 域名配置项管理的单条 DNS 记录
+struct DomainRecord<'a> {
+    /// 更新器昵称，同时管理 A 及 AAAA 记录时附加记录类型，如 `home/AAAA`
+    nickname: Arc<str>,
+    /// 记录 Cloudflare id 或名称
+    record: RecordRef,
+    /// 记录的 IP 来源方式，为 `None` 时使用全局 IP 来源方式
+    ip_source: Option<&'a IpSourceType>,
+    /// 记录不存在时创建记录使用的参数，为 `None` 时不创建
+    template: Option<RecordTemplate>,
+    /// 由同一更新器一同更新的其他记录
+    extra_records: Vec<RecordRef>,
 }
 
 impl Domain {
@@ -319,24 +2545,25 @@ impl Domain {
         self.bind_address
     }
 
+    /// 校验绑定网卡配置，当前不支持，配置时报错而非忽略
+    fn validate_bind_interface(&self) -> Result<(), Error> {
+        if let Some(interface) = &self.bind_interface {
+            return Err(Error::config(format!(
+                "域名 {} 配置了 bind_interface {}，但当前使用的 HTTP 实现无法将连接绑定至网卡（SO_BINDTODEVICE），暂不支持该选项",
+                self.nickname, interface
+            )));
+        }
+        Ok(())
+    }
+
     /// 获取刷新间隔，单位秒。
     pub fn fresh_interval(&self) -> Option<u64> {
         self.fresh_interval
     }
 
     /// 获取域名昵称，用于输出日志
-    pub fn nickname(&self) -> &str {
-        self.nickname.as_ref()
-    }
-
-    /// 获取域名 Cloudflare id
-    pub fn id(&self) -> &str {
-        self.id.as_ref()
-    }
-
-    /// 获取域名 Cloudflare zone id
-    pub fn zone_id(&self) -> &str {
-        self.zone_id.as_ref()
+    pub fn nickname(&self) -> &Arc<str> {
+        &self.nickname
     }
 
     /// 获取出现错误时重试间隔，单位秒
@@ -344,80 +2571,143 @@ impl Domain {
         self.retry_interval
     }
 
+    /// 获取单次更新的最长时间，单位秒
+    pub fn update_deadline(&self) -> Option<u64> {
+        self.update_deadline
+    }
+
     /// 获取 IP 来源方式
     pub fn ip_source_type(&self) -> Option<&IpSourceType> {
         self.ip_source.as_ref()
     }
-}
-
-/// Cloudflare 访问代理
-// #[derive(serde::Deserialize, Debug, Clone)]
-// pub struct Proxy {
-//     url: String,
-//     no_proxies: Vec<String>,
-//     username: Option<String>,
-//     password: Option<String>,
-// }
-#[derive(Debug, Clone)]
-pub struct Proxy(reqwest::Proxy);
-
-impl<'de> Deserialize<'de> for Proxy {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        struct ProxyVisitor;
-        impl<'de> Visitor<'de> for ProxyVisitor {
-            type Value = Proxy;
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("Cloudflare 访问代理配置")
+    /// 该配置项管理的 DNS 记录
+    ///
+    /// 指定 `id_v6` 或启用 `dual_stack` 时依次为 A 及 AAAA 记录，否则仅有 `id` 对应的记录。
+    /// `id` 为列表时首个 id 以外的记录均置于 `extra_records`。
+    fn records(&self) -> Result<SmallVec<[DomainRecord<'_>; 2]>, Error> {
+        let mut records = SmallVec::new();
+        let dual_stack = self.dual_stack.unwrap_or(false);
+        let ids = self.id.as_ref().map(RecordIds::as_slice);
+        let extra_records = match ids {
+            Some([]) => {
+                return Err(Error::config(format!(
+                    "域名 {} 的 id 列表不能为空",
+                    self.nickname
+                )))
             }
-
-            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
-            where
-                A: de::MapAccess<'de>,
-            {
-                let mut proxy_url = None;
-                let mut basic_auth_username = None;
-                let mut basic_auth_password = None;
-                while let Some(key) = map.next_key::<String>()? {
-                    match key.as_str() {
-                        "url" => proxy_url = Some(map.next_value::<String>()?),
-                        "username" => basic_auth_username = Some(map.next_value::<String>()?),
-                        "password" => basic_auth_password = Some(map.next_value::<String>()?),
-                        _ => {}
-                    }
+            Some([_, extra @ ..]) if !extra.is_empty() => {
+                if self.id_v6.is_some() || dual_stack || self.create_if_missing.is_some() {
+                    return Err(Error::config(format!(
+                        "域名 {} 的 id 为列表时不能指定 id_v6、dual_stack 或 create_if_missing",
+                        self.nickname
+                    )));
                 }
-
-                let Some(proxy_url) = proxy_url else {
-                    return Err(serde::de::Error::missing_field("proxy.url"));
-                };
-                let Ok(mut proxy) = reqwest::Proxy::https(proxy_url.as_str()) else {
-                    return Err(serde::de::Error::invalid_value(
-                        serde::de::Unexpected::Str(proxy_url.as_str()),
-                        &"http, https or socks proxy url",
-                    ));
-                };
-
-                match (basic_auth_username, basic_auth_password) {
-                    (None, None) => {}
-                    (None, Some(_)) => {
-                        return Err(serde::de::Error::missing_field("proxy.username"))
-                    }
-                    (Some(_), None) => {
-                        return Err(serde::de::Error::missing_field("proxy.password"))
-                    }
-                    (Some(username), Some(password)) => {
-                        proxy = proxy.basic_auth(username.as_str(), password.as_str());
-                    }
-                }
-
-                Ok(Proxy(proxy))
+                extra.iter().cloned().map(RecordRef::Id).collect()
             }
-        }
+            _ => Vec::new(),
+        };
+        let record = match (ids.and_then(<[_]>::first), &self.name, self.r#type) {
+            (Some(id), None, None) => RecordRef::Id(id.clone()),
+            (None, Some(name), None) if dual_stack && self.id_v6.is_none() => RecordRef::Name {
+                name: name.clone(),
+                r#type: RecordType::A,
+            },
+            (None, Some(_), Some(_)) if dual_stack => {
+                return Err(Error::config(format!(
+                    "域名 {} 启用 dual_stack 时同时管理 A 及 AAAA 记录，不能指定 type",
+                    self.nickname
+                )))
+            }
+            (None, Some(_), _) if self.id_v6.is_some() => {
+                return Err(Error::config(format!(
+                    "域名 {} 的 id_v6 仅在指定 id 时可用，通过 name 管理 A 及 AAAA 记录时请启用 dual_stack",
+                    self.nickname
+                )))
+            }
+            (None, Some(name), Some(r#type)) => RecordRef::Name {
+                name: name.clone(),
+                r#type,
+            },
+            (None, Some(_), None) => {
+                return Err(Error::config(format!(
+                    "域名 {} 通过 name 指定记录时必须指定 type（A 或 AAAA）或启用 dual_stack",
+                    self.nickname
+                )))
+            }
+            (Some(_), Some(_), _) => {
+                return Err(Error::config(format!(
+                    "域名 {} 的 id 与 name 不能同时指定",
+                    self.nickname
+                )))
+            }
+            (Some(_), None, Some(_)) | (None, None, Some(_)) => {
+                return Err(Error::config(format!(
+                    "域名 {} 的 type 仅在指定 name 时可用",
+                    self.nickname
+                )))
+            }
+            (None, None, None) => {
+                return Err(Error::config(format!(
+                    "域名 {} 必须指定 id 或 name",
+                    self.nickname
+                )))
+            }
+        };
+        let record_v6 = match (&self.id_v6, &self.name) {
+            (Some(id_v6), _) => Some(RecordRef::Id(id_v6.clone())),
+            (None, Some(name)) if dual_stack => Some(RecordRef::Name {
+                name: name.clone(),
+                r#type: RecordType::Aaaa,
+            }),
+            (None, _) if dual_stack => {
+                return Err(Error::config(format!(
+                    "域名 {} 启用 dual_stack 时需通过 name 指定记录，或通过 id 及 id_v6 分别指定",
+                    self.nickname
+                )))
+            }
+            (None, _) => None,
+        };
+        let Some(record_v6) = record_v6 else {
+            if self.ip_source_v4.is_some() || self.ip_source_v6.is_some() {
+                return Err(Error::config(format!(
+                    "域名 {} 的 ip_source_v4 及 ip_source_v6 仅在指定 id_v6 或启用 dual_stack 时可用",
+                    self.nickname
+                )));
+            }
+            records.push(DomainRecord {
+                nickname: self.nickname.clone(),
+                record,
+                ip_source: self.ip_source.as_ref(),
+                template: self.template(self.r#type)?,
+                extra_records,
+            });
+            return Ok(records);
+        };
 
-        deserializer.deserialize_map(ProxyVisitor)
+        records.push(DomainRecord {
+            nickname: Arc::from(format!("{}/A", self.nickname)),
+            record,
+            ip_source: self.ip_source_v4.as_ref().or(self.ip_source.as_ref()),
+            template: self.template(Some(RecordType::A))?,
+            extra_records: Vec::new(),
+        });
+        records.push(DomainRecord {
+            nickname: Arc::from(format!("{}/AAAA", self.nickname)),
+            record: record_v6,
+            ip_source: self.ip_source_v6.as_ref().or(self.ip_source.as_ref()),
+            template: self.template(Some(RecordType::Aaaa))?,
+            extra_records: Vec::new(),
+        });
+        Ok(records)
+    }
+
+    /// 类型为 `r#type` 的记录不存在时创建记录使用的参数，未启用 `create_if_missing` 时返回 `None`
+    fn template(&self, r#type: Option<RecordType>) -> Result<Option<RecordTemplate>, Error> {
+        self.create_if_missing
+            .as_ref()
+            .map(|create| create.to_template(&self.nickname, self.name.as_ref(), r#type))
+            .transpose()
     }
 }
 
@@ -445,18 +2735,44 @@ impl<'de> Deserialize<'de> for Proxy {
 //     }
 // }
 
-const DEFAULT_CONFIGURATION_NAME: &'static str = "config.json5";
+const DEFAULT_CONFIGURATION_NAME: &str = "config.json5";
+
+/// 获取配置文件路径，未指定时为程序所在文件夹中的 `config.json5`
+pub fn configuration_path(matches: &clap::ArgMatches) -> Result<PathBuf, Error> {
+    // 配置文件路径参数为全局参数，可能出现在子命令之后
+    let path = matches.value_of("config").or_else(|| {
+        matches
+            .subcommand()
+            .1
+            .and_then(|matches| matches.value_of("config"))
+    });
+    match path {
+        Some(value) => Ok(PathBuf::from(value)),
+        None => Ok(env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .ok_or(Error::config("无法获取当前程序所在文件夹"))?
+            .join(DEFAULT_CONFIGURATION_NAME)),
+    }
+}
 
 /// 获取配置数据
-pub fn configuration() -> Result<Configuration, Error> {
-    let matches = args::arguments();
-    match matches.value_of("config") {
-        Some(value) => read_configuration(value),
-        None => read_configuration(
-            env::current_exe()
-                .or(Err(Error::new_str("无法获取当前程序所在文件夹")))?
-                .join(DEFAULT_CONFIGURATION_NAME),
-        ),
+pub fn configuration(matches: &clap::ArgMatches) -> Result<Arc<Configuration>, Error> {
+    let mut configuration = read_configuration(configuration_path(matches)?)?;
+    if matches.is_present("dry-run") {
+        configuration.dry_run = Some(true);
+    }
+    Ok(Arc::new(configuration))
+}
+
+/// 根据域名数量及 CPU 核心数计算默认的运行时工作线程数量
+fn default_runtime_threads(domains: usize, cpus: usize) -> usize {
+    if domains <= 1 {
+        1
+    } else {
+        (domains / DOMAINS_PER_RUNTIME_THREAD)
+            .max(MIN_RUNTIME_THREADS)
+            .min(cpus.max(1))
     }
 }
 
@@ -466,7 +2782,1304 @@ where
     P: AsRef<Path>,
 {
     let text = fs::read_to_string(path.as_ref())
-        .or_else(|err| Err(Error::read_configuration_failure(err, path.as_ref())))?;
-    Ok(json5::from_str(text.as_str())
-        .or_else(|err| Err(Error::read_configuration_failure(err, path.as_ref())))?)
+        .map_err(|err| Error::read_configuration_failure(err, path.as_ref()))?;
+    json5::from_str(text.as_str())
+        .map_err(|err| Error::read_configuration_failure(err, path.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+
+    use super::{
+        default_runtime_threads, validate_interval, validate_network, AutoTtlConfig,
+        ClientRegistry, Configuration, Domain, HeaderMap, Http, IpFamily, IpSourceType, Method,
+        NetworkMode, Period, PoolOptions, ProxySetting, RecordRef, RecordTemplate, RecordType,
+        RouterOsAuth, SourceHttpOptions, TlsOptions, ZoneRef, MAX_INTERVAL_SECONDS,
+        MIN_FRESH_INTERVAL_SECONDS,
+    };
+    use crate::libs::http::tests::connections;
+
+    #[test]
+    fn test_default_runtime_threads() {
+        assert_eq!(default_runtime_threads(1, 32), 1);
+        assert_eq!(default_runtime_threads(3, 32), 2);
+        assert_eq!(default_runtime_threads(40, 32), 10);
+        assert_eq!(default_runtime_threads(400, 32), 32);
+        assert_eq!(default_runtime_threads(40, 1), 1);
+    }
+
+    #[test]
+    fn test_shared_token() {
+        let configuration: Configuration = json5::from_str(
+            r#"{
+                accounts: [{
+                    token: "secret-token",
+                    domains: [
+                        { nickname: "home", id: "1", zone_id: "z" },
+                        { nickname: "office", id: "2", zone_id: "z" },
+                    ],
+                }],
+            }"#,
+        )
+        .unwrap();
+        let client = reqwest::Client::new();
+        let account = &configuration.accounts()[0];
+        let apis = account
+            .domains()
+            .iter()
+            .map(|domain| {
+                let record = &domain.records().unwrap()[0];
+                let zone =
+                    Configuration::resolve_zone(&mut HashMap::new(), account, domain).unwrap();
+                Configuration::create_cf_api(account, zone, record, &client, &None, &None)
+            })
+            .collect::<Vec<_>>();
+
+        // 同一账号下的域名共享同一份 token 存储，且调试输出时脱敏
+        assert!(apis[0].token().ptr_eq(apis[1].token()));
+        assert_eq!(apis[0].token().expose(), "secret-token");
+        assert!(!format!("{:?}", apis[0]).contains("secret-token"));
+    }
+
+    #[test]
+    fn test_resolve_zone() {
+        let configuration: Configuration = json5::from_str(
+            r#"{
+                accounts: [
+                    {
+                        token: "a",
+                        zone_name: "example.com",
+                        domains: [
+                            { nickname: "home", id: "1" },
+                            { nickname: "office", id: "2", zone_name: "example.com" },
+                            { nickname: "shop", id: "3", zone_name: "example.net" },
+                            { nickname: "lab", id: "4", zone_id: "z" },
+                        ],
+                    },
+                    {
+                        token: "b",
+                        domains: [
+                            { nickname: "other", id: "5", zone_name: "example.com" },
+                            { nickname: "none", id: "6" },
+                            { nickname: "both", id: "7", zone_id: "z", zone_name: "example.com" },
+                        ],
+                    },
+                ],
+            }"#,
+        )
+        .unwrap();
+        let mut zones = HashMap::new();
+        let [first, second] = configuration.accounts() else {
+            panic!("unexpected accounts");
+        };
+        let zone = |zones: &mut _, account, index: usize| {
+            Configuration::resolve_zone(zones, account, &account.domains()[index])
+        };
+
+        let home = zone(&mut zones, first, 0).unwrap();
+        let office = zone(&mut zones, first, 1).unwrap();
+        let shop = zone(&mut zones, first, 2).unwrap();
+        let other = zone(&mut zones, second, 0).unwrap();
+        let (ZoneRef::Name(home), ZoneRef::Name(office), ZoneRef::Name(shop), ZoneRef::Name(other)) =
+            (home, office, shop, other)
+        else {
+            panic!("unexpected zones");
+        };
+        // 同一账号下相同名称的 zone 共享查询，不同账号使用不同 token 分别查询
+        assert!(Arc::ptr_eq(&home, &office));
+        assert!(!Arc::ptr_eq(&home, &shop));
+        assert!(!Arc::ptr_eq(&home, &other));
+        assert_eq!(zones.len(), 3);
+
+        assert!(matches!(
+            zone(&mut zones, first, 3).unwrap(),
+            ZoneRef::Id(id) if &*id == "z"
+        ));
+        assert!(zone(&mut zones, second, 1).is_err());
+        assert!(zone(&mut zones, second, 2).is_err());
+    }
+
+    #[test]
+    fn test_domain_records() {
+        let domain = |text: &str| json5::from_str::<Domain>(text).unwrap();
+
+        let single = domain("{ nickname: 'home', id: '1', zone_id: 'z', ip_source: 'ipify' }");
+        let records = single.records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(&*records[0].nickname, "home");
+        assert!(matches!(records[0].ip_source, Some(IpSourceType::Ipify(_))));
+
+        let dual = domain(
+            "{ nickname: 'home', id: '1', id_v6: '2', zone_id: 'z', ip_source: 'cf_trace', ip_source_v6: { type: 'ipify', family: 'v6' } }",
+        );
+        let records = dual.records().unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (&*record.nickname, &record.record))
+                .collect::<Vec<_>>(),
+            [
+                ("home/A", &RecordRef::Id("1".into())),
+                ("home/AAAA", &RecordRef::Id("2".into()))
+            ]
+        );
+        // 未单独指定时使用 ip_source
+        assert!(matches!(
+            records[0].ip_source,
+            Some(IpSourceType::CfTrace(_))
+        ));
+        assert!(matches!(
+            records[1].ip_source,
+            Some(IpSourceType::Ipify(IpFamily::V6))
+        ));
+
+        let without_id_v6 =
+            domain("{ nickname: 'home', id: '1', zone_id: 'z', ip_source_v6: 'ipify' }");
+        assert!(without_id_v6.records().is_err());
+
+        // 启用 dual_stack 时通过名称分别查询 A 及 AAAA 记录
+        let dual_stack = domain(
+            "{ nickname: 'home', name: 'home.example.com', dual_stack: true, zone_id: 'z', ip_source_v4: 'cf_trace', ip_source_v6: { type: 'ipify', family: 'v6' } }",
+        );
+        let records = dual_stack.records().unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (&*record.nickname, &record.record))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "home/A",
+                    &RecordRef::Name {
+                        name: "home.example.com".into(),
+                        r#type: RecordType::A
+                    }
+                ),
+                (
+                    "home/AAAA",
+                    &RecordRef::Name {
+                        name: "home.example.com".into(),
+                        r#type: RecordType::Aaaa
+                    }
+                )
+            ]
+        );
+        assert!(matches!(
+            records[0].ip_source,
+            Some(IpSourceType::CfTrace(_))
+        ));
+        // 通过 id 及 id_v6 指定时 dual_stack 不改变结果
+        let dual_stack =
+            domain("{ nickname: 'home', id: '1', id_v6: '2', dual_stack: true, zone_id: 'z' }");
+        assert_eq!(dual_stack.records().unwrap().len(), 2);
+
+        // 通过名称及类型指定记录
+        let by_name =
+            domain("{ nickname: 'home', name: 'home.example.com', type: 'AAAA', zone_id: 'z' }");
+        assert_eq!(
+            by_name.records().unwrap()[0].record,
+            RecordRef::Name {
+                name: "home.example.com".into(),
+                r#type: RecordType::Aaaa
+            }
+        );
+        for text in [
+            "{ nickname: 'home', name: 'home.example.com', zone_id: 'z' }",
+            "{ nickname: 'home', id: '1', name: 'home.example.com', type: 'A', zone_id: 'z' }",
+            "{ nickname: 'home', id: '1', type: 'A', zone_id: 'z' }",
+            "{ nickname: 'home', name: 'home.example.com', type: 'A', id_v6: '2', zone_id: 'z' }",
+            "{ nickname: 'home', name: 'home.example.com', type: 'A', dual_stack: true, zone_id: 'z' }",
+            "{ nickname: 'home', name: 'home.example.com', dual_stack: true, id_v6: '2', zone_id: 'z' }",
+            "{ nickname: 'home', id: '1', dual_stack: true, zone_id: 'z' }",
+            "{ nickname: 'home', zone_id: 'z' }",
+        ] {
+            assert!(domain(text).records().is_err(), "{}", text);
+        }
+        assert!(json5::from_str::<Domain>(
+            "{ nickname: 'home', name: 'home.example.com', type: 'CNAME', zone_id: 'z' }"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_create_if_missing() {
+        let template = |text: &str| {
+            json5::from_str::<Domain>(text)
+                .unwrap()
+                .records()
+                .map(|records| {
+                    records
+                        .into_iter()
+                        .map(|record| record.template)
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        assert_eq!(
+            template("{ nickname: 'home', id: '1', zone_id: 'z' }").unwrap(),
+            [None]
+        );
+        assert_eq!(
+            template(
+                "{ nickname: 'home', name: 'home.example.com', type: 'AAAA', zone_id: 'z', create_if_missing: {} }"
+            )
+            .unwrap(),
+            [Some(RecordTemplate {
+                name: "home.example.com".into(),
+                r#type: Some(RecordType::Aaaa),
+                ttl: 1,
+                proxied: false,
+            })]
+        );
+        // 同时管理 A 及 AAAA 记录时分别使用对应的记录类型
+        let dual = template(
+            "{ nickname: 'home', id: '1', id_v6: '2', zone_id: 'z', create_if_missing: { name: 'home.example.com', ttl: 300, proxied: true } }",
+        )
+        .unwrap();
+        assert_eq!(
+            dual.iter()
+                .map(|template| template.as_ref().unwrap().r#type)
+                .collect::<Vec<_>>(),
+            [Some(RecordType::A), Some(RecordType::Aaaa)]
+        );
+        assert_eq!(dual[0].as_ref().unwrap().ttl, 300);
+        assert!(dual[1].as_ref().unwrap().proxied);
+
+        for text in [
+            // 通过 id 指定记录时必须指定名称
+            "{ nickname: 'home', id: '1', zone_id: 'z', create_if_missing: {} }",
+            "{ nickname: 'home', name: 'home.example.com', type: 'A', zone_id: 'z', create_if_missing: { name: 'other.example.com' } }",
+            "{ nickname: 'home', name: 'home.example.com', type: 'A', zone_id: 'z', create_if_missing: { type: 'AAAA' } }",
+        ] {
+            assert!(template(text).is_err(), "{}", text);
+        }
+        // TTL 超出 Cloudflare 接受的范围时解析失败
+        assert!(json5::from_str::<Domain>(
+            "{ nickname: 'home', id: '1', zone_id: 'z', create_if_missing: { name: 'home.example.com', ttl: 30 } }"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_record_ids() {
+        let records = |text: &str| {
+            json5::from_str::<Domain>(text)
+                .unwrap()
+                .records()
+                .map(|records| {
+                    records
+                        .into_iter()
+                        .map(|record| (record.record, record.extra_records))
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        assert_eq!(
+            records("{ nickname: 'home', id: '1', zone_id: 'z' }").unwrap(),
+            [(RecordRef::Id("1".into()), vec![])]
+        );
+        // id 为列表时由同一更新器管理所有记录
+        assert_eq!(
+            records("{ nickname: 'home', id: ['1', '2', '3'], zone_id: 'z' }").unwrap(),
+            [(
+                RecordRef::Id("1".into()),
+                vec![RecordRef::Id("2".into()), RecordRef::Id("3".into())]
+            )]
+        );
+        assert_eq!(
+            records("{ nickname: 'home', id: ['1'], zone_id: 'z' }").unwrap(),
+            [(RecordRef::Id("1".into()), vec![])]
+        );
+
+        for text in [
+            "{ nickname: 'home', id: [], zone_id: 'z' }",
+            "{ nickname: 'home', id: ['1', '2'], id_v6: '3', zone_id: 'z' }",
+            "{ nickname: 'home', id: ['1', '2'], zone_id: 'z', create_if_missing: { name: 'home.example.com' } }",
+        ] {
+            assert!(records(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_auto_ttl_bounds() {
+        let auto_ttl = |text: &str| {
+            json5::from_str::<AutoTtlConfig>(text)
+                .unwrap()
+                .to_auto_ttl("home")
+        };
+
+        assert!(auto_ttl("{}").is_ok());
+        assert!(auto_ttl("{ low: 120, high: 7200 }").is_ok());
+        assert!(auto_ttl("{ low: 30 }").is_err());
+        assert!(auto_ttl("{ high: 100000 }").is_err());
+        assert!(auto_ttl("{ low: 3600, high: 3600 }").is_err());
+    }
+
+    #[test]
+    fn test_fixed_ttl() {
+        let configuration = |ttl: &str, domain: &str| {
+            json5::from_str::<Configuration>(&format!(
+                "{{ {} ip_source: 'ipify', accounts: [{{ token: 't', domains: [{{ nickname: 'home', id: '1', zone_id: 'z', {} }}] }}] }}",
+                ttl, domain
+            ))
+        };
+
+        assert!(configuration("ttl: 60,", "ttl: 1").is_ok());
+        assert!(configuration("ttl: 30,", "").is_err());
+        assert!(configuration("", "ttl: 100000").is_err());
+        assert!(configuration("ttl: 60,", "")
+            .unwrap()
+            .create_updaters()
+            .is_ok());
+        // 固定 TTL 与自动 TTL 不能同时使用
+        assert!(configuration("ttl: 60,", "auto_ttl: {}")
+            .unwrap()
+            .create_updaters()
+            .is_err());
+    }
+
+    #[test]
+    fn test_netns() {
+        let configuration = |domain: &str| {
+            json5::from_str::<Configuration>(&format!(
+                "{{ ip_source: 'ipify', accounts: [{{ token: 't', domains: [{{ nickname: 'home', id: '1', zone_id: 'z', {} }}] }}] }}",
+                domain
+            ))
+            .unwrap()
+            .create_updaters()
+        };
+
+        // 命名空间不存在时启动失败
+        let err = configuration("netns: 'ddns4cf-missing'").unwrap_err();
+        assert!(err.to_string().contains("ddns4cf-missing"), "{}", err);
+        assert!(configuration("netns: '../net'").is_err());
+
+        // 绑定网卡不受支持，配置时报错而非忽略
+        let err = configuration("bind_interface: 'vrf-wan'").unwrap_err();
+        assert!(err.to_string().contains("bind_interface"), "{}", err);
+    }
+
+    #[test]
+    fn test_period() {
+        let period = |value: &str| {
+            json5::from_str::<Period>(value).map(|period| period.duration().as_secs())
+        };
+
+        assert_eq!(period("90").unwrap(), 90);
+        assert_eq!(period("'90'").unwrap(), 90);
+        assert_eq!(period("'45s'").unwrap(), 45);
+        assert_eq!(period("'30m'").unwrap(), 30 * 60);
+        assert_eq!(period("'12h'").unwrap(), 12 * 60 * 60);
+        assert_eq!(period("' 7d '").unwrap(), 7 * 24 * 60 * 60);
+        assert_eq!(period("'0d'").unwrap(), 0);
+        assert!(period("'7w'").is_err());
+        assert!(period("'d'").is_err());
+        assert!(period("'-1d'").is_err());
+        assert!(period("'99999999999999999999d'").is_err());
+    }
+
+    #[test]
+    fn test_validate_network() {
+        let standalone = |url: &str, family| IpSourceType::Standalone {
+            url: url.parse().unwrap(),
+            method: Method::GET,
+            headers: HeaderMap::new(),
+            body: None,
+            secret: None,
+            family,
+            tls: TlsOptions::default(),
+        };
+        let validate = |network, bind_address: Option<&str>, ip_source: &IpSourceType| {
+            validate_network(
+                "home",
+                network,
+                bind_address.map(|ip| ip.parse().unwrap()),
+                ip_source,
+            )
+        };
+
+        let domain = standalone("http://ip.example.com/", None);
+        assert!(validate(NetworkMode::Ipv6Only, None, &domain).is_ok());
+        assert!(validate(NetworkMode::Ipv6Only, Some("2001:db8::1"), &domain).is_ok());
+        assert!(validate(NetworkMode::Ipv6Only, Some("192.168.1.2"), &domain).is_err());
+        assert!(validate(NetworkMode::Dual, Some("192.168.1.2"), &domain).is_ok());
+
+        // 使用 IP 地址访问的独立服务器
+        let ipv4 = standalone("http://1.2.3.4/ip", None);
+        let ipv6 = standalone("http://[2001:db8::2]:8000/ip", None);
+        assert!(validate(NetworkMode::Ipv6Only, None, &ipv4).is_err());
+        assert!(validate(NetworkMode::Ipv6Only, None, &ipv6).is_ok());
+        assert!(validate(NetworkMode::Ipv4Only, None, &ipv6).is_err());
+        assert!(validate(NetworkMode::Dual, None, &ipv6).is_ok());
+
+        // 指定地址族的独立服务器
+        let domain_v6 = standalone("http://ip.example.com/", Some(IpFamily::V6));
+        assert!(validate(NetworkMode::Ipv4Only, None, &domain_v6).is_err());
+        assert!(validate(NetworkMode::Dual, Some("192.168.1.2"), &domain_v6).is_err());
+        assert!(validate(NetworkMode::Dual, Some("2001:db8::1"), &domain_v6).is_ok());
+        let ipv4_v6 = standalone("http://1.2.3.4/ip", Some(IpFamily::V6));
+        assert!(validate(NetworkMode::Dual, None, &ipv4_v6).is_err());
+        let json_v6 = IpSourceType::HttpJson {
+            url: "http://1.2.3.4/json".parse().unwrap(),
+            path: vec!["ip".to_string()],
+            headers: HeaderMap::new(),
+            family: Some(IpFamily::V6),
+        };
+        assert!(validate(NetworkMode::Dual, None, &json_v6).is_err());
+        let regex_v4 = IpSourceType::HttpRegex {
+            url: "http://router.lan/status".parse().unwrap(),
+            regex: regex::Regex::new("WAN IP: (\\S+)").unwrap(),
+            headers: HeaderMap::new(),
+            family: Some(IpFamily::V4),
+        };
+        assert!(validate(NetworkMode::Ipv6Only, None, &regex_v4).is_err());
+        assert!(validate(NetworkMode::Dual, Some("2001:db8::1"), &regex_v4).is_err());
+        assert!(validate(NetworkMode::Dual, Some("192.168.1.2"), &regex_v4).is_ok());
+
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        {
+            use super::AddressRules;
+
+            let local_ipv6 = IpSourceType::LocalIPv6 {
+                interface: None,
+                rules: AddressRules::default(),
+            };
+            assert!(validate(NetworkMode::Ipv4Only, None, &local_ipv6).is_err());
+            assert!(validate(NetworkMode::Ipv6Only, None, &local_ipv6).is_ok());
+        }
+
+        let ipify_v6 = IpSourceType::Ipify(IpFamily::V6);
+        assert!(validate(NetworkMode::Ipv4Only, None, &ipify_v6).is_err());
+        assert!(validate(NetworkMode::Dual, None, &ipify_v6).is_ok());
+        let ipify_v4 = IpSourceType::Ipify(IpFamily::V4);
+        assert!(validate(NetworkMode::Ipv6Only, None, &ipify_v4).is_err());
+
+        assert_eq!(
+            json5::from_str::<NetworkMode>("'ipv6-only'").unwrap(),
+            NetworkMode::Ipv6Only
+        );
+    }
+
+    #[test]
+    fn test_ipify_source_type() {
+        let family = |text: &str| match json5::from_str::<IpSourceType>(text).unwrap() {
+            IpSourceType::Ipify(family) => family,
+            other => panic!("unexpected ip source: {:?}", other),
+        };
+
+        assert_eq!(family("3"), IpFamily::V4);
+        assert_eq!(family("{ type: 3 }"), IpFamily::V4);
+        assert_eq!(family("{ type: 3, family: 'v6' }"), IpFamily::V6);
+        assert!(json5::from_str::<IpSourceType>("{ type: 3, family: 'v5' }").is_err());
+        // 仅独立服务器支持 any
+        assert!(json5::from_str::<IpSourceType>("{ type: 3, family: 'any' }").is_err());
+    }
+
+    #[test]
+    fn test_standalone_family() {
+        let family = |text: &str| match json5::from_str::<IpSourceType>(text).unwrap() {
+            IpSourceType::Standalone { family, .. } => family,
+            other => panic!("unexpected ip source: {:?}", other),
+        };
+
+        assert_eq!(family("{ type: 1, server: 'http://127.0.0.1/ip' }"), None);
+        assert_eq!(
+            family("{ type: 1, server: 'http://127.0.0.1/ip', family: 'any' }"),
+            None
+        );
+        assert_eq!(
+            family("{ type: 1, server: 'http://127.0.0.1/ip', family: 'v6' }"),
+            Some(IpFamily::V6)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_wan_ipv4() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source("{ type: 'wan_ipv4', interface: 'ppp0' }").unwrap() {
+            IpSourceType::WanIpv4 {
+                interface,
+                allow_cgnat,
+            } => {
+                assert_eq!(interface, "ppp0");
+                assert!(!allow_cgnat);
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(matches!(
+            ip_source("{ type: 14, interface: 'ppp0', allow_cgnat: true }").unwrap(),
+            IpSourceType::WanIpv4 {
+                allow_cgnat: true,
+                ..
+            }
+        ));
+        assert!(ip_source("14").is_err());
+        assert!(ip_source("{ type: 'wan_ipv4' }").is_err());
+        assert!(ip_source("{ type: 'wan_ipv4', interface: 'ppp0', proxy: false }").is_err());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    #[test]
+    fn test_local_ipv6_rules() {
+        use super::AddressRules;
+
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text).unwrap();
+
+        match ip_source("2") {
+            IpSourceType::LocalIPv6 { interface, rules } => {
+                assert_eq!(interface, None);
+                assert_eq!(rules, AddressRules::default());
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        match ip_source(
+            "{ type: 'local_ipv6', interface: 'enp1s0', require_dynamic: false, require_mngtmpaddr: false, allow_temporary: true }",
+        ) {
+            IpSourceType::LocalIPv6 { interface, rules } => {
+                assert_eq!(interface.as_deref(), Some("enp1s0"));
+                assert_eq!(
+                    rules,
+                    AddressRules {
+                        require_dynamic: false,
+                        require_mngtmpaddr: false,
+                        require_noprefixroute: true,
+                        allow_temporary: true,
+                    }
+                );
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+    }
+
+    #[test]
+    fn test_standalone_headers() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source(
+            "{ type: 1, server: 'http://127.0.0.1/ip', token: 'secret', headers: { 'X-Host': 'nas' } }",
+        )
+        .unwrap()
+        {
+            IpSourceType::Standalone {
+                url,
+                method,
+                headers,
+                body,
+                secret,
+                family,
+                tls,
+            } => {
+                assert_eq!(url.as_str(), "http://127.0.0.1/ip");
+                assert_eq!(family, None);
+                assert_eq!(method, Method::GET);
+                assert_eq!(body, None);
+                assert!(secret.is_none());
+                assert_eq!(tls, TlsOptions::default());
+                assert_eq!(headers["authorization"], "Bearer secret");
+                assert!(headers["authorization"].is_sensitive());
+                assert_eq!(headers["x-host"], "nas");
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(ip_source(
+            "{ type: 1, server: 'http://127.0.0.1/ip', headers: { 'X Host': 'nas' } }"
+        )
+        .is_err());
+        assert!(ip_source("{ type: 1, server: 'http://127.0.0.1/ip', token: 'a\nb' }").is_err());
+        match ip_source("{ type: 1, server: 'http://127.0.0.1/ip', secret: 'shared' }").unwrap() {
+            IpSourceType::Standalone { secret, .. } => {
+                assert_eq!(secret.unwrap().expose(), "shared")
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(ip_source(
+            "{ type: 1, server: 'http://127.0.0.1/ip', token: 'secret', headers: { Authorization: 'Basic abc' } }"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_standalone_tls() {
+        const CA_CERT: &str = "\
+-----BEGIN CERTIFICATE-----\n\
+MIIBizCCATGgAwIBAgIUVEKPWePDby08VCL4F+EiGWx0op4wCgYIKoZIzj0EAwIw\n\
+GjEYMBYGA1UEAwwPZGRuczRjZiB0ZXN0IENBMCAXDTI2MTAxNzIzMDYxOVoYDzIx\n\
+MjYwOTIzMjMwNjE5WjAaMRgwFgYDVQQDDA9kZG5zNGNmIHRlc3QgQ0EwWTATBgcq\n\
+hkjOPQIBBggqhkjOPQMBBwNCAAQLx8tIwBTey1yT5eI/Avjp6TusFqlaKKdaKgF2\n\
+UnGXJ88B44m3CMZhe1oCOM8X/bE3Z6MAOdYiYAcGMLTRonA3o1MwUTAdBgNVHQ4E\n\
+FgQULkBwhEYwvX7vh+CBqJNeWx+nJA8wHwYDVR0jBBgwFoAULkBwhEYwvX7vh+CB\n\
+qJNeWx+nJA8wDwYDVR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiBdjn26\n\
+agZJ11CxQH7fjmNuuzlTfMIH70kPx9xOfBLxAAIhANpkDo9vfF7mwWtGYDFCTJeP\n\
+/SiEjDjvcTKXiGn8lVKC\n\
+-----END CERTIFICATE-----\n";
+
+        let dir = std::env::temp_dir().join(format!("ddns4cf-ca-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ca.pem");
+        std::fs::write(&path, CA_CERT).unwrap();
+        let invalid = dir.join("invalid.pem");
+        std::fs::write(&invalid, "not a certificate").unwrap();
+
+        let ip_source = |options: &str| {
+            json5::from_str::<IpSourceType>(&format!(
+                "{{ type: 1, server: 'https://10.0.0.1/ip', {} }}",
+                options
+            ))
+        };
+        let tls = |options: &str| match ip_source(options).unwrap() {
+            IpSourceType::Standalone { tls, .. } => tls,
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        };
+
+        assert_eq!(tls("method: 'get'"), TlsOptions::default());
+        let with_ca = tls(&format!("ca_cert: {:?}", path.display().to_string()));
+        assert!(with_ca.ca_cert.is_some());
+        assert!(!with_ca.accept_invalid_certs);
+        assert!(tls("accept_invalid_certs: true").accept_invalid_certs);
+
+        // 路径或内容有误时加载配置即失败
+        let err = ip_source(&format!(
+            "ca_cert: {:?}",
+            dir.join("missing.pem").display().to_string()
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("CA 证书读取失败"), "{}", err);
+        let err = ip_source(&format!("ca_cert: {:?}", invalid.display().to_string())).unwrap_err();
+        assert!(err.to_string().contains("CA 证书解析失败"), "{}", err);
+
+        // 证书可用于创建客户端，TLS 选项不同的来源不共享客户端
+        let clients = ClientRegistry::default();
+        let options = SourceHttpOptions::default();
+        let timeout = Duration::from_secs(15);
+        for options_text in [
+            "method: 'get'",
+            &format!("ca_cert: {:?}", path.display().to_string()),
+        ] {
+            ip_source(options_text)
+                .unwrap()
+                .to_ip_source(&None, timeout, &options, &None, &clients)
+                .unwrap();
+        }
+        assert_eq!(clients.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_routeros() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source(
+            "{ type: 'routeros', url: 'https://192.168.88.1', interface: 'pppoe-out1', username: 'admin', password: 'hunter2' }",
+        )
+        .unwrap()
+        {
+            IpSourceType::RouterOs {
+                url,
+                interface,
+                auth,
+                tls,
+            } => {
+                assert_eq!(url.as_str(), "https://192.168.88.1/");
+                assert_eq!(interface, "pppoe-out1");
+                assert!(matches!(auth, RouterOsAuth::Basic { username, .. } if username == "admin"));
+                assert_eq!(tls, TlsOptions::default());
+                // 调试输出中不包含密码
+                let debug = format!("{:?}", ip_source("{ type: 16, url: 'https://192.168.88.1', interface: 'ether1', username: 'admin', password: 'hunter2' }").unwrap());
+                assert!(!debug.contains("hunter2"), "{}", debug);
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        match ip_source(
+            "{ type: 16, url: 'https://192.168.88.1', interface: 'ether1', token: 't', verify_tls: false }",
+        )
+        .unwrap()
+        {
+            IpSourceType::RouterOs { auth, tls, .. } => {
+                assert!(matches!(auth, RouterOsAuth::Token(_)));
+                assert!(tls.accept_invalid_certs);
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+
+        // 认证信息必须完整且唯一
+        assert!(ip_source(
+            "{ type: 16, url: 'https://192.168.88.1', interface: 'ether1', username: 'admin' }"
+        )
+        .is_err());
+        assert!(ip_source("{ type: 16, url: 'https://192.168.88.1', interface: 'ether1', username: 'admin', password: 'p', token: 't' }").is_err());
+        assert!(ip_source(
+            "{ type: 16, url: 'https://admin:p@192.168.88.1', interface: 'ether1', token: 't' }"
+        )
+        .is_err());
+        assert!(ip_source("{ type: 16, url: 'https://192.168.88.1', token: 't' }").is_err());
+        assert!(ip_source("16").is_err());
+    }
+
+    #[test]
+    fn test_ubus() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source("'ubus'").unwrap() {
+            IpSourceType::Ubus {
+                interface,
+                family,
+                remote,
+            } => {
+                assert_eq!(interface, "wan");
+                assert_eq!(family, IpFamily::V4);
+                assert!(remote.is_none());
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        match ip_source(
+            "{ type: 17, interface: 'wan6', family: 'v6', url: 'http://192.168.1.1/ubus', session: 'abc' }",
+        )
+        .unwrap()
+        {
+            IpSourceType::Ubus {
+                interface,
+                family,
+                remote: Some((url, session)),
+            } => {
+                assert_eq!(interface, "wan6");
+                assert_eq!(family, IpFamily::V6);
+                assert_eq!(url.as_str(), "http://192.168.1.1/ubus");
+                assert_eq!(session.expose(), "abc");
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(ip_source("{ type: 'ubus', url: 'http://192.168.1.1/ubus' }").is_err());
+        assert!(ip_source("{ type: 'ubus', session: 'abc' }").is_err());
+        assert!(ip_source("{ type: 'ubus', url: 'ubus', session: 'abc' }").is_err());
+    }
+
+    #[test]
+    fn test_fritzbox() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source("'fritzbox'").unwrap() {
+            IpSourceType::FritzBox { url, family } => {
+                assert_eq!(url.as_str(), "http://fritz.box:49000/");
+                assert_eq!(family, IpFamily::V4);
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        match ip_source("{ type: 18, url: 'http://192.168.178.1:49000', family: 'v6' }").unwrap() {
+            IpSourceType::FritzBox { url, family } => {
+                assert_eq!(url.as_str(), "http://192.168.178.1:49000/");
+                assert_eq!(family, IpFamily::V6);
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(ip_source("{ type: 'fritzbox', url: 'fritz' }").is_err());
+    }
+
+    #[test]
+    fn test_prefix_compose() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source(
+            "{ type: 'prefix_compose', source: { type: 'ipify', family: 'v6' }, prefix_len: 56, suffix: '::12:0:0:0:1' }",
+        )
+        .unwrap()
+        {
+            IpSourceType::PrefixCompose {
+                source,
+                prefix_len,
+                suffix,
+            } => {
+                assert!(matches!(*source, IpSourceType::Ipify(IpFamily::V6)));
+                assert_eq!(prefix_len, 56);
+                assert_eq!(suffix, "::12:0:0:0:1".parse::<std::net::Ipv6Addr>().unwrap());
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(matches!(
+            ip_source("{ type: 15, source: 'ipify', suffix: '::1', suffix_len: 64 }").unwrap(),
+            IpSourceType::PrefixCompose { prefix_len: 64, .. }
+        ));
+
+        // 前缀与后缀长度之和必须为 128
+        assert!(ip_source("{ type: 15, source: 'ipify', suffix: '::1', suffix_len: 72 }").is_err());
+        assert!(ip_source(
+            "{ type: 15, source: 'ipify', prefix_len: 56, suffix: '::1', suffix_len: 64 }"
+        )
+        .is_err());
+        // 后缀与前缀重叠
+        assert!(ip_source("{ type: 15, source: 'ipify', suffix: '::1:0:0:0:1' }").is_err());
+        assert!(
+            ip_source("{ type: 15, source: 'ipify', prefix_len: 129, suffix: '::1' }").is_err()
+        );
+        assert!(ip_source("{ type: 15, source: 'ipify', suffix: '1.2.3.4' }").is_err());
+        assert!(ip_source("{ type: 15, source: 'ipify' }").is_err());
+        assert!(ip_source("15").is_err());
+    }
+
+    #[test]
+    fn test_standalone_method() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source(
+            "{ type: 1, server: 'http://127.0.0.1/ip', method: 'post', body: '{\\\"query\\\":\\\"ip\\\"}', content_type: 'application/json' }",
+        )
+        .unwrap()
+        {
+            IpSourceType::Standalone {
+                method,
+                headers,
+                body,
+                ..
+            } => {
+                assert_eq!(method, Method::POST);
+                assert_eq!(body.as_deref(), Some("{\"query\":\"ip\"}"));
+                assert_eq!(headers["content-type"], "application/json");
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(ip_source("{ type: 1, server: 'http://127.0.0.1/ip', method: 'DELETE' }").is_err());
+        assert!(ip_source("{ type: 1, server: 'http://127.0.0.1/ip', body: 'ip' }").is_err());
+        assert!(ip_source(
+            "{ type: 1, server: 'http://127.0.0.1/ip', method: 'PUT', body: 'ip', content_type: 'text/plain', headers: { 'Content-Type': 'text/plain' } }"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_source_timeout() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source("{ type: 1, server: 'http://127.0.0.1/ip', timeout: 5 }").unwrap() {
+            IpSourceType::WithTimeout { source, timeout } => {
+                assert!(matches!(*source, IpSourceType::Standalone { .. }));
+                assert_eq!(timeout, Duration::from_secs(5));
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert_eq!(
+            ip_source("{ type: 'ipify', timeout: 3 }")
+                .unwrap()
+                .timeout(),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(ip_source("{ type: 'ipify' }").unwrap().timeout(), None);
+        // 自定义命令的超时时间为命令最长执行时间
+        match ip_source("{ type: 'command', program: 'wanip', timeout: 10 }").unwrap() {
+            ip_source @ IpSourceType::Command { .. } => {
+                assert_eq!(ip_source.timeout(), Some(Duration::from_secs(10)))
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(ip_source("{ type: 'ipify', timeout: 0 }").is_err());
+    }
+
+    #[test]
+    fn test_source_user_agent() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source("{ type: 'ipify', user_agent: 'corp-agent/1.0', timeout: 5 }").unwrap() {
+            IpSourceType::WithTimeout { source, .. } => match *source {
+                IpSourceType::WithUserAgent { source, user_agent } => {
+                    assert!(matches!(*source, IpSourceType::Ipify(_)));
+                    assert_eq!(&*user_agent, "corp-agent/1.0");
+                }
+                ip_source => panic!("unexpected ip source {:?}", ip_source),
+            },
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(
+            ip_source("{ type: 'chain', sources: [3, 4], user_agent: 'corp-agent/1.0' }").is_ok()
+        );
+        // 非 HTTP 来源不支持 User-Agent
+        assert!(ip_source("{ type: 'dns', user_agent: 'corp-agent/1.0' }").is_err());
+        assert!(ip_source("{ type: 'stun', user_agent: 'corp-agent/1.0' }").is_err());
+    }
+
+    #[test]
+    fn test_source_proxy() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        match ip_source("{ type: 'ipify', proxy: false }").unwrap() {
+            IpSourceType::WithProxy { source, proxy } => {
+                assert!(matches!(*source, IpSourceType::Ipify(_)));
+                assert_eq!(proxy, ProxySetting::Disabled);
+            }
+            ip_source => panic!("unexpected ip source {:?}", ip_source),
+        }
+        assert!(matches!(
+            ip_source("{ type: 1, server: 'http://127.0.0.1/ip', proxy: { url: 'socks5://127.0.0.1:1080' } }").unwrap(),
+            IpSourceType::WithProxy {
+                proxy: ProxySetting::Proxy(_),
+                ..
+            }
+        ));
+        assert!(ip_source("{ type: 'ipify', proxy: true }").is_err());
+        // 非 HTTP 来源不支持访问代理
+        assert!(ip_source("{ type: 'dns', proxy: false }").is_err());
+
+        let configuration: Configuration =
+            json5::from_str("{ accounts: [], source_proxy: { url: 'socks5://127.0.0.1:1080' } }")
+                .unwrap();
+        assert!(matches!(
+            configuration.source_proxy,
+            Some(ProxySetting::Proxy(_))
+        ));
+    }
+
+    #[test]
+    fn test_source_type_name() {
+        let ip_source = |text: &str| json5::from_str::<IpSourceType>(text);
+
+        assert!(matches!(
+            ip_source("{ type: 'icanhazip', family: 'v6' }").unwrap(),
+            IpSourceType::Icanhazip(IpFamily::V6)
+        ));
+        assert!(matches!(
+            ip_source("'icanhazip'").unwrap(),
+            IpSourceType::Icanhazip(IpFamily::V4)
+        ));
+        assert!(matches!(
+            ip_source("{ type: 4 }").unwrap(),
+            IpSourceType::Icanhazip(IpFamily::V4)
+        ));
+        assert!(matches!(
+            ip_source("{ type: 'ipify', family: 'v6' }").unwrap(),
+            IpSourceType::Ipify(IpFamily::V6)
+        ));
+        assert!(matches!(
+            ip_source("{ type: 'cf_trace', family: 'v6' }").unwrap(),
+            IpSourceType::CfTrace(IpFamily::V6)
+        ));
+        assert!(matches!(
+            ip_source("5").unwrap(),
+            IpSourceType::CfTrace(IpFamily::V4)
+        ));
+        match ip_source("'doh_whoami'").unwrap() {
+            IpSourceType::DohWhoami { url, family } => {
+                assert_eq!(url.as_str(), "https://cloudflare-dns.com/dns-query");
+                assert_eq!(family, IpFamily::V4);
+            }
+            other => panic!("unexpected source: {:?}", other),
+        }
+        match ip_source("{ type: 13, url: 'https://1.1.1.1/dns-query', family: 'v6' }").unwrap() {
+            IpSourceType::DohWhoami { url, family } => {
+                assert_eq!(url.as_str(), "https://1.1.1.1/dns-query");
+                assert_eq!(family, IpFamily::V6);
+            }
+            other => panic!("unexpected source: {:?}", other),
+        }
+        assert!(ip_source("{ type: 13, url: 'dns' }").is_err());
+        assert!(ip_source("{ type: 'doh_whoami', family: 'any' }").is_err());
+        match ip_source("{ type: 'dns', resolver: 'ns1.google.com', name: 'o-o.myaddr.l.google.com', txt: true }")
+            .unwrap()
+        {
+            IpSourceType::Dns {
+                resolver,
+                name,
+                family,
+                txt,
+            } => {
+                assert_eq!(resolver, "ns1.google.com:53");
+                assert_eq!(name, "o-o.myaddr.l.google.com");
+                assert_eq!(family, IpFamily::V4);
+                assert!(txt);
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(matches!(
+            ip_source("6").unwrap(),
+            IpSourceType::Dns { ref resolver, txt: false, .. } if resolver == "resolver1.opendns.com:53"
+        ));
+        match ip_source("{ type: 'stun', servers: ['stun.example.com', 'stun.l.google.com:19302'], family: 'v6' }")
+            .unwrap()
+        {
+            IpSourceType::Stun { servers, family } => {
+                assert_eq!(
+                    servers,
+                    ["stun.example.com:3478", "stun.l.google.com:19302"]
+                );
+                assert_eq!(family, IpFamily::V6);
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(matches!(
+            ip_source("7").unwrap(),
+            IpSourceType::Stun { ref servers, .. } if servers.len() == 2
+        ));
+        assert!(ip_source("{ type: 'stun', servers: [] }").is_err());
+        match ip_source(
+            "{ type: 'command', program: '/usr/local/bin/wanip', args: ['--v4'], timeout: 10 }",
+        )
+        .unwrap()
+        {
+            IpSourceType::Command {
+                program,
+                args,
+                timeout,
+            } => {
+                assert_eq!(program, "/usr/local/bin/wanip");
+                assert_eq!(args, ["--v4"]);
+                assert_eq!(timeout, Some(Duration::from_secs(10)));
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(ip_source("{ type: 'command' }").is_err());
+        assert!(ip_source("{ type: 'command', program: '' }").is_err());
+        assert!(ip_source("{ type: 'command', program: 'wanip', timeout: 0 }").is_err());
+        assert!(ip_source("8").is_err());
+        match ip_source(
+            "{ type: 'http_json', url: 'https://ipinfo.io/json', path: 'data.ip', headers: { Authorization: 'Bearer token' } }",
+        )
+        .unwrap()
+        {
+            IpSourceType::HttpJson {
+                url,
+                path,
+                headers,
+                family,
+            } => {
+                assert_eq!(url.as_str(), "https://ipinfo.io/json");
+                assert_eq!(path, ["data", "ip"]);
+                assert_eq!(headers["authorization"], "Bearer token");
+                assert_eq!(family, None);
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(ip_source("{ type: 'http_json', url: 'https://ipinfo.io/json' }").is_err());
+        assert!(ip_source(
+            "{ type: 'http_json', url: 'https://ipinfo.io/json', path: 'data..ip' }"
+        )
+        .is_err());
+        assert!(ip_source("{ type: 'http_json', url: 'ipinfo', path: 'ip' }").is_err());
+        assert!(ip_source(
+            "{ type: 'http_json', url: 'https://ipinfo.io/json', path: 'ip', headers: { 'bad header': 'x' } }"
+        )
+        .is_err());
+        match ip_source(
+            r#"{ type: 'http_regex', url: 'http://192.168.1.1/status', regex: 'WAN IP: (\\S+)' }"#,
+        )
+        .unwrap()
+        {
+            IpSourceType::HttpRegex { url, regex, .. } => {
+                assert_eq!(url.as_str(), "http://192.168.1.1/status");
+                assert_eq!(regex.as_str(), r"WAN IP: (\S+)");
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(matches!(
+            ip_source(
+                r#"{ type: 'http_regex', url: 'http://192.168.1.1/', regex: 'WAN IP: (\\S+)', family: 'v6' }"#
+            )
+            .unwrap(),
+            IpSourceType::HttpRegex {
+                family: Some(IpFamily::V6),
+                ..
+            }
+        ));
+        assert!(matches!(
+            ip_source(
+                "{ type: 'http_json', url: 'https://ipinfo.io/json', path: 'ip', family: 'any' }"
+            )
+            .unwrap(),
+            IpSourceType::HttpJson { family: None, .. }
+        ));
+        assert!(ip_source("{ type: 'icanhazip', family: 'any' }").is_err());
+        let err = ip_source("{ type: 'http_regex', url: 'http://192.168.1.1/', regex: 'WAN IP' }")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("捕获组"), "{}", err);
+        assert!(
+            ip_source("{ type: 'http_regex', url: 'http://192.168.1.1/', regex: '(' }").is_err()
+        );
+        assert!(ip_source("{ type: 'http_regex', url: 'http://192.168.1.1/' }").is_err());
+        match ip_source(
+            "{ type: 'chain', sources: [{ type: 'icanhazip' }, { type: 1, server: 'http://127.0.0.1:8000/ip' }, { type: 'chain', sources: [5] }] }",
+        )
+        .unwrap()
+        {
+            IpSourceType::Chain(sources) => {
+                assert!(matches!(sources[0], IpSourceType::Icanhazip(IpFamily::V4)));
+                assert!(matches!(sources[1], IpSourceType::Standalone { .. }));
+                assert!(matches!(
+                    &sources[2],
+                    IpSourceType::Chain(nested) if matches!(nested[..], [IpSourceType::CfTrace(_)])
+                ));
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(ip_source("{ type: 'chain', sources: [] }").is_err());
+        assert!(ip_source("{ type: 'chain' }").is_err());
+        // 成员配置错误
+        assert!(ip_source("{ type: 'chain', sources: [{ type: 1 }] }").is_err());
+        match ip_source(
+            "{ type: 'consensus', sources: ['ipify', 'icanhazip', { type: 'chain', sources: [5] }] }",
+        )
+        .unwrap()
+        {
+            IpSourceType::Consensus { sources, quorum } => {
+                assert_eq!(sources.len(), 3);
+                assert!(matches!(sources[2], IpSourceType::Chain(_)));
+                assert_eq!(quorum, 2);
+            }
+            other => panic!("unexpected ip source: {:?}", other),
+        }
+        assert!(matches!(
+            ip_source("{ type: 12, sources: [3, 4, 5], quorum: 3 }").unwrap(),
+            IpSourceType::Consensus { quorum: 3, .. }
+        ));
+        assert!(ip_source("{ type: 12, sources: [3, 4], quorum: 3 }").is_err());
+        assert!(ip_source("{ type: 12, sources: [3, 4], quorum: 0 }").is_err());
+        assert!(ip_source("{ type: 'consensus', sources: [] }").is_err());
+        assert!(ip_source("12").is_err());
+        assert!(ip_source("{ type: 'standalone' }").is_err());
+        assert!(ip_source("{ type: 'ipip' }").is_err());
+        assert!(ip_source("'ipip'").is_err());
+    }
+
+    #[test]
+    fn test_http_pool_options() {
+        let http = |text: &str| json5::from_str::<Http>(text).unwrap().pool_options();
+
+        assert_eq!(http("{}"), PoolOptions::default());
+        assert_eq!(
+            http("{ low_memory: true }"),
+            PoolOptions {
+                idle_timeout: Some(Duration::from_secs(5)),
+                max_idle_per_host: 1,
+            }
+        );
+
+        // 单独配置的参数优先于低内存模式
+        assert_eq!(
+            http("{ low_memory: true, pool_idle_timeout: 30 }"),
+            PoolOptions {
+                idle_timeout: Some(Duration::from_secs(30)),
+                max_idle_per_host: 1,
+            }
+        );
+        assert_eq!(
+            http("{ pool_max_idle_per_host: 2 }"),
+            PoolOptions {
+                idle_timeout: Some(Duration::from_secs(90)),
+                max_idle_per_host: 2,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_pool_client() {
+        let client = |http: &str| {
+            json5::from_str::<Configuration>(&format!(
+                "{{ {} ip_source: 'ipify', accounts: [{{ token: 't', domains: [{{ nickname: 'home', id: '1', zone_id: 'z' }}] }}] }}",
+                http
+            ))
+            .unwrap()
+            .create_cf_http_client()
+            .unwrap()
+        };
+        let pause = Duration::from_millis(20);
+
+        // 配置的连接池参数应用于实际创建的客户端
+        assert_eq!(connections(&client(""), pause).await, 1);
+        assert_eq!(
+            connections(&client("http: { pool_max_idle_per_host: 0 },"), pause).await,
+            2
+        );
+        assert_eq!(
+            connections(
+                &client("http: { pool_max_idle_per_host: 0 }, cf_keepalive: 30,"),
+                pause
+            )
+            .await,
+            2
+        );
+    }
+
+    #[test]
+    fn test_shared_client() {
+        let clients = ClientRegistry::default();
+        let timeout = Duration::from_secs(15);
+        let source = |url: &str, bind_address: &str| {
+            IpSourceType::Standalone {
+                url: url.parse().unwrap(),
+                method: Method::GET,
+                headers: HeaderMap::new(),
+                body: None,
+                secret: None,
+                family: None,
+                tls: TlsOptions::default(),
+            }
+            .to_ip_source(
+                &Some(bind_address.parse().unwrap()),
+                timeout,
+                &SourceHttpOptions::default(),
+                &None,
+                &clients,
+            )
+            .unwrap()
+        };
+
+        // 连接参数相同的来源共享同一客户端
+        source("http://a.example.com/ip", "127.0.0.1");
+        source("http://b.example.com/ip", "127.0.0.1");
+        assert_eq!(clients.len(), 1);
+
+        source("http://a.example.com/ip", "127.0.0.2");
+        assert_eq!(clients.len(), 2);
+    }
+
+    #[test]
+    fn test_validate_interval() {
+        assert_eq!(
+            validate_interval(
+                "home",
+                "fresh_interval",
+                900,
+                MIN_FRESH_INTERVAL_SECONDS,
+                true
+            )
+            .unwrap(),
+            900
+        );
+
+        // 低于下限时默认使用下限值，严格模式下报错
+        assert_eq!(
+            validate_interval(
+                "home",
+                "fresh_interval",
+                0,
+                MIN_FRESH_INTERVAL_SECONDS,
+                false
+            )
+            .unwrap(),
+            MIN_FRESH_INTERVAL_SECONDS
+        );
+        assert_eq!(
+            validate_interval(
+                "home",
+                "fresh_interval",
+                0,
+                MIN_FRESH_INTERVAL_SECONDS,
+                true
+            )
+            .unwrap_err()
+            .to_string(),
+            "域名 home 的 fresh_interval 为 0 秒，低于下限 30 秒"
+        );
+
+        // 超过上限时始终报错
+        [false, true].into_iter().for_each(|strict| {
+            assert!(validate_interval(
+                "home",
+                "fresh_interval",
+                MAX_INTERVAL_SECONDS + 1,
+                MIN_FRESH_INTERVAL_SECONDS,
+                strict
+            )
+            .is_err());
+        });
+    }
 }
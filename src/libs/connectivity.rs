@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode, Url};
+use tokio::{sync::Mutex, time::Instant};
+
+/// 默认连通性检查地址，正常网络中返回 204 且响应体为空
+pub const DEFAULT_CONNECTIVITY_CHECK_URL: &str = "http://cp.cloudflare.com/generate_204";
+/// 默认连通性检查结果缓存时间
+pub const DEFAULT_CONNECTIVITY_CACHE: Duration = Duration::from_secs(30);
+
+/// 网络连通性检查
+///
+/// 酒店、机场等网络在完成认证前会拦截 HTTP 请求并返回认证页面（captive portal），
+/// 此时 IP 地址来源可能返回网关地址或 HTML 页面。检查地址返回的状态码及响应体与预期完全一致时才视为网络可用。
+/// 检查结果缓存一段时间，多个域名同时更新时仅检查一次。
+#[derive(Debug)]
+pub struct ConnectivityProbe {
+    client: Client,
+    url: Url,
+    expected_status: StatusCode,
+    expected_body: String,
+    cache: Duration,
+    /// 最近一次检查的时间及结果
+    last: Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+impl ConnectivityProbe {
+    pub fn new(
+        client: Client,
+        url: Url,
+        expected_status: StatusCode,
+        expected_body: String,
+        cache: Duration,
+    ) -> Self {
+        Self {
+            client,
+            url,
+            expected_status,
+            expected_body,
+            cache,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// 检查网络连通性，未通过时返回原因
+    pub async fn check(&self) -> Result<(), String> {
+        // 检查期间持有锁，同时到达的检查等待并复用同一结果
+        let mut last = self.last.lock().await;
+        if let Some((time, result)) = last.as_ref() {
+            if time.elapsed() < self.cache {
+                return result.clone();
+            }
+        }
+
+        let result = self.probe().await;
+        *last = Some((Instant::now(), result.clone()));
+        result
+    }
+
+    async fn probe(&self) -> Result<(), String> {
+        let response = self
+            .client
+            .get(self.url.clone())
+            .send()
+            .await
+            .map_err(|err| format!("访问 {} 失败：{}", self.url, err))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|err| format!("读取 {} 响应失败：{}", self.url, err))?;
+
+        if status != self.expected_status {
+            Err(format!(
+                "{} 返回 HTTP {}，预期为 HTTP {}",
+                self.url,
+                status.as_u16(),
+                self.expected_status.as_u16()
+            ))
+        } else if body.as_ref() != self.expected_body.as_bytes() {
+            Err(format!("{} 返回的响应体与预期不一致", self.url))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use reqwest::{Client, StatusCode};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::ConnectivityProbe;
+
+    /// 启动始终返回 `response` 的 HTTP 服务器，返回检查地址及请求计数
+    async fn serve(response: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/generate_204", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buffer = [0; 1024];
+                let _ = stream.read(&mut buffer).await;
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (url, hits)
+    }
+
+    fn probe(url: &str, cache: Duration) -> ConnectivityProbe {
+        ConnectivityProbe::new(
+            Client::new(),
+            url.parse().unwrap(),
+            StatusCode::NO_CONTENT,
+            String::new(),
+            cache,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_connectivity() {
+        let (url, hits) = serve("HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n").await;
+        let probe = probe(&url, Duration::from_secs(60));
+        assert!(probe.check().await.is_ok());
+
+        // 缓存期内不再重复检查
+        assert!(probe.check().await.is_ok());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_captive_portal() {
+        // 认证页面返回 200 及 HTML 页面
+        let (url, _) = serve(
+            "HTTP/1.1 200 OK\r\nContent-Length: 13\r\nConnection: close\r\n\r\n<html></html>",
+        )
+        .await;
+        assert!(probe(&url, Duration::ZERO).check().await.is_err());
+
+        // 状态码一致但响应体被篡改
+        let (url, hits) =
+            serve("HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nHTML").await;
+        let probe = ConnectivityProbe::new(
+            Client::new(),
+            url.parse().unwrap(),
+            StatusCode::OK,
+            "Success".to_string(),
+            Duration::ZERO,
+        );
+        assert!(probe.check().await.is_err());
+
+        // 未启用缓存时每次均重新检查
+        assert!(probe.check().await.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,77 +1,775 @@
-use std::{borrow::Cow, fmt::Display, path::Path};
+use std::{borrow::Cow, fmt::Display, io, path::Path, sync::Arc};
 
-/// 字符串化错误，仅用于打印异常内容，不用作任何判断。
+/// 错误来源
+type Source = Arc<dyn std::error::Error + Send + Sync>;
+
+/// 不可重试的 Cloudflare 响应代码，通常表示 token、zone id 或 record id 配置有误
+const FATAL_CLOUDFLARE_CODES: [u32; 7] = [7000, 7003, 9103, 9106, 9109, 10000, 81044];
+/// Cloudflare 记录不存在的响应代码
+const RECORD_MISSING_CODE: u32 = 81044;
+/// 表示 token 认证失败的 Cloudflare 响应代码
+const AUTH_FAILURE_CODES: [u32; 3] = [1000, 9109, 10000];
+
+/// 错误类型，用于指标标签、退避策略等
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Config,
+    Network,
+    CloudflareApi,
+    CloudflareServer,
+    RateLimited,
+    Parse,
+    Source,
+    Uninitialized,
+    Io,
+    Task,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Config => "config",
+            ErrorKind::Network => "network",
+            ErrorKind::CloudflareApi => "cloudflare_api",
+            ErrorKind::CloudflareServer => "cloudflare_server",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Source => "source",
+            ErrorKind::Uninitialized => "uninitialized",
+            ErrorKind::Io => "io",
+            ErrorKind::Task => "task",
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 出现错误时更新所处的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// 从 IP 地址来源获取 IP 地址
+    Source,
+    /// 获取 Cloudflare DNS 记录详情
+    Fetch,
+    /// 更新 Cloudflare DNS 记录
+    Update,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Source => "source",
+            Phase::Fetch => "fetch",
+            Phase::Update => "update",
+        }
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 程序错误
+///
+/// 每个变体均保存完整的错误描述用于输出日志，同时携带结构化的错误信息用于判断。
 #[derive(Debug, Clone)]
-pub struct Error(Cow<'static, str>);
+pub enum Error {
+    /// 配置错误
+    Config { message: Cow<'static, str> },
+    /// 网络错误
+    Network {
+        message: Cow<'static, str>,
+        source: Option<Source>,
+    },
+    /// Cloudflare API 返回错误
+    CloudflareApi {
+        message: Cow<'static, str>,
+        codes: Vec<u32>,
+    },
+    /// Cloudflare API 返回 5xx 服务器错误，通常为 Cloudflare 服务故障或维护
+    CloudflareServer {
+        message: Cow<'static, str>,
+        status: u16,
+    },
+    /// Cloudflare API 返回 429，请求过于频繁
+    RateLimited {
+        message: Cow<'static, str>,
+        /// 响应头 `Retry-After` 指定的等待时间，单位秒
+        retry_after: Option<u64>,
+    },
+    /// 解析响应或数据时出现错误
+    Parse {
+        message: Cow<'static, str>,
+        source: Option<Source>,
+    },
+    /// IP 地址来源错误
+    Source {
+        name: Cow<'static, str>,
+        message: Cow<'static, str>,
+        /// 获取的地址已被拒绝发布，重试无法改变结果
+        rejected: bool,
+    },
+    /// 更新器未初始化
+    Uninitialized,
+    /// 读写文件或执行命令时出现错误
+    Io {
+        message: Cow<'static, str>,
+        source: Arc<io::Error>,
+    },
+    /// 必要的后台任务异常退出或子命令执行失败
+    Task { message: Cow<'static, str> },
+    /// 附带域名、阶段及 HTTP 状态码的错误，由 [`Error::context`] 创建
+    Context {
+        nickname: String,
+        phase: Phase,
+        status: Option<u16>,
+        inner: Box<Error>,
+    },
+}
 
 impl Error {
-    pub fn new_str(reason: &'static str) -> Self {
-        Self(Cow::Borrowed(&reason))
+    pub fn config<M>(message: M) -> Self
+    where
+        M: Into<Cow<'static, str>>,
+    {
+        Self::Config {
+            message: message.into(),
+        }
     }
 
-    pub fn new_string(reason: String) -> Self {
-        Self(Cow::Owned(reason))
+    pub fn network<M, E>(message: M, source: E) -> Self
+    where
+        M: Into<Cow<'static, str>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::Network {
+            message: message.into(),
+            source: Some(Arc::new(source)),
+        }
     }
 
-    pub fn uninitialized() -> Self {
-        Self(Cow::Borrowed("Updater 未初始化"))
+    pub fn parse<M, E>(message: M, source: E) -> Self
+    where
+        M: Into<Cow<'static, str>>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        Self::Parse {
+            message: message.into(),
+            source: Some(Arc::new(source)),
+        }
     }
 
-    pub fn read_configuration_failure<E>(err: E, path: &Path) -> Self
+    pub fn ip_source<N, M>(name: N, message: M) -> Self
     where
-        E: std::error::Error,
+        N: Into<Cow<'static, str>>,
+        M: Into<Cow<'static, str>>,
     {
-        Self::new_string(format!("配置文件读取失败：{} {}", err, path.display()))
+        Self::Source {
+            name: name.into(),
+            message: message.into(),
+            rejected: false,
+        }
     }
 
-    pub fn cloudflare_network_failure<E>(err: E) -> Self
+    /// IP 地址来源获取的地址不允许发布，该错误不可重试
+    pub fn ip_source_rejected<N, M>(name: N, message: M) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        M: Into<Cow<'static, str>>,
+    {
+        Self::Source {
+            name: name.into(),
+            message: message.into(),
+            rejected: true,
+        }
+    }
+
+    pub fn io<M>(message: M, source: io::Error) -> Self
+    where
+        M: Into<Cow<'static, str>>,
+    {
+        Self::Io {
+            message: message.into(),
+            source: Arc::new(source),
+        }
+    }
+
+    pub fn uninitialized() -> Self {
+        Self::Uninitialized
+    }
+
+    /// 必要的后台任务 `name` 异常退出
+    pub fn task_failure(name: &str) -> Self {
+        Self::Task {
+            message: Cow::Owned(format!("必要任务 {} 异常退出，程序已停止", name)),
+        }
+    }
+
+    /// 向 `failures` 个通知渠道发送测试消息失败
+    pub fn notification_test_failure(failures: usize) -> Self {
+        Self::Task {
+            message: Cow::Owned(format!("{} 个通知渠道测试消息发送失败", failures)),
+        }
+    }
+
+    pub fn read_configuration_failure<E>(err: E, path: &Path) -> Self
     where
         E: std::error::Error,
     {
-        Self::new_string(format!(
-            "访问 Cloudflare 失败，请确认网络连接正常，错误原因：{}",
+        Self::config(format!("配置文件读取失败：{} {}", err, path.display()))
+    }
+
+    pub fn cloudflare_network_failure(err: reqwest::Error) -> Self {
+        Self::network(
+            format!(
+                "访问 Cloudflare 失败，请确认网络连接正常，错误原因：{}",
+                err,
+            ),
             err,
-        ))
+        )
     }
 
-    pub fn cloudflare_record_failure(reason: Option<Cow<'_, str>>) -> Self {
-        match reason {
-            Some(reason) => Self::new_string(format!(
+    /// 拼接 Cloudflare 错误消息，无错误消息时返回 `None`
+    fn cloudflare_reason(codes: &[u32], messages: &[String]) -> Option<String> {
+        if codes.is_empty() {
+            return None;
+        }
+
+        Some(
+            codes
+                .iter()
+                .zip(messages)
+                .map(|(code, message)| format!("Cloudflare 响应代码 {}：{}", code, message))
+                .collect::<Vec<_>>()
+                .join("；"),
+        )
+    }
+
+    /// 获取 Cloudflare DNS 记录详情失败，`codes` 与 `messages` 一一对应
+    pub fn cloudflare_record_failure(codes: Vec<u32>, messages: Vec<String>) -> Self {
+        let message = match Self::cloudflare_reason(&codes, &messages) {
+            Some(reason) => Cow::Owned(format!(
                 "获取 Cloudflare DNS 记录详情失败，错误原因：{}",
                 reason,
             )),
-            None => Self::new_str("获取 Cloudflare DNS 记录详情失败，错误原因：未知原因"),
+            None => Cow::Borrowed("获取 Cloudflare DNS 记录详情失败，错误原因：未知原因"),
+        };
+        Self::CloudflareApi { message, codes }
+    }
+
+    /// 按名称查询 Cloudflare zone 失败，`codes` 与 `messages` 一一对应
+    pub fn cloudflare_zone_failure(codes: Vec<u32>, messages: Vec<String>) -> Self {
+        let message = match Self::cloudflare_reason(&codes, &messages) {
+            Some(reason) => Cow::Owned(format!("查询 Cloudflare zone 失败，错误原因：{}", reason,)),
+            None => Cow::Borrowed("查询 Cloudflare zone 失败，错误原因：未知原因"),
+        };
+        Self::CloudflareApi { message, codes }
+    }
+
+    /// 验证 Cloudflare API token 失败，`codes` 与 `messages` 一一对应
+    pub fn cloudflare_token_failure(codes: Vec<u32>, messages: Vec<String>) -> Self {
+        let message = match Self::cloudflare_reason(&codes, &messages) {
+            Some(reason) => Cow::Owned(format!("Cloudflare API token 无效，错误原因：{}", reason,)),
+            None => Cow::Borrowed("Cloudflare API token 无效，错误原因：未知原因"),
+        };
+        Self::CloudflareApi { message, codes }
+    }
+
+    /// Cloudflare API token 有效但当前不可用，如已过期或已禁用
+    pub fn cloudflare_token_inactive(status: &str) -> Self {
+        Self::CloudflareApi {
+            message: Cow::Owned(format!(
+                "Cloudflare API token 当前不可用，token 状态为 {}",
+                status
+            )),
+            codes: Vec::new(),
         }
     }
 
-    pub fn cloudflare_update_failure(reason: Option<Cow<'_, str>>) -> Self {
-        match reason {
-            Some(reason) => Self::new_string(format!(
+    /// Cloudflare 中不存在需要更新的记录，与 Cloudflare 响应代码 81044 同样处理
+    pub fn cloudflare_record_missing(message: String) -> Self {
+        Self::CloudflareApi {
+            message: Cow::Owned(message),
+            codes: vec![RECORD_MISSING_CODE],
+        }
+    }
+
+    /// 创建 Cloudflare DNS 记录失败，`codes` 与 `messages` 一一对应
+    pub fn cloudflare_create_failure(codes: Vec<u32>, messages: Vec<String>) -> Self {
+        let message = match Self::cloudflare_reason(&codes, &messages) {
+            Some(reason) => Cow::Owned(format!(
+                "创建 Cloudflare DNS 记录失败，错误原因：{}",
+                reason,
+            )),
+            None => Cow::Borrowed("创建 Cloudflare DNS 记录失败，错误原因：未知原因"),
+        };
+        Self::CloudflareApi { message, codes }
+    }
+
+    /// 更新 Cloudflare DNS 记录失败，`codes` 与 `messages` 一一对应
+    pub fn cloudflare_update_failure(codes: Vec<u32>, messages: Vec<String>) -> Self {
+        let message = match Self::cloudflare_reason(&codes, &messages) {
+            Some(reason) => Cow::Owned(format!(
                 "更新 Cloudflare DNS 记录失败。错误原因：{}",
                 reason,
             )),
-            None => Self::new_str("更新 Cloudflare DNS 记录失败。错误原因：未知原因"),
+            None => Cow::Borrowed("更新 Cloudflare DNS 记录失败。错误原因：未知原因"),
+        };
+        Self::CloudflareApi { message, codes }
+    }
+
+    /// Cloudflare API 返回 5xx 服务器错误，`ray_id` 为响应头 `cf-ray` 的值
+    pub fn cloudflare_server_failure(status: reqwest::StatusCode, ray_id: Option<&str>) -> Self {
+        Self::CloudflareServer {
+            message: Cow::Owned(format!(
+                "Cloudflare API 返回服务器错误（HTTP {}，Ray ID：{}），通常为 Cloudflare 服务故障或维护，稍后将自动重试",
+                status,
+                ray_id.unwrap_or("无")
+            )),
+            status: status.as_u16(),
+        }
+    }
+
+    /// Cloudflare API 返回 429，`retry_after` 为响应头 `Retry-After` 指定的等待秒数
+    pub fn cloudflare_rate_limited(retry_after: Option<u64>) -> Self {
+        let message = match retry_after {
+            Some(seconds) => Cow::Owned(format!(
+                "Cloudflare API 请求过于频繁（HTTP 429），Cloudflare 要求等待 {} 秒后重试",
+                seconds
+            )),
+            None => Cow::Borrowed("Cloudflare API 请求过于频繁（HTTP 429），稍后将自动重试"),
+        };
+        Self::RateLimited {
+            message,
+            retry_after,
         }
     }
 
     pub fn cloudflare_deserialized_failure<E>(err: E) -> Self
     where
-        E: std::error::Error,
+        E: std::error::Error + Send + Sync + 'static,
     {
-        Self::new_string(format!("解析 Cloudflare 响应时出现错误，错误原因：{}", err))
+        Self::parse(
+            format!("解析 Cloudflare 响应时出现错误，错误原因：{}", err),
+            err,
+        )
+    }
+
+    /// 附加域名昵称及所处阶段，已附加过的错误保持不变
+    pub fn context(self, nickname: &str, phase: Phase) -> Self {
+        match self {
+            Error::Context { .. } => self,
+            inner => Error::Context {
+                nickname: nickname.to_string(),
+                phase,
+                status: None,
+                inner: Box::new(inner),
+            },
+        }
+    }
+
+    /// 在错误描述前附加说明，错误类型及其余信息保持不变
+    pub fn with_prefix(self, prefix: &str) -> Self {
+        match self {
+            Error::Context {
+                nickname,
+                phase,
+                status,
+                inner,
+            } => Error::Context {
+                nickname,
+                phase,
+                status,
+                inner: Box::new(inner.with_prefix(prefix)),
+            },
+            mut err => {
+                match &mut err {
+                    Error::Config { message }
+                    | Error::Network { message, .. }
+                    | Error::CloudflareApi { message, .. }
+                    | Error::CloudflareServer { message, .. }
+                    | Error::RateLimited { message, .. }
+                    | Error::Parse { message, .. }
+                    | Error::Source { message, .. }
+                    | Error::Io { message, .. }
+                    | Error::Task { message } => {
+                        *message = Cow::Owned(format!("{}{}", prefix, message));
+                    }
+                    Error::Uninitialized | Error::Context { .. } => {}
+                }
+                err
+            }
+        }
+    }
+
+    /// 附加 HTTP 状态码，仅对通过 [`Error::context`] 附加过上下文的错误生效
+    pub fn with_http_status(mut self, http_status: reqwest::StatusCode) -> Self {
+        if let Error::Context { status, .. } = &mut self {
+            *status = Some(http_status.as_u16());
+        }
+        self
+    }
+
+    /// 错误类型
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Context { inner, .. } => inner.kind(),
+            Error::Config { .. } => ErrorKind::Config,
+            Error::Network { .. } => ErrorKind::Network,
+            Error::CloudflareApi { .. } => ErrorKind::CloudflareApi,
+            Error::CloudflareServer { .. } => ErrorKind::CloudflareServer,
+            Error::RateLimited { .. } => ErrorKind::RateLimited,
+            Error::Parse { .. } => ErrorKind::Parse,
+            Error::Source { .. } => ErrorKind::Source,
+            Error::Uninitialized => ErrorKind::Uninitialized,
+            Error::Io { .. } => ErrorKind::Io,
+            Error::Task { .. } => ErrorKind::Task,
+        }
+    }
+
+    /// 导致错误的 HTTP 状态码，仅对附加过状态码的错误及 Cloudflare 服务器错误、429 错误有效
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            Error::Context { inner, status, .. } => status.or_else(|| inner.http_status()),
+            Error::CloudflareServer { status, .. } => Some(*status),
+            Error::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+
+    /// 出错的 IP 地址来源名称，仅对 IP 地址来源错误有效
+    pub fn source_name(&self) -> Option<&str> {
+        match self {
+            Error::Context { inner, .. } => inner.source_name(),
+            Error::Source { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// 是否表示 Cloudflare 中不存在需要更新的记录，即响应代码 81044
+    ///
+    /// 仅凭 HTTP 404 无法区分记录不存在与 zone id 有误（响应代码 7003 等），因此不视为记录不存在。
+    pub fn is_record_missing(&self) -> bool {
+        match self {
+            Error::Context { inner, .. } => inner.is_record_missing(),
+            Error::CloudflareApi { codes, .. } => codes.contains(&RECORD_MISSING_CODE),
+            _ => false,
+        }
+    }
+
+    /// 是否表示 API token 认证失败，即 HTTP 401、403 或响应代码 1000、9109、10000
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            Error::Context { inner, status, .. } => {
+                matches!(status, Some(401 | 403)) || inner.is_auth_failure()
+            }
+            Error::CloudflareApi { codes, .. } => {
+                codes.iter().any(|code| AUTH_FAILURE_CODES.contains(code))
+            }
+            _ => false,
+        }
+    }
+
+    /// Cloudflare 要求的最短重试等待时间，单位秒，仅对响应头中带有 `Retry-After` 的 429 错误有效
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Error::Context { inner, .. } => inner.retry_after(),
+            Error::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// 重试是否可能成功
+    ///
+    /// 配置错误、文件读写错误、被拒绝发布的地址及表示 token 或记录无效的 Cloudflare 错误不可重试，
+    /// 其余错误均视为暂时性错误。
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Context { inner, .. } => inner.is_retryable(),
+            Error::Config { .. } | Error::Io { .. } | Error::Task { .. } => false,
+            Error::CloudflareApi { codes, .. } => !codes
+                .iter()
+                .any(|code| FATAL_CLOUDFLARE_CODES.contains(code)),
+            Error::Source { rejected, .. } => !rejected,
+            Error::Network { .. }
+            | Error::CloudflareServer { .. }
+            | Error::RateLimited { .. }
+            | Error::Parse { .. }
+            | Error::Uninitialized => true,
+        }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.0)
+        match self {
+            Error::Config { message }
+            | Error::Network { message, .. }
+            | Error::CloudflareApi { message, .. }
+            | Error::CloudflareServer { message, .. }
+            | Error::RateLimited { message, .. }
+            | Error::Parse { message, .. }
+            | Error::Source { message, .. }
+            | Error::Io { message, .. }
+            | Error::Task { message } => f.write_str(message),
+            Error::Uninitialized => f.write_str("Updater 未初始化"),
+            Error::Context {
+                nickname,
+                phase,
+                status,
+                inner,
+            } => {
+                write!(f, "[{}][{}] {}", nickname, phase, inner)?;
+                if let Some(status) = status {
+                    write!(f, "（HTTP {}）", status)?;
+                }
+                Ok(())
+            }
+        }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Network {
+                source: Some(source),
+                ..
+            }
+            | Error::Parse {
+                source: Some(source),
+                ..
+            } => Some(source.as_ref()),
+            Error::Io { source, .. } => Some(source.as_ref()),
+            Error::Context { inner, .. } => inner.source(),
+            _ => None,
+        }
+    }
+}
 
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
-        Self::new_string(format!("HTTP 请求出错：{value}"))
+        let message = format!("HTTP 请求出错：{value}");
+        if value.is_builder() {
+            Self::config(message)
+        } else if value.is_decode() {
+            Self::parse(message, value)
+        } else {
+            Self::network(message, value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::{Error, ErrorKind, Phase};
+
+    #[test]
+    fn test_reqwest_classification() {
+        let err = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let err = Error::from(err);
+        assert_eq!(err.kind(), ErrorKind::Config);
+        assert!(!err.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_network_classification() {
+        // 绑定后立即释放端口，保证连接被拒绝
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let err = reqwest::get(format!("http://{}", address))
+            .await
+            .unwrap_err();
+        let err = Error::cloudflare_network_failure(err);
+
+        assert_eq!(err.kind(), ErrorKind::Network);
+        assert!(err.is_retryable());
+        assert!(err.source().is_some());
+        assert!(err
+            .to_string()
+            .starts_with("访问 Cloudflare 失败，请确认网络连接正常，错误原因："));
+    }
+
+    #[test]
+    fn test_cloudflare_classification() {
+        let err =
+            Error::cloudflare_update_failure(vec![10000], vec!["Authentication error".to_string()]);
+        assert_eq!(err.kind(), ErrorKind::CloudflareApi);
+        assert!(!err.is_retryable());
+        assert_eq!(
+            err.to_string(),
+            "更新 Cloudflare DNS 记录失败。错误原因：Cloudflare 响应代码 10000：Authentication error"
+        );
+
+        let err = Error::cloudflare_record_failure(
+            vec![1000, 81044],
+            vec!["a".to_string(), "Record does not exist.".to_string()],
+        );
+        assert!(!err.is_retryable());
+        assert_eq!(
+            err.to_string(),
+            "获取 Cloudflare DNS 记录详情失败，错误原因：Cloudflare 响应代码 1000：a；Cloudflare 响应代码 81044：Record does not exist."
+        );
+
+        let err = Error::cloudflare_update_failure(vec![], vec![]);
+        assert!(err.is_retryable());
+        assert_eq!(
+            err.to_string(),
+            "更新 Cloudflare DNS 记录失败。错误原因：未知原因"
+        );
+
+        let err = Error::cloudflare_server_failure(
+            reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            Some("8a1b2c3d4e5f6789-SJC"),
+        );
+        assert_eq!(err.kind(), ErrorKind::CloudflareServer);
+        assert!(err.is_retryable());
+        assert_eq!(err.http_status(), Some(503));
+        assert!(err
+            .to_string()
+            .starts_with("Cloudflare API 返回服务器错误（HTTP 503 Service Unavailable，Ray ID：8a1b2c3d4e5f6789-SJC）"));
+
+        let err = Error::cloudflare_rate_limited(Some(30)).context("home", Phase::Fetch);
+        assert_eq!(err.kind(), ErrorKind::RateLimited);
+        assert!(err.is_retryable());
+        assert_eq!(err.retry_after(), Some(30));
+        assert_eq!(err.http_status(), Some(429));
+        assert!(err.to_string().contains("等待 30 秒"), "{}", err);
+        assert_eq!(Error::cloudflare_rate_limited(None).retry_after(), None);
+
+        let err = Error::cloudflare_deserialized_failure(
+            serde_json::from_str::<u32>("<html>").unwrap_err(),
+        );
+        assert_eq!(err.kind(), ErrorKind::Parse);
+        assert!(err.is_retryable());
+    }
+
+    #[test]
+    fn test_context() {
+        let err = Error::cloudflare_update_failure(vec![9109], vec!["Invalid access token".into()])
+            .context("home", Phase::Update)
+            .with_http_status(reqwest::StatusCode::FORBIDDEN);
+        assert_eq!(
+            err.to_string(),
+            "[home][update] 更新 Cloudflare DNS 记录失败。错误原因：Cloudflare 响应代码 9109：Invalid access token（HTTP 403）"
+        );
+        assert_eq!(err.kind(), ErrorKind::CloudflareApi);
+        assert!(!err.is_retryable());
+        assert_eq!(err.http_status(), Some(403));
+        assert_eq!(err.source_name(), None);
+
+        // 已附加的上下文不会被覆盖
+        let err = Error::uninitialized()
+            .context("home", Phase::Source)
+            .context("office", Phase::Fetch);
+        assert_eq!(err.to_string(), "[home][source] Updater 未初始化");
+        assert_eq!(err.kind(), ErrorKind::Uninitialized);
+
+        // 未附加上下文时忽略 HTTP 状态码
+        let err = Error::config("无效配置").with_http_status(reqwest::StatusCode::BAD_REQUEST);
+        assert_eq!(err.to_string(), "无效配置");
+
+        // 附加说明不改变错误类型及上下文
+        let err = Error::ip_source("Mock", "无可用地址")
+            .context("home", Phase::Source)
+            .with_prefix("已尝试 3 次：");
+        assert_eq!(err.to_string(), "[home][source] 已尝试 3 次：无可用地址");
+        assert_eq!(err.kind(), ErrorKind::Source);
+        assert_eq!(err.source_name(), Some("Mock"));
+        assert_eq!(err.http_status(), None);
+    }
+
+    #[test]
+    fn test_other_classification() {
+        assert!(Error::uninitialized().is_retryable());
+        assert_eq!(Error::uninitialized().to_string(), "Updater 未初始化");
+        assert!(!Error::config("无效配置").is_retryable());
+
+        let err = Error::ip_source("Standalone Server", "独立服务器响应消息并非合法 IP 地址");
+        assert_eq!(err.kind(), ErrorKind::Source);
+        assert!(err.is_retryable());
+
+        // 被拒绝发布的地址重试后依然会被拒绝
+        let err =
+            Error::ip_source_rejected("Mock", "获取的 IP 地址 10.0.0.2 为私有地址，已拒绝发布")
+                .context("home", Phase::Source);
+        assert_eq!(err.kind(), ErrorKind::Source);
+        assert_eq!(err.source_name(), Some("Mock"));
+        assert!(!err.is_retryable());
+
+        let err = Error::io(
+            "读取证书失败",
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        );
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert!(!err.is_retryable());
+        assert!(err.source().is_some());
+
+        let err = Error::notification_test_failure(2);
+        assert_eq!(err.kind(), ErrorKind::Task);
+        assert_eq!(err.to_string(), "2 个通知渠道测试消息发送失败");
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn test_record_missing() {
+        let err =
+            Error::cloudflare_record_failure(vec![81044], vec!["Record does not exist.".into()])
+                .context("home", Phase::Fetch);
+        assert!(err.is_record_missing());
+        assert!(!err.is_retryable());
+
+        // zone id 有误时同样返回 404，不视为记录不存在
+        let err = Error::cloudflare_record_failure(vec![7003], vec!["Could not route".into()])
+            .context("home", Phase::Fetch)
+            .with_http_status(reqwest::StatusCode::NOT_FOUND);
+        assert!(!err.is_record_missing());
+
+        let err = Error::cloudflare_record_missing("不存在 home.example.com 的 A 记录".into());
+        assert!(err.is_record_missing());
+        assert_eq!(err.to_string(), "不存在 home.example.com 的 A 记录");
+        assert!(!Error::config("无效配置").is_record_missing());
+        assert!(
+            !Error::cloudflare_record_failure(vec![9109], vec!["Invalid access token".into()])
+                .is_record_missing()
+        );
+    }
+
+    #[test]
+    fn test_auth_failure() {
+        assert!(
+            Error::cloudflare_token_failure(vec![9109], vec!["Invalid access token".into()])
+                .context("home", Phase::Fetch)
+                .is_auth_failure()
+        );
+        assert!(Error::cloudflare_token_failure(vec![], vec![])
+            .context("home", Phase::Fetch)
+            .with_http_status(reqwest::StatusCode::FORBIDDEN)
+            .is_auth_failure());
+
+        // 限流、服务器错误及其他 API 错误无法说明 token 无效
+        assert!(!Error::cloudflare_rate_limited(Some(30))
+            .context("home", Phase::Fetch)
+            .is_auth_failure());
+        assert!(
+            !Error::cloudflare_token_failure(vec![971], vec!["Please wait".into()])
+                .context("home", Phase::Fetch)
+                .with_http_status(reqwest::StatusCode::TOO_MANY_REQUESTS)
+                .is_auth_failure()
+        );
+        assert!(
+            !Error::cloudflare_server_failure(reqwest::StatusCode::BAD_GATEWAY, None)
+                .is_auth_failure()
+        );
     }
 }
@@ -0,0 +1,245 @@
+use std::{collections::VecDeque, net::IpAddr};
+
+use chrono::{DateTime, Duration, Local};
+
+/// 默认保留的 IP 地址变化记录数量
+pub const DEFAULT_HISTORY_DEPTH: usize = 50;
+
+/// 单次 IP 地址变化记录
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IpChange {
+    /// 变化时间
+    pub time: DateTime<Local>,
+    /// 变化前的 IP 地址
+    pub old_ip: IpAddr,
+    /// 变化后的 IP 地址
+    pub new_ip: IpAddr,
+}
+
+/// IP 地址变化历史
+///
+/// 仅保留最近 `depth` 条记录，超出时丢弃最早的记录。
+/// 序列化时仅包含记录列表，新增字段必须提供默认值，以便读取旧版本保存的数据。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct IpHistory {
+    #[serde(skip, default = "default_depth")]
+    depth: usize,
+    #[serde(default)]
+    changes: VecDeque<IpChange>,
+}
+
+fn default_depth() -> usize {
+    DEFAULT_HISTORY_DEPTH
+}
+
+impl Default for IpHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_DEPTH)
+    }
+}
+
+impl IpHistory {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            changes: VecDeque::with_capacity(depth.min(DEFAULT_HISTORY_DEPTH)),
+        }
+    }
+
+    /// 记录一次 IP 地址变化
+    pub fn push(&mut self, change: IpChange) {
+        self.changes.push_back(change);
+        while self.changes.len() > self.depth {
+            self.changes.pop_front();
+        }
+    }
+
+    /// 恢复保存的记录，保留当前的记录数量上限
+    pub fn restore(&mut self, saved: IpHistory) {
+        self.changes.clear();
+        saved
+            .changes
+            .into_iter()
+            .for_each(|change| self.push(change));
+    }
+
+    /// 按时间顺序获取所有记录
+    #[cfg(test)]
+    pub fn changes(&self) -> impl DoubleEndedIterator<Item = &IpChange> {
+        self.changes.iter()
+    }
+
+    /// 最近一次变化
+    pub fn last(&self) -> Option<&IpChange> {
+        self.changes.back()
+    }
+
+    /// `now` 之前 `period` 时间内的变化次数
+    pub fn changes_within(&self, now: DateTime<Local>, period: Duration) -> usize {
+        let since = now - period;
+        self.changes
+            .iter()
+            .rev()
+            .take_while(|change| change.time > since)
+            .count()
+    }
+
+    /// 相邻两次变化间隔的中位数，记录少于两条时返回 `None`
+    pub fn median_interval(&self) -> Option<Duration> {
+        let mut intervals = self
+            .changes
+            .iter()
+            .zip(self.changes.iter().skip(1))
+            .map(|(previous, next)| next.time - previous.time)
+            .collect::<Vec<_>>();
+        if intervals.is_empty() {
+            return None;
+        }
+
+        intervals.sort();
+        let middle = intervals.len() / 2;
+        if intervals.len() % 2 == 0 {
+            Some((intervals[middle - 1] + intervals[middle]) / 2)
+        } else {
+            Some(intervals[middle])
+        }
+    }
+
+    /// 统计信息
+    pub fn statistics(&self, now: DateTime<Local>) -> IpHistoryStatistics {
+        IpHistoryStatistics {
+            last_24h: self.changes_within(now, Duration::hours(24)),
+            last_7d: self.changes_within(now, Duration::days(7)),
+            median_interval: self.median_interval(),
+        }
+    }
+}
+
+/// IP 地址变化统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpHistoryStatistics {
+    /// 最近 24 小时内变化次数
+    pub last_24h: usize,
+    /// 最近 7 天内变化次数
+    pub last_7d: usize,
+    /// 相邻两次变化间隔的中位数
+    pub median_interval: Option<Duration>,
+}
+
+/// 将时长格式化为 `1 天 2 小时 3 分 4 秒`，省略为零的高位单位
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let (days, hours, minutes, seconds) = (
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    );
+
+    if days > 0 {
+        format!("{} 天 {} 小时 {} 分 {} 秒", days, hours, minutes, seconds)
+    } else if hours > 0 {
+        format!("{} 小时 {} 分 {} 秒", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{} 分 {} 秒", minutes, seconds)
+    } else {
+        format!("{} 秒", seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, TimeZone};
+
+    use super::{format_duration, IpChange, IpHistory};
+
+    fn change(hours_ago: i64) -> IpChange {
+        IpChange {
+            time: Local.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap()
+                - Duration::hours(hours_ago),
+            old_ip: "1.1.1.1".parse().unwrap(),
+            new_ip: "2.2.2.2".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut history = IpHistory::new(3);
+        (0..5)
+            .rev()
+            .for_each(|hours_ago| history.push(change(hours_ago)));
+        assert_eq!(history.changes().count(), 3);
+        assert_eq!(history.changes().next(), Some(&change(2)));
+        assert_eq!(history.last(), Some(&change(0)));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let now = Local.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let mut history = IpHistory::default();
+        let statistics = history.statistics(now);
+        assert_eq!(statistics.last_24h, 0);
+        assert_eq!(statistics.median_interval, None);
+
+        // 间隔依次为 96、48、20、4 小时
+        [168, 72, 24, 4, 0]
+            .into_iter()
+            .for_each(|hours_ago| history.push(change(hours_ago)));
+        let statistics = history.statistics(now);
+        assert_eq!(statistics.last_24h, 2);
+        assert_eq!(statistics.last_7d, 4);
+        assert_eq!(statistics.median_interval, Some(Duration::hours(34)));
+
+        history.push(change(-1));
+        assert_eq!(
+            history.statistics(now).median_interval,
+            Some(Duration::hours(20))
+        );
+    }
+
+    #[test]
+    fn test_serialization_compatibility() {
+        let mut history = IpHistory::new(2);
+        history.push(change(1));
+        let json = serde_json::to_string(&history).unwrap();
+        assert!(!json.contains("depth"));
+
+        let restored = serde_json::from_str::<IpHistory>(&json).unwrap();
+        assert_eq!(restored.changes().collect::<Vec<_>>(), vec![&change(1)]);
+
+        // 缺少字段或包含未知字段时均可读取
+        let restored = serde_json::from_str::<IpHistory>(r#"{"unknown":1}"#).unwrap();
+        assert_eq!(restored, IpHistory::default());
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut saved = IpHistory::default();
+        (0..5)
+            .rev()
+            .for_each(|hours_ago| saved.push(change(hours_ago)));
+        let saved = serde_json::from_str(&serde_json::to_string(&saved).unwrap()).unwrap();
+
+        // 按配置的数量上限保留最近的记录
+        let mut history = IpHistory::new(3);
+        history.push(change(10));
+        history.restore(saved);
+        assert_eq!(
+            history.changes().collect::<Vec<_>>(),
+            vec![&change(2), &change(1), &change(0)]
+        );
+        history.push(change(-1));
+        assert_eq!(history.changes().count(), 3);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::seconds(5)), "5 秒");
+        assert_eq!(format_duration(Duration::seconds(3725)), "1 小时 2 分 5 秒");
+        assert_eq!(
+            format_duration(Duration::days(2) + Duration::minutes(3)),
+            "2 天 0 小时 3 分 0 秒"
+        );
+        assert_eq!(format_duration(Duration::seconds(-5)), "0 秒");
+    }
+}
@@ -0,0 +1,592 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    net::IpAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::{header::HeaderValue, Client};
+use serde::{
+    de::{self, Visitor},
+    Deserialize,
+};
+
+use super::{
+    error::Error,
+    network::NetworkMode,
+    resolve::{CfResolve, FamilyResolver, SystemResolver},
+};
+
+/// HTTP 客户端连接参数，参数相同的请求共享同一客户端及其连接池
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClientKey {
+    /// 绑定的本地 IP 地址
+    pub bind_address: Option<IpAddr>,
+    /// 请求超时时间，为 `None` 时不限制
+    pub timeout: Option<Duration>,
+    /// 代理设置
+    pub proxy: ProxySetting,
+    /// 是否应用 Cloudflare API 域名解析方式
+    pub cf_resolve: bool,
+    /// 是否应用 Cloudflare API 连接保活配置
+    pub keepalive: bool,
+    /// 请求的 User-Agent，为 `None` 时使用 reqwest 的默认值
+    pub user_agent: Option<Arc<str>>,
+    /// TLS 选项
+    pub tls: TlsOptions,
+}
+
+/// reqwest 默认的空闲连接过期时间
+const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// HTTP 连接池参数，应用于注册表创建的所有客户端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolOptions {
+    /// 空闲连接过期时间，为 `None` 时不过期
+    pub idle_timeout: Option<Duration>,
+    /// 每个主机保留的空闲连接数量上限
+    pub max_idle_per_host: usize,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Some(DEFAULT_POOL_IDLE_TIMEOUT),
+            max_idle_per_host: usize::MAX,
+        }
+    }
+}
+
+/// 访问代理
+///
+/// 保存代理地址及认证信息而非 [`reqwest::Proxy`]，以便作为 [`ClientKey`] 的一部分比较，
+/// 配置相同的代理共享同一客户端。代理同时应用于 HTTP 及 HTTPS 请求。
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Proxy {
+    url: String,
+    /// Basic 认证的用户名及密码
+    basic_auth: Option<(String, String)>,
+}
+
+impl Proxy {
+    fn to_reqwest(&self) -> Result<reqwest::Proxy, Error> {
+        let mut proxy = reqwest::Proxy::all(self.url.as_str())?;
+        if let Some((username, password)) = &self.basic_auth {
+            proxy = proxy.basic_auth(username, password);
+        }
+        Ok(proxy)
+    }
+}
+
+impl Debug for Proxy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Proxy")
+            .field("url", &self.url)
+            .field(
+                "username",
+                &self.basic_auth.as_ref().map(|(username, _)| username),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'de> Deserialize<'de> for Proxy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ProxyVisitor;
+        impl<'de> Visitor<'de> for ProxyVisitor {
+            type Value = Proxy;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("访问代理配置")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::MapAccess<'de>,
+            {
+                let mut proxy_url = None;
+                let mut basic_auth_username = None;
+                let mut basic_auth_password = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "url" => proxy_url = Some(map.next_value::<String>()?),
+                        "username" => basic_auth_username = Some(map.next_value::<String>()?),
+                        "password" => basic_auth_password = Some(map.next_value::<String>()?),
+                        _ => {}
+                    }
+                }
+
+                let Some(proxy_url) = proxy_url else {
+                    return Err(de::Error::missing_field("proxy.url"));
+                };
+                if reqwest::Proxy::all(proxy_url.as_str()).is_err() {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Str(proxy_url.as_str()),
+                        &"http, https or socks proxy url",
+                    ));
+                }
+
+                let basic_auth = match (basic_auth_username, basic_auth_password) {
+                    (None, None) => None,
+                    (None, Some(_)) => return Err(de::Error::missing_field("proxy.username")),
+                    (Some(_), None) => return Err(de::Error::missing_field("proxy.password")),
+                    (Some(username), Some(password)) => Some((username, password)),
+                };
+
+                Ok(Proxy {
+                    url: proxy_url,
+                    basic_auth,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(ProxyVisitor)
+    }
+}
+
+/// 客户端的代理设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(try_from = "ProxySettingValue")]
+pub enum ProxySetting {
+    /// 使用系统代理，即 reqwest 默认读取的 `HTTP_PROXY`、`HTTPS_PROXY` 等环境变量
+    #[default]
+    System,
+    /// 不使用任何代理，包括系统代理
+    Disabled,
+    /// 使用指定代理
+    Proxy(Proxy),
+}
+
+/// 配置中的代理设置，`false` 表示不使用任何代理
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ProxySettingValue {
+    Enabled(bool),
+    Proxy(Proxy),
+}
+
+impl TryFrom<ProxySettingValue> for ProxySetting {
+    type Error = &'static str;
+
+    fn try_from(value: ProxySettingValue) -> Result<Self, Self::Error> {
+        match value {
+            ProxySettingValue::Enabled(false) => Ok(ProxySetting::Disabled),
+            ProxySettingValue::Enabled(true) => Err("代理设置仅可为 false 或代理配置"),
+            ProxySettingValue::Proxy(proxy) => Ok(ProxySetting::Proxy(proxy)),
+        }
+    }
+}
+
+/// 额外信任的 CA 证书
+///
+/// 保存 PEM 内容而非 [`reqwest::Certificate`]，以便作为 [`ClientKey`] 的一部分比较。
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct CaCert {
+    path: Arc<Path>,
+    pem: Arc<[u8]>,
+}
+
+impl CaCert {
+    /// 读取并解析 PEM 格式的证书文件，在加载配置时调用，使路径或内容有误时立即失败
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let pem = std::fs::read(path)
+            .map_err(|err| Error::config(format!("CA 证书读取失败：{} {}", err, path.display())))?;
+        reqwest::Certificate::from_pem(&pem)
+            .map_err(|err| Error::config(format!("CA 证书解析失败：{} {}", err, path.display())))?;
+        Ok(Self {
+            path: Arc::from(path),
+            pem: Arc::from(pem),
+        })
+    }
+}
+
+impl Debug for CaCert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CaCert").field(&self.path).finish()
+    }
+}
+
+/// TLS 选项，由基于 HTTP 的 IP 地址来源使用
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TlsOptions {
+    /// 额外信任的 CA 证书，用于使用内部 CA 签发证书的服务器
+    pub ca_cert: Option<CaCert>,
+    /// 是否跳过证书校验，不推荐使用
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsOptions {
+    /// 将 TLS 选项应用于客户端
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, Error> {
+        if let Some(ca_cert) = &self.ca_cert {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&ca_cert.pem)?);
+        }
+        if self.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        Ok(builder)
+    }
+}
+
+/// HTTP 客户端注册表
+///
+/// 按连接参数缓存 [`Client`]，避免每个 IP 地址来源各自创建客户端，
+/// 域名较多时产生大量空闲连接池。[`Client`] 内部使用引用计数，克隆开销很小。
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    cf_resolve: Option<CfResolve>,
+    /// 连接保活间隔
+    keepalive: Option<Duration>,
+    pool: PoolOptions,
+    /// 网络地址族模式，应用于所有客户端的域名解析
+    network: NetworkMode,
+    clients: Mutex<HashMap<ClientKey, Client>>,
+}
+
+impl ClientRegistry {
+    pub fn new(
+        cf_resolve: Option<CfResolve>,
+        keepalive: Option<Duration>,
+        pool: PoolOptions,
+        network: NetworkMode,
+    ) -> Self {
+        Self {
+            cf_resolve,
+            keepalive,
+            pool,
+            network,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 获取指定连接参数的客户端，不存在时创建
+    pub fn client(&self, key: ClientKey) -> Result<Client, Error> {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let client = self.build(&key)?;
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// 指定连接参数的客户端实际使用的连接池参数
+    ///
+    /// 启用连接保活的客户端空闲连接不过期，由定时保活请求维持连接。
+    fn pool_options(&self, key: &ClientKey) -> PoolOptions {
+        let mut pool = self.pool;
+        if key.keepalive && self.keepalive.is_some() {
+            pool.idle_timeout = None;
+        }
+        pool
+    }
+
+    fn build(&self, key: &ClientKey) -> Result<Client, Error> {
+        let pool = self.pool_options(key);
+        let mut builder = reqwest::ClientBuilder::new()
+            .local_address(key.bind_address)
+            .pool_idle_timeout(pool.idle_timeout)
+            .pool_max_idle_per_host(pool.max_idle_per_host);
+        if let Some(timeout) = key.timeout {
+            builder = builder.timeout(timeout);
+        }
+        match &key.proxy {
+            ProxySetting::System => {}
+            ProxySetting::Disabled => builder = builder.no_proxy(),
+            ProxySetting::Proxy(proxy) => builder = builder.proxy(proxy.to_reqwest()?),
+        }
+        if key.keepalive {
+            if let Some(keepalive) = self.keepalive {
+                builder = builder.tcp_keepalive(keepalive);
+            }
+        }
+        if self.network != NetworkMode::Dual {
+            builder = builder.dns_resolver(Arc::new(FamilyResolver::new(
+                Arc::new(SystemResolver),
+                self.network,
+            )));
+        }
+        if let Some(user_agent) = &key.user_agent {
+            let user_agent = HeaderValue::from_str(user_agent)
+                .map_err(|_| Error::config(format!("无效 User-Agent：{}", user_agent)))?;
+            builder = builder.user_agent(user_agent);
+        }
+        if key.cf_resolve {
+            if let Some(cf_resolve) = &self.cf_resolve {
+                builder = cf_resolve.apply(builder, key.bind_address, self.network)?;
+            }
+        }
+        builder = key.tls.apply(builder)?;
+
+        Ok(builder.build()?)
+    }
+
+    /// 已创建的客户端数量
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use reqwest::Client;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::{ClientKey, ClientRegistry, PoolOptions, ProxySetting, TlsOptions};
+    use crate::libs::network::NetworkMode;
+
+    fn key(keepalive: bool) -> ClientKey {
+        ClientKey {
+            bind_address: None,
+            timeout: None,
+            proxy: ProxySetting::System,
+            cf_resolve: false,
+            keepalive,
+            user_agent: None,
+            tls: TlsOptions::default(),
+        }
+    }
+
+    /// 通过 `client` 依次发送两次请求，间隔 `pause`，返回服务器接受的连接数量
+    ///
+    /// 连接池保留空闲连接且未过期时第二次请求复用第一次的连接。
+    pub(crate) async fn connections(client: &Client, pause: Duration) -> usize {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let server = {
+            let accepted = accepted.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (mut stream, _) = listener.accept().await.unwrap();
+                    accepted.fetch_add(1, Ordering::SeqCst);
+                    // 同一连接上的每个请求均以保持连接的空响应回复
+                    tokio::spawn(async move {
+                        let mut request = Vec::new();
+                        let mut buf = [0; 1024];
+                        loop {
+                            match stream.read(&mut buf).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => request.extend_from_slice(&buf[..n]),
+                            }
+                            if request.ends_with(b"\r\n\r\n") {
+                                request.clear();
+                                let response = b"HTTP/1.1 204 No Content\r\n\r\n";
+                                if stream.write_all(response).await.is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                    });
+                }
+            })
+        };
+
+        client.get(&url).send().await.unwrap();
+        tokio::time::sleep(pause).await;
+        client.get(&url).send().await.unwrap();
+        server.abort();
+        accepted.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_pool_client() {
+        let client = |pool| {
+            ClientRegistry::new(None, None, pool, NetworkMode::Dual)
+                .client(key(false))
+                .unwrap()
+        };
+        let pause = Duration::from_millis(20);
+
+        // 默认保留空闲连接，第二次请求复用连接
+        assert_eq!(connections(&client(PoolOptions::default()), pause).await, 1);
+
+        // 不保留空闲连接时每次请求均建立新连接
+        let pool = PoolOptions {
+            max_idle_per_host: 0,
+            ..PoolOptions::default()
+        };
+        assert_eq!(connections(&client(pool), pause).await, 2);
+
+        // 空闲连接过期后建立新连接
+        let pool = PoolOptions {
+            idle_timeout: Some(Duration::from_millis(50)),
+            ..PoolOptions::default()
+        };
+        assert_eq!(
+            connections(&client(pool), Duration::from_millis(300)).await,
+            2
+        );
+    }
+
+    #[test]
+    fn test_pool_options() {
+        let pool = PoolOptions {
+            idle_timeout: Some(Duration::from_secs(5)),
+            max_idle_per_host: 1,
+        };
+
+        // 配置的连接池参数应用于所有客户端
+        let clients = ClientRegistry::new(None, None, pool, NetworkMode::Dual);
+        assert_eq!(clients.pool_options(&key(false)), pool);
+        assert_eq!(clients.pool_options(&key(true)), pool);
+        assert!(clients.client(key(false)).is_ok());
+
+        // 启用连接保活的客户端空闲连接不过期
+        let clients =
+            ClientRegistry::new(None, Some(Duration::from_secs(30)), pool, NetworkMode::Dual);
+        assert_eq!(clients.pool_options(&key(false)), pool);
+        assert_eq!(
+            clients.pool_options(&key(true)),
+            PoolOptions {
+                idle_timeout: None,
+                max_idle_per_host: 1,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_user_agent() {
+        // 返回收到的请求头的服务器
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ip", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap().to_ascii_lowercase()
+        });
+
+        let clients = ClientRegistry::default();
+        let client = clients
+            .client(ClientKey {
+                user_agent: Some("ddns4cf-test/1.0".into()),
+                ..key(false)
+            })
+            .unwrap();
+        client.get(url).send().await.unwrap();
+        let request = server.await.unwrap();
+        assert!(
+            request.contains("user-agent: ddns4cf-test/1.0\r\n"),
+            "{}",
+            request
+        );
+
+        // User-Agent 不同的来源不共享客户端
+        assert!(clients.client(key(false)).is_ok());
+        assert_eq!(clients.len(), 2);
+        assert!(clients
+            .client(ClientKey {
+                user_agent: Some("bad\nagent".into()),
+                ..key(false)
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_proxy_setting() {
+        let setting = |text: &str| json5::from_str::<ProxySetting>(text);
+
+        assert_eq!(setting("false").unwrap(), ProxySetting::Disabled);
+        assert!(setting("true").is_err());
+        assert!(setting("{ username: 'user', password: 'pass' }").is_err());
+        assert!(setting("{ url: 'socks5://127.0.0.1:1080', username: 'user' }").is_err());
+
+        let ProxySetting::Proxy(proxy) =
+            setting("{ url: 'socks5://127.0.0.1:1080', username: 'user', password: 'hunter2' }")
+                .unwrap()
+        else {
+            panic!("expected proxy");
+        };
+        // 调试输出不包含密码
+        let debug = format!("{:?}", proxy);
+        assert!(debug.contains("socks5://127.0.0.1:1080"), "{}", debug);
+        assert!(!debug.contains("hunter2"), "{}", debug);
+    }
+
+    #[tokio::test]
+    async fn test_proxy() {
+        // 记录收到的请求行的 HTTP 代理
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let proxy =
+            || json5::from_str::<ProxySetting>(&format!("{{ url: '{}' }}", proxy_url)).unwrap();
+        let clients = ClientRegistry::default();
+        let client = clients
+            .client(ClientKey {
+                proxy: proxy(),
+                ..key(false)
+            })
+            .unwrap();
+        // 目标地址无法直接访问，仅能经由代理完成请求
+        client
+            .get("http://ip.example.invalid/ip")
+            .send()
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(
+            request.starts_with("GET http://ip.example.invalid/ip HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+
+        // 代理配置相同的来源共享客户端
+        assert!(clients
+            .client(ClientKey {
+                proxy: proxy(),
+                ..key(false)
+            })
+            .is_ok());
+        assert_eq!(clients.len(), 1);
+        assert!(clients
+            .client(ClientKey {
+                proxy: ProxySetting::Disabled,
+                ..key(false)
+            })
+            .is_ok());
+        assert_eq!(clients.len(), 2);
+    }
+}
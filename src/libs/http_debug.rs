@@ -0,0 +1,212 @@
+use std::{fmt::Write, sync::Arc};
+
+use bytes::Bytes;
+use log::info;
+use reqwest::{header::HeaderMap, Client, Request, StatusCode};
+
+/// 默认请求及响应体最大输出长度，单位字节
+pub const DEFAULT_BODY_LIMIT: usize = 4096;
+/// 脱敏后的替代文本
+const REDACTED: &str = "<redacted>";
+/// 需要脱敏的请求头
+const SENSITIVE_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-auth-key",
+    "x-auth-email",
+];
+
+/// HTTP 请求及响应调试日志
+///
+/// 启用后，所有 Cloudflare 及 IP 地址来源的请求均输出请求方法、地址、请求体、响应状态及响应体。
+/// 敏感请求头及配置中的所有 token 均会被脱敏，请求体及响应体超过长度限制时将被截断。
+#[derive(Debug, Clone)]
+pub struct HttpDebug {
+    body_limit: usize,
+    /// 需要脱敏的内容
+    secrets: Arc<[String]>,
+}
+
+impl HttpDebug {
+    pub fn new<I>(body_limit: usize, secrets: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self {
+            body_limit,
+            secrets: secrets
+                .into_iter()
+                .filter(|secret| !secret.is_empty())
+                .collect(),
+        }
+    }
+
+    /// 追加需要脱敏的内容，用于仅个别来源使用的凭据，如写在请求体中的会话 ID
+    pub fn with_secret(&self, secret: &str) -> Self {
+        Self::new(
+            self.body_limit,
+            self.secrets
+                .iter()
+                .cloned()
+                .chain(std::iter::once(secret.to_string())),
+        )
+    }
+
+    /// 替换文本中的所有敏感内容
+    fn redact(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+    }
+
+    /// 脱敏并截断请求体或响应体
+    fn body(&self, body: &[u8]) -> String {
+        let text = String::from_utf8_lossy(body);
+        // 先脱敏再截断，避免敏感内容被截断后无法匹配
+        let text = self.redact(&text);
+        if text.len() <= self.body_limit {
+            return text;
+        }
+
+        let mut end = self.body_limit;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...（共 {} 字节，已截断）", &text[..end], text.len())
+    }
+
+    fn headers(&self, headers: &HeaderMap) -> String {
+        let mut output = String::new();
+        headers.iter().for_each(|(name, value)| {
+            let value = if value.is_sensitive() || SENSITIVE_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                self.redact(&String::from_utf8_lossy(value.as_bytes()))
+            };
+            let _ = write!(output, " {}: {};", name, value);
+        });
+        output
+    }
+
+    fn format_request(&self, request: &Request) -> String {
+        let mut output = format!(
+            "HTTP 请求：{} {}",
+            request.method(),
+            self.redact(request.url().as_str())
+        );
+        if !request.headers().is_empty() {
+            let _ = write!(output, "，请求头：{}", self.headers(request.headers()));
+        }
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            let _ = write!(output, "，请求体：{}", self.body(body));
+        }
+        output
+    }
+
+    fn format_response(&self, request: &str, status: StatusCode, body: &[u8]) -> String {
+        format!(
+            "HTTP 响应：{} {}，响应体：{}",
+            self.redact(request),
+            status,
+            self.body(body)
+        )
+    }
+}
+
+/// 发送请求并读取完整响应，启用 HTTP 调试日志时输出请求及响应内容
+///
+/// 所有 Cloudflare 及 IP 地址来源的请求均应通过该函数发送，以保证调试日志覆盖每一次请求。
+pub async fn execute(
+    client: &Client,
+    request: Request,
+    debug: Option<&HttpDebug>,
+) -> Result<(StatusCode, HeaderMap, Bytes), reqwest::Error> {
+    let target = format!("{} {}", request.method(), request.url());
+    if let Some(debug) = debug {
+        info!("{}", debug.format_request(&request));
+    }
+
+    let response = client.execute(request).await?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+
+    if let Some(debug) = debug {
+        info!("{}", debug.format_response(&target, status, &body));
+    }
+
+    Ok((status, headers, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{header, Client, StatusCode};
+
+    use super::HttpDebug;
+
+    const TOKEN: &str = "s3cr3t-T0ken_value";
+
+    #[test]
+    fn test_token_never_logged() {
+        let debug = HttpDebug::new(4096, [TOKEN.to_string(), String::new()]);
+        let request = Client::new()
+            .put(format!(
+                "https://api.cloudflare.com/client/v4/zones/zone/dns_records/id?token={}",
+                TOKEN
+            ))
+            .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+            .header("X-Auth-Key", "global-key")
+            .header("X-Custom", format!("prefix-{}", TOKEN))
+            .header("X-Api-Key", {
+                let mut value = header::HeaderValue::from_static("source-key");
+                value.set_sensitive(true);
+                value
+            })
+            .body(format!(r#"{{"content":"1.1.1.1","token":"{}"}}"#, TOKEN))
+            .build()
+            .unwrap();
+
+        let output = debug.format_request(&request);
+        assert!(!output.contains(TOKEN), "{}", output);
+        assert!(!output.contains("global-key"), "{}", output);
+        assert!(!output.contains("source-key"), "{}", output);
+        assert!(
+            output.contains("PUT https://api.cloudflare.com/client/v4/zones/zone/dns_records/id")
+        );
+        assert!(output.contains(r#""content":"1.1.1.1""#));
+
+        let output = debug.format_response(
+            request.url().as_str(),
+            StatusCode::BAD_REQUEST,
+            format!(
+                r#"{{"success":false,"errors":[{{"message":"bad {}"}}]}}"#,
+                TOKEN
+            )
+            .as_bytes(),
+        );
+        assert!(!output.contains(TOKEN), "{}", output);
+        assert!(output.contains("400 Bad Request"));
+
+        // 追加的敏感内容同样脱敏，且不影响原有内容
+        let session = debug.with_secret("session-id");
+        let output = session.body(format!(r#"["session-id","{}"]"#, TOKEN).as_bytes());
+        assert!(!output.contains("session-id"), "{}", output);
+        assert!(!output.contains(TOKEN), "{}", output);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let debug = HttpDebug::new(8, [TOKEN.to_string()]);
+        assert_eq!(debug.body(b"1.1.1.1"), "1.1.1.1");
+        assert_eq!(
+            debug.body("地址地址".as_bytes()),
+            "地址...（共 12 字节，已截断）"
+        );
+
+        // 截断位置位于 token 中间时 token 也不应泄露
+        let body = format!("xx{}", TOKEN);
+        let output = debug.body(body.as_bytes());
+        assert!(!output.contains(&TOKEN[..6]), "{}", output);
+    }
+}
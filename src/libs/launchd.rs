@@ -0,0 +1,126 @@
+use std::path::Path;
+
+/// 默认的 launchd 任务标签
+pub const DEFAULT_LABEL: &str = "com.github.magiccrazyman.ddns4cf";
+/// LaunchDaemon 的日志文件路径
+const DAEMON_LOG_PATH: &str = "/var/log/ddns4cf.log";
+/// LaunchAgent 的日志文件路径，相对于用户主目录
+const AGENT_LOG_PATH: &str = "Library/Logs/ddns4cf.log";
+
+/// launchd 任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchdKind {
+    /// 系统级任务，安装至 `/Library/LaunchDaemons`
+    Daemon,
+    /// 用户级任务，安装至 `~/Library/LaunchAgents`
+    Agent,
+}
+
+impl LaunchdKind {
+    /// 日志文件路径，`home` 为用户主目录
+    pub fn log_path(self, home: &Path) -> String {
+        match self {
+            LaunchdKind::Daemon => DAEMON_LOG_PATH.to_string(),
+            LaunchdKind::Agent => home.join(AGENT_LOG_PATH).display().to_string(),
+        }
+    }
+}
+
+/// 转义 XML 文本
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 生成 launchd 任务配置
+///
+/// 程序始终在前台运行，由 launchd 管理生命周期。launchd 停止任务时发送 SIGTERM，程序正常退出后不再重启，
+/// 仅在异常退出时由 `KeepAlive` 重新启动。
+pub fn plist(label: &str, program: &Path, config: &Path, log_path: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>{label}</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>{program}</string>
+		<string>-c</string>
+		<string>{config}</string>
+	</array>
+	<key>RunAtLoad</key>
+	<true/>
+	<key>KeepAlive</key>
+	<dict>
+		<key>SuccessfulExit</key>
+		<false/>
+	</dict>
+	<key>StandardOutPath</key>
+	<string>{log}</string>
+	<key>StandardErrorPath</key>
+	<string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = escape(label),
+        program = escape(&program.display().to_string()),
+        config = escape(&config.display().to_string()),
+        log = escape(log_path),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{plist, LaunchdKind, DEFAULT_LABEL};
+
+    #[test]
+    fn test_plist() {
+        let home = Path::new("/Users/me");
+        assert_eq!(
+            LaunchdKind::Agent.log_path(home),
+            "/Users/me/Library/Logs/ddns4cf.log"
+        );
+
+        let plist = plist(
+            DEFAULT_LABEL,
+            Path::new("/usr/local/bin/ddns4cf"),
+            Path::new("/usr/local/etc/ddns4cf/R&D.json5"),
+            &LaunchdKind::Daemon.log_path(home),
+        );
+        assert_eq!(
+            plist,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>com.github.magiccrazyman.ddns4cf</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>/usr/local/bin/ddns4cf</string>
+		<string>-c</string>
+		<string>/usr/local/etc/ddns4cf/R&amp;D.json5</string>
+	</array>
+	<key>RunAtLoad</key>
+	<true/>
+	<key>KeepAlive</key>
+	<dict>
+		<key>SuccessfulExit</key>
+		<false/>
+	</dict>
+	<key>StandardOutPath</key>
+	<string>/var/log/ddns4cf.log</string>
+	<key>StandardErrorPath</key>
+	<string>/var/log/ddns4cf.log</string>
+</dict>
+</plist>
+"#
+        );
+    }
+}
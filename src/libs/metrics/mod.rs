@@ -0,0 +1,222 @@
+pub mod prometheus;
+pub mod statsd;
+pub mod textfile;
+
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::error::Error;
+
+/// 指标名称
+pub mod names {
+    /// 更新尝试次数
+    pub const ATTEMPTS: &str = "attempts";
+    /// 更新成功次数
+    pub const SUCCESSES: &str = "successes";
+    /// 更新失败次数
+    pub const FAILURES: &str = "failures";
+    /// IP 地址变化次数
+    pub const IP_CHANGES: &str = "ip_changes";
+    /// IP 地址来源耗时
+    pub const SOURCE_LATENCY: &str = "source_latency";
+    /// Cloudflare API 返回 4xx 客户端错误次数
+    pub const CLOUDFLARE_CLIENT_ERRORS: &str = "cloudflare_client_errors";
+    /// Cloudflare API 返回 5xx 服务器错误次数
+    pub const CLOUDFLARE_SERVER_ERRORS: &str = "cloudflare_server_errors";
+    /// Cloudflare API 耗时
+    pub const CLOUDFLARE_LATENCY: &str = "cloudflare_latency";
+    /// 最近一次 IP 地址变化的时间戳
+    pub const LAST_IP_CHANGE: &str = "last_ip_change";
+}
+
+/// 指标输出后端
+///
+/// 所有方法均不得阻塞，发送失败时应直接丢弃。
+pub trait MetricsSink: Debug + Send + Sync {
+    /// 计数器增加
+    fn count(&self, name: &str, nickname: &str, value: u64);
+
+    /// 记录耗时
+    fn timing(&self, name: &str, nickname: &str, duration: Duration);
+
+    /// 设置瞬时值
+    fn gauge(&self, name: &str, nickname: &str, value: f64);
+}
+
+/// 耗时统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Latency {
+    /// 累计耗时
+    pub sum: Duration,
+    /// 统计次数
+    pub count: u64,
+}
+
+impl Latency {
+    fn observe(&mut self, duration: Duration) {
+        self.sum += duration;
+        self.count += 1;
+    }
+}
+
+/// 单个域名的指标
+#[derive(Debug, Clone, Default)]
+pub struct DomainMetrics {
+    pub attempts: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub ip_changes: u64,
+    /// Cloudflare API 返回 4xx 的次数，通常为配置问题
+    pub cloudflare_client_errors: u64,
+    /// Cloudflare API 返回 5xx 的次数，通常为 Cloudflare 服务故障
+    pub cloudflare_server_errors: u64,
+    pub source_latency: Latency,
+    pub cloudflare_latency: Latency,
+    /// 最近一次 IP 地址变化的 Unix 时间戳，单位秒
+    pub last_ip_change: Option<u64>,
+}
+
+/// 指标注册表
+///
+/// 所有埋点均通过该结构记录，同时保存在进程内并转发至各个输出后端，保证不同后端观察到的数据一致。
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// 各域名指标，以域名昵称为键
+    domains: Mutex<BTreeMap<String, DomainMetrics>>,
+    sinks: Vec<Box<dyn MetricsSink>>,
+    /// node_exporter textfile collector 输出
+    textfile: Option<textfile::Textfile>,
+}
+
+impl Metrics {
+    /// 添加输出后端
+    pub fn add_sink(&mut self, sink: Box<dyn MetricsSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// 设置 node_exporter textfile collector 输出路径
+    pub fn set_textfile(&mut self, path: PathBuf) {
+        self.textfile = Some(textfile::Textfile::new(path));
+    }
+
+    /// 获取当前所有域名指标的快照
+    pub fn snapshot(&self) -> BTreeMap<String, DomainMetrics> {
+        self.domains.lock().unwrap().clone()
+    }
+
+    /// 一次更新流程完成，安排写入指标文件
+    pub fn cycle_completed(self: &Arc<Self>) {
+        if let Some(textfile) = &self.textfile {
+            textfile.schedule(self);
+        }
+    }
+
+    fn with_domain<F>(&self, nickname: &str, f: F)
+    where
+        F: FnOnce(&mut DomainMetrics),
+    {
+        let mut domains = self.domains.lock().unwrap();
+        match domains.get_mut(nickname) {
+            Some(metrics) => f(metrics),
+            None => f(domains.entry(nickname.to_string()).or_default()),
+        }
+    }
+
+    fn count(&self, name: &str, nickname: &str) {
+        self.sinks
+            .iter()
+            .for_each(|sink| sink.count(name, nickname, 1));
+    }
+
+    /// 记录一次更新尝试
+    pub fn attempt(&self, nickname: &str) {
+        self.with_domain(nickname, |metrics| metrics.attempts += 1);
+        self.count(names::ATTEMPTS, nickname);
+    }
+
+    /// 记录一次更新成功
+    pub fn success(&self, nickname: &str) {
+        self.with_domain(nickname, |metrics| metrics.successes += 1);
+        self.count(names::SUCCESSES, nickname);
+    }
+
+    /// 记录一次更新失败
+    pub fn failure(&self, nickname: &str) {
+        self.with_domain(nickname, |metrics| metrics.failures += 1);
+        self.count(names::FAILURES, nickname);
+    }
+
+    /// 记录 IP 地址来源耗时
+    pub fn source_latency(&self, nickname: &str, duration: Duration) {
+        self.with_domain(nickname, |metrics| metrics.source_latency.observe(duration));
+        self.sinks
+            .iter()
+            .for_each(|sink| sink.timing(names::SOURCE_LATENCY, nickname, duration));
+    }
+
+    /// 记录 Cloudflare API 耗时
+    pub fn cloudflare_latency(&self, nickname: &str, duration: Duration) {
+        self.with_domain(nickname, |metrics| {
+            metrics.cloudflare_latency.observe(duration)
+        });
+        self.sinks
+            .iter()
+            .for_each(|sink| sink.timing(names::CLOUDFLARE_LATENCY, nickname, duration));
+    }
+
+    /// 记录 Cloudflare API 返回的错误状态码，4xx 与 5xx 分别计数
+    pub fn cloudflare_error(&self, nickname: &str, status: reqwest::StatusCode) {
+        if status.is_server_error() {
+            self.with_domain(nickname, |metrics| metrics.cloudflare_server_errors += 1);
+            self.count(names::CLOUDFLARE_SERVER_ERRORS, nickname);
+        } else if status.is_client_error() {
+            self.with_domain(nickname, |metrics| metrics.cloudflare_client_errors += 1);
+            self.count(names::CLOUDFLARE_CLIENT_ERRORS, nickname);
+        }
+    }
+
+    /// 记录一次 IP 地址变化
+    pub fn ip_changed(&self, nickname: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        self.with_domain(nickname, |metrics| {
+            metrics.ip_changes += 1;
+            metrics.last_ip_change = Some(timestamp);
+        });
+        self.count(names::IP_CHANGES, nickname);
+        self.sinks
+            .iter()
+            .for_each(|sink| sink.gauge(names::LAST_IP_CHANGE, nickname, timestamp as f64));
+    }
+}
+
+/// 指标配置
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct MetricsConfig {
+    /// StatsD 输出
+    statsd: Option<statsd::StatsdConfig>,
+    /// node_exporter textfile collector 输出路径
+    textfile_path: Option<PathBuf>,
+}
+
+impl MetricsConfig {
+    /// 通过当前配置创建指标注册表
+    pub fn create_metrics(&self) -> Result<Metrics, Error> {
+        let mut metrics = Metrics::default();
+        if let Some(statsd) = &self.statsd {
+            metrics.add_sink(Box::new(statsd.create_sink()?));
+        }
+        if let Some(path) = &self.textfile_path {
+            metrics.set_textfile(path.clone());
+        }
+
+        Ok(metrics)
+    }
+}
@@ -0,0 +1,211 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use super::{names, DomainMetrics, Latency};
+
+/// 指标名称前缀
+const PREFIX: &str = "ddns4cf";
+
+/// 计数器名称、说明及取值方式
+type Counter = (&'static str, &'static str, fn(&DomainMetrics) -> u64);
+
+/// 转义标签值中的 `\`、`"` 及换行符
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    value.chars().for_each(|c| match c {
+        '\\' => escaped.push_str("\\\\"),
+        '"' => escaped.push_str("\\\""),
+        '\n' => escaped.push_str("\\n"),
+        c => escaped.push(c),
+    });
+    escaped
+}
+
+/// 输出单个指标族
+fn family<'a, F>(
+    output: &mut String,
+    name: &str,
+    r#type: &str,
+    help: &str,
+    domains: &'a BTreeMap<String, DomainMetrics>,
+    value: F,
+) where
+    F: Fn(&'a DomainMetrics) -> Option<String>,
+{
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, r#type);
+    domains.iter().for_each(|(nickname, metrics)| {
+        if let Some(value) = value(metrics) {
+            let _ = writeln!(
+                output,
+                "{}{{nickname=\"{}\"}} {}",
+                name,
+                escape_label(nickname),
+                value
+            );
+        }
+    });
+}
+
+/// 输出耗时统计，使用 summary 类型的 `_sum` 及 `_count`
+fn latency<F>(
+    output: &mut String,
+    name: &str,
+    help: &str,
+    domains: &BTreeMap<String, DomainMetrics>,
+    value: F,
+) where
+    F: Fn(&DomainMetrics) -> Latency,
+{
+    let name = format!("{}_{}_seconds", PREFIX, name);
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} summary", name);
+    domains.iter().for_each(|(nickname, metrics)| {
+        let latency = value(metrics);
+        let nickname = escape_label(nickname);
+        let _ = writeln!(
+            output,
+            "{}_sum{{nickname=\"{}\"}} {}",
+            name,
+            nickname,
+            latency.sum.as_secs_f64()
+        );
+        let _ = writeln!(
+            output,
+            "{}_count{{nickname=\"{}\"}} {}",
+            name, nickname, latency.count
+        );
+    });
+}
+
+/// 以 Prometheus 文本格式渲染指标
+///
+/// HTTP 导出及 node_exporter textfile 输出共用该函数，以保证指标名称一致。
+/// `last_write_timestamp` 为写入时间的 Unix 时间戳，用于检测数据是否过期，仅 textfile 输出需要。
+pub fn render(
+    domains: &BTreeMap<String, DomainMetrics>,
+    last_write_timestamp: Option<f64>,
+) -> String {
+    let mut output = String::with_capacity(1024);
+
+    let counters: [Counter; 6] = [
+        (names::ATTEMPTS, "更新尝试次数", |metrics| {
+            metrics.attempts
+        }),
+        (names::SUCCESSES, "更新成功次数", |metrics| {
+            metrics.successes
+        }),
+        (names::FAILURES, "更新失败次数", |metrics| {
+            metrics.failures
+        }),
+        (names::IP_CHANGES, "IP 地址变化次数", |metrics| {
+            metrics.ip_changes
+        }),
+        (
+            names::CLOUDFLARE_CLIENT_ERRORS,
+            "Cloudflare API 返回 4xx 客户端错误次数",
+            |metrics| metrics.cloudflare_client_errors,
+        ),
+        (
+            names::CLOUDFLARE_SERVER_ERRORS,
+            "Cloudflare API 返回 5xx 服务器错误次数",
+            |metrics| metrics.cloudflare_server_errors,
+        ),
+    ];
+    counters.into_iter().for_each(|(name, help, value)| {
+        family(
+            &mut output,
+            &format!("{}_{}_total", PREFIX, name),
+            "counter",
+            help,
+            domains,
+            |metrics| Some(value(metrics).to_string()),
+        )
+    });
+
+    latency(
+        &mut output,
+        names::SOURCE_LATENCY,
+        "IP 地址来源耗时",
+        domains,
+        |metrics| metrics.source_latency,
+    );
+    latency(
+        &mut output,
+        names::CLOUDFLARE_LATENCY,
+        "Cloudflare API 耗时",
+        domains,
+        |metrics| metrics.cloudflare_latency,
+    );
+
+    family(
+        &mut output,
+        &format!("{}_{}_timestamp_seconds", PREFIX, names::LAST_IP_CHANGE),
+        "gauge",
+        "最近一次 IP 地址变化的 Unix 时间戳",
+        domains,
+        |metrics| {
+            metrics
+                .last_ip_change
+                .map(|timestamp| timestamp.to_string())
+        },
+    );
+
+    if let Some(timestamp) = last_write_timestamp {
+        let name = format!("{}_last_write_timestamp", PREFIX);
+        let _ = writeln!(output, "# HELP {} 指标文件写入时间的 Unix 时间戳", name);
+        let _ = writeln!(output, "# TYPE {} gauge", name);
+        let _ = writeln!(output, "{} {}", name, timestamp);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, time::Duration};
+
+    use crate::libs::metrics::{DomainMetrics, Latency};
+
+    use super::render;
+
+    #[test]
+    fn test_render() {
+        let mut domains = BTreeMap::new();
+        domains.insert(
+            "home \"v4\"".to_string(),
+            DomainMetrics {
+                attempts: 3,
+                successes: 2,
+                failures: 1,
+                ip_changes: 1,
+                cloudflare_client_errors: 0,
+                cloudflare_server_errors: 2,
+                source_latency: Latency {
+                    sum: Duration::from_millis(1500),
+                    count: 3,
+                },
+                cloudflare_latency: Latency::default(),
+                last_ip_change: Some(1714559412),
+            },
+        );
+        domains.insert("office".to_string(), DomainMetrics::default());
+
+        let output = render(&domains, Some(1714559500.0));
+        assert!(output.contains("# TYPE ddns4cf_attempts_total counter\n"));
+        assert!(output.contains("ddns4cf_attempts_total{nickname=\"home \\\"v4\\\"\"} 3\n"));
+        assert!(output.contains("ddns4cf_failures_total{nickname=\"office\"} 0\n"));
+        assert!(output
+            .contains("ddns4cf_cloudflare_server_errors_total{nickname=\"home \\\"v4\\\"\"} 2\n"));
+        assert!(output.contains("ddns4cf_source_latency_seconds_sum{nickname=\"office\"} 0\n"));
+        assert!(output
+            .contains("ddns4cf_source_latency_seconds_sum{nickname=\"home \\\"v4\\\"\"} 1.5\n"));
+        assert!(output.contains(
+            "ddns4cf_last_ip_change_timestamp_seconds{nickname=\"home \\\"v4\\\"\"} 1714559412\n"
+        ));
+        assert!(!output.contains("ddns4cf_last_ip_change_timestamp_seconds{nickname=\"office\"}"));
+        assert!(output.ends_with("ddns4cf_last_write_timestamp 1714559500\n"));
+
+        let output = render(&domains, None);
+        assert!(!output.contains("ddns4cf_last_write_timestamp"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fmt::Write,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use log::warn;
+
+use crate::libs::error::Error;
+
+use super::MetricsSink;
+
+/// 默认指标名称前缀
+const DEFAULT_PREFIX: &str = "ddns4cf";
+
+/// StatsD 输出配置
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct StatsdConfig {
+    /// StatsD 服务地址，格式为 `host:port`
+    address: String,
+    /// 指标名称前缀，默认为 `ddns4cf`
+    prefix: Option<String>,
+    /// 固定标签，格式为 `key:value`，仅 DogStatsD 模式可用
+    #[serde(default)]
+    tags: Vec<String>,
+    /// 是否使用 DogStatsD 标签扩展。
+    ///
+    /// 启用时域名昵称以 `nickname:<nickname>` 标签发送，否则作为指标名称的一部分发送。
+    #[serde(default)]
+    dogstatsd: bool,
+}
+
+impl StatsdConfig {
+    pub(super) fn create_sink(&self) -> Result<Statsd, Error> {
+        let address = self
+            .address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or(Error::config(format!(
+                "无效 StatsD 服务地址：{}",
+                self.address
+            )))?;
+
+        if !self.dogstatsd && !self.tags.is_empty() {
+            warn!("StatsD 固定标签仅在 DogStatsD 模式下可用，已忽略");
+        }
+
+        Statsd::new(
+            address,
+            self.prefix.as_deref().unwrap_or(DEFAULT_PREFIX),
+            self.dogstatsd.then(|| self.tags.clone()),
+        )
+    }
+}
+
+/// 通过 UDP 发送 StatsD 指标
+///
+/// 发送方式为非阻塞，发送失败的指标将被直接丢弃，且仅输出一次警告。
+#[derive(Debug)]
+pub struct Statsd {
+    address: SocketAddr,
+    socket: UdpSocket,
+    prefix: String,
+    /// DogStatsD 固定标签，为 `None` 时使用标准 StatsD 格式
+    tags: Option<Vec<String>>,
+    warned: AtomicBool,
+}
+
+impl Statsd {
+    pub fn new(
+        address: SocketAddr,
+        prefix: &str,
+        tags: Option<Vec<String>>,
+    ) -> Result<Self, Error> {
+        let bind_address: SocketAddr = if address.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(bind_address)
+            .and_then(|socket| {
+                socket.set_nonblocking(true)?;
+                socket.connect(address)?;
+                Ok(socket)
+            })
+            .map_err(|err| Error::io(format!("创建 StatsD 连接失败：{}", err), err))?;
+
+        Ok(Self {
+            address,
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+            tags,
+            warned: AtomicBool::new(false),
+        })
+    }
+
+    /// 格式化单条指标
+    fn format(&self, name: &str, nickname: &str, value: &str, r#type: &str) -> String {
+        let mut line = String::with_capacity(64);
+        match &self.tags {
+            Some(tags) => {
+                let _ = write!(
+                    line,
+                    "{}.{}:{}|{}|#nickname:{}",
+                    self.prefix,
+                    name,
+                    value,
+                    r#type,
+                    sanitize(nickname)
+                );
+                tags.iter().for_each(|tag| {
+                    let _ = write!(line, ",{}", tag);
+                });
+            }
+            None => {
+                let _ = write!(
+                    line,
+                    "{}.{}.{}:{}|{}",
+                    self.prefix,
+                    sanitize(nickname),
+                    name,
+                    value,
+                    r#type
+                );
+            }
+        }
+        line
+    }
+
+    fn send(&self, line: String) {
+        if let Err(err) = self.socket.send(line.as_bytes()) {
+            if !self.warned.swap(true, Ordering::Relaxed) {
+                warn!(
+                    "发送 StatsD 指标至 {} 失败，后续失败将不再提示：{}",
+                    self.address, err
+                );
+            }
+        }
+    }
+}
+
+/// 替换 StatsD 协议中的保留字符
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            ':' | '|' | '@' | '#' | ',' | '.' | ' ' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+impl MetricsSink for Statsd {
+    fn count(&self, name: &str, nickname: &str, value: u64) {
+        self.send(self.format(name, nickname, &value.to_string(), "c"));
+    }
+
+    fn timing(&self, name: &str, nickname: &str, duration: Duration) {
+        self.send(self.format(name, nickname, &duration.as_millis().to_string(), "ms"));
+    }
+
+    fn gauge(&self, name: &str, nickname: &str, value: f64) {
+        self.send(self.format(name, nickname, &value.to_string(), "g"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::UdpSocket, time::Duration};
+
+    use crate::libs::metrics::{Metrics, MetricsSink};
+
+    use super::Statsd;
+
+    #[test]
+    fn test_format() {
+        let address = "127.0.0.1:8125".parse().unwrap();
+
+        let statsd = Statsd::new(address, "ddns4cf.", None).unwrap();
+        assert_eq!(
+            statsd.format("attempts", "home.v4", "1", "c"),
+            "ddns4cf.home_v4.attempts:1|c"
+        );
+
+        let statsd = Statsd::new(address, "ddns4cf", Some(vec!["env:prod".to_string()])).unwrap();
+        assert_eq!(
+            statsd.format("source_latency", "home", "12", "ms"),
+            "ddns4cf.source_latency:12|ms|#nickname:home,env:prod"
+        );
+    }
+
+    #[test]
+    fn test_send() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        let statsd = Statsd::new(server.local_addr().unwrap(), "ddns4cf", Some(vec![])).unwrap();
+        let mut metrics = Metrics::default();
+        metrics.add_sink(Box::new(statsd));
+        metrics.attempt("home");
+        metrics.cloudflare_latency("home", Duration::from_millis(250));
+
+        let mut buf = [0; 128];
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"ddns4cf.attempts:1|c|#nickname:home");
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"ddns4cf.cloudflare_latency:250|ms|#nickname:home"
+        );
+    }
+
+    #[test]
+    fn test_drop_on_error() {
+        // 目标端口未监听时发送失败不应阻塞或崩溃
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        drop(server);
+
+        let statsd = Statsd::new(address, "ddns4cf", None).unwrap();
+        for _ in 0..10 {
+            statsd.count("attempts", "home", 1);
+        }
+    }
+}
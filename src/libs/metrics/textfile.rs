@@ -0,0 +1,88 @@
+use std::{
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+use tokio::time::sleep;
+
+use super::{prometheus, Metrics};
+use crate::libs::atomic_write::write_atomic;
+
+/// 写入防抖时间，同一时间段内多个域名完成更新时仅写入一次
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// node_exporter textfile collector 输出
+///
+/// 每次更新完成后（防抖）将指标以 Prometheus 文本格式写入临时文件，再原子性地重命名至目标路径。
+/// 写入失败仅输出一次警告，不影响更新流程。
+#[derive(Debug)]
+pub struct Textfile {
+    path: PathBuf,
+    /// 是否已安排写入
+    pending: AtomicBool,
+    /// 是否已输出写入失败警告
+    warned: AtomicBool,
+}
+
+impl Textfile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            pending: AtomicBool::new(false),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// 安排一次写入，若已有写入在等待则忽略
+    pub(super) fn schedule(&self, metrics: &Arc<Metrics>) {
+        if self.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let metrics = metrics.clone();
+        tokio::spawn(async move {
+            sleep(DEBOUNCE).await;
+            let Some(textfile) = metrics.textfile.as_ref() else {
+                return;
+            };
+            textfile.pending.store(false, Ordering::Release);
+
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs_f64())
+                .unwrap_or_default();
+            let content = prometheus::render(&metrics.snapshot(), Some(timestamp.floor()));
+
+            let path = textfile.path.clone();
+            let result = tokio::task::spawn_blocking(move || write_atomic(&path, &content))
+                .await
+                .unwrap_or_else(|err| Err(io::Error::other(err)));
+            textfile.report(result);
+        });
+    }
+
+    fn report(&self, result: io::Result<()>) {
+        match result {
+            Ok(_) => {
+                if self.warned.swap(false, Ordering::Relaxed) {
+                    info!("指标文件 {} 恢复写入", self.path.display());
+                }
+            }
+            Err(err) => {
+                if !self.warned.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "写入指标文件 {} 失败，恢复前不再提示：{}",
+                        self.path.display(),
+                        err
+                    );
+                }
+            }
+        }
+    }
+}
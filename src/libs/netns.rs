@@ -0,0 +1,210 @@
+use std::{fs::File, future::Future, io, path::Path, sync::Arc};
+
+use log::error;
+use tokio::sync::oneshot;
+
+use super::error::Error;
+
+/// 命名网络命名空间所在目录，与 `ip netns` 一致
+const NETNS_DIR: &str = "/var/run/netns";
+/// 进入网络命名空间所需的 CAP_SYS_ADMIN 权限编号
+const CAP_SYS_ADMIN: u32 = 21;
+
+/// 命名网络命名空间（仅限 linux 系统）
+///
+/// 网络命名空间属于线程而非进程。命名空间中的更新器任务由独立的线程运行：
+/// 线程通过 `setns` 进入命名空间后使用单独的单线程运行时运行任务，
+/// 任务创建的套接字及该线程派生的线程（如域名解析使用的阻塞线程）均属于该命名空间，
+/// 不同命名空间中的域名可以在同一进程中共存。
+#[derive(Debug)]
+pub struct Netns {
+    name: String,
+    file: File,
+}
+
+impl Netns {
+    /// 打开 `ip netns` 创建的命名网络命名空间，并确认当前进程能够进入该命名空间
+    pub fn open(name: &str) -> Result<Self, Error> {
+        if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+            return Err(Error::config(format!("无效的网络命名空间名称：{}", name)));
+        }
+        Self::open_path(name, &Path::new(NETNS_DIR).join(name))
+    }
+
+    fn open_path(name: &str, path: &Path) -> Result<Self, Error> {
+        if !cfg!(target_os = "linux") {
+            return Err(Error::config(format!(
+                "网络命名空间 {} 仅支持 linux 系统",
+                name
+            )));
+        }
+
+        let file = File::open(path).map_err(|err| {
+            Error::config(format!(
+                "打开网络命名空间 {} 失败：{} {}",
+                name,
+                err,
+                path.display()
+            ))
+        })?;
+        let netns = Self {
+            name: name.to_string(),
+            file,
+        };
+        netns.probe()?;
+        Ok(netns)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 使当前线程进入该网络命名空间
+    #[cfg(target_os = "linux")]
+    fn enter(&self) -> io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        // SAFETY: 文件描述符在 `self` 存活期间始终有效
+        if unsafe { libc::setns(self.file.as_raw_fd(), libc::CLONE_NEWNET) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn enter(&self) -> io::Result<()> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// 在临时线程中尝试进入网络命名空间，权限不足时给出明确的错误
+    fn probe(&self) -> Result<(), Error> {
+        let missing = !has_capability(CAP_SYS_ADMIN).unwrap_or(true);
+        let result = std::thread::scope(|scope| scope.spawn(|| self.enter()).join())
+            .unwrap_or_else(|_| Err(io::Error::other("线程异常退出")));
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) if missing || err.kind() == io::ErrorKind::PermissionDenied => {
+                Err(Error::config(format!(
+                    "进入网络命名空间 {} 失败：{}。当前进程缺少 CAP_SYS_ADMIN 权限，请以 root 用户运行，或通过 setcap cap_sys_admin+ep 为程序授予该权限",
+                    self.name, err
+                )))
+            }
+            Err(err) => Err(Error::config(format!(
+                "进入网络命名空间 {} 失败：{}",
+                self.name, err
+            ))),
+        }
+    }
+
+    /// 在进入该网络命名空间的独立线程中运行 `future`，线程内使用单独的单线程运行时
+    ///
+    /// 无法创建线程、进入命名空间或创建运行时时输出日志，返回的接收端随即关闭。
+    pub fn spawn<F>(self: &Arc<Self>, future: F) -> oneshot::Receiver<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let netns = self.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("netns-{}", self.name))
+            .spawn(move || {
+                if let Err(err) = netns.enter() {
+                    error!("进入网络命名空间 {} 失败：{}", netns.name, err);
+                    return;
+                }
+                match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => {
+                        let _ = tx.send(runtime.block_on(future));
+                    }
+                    Err(err) => error!("创建网络命名空间 {} 的运行时失败：{}", netns.name, err),
+                }
+            });
+        if let Err(err) = spawned {
+            error!("创建网络命名空间 {} 的线程失败：{}", self.name, err);
+        }
+        rx
+    }
+}
+
+/// 当前进程是否具有指定权限，无法读取权限信息时返回 `None`
+fn has_capability(capability: u32) -> Option<bool> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    effective_capabilities(&status).map(|capabilities| capabilities & (1 << capability) != 0)
+}
+
+/// 解析 `/proc/self/status` 中的有效权限集合
+fn effective_capabilities(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|capabilities| u64::from_str_radix(capabilities.trim(), 16).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use super::{effective_capabilities, has_capability, Netns, CAP_SYS_ADMIN};
+
+    #[test]
+    fn test_effective_capabilities() {
+        let status = "Name:\tddns4cf\nCapInh:\t0000000000000000\nCapEff:\t000001ffffffffff\n";
+        assert_eq!(effective_capabilities(status), Some(0x1ffffffffff));
+        assert_eq!(
+            effective_capabilities("CapEff:\t0000000000003000\n"),
+            Some(0x3000)
+        );
+        assert_eq!(effective_capabilities("Name:\tddns4cf\n"), None);
+    }
+
+    #[test]
+    fn test_open() {
+        for name in ["", ".", "..", "../net", "a/b"] {
+            let err = Netns::open(name).unwrap_err();
+            assert!(
+                err.to_string().contains("无效的网络命名空间名称"),
+                "{}",
+                err
+            );
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let err = Netns::open("ddns4cf-missing").unwrap_err();
+            assert!(err.to_string().contains("打开网络命名空间"), "{}", err);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_spawn() {
+        // 进入当前线程所在的网络命名空间，缺少权限时给出明确的错误
+        let netns = match Netns::open_path("self", Path::new("/proc/self/ns/net")) {
+            Ok(netns) => Arc::new(netns),
+            Err(err) => {
+                assert_eq!(has_capability(CAP_SYS_ADMIN), Some(false), "{}", err);
+                assert!(err.to_string().contains("CAP_SYS_ADMIN"), "{}", err);
+                return;
+            }
+        };
+
+        // 在独立线程的运行时中运行，可使用网络及定时器
+        let result = netns.spawn(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            (
+                std::thread::current().name().map(str::to_string),
+                listener.local_addr().is_ok(),
+            )
+        });
+        assert_eq!(
+            result.await.unwrap(),
+            (Some("netns-self".to_string()), true)
+        );
+    }
+}
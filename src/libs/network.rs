@@ -0,0 +1,139 @@
+use std::{io, net::IpAddr, time::Duration};
+
+use log::{info, warn};
+use tokio::{
+    net::{lookup_host, TcpSocket},
+    time::{sleep, timeout, Instant},
+};
+
+/// 默认等待网络就绪的最长时间，单位秒
+pub const DEFAULT_WAIT_FOR_NETWORK_SECONDS: u64 = 120;
+/// 检查网络的间隔
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+/// 单次连接的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 网络地址族模式
+///
+/// - `dual`：同时使用 IPv4 及 IPv6
+/// - `ipv4-only`：仅使用 IPv4
+/// - `ipv6-only`：仅使用 IPv6，Cloudflare API 仅解析 AAAA 记录
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NetworkMode {
+    #[default]
+    Dual,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+impl NetworkMode {
+    /// 当前模式是否可使用地址 `ip`
+    pub fn allows(self, ip: &IpAddr) -> bool {
+        match self {
+            NetworkMode::Dual => true,
+            NetworkMode::Ipv4Only => ip.is_ipv4(),
+            NetworkMode::Ipv6Only => ip.is_ipv6(),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NetworkMode::Dual => "dual",
+            NetworkMode::Ipv4Only => "ipv4-only",
+            NetworkMode::Ipv6Only => "ipv6-only",
+        }
+    }
+}
+
+/// 启动时的网络就绪检查
+///
+/// 首次初始化前定时尝试解析并连接目标地址，任一目标连接成功即视为网络就绪，
+/// 避免开机时网络尚未就绪导致首次初始化失败后等待整个重试间隔。
+#[derive(Debug, Clone)]
+pub struct NetworkGate {
+    /// 目标地址，格式为 `host:port`
+    targets: Vec<String>,
+    bind_address: Option<IpAddr>,
+    network: NetworkMode,
+    limit: Duration,
+}
+
+impl NetworkGate {
+    pub fn new(
+        targets: Vec<String>,
+        bind_address: Option<IpAddr>,
+        network: NetworkMode,
+        limit: Duration,
+    ) -> Self {
+        Self {
+            targets,
+            bind_address,
+            network,
+            limit,
+        }
+    }
+
+    /// 解析并连接单个目标
+    async fn probe(&self, target: &str) -> io::Result<()> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "未解析到任何地址");
+        for address in lookup_host(target).await? {
+            if !self.network.allows(&address.ip()) {
+                continue;
+            }
+            let socket = if address.is_ipv4() {
+                TcpSocket::new_v4()?
+            } else {
+                TcpSocket::new_v6()?
+            };
+            if let Some(bind_address) = self.bind_address {
+                if bind_address.is_ipv4() != address.is_ipv4() {
+                    continue;
+                }
+                socket.bind((bind_address, 0).into())?;
+            }
+
+            match timeout(CONNECT_TIMEOUT, socket.connect(address)).await {
+                Ok(Ok(_)) => return Ok(()),
+                Ok(Err(err)) => last_error = err,
+                Err(_) => last_error = io::Error::new(io::ErrorKind::TimedOut, "连接超时"),
+            }
+        }
+        Err(last_error)
+    }
+
+    /// 等待网络就绪，超过最长等待时间后直接返回
+    pub async fn wait(&self) {
+        let start = Instant::now();
+        loop {
+            let mut reasons = Vec::with_capacity(self.targets.len());
+            for target in &self.targets {
+                match self.probe(target).await {
+                    Ok(()) => {
+                        if start.elapsed() >= PROBE_INTERVAL {
+                            info!("网络已就绪，共等待 {} 秒", start.elapsed().as_secs());
+                        }
+                        return;
+                    }
+                    Err(err) => reasons.push(format!("{}：{}", target, err)),
+                }
+            }
+
+            if start.elapsed() + PROBE_INTERVAL > self.limit {
+                warn!(
+                    "等待网络就绪超时（{} 秒），继续启动。{}",
+                    self.limit.as_secs(),
+                    reasons.join("；")
+                );
+                return;
+            }
+            info!(
+                "网络尚未就绪，{} 秒后重新检查，已等待 {} 秒。{}",
+                PROBE_INTERVAL.as_secs(),
+                start.elapsed().as_secs(),
+                reasons.join("；")
+            );
+            sleep(PROBE_INTERVAL).await;
+        }
+    }
+}
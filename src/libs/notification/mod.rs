@@ -0,0 +1,344 @@
+pub mod mqtt;
+pub mod template;
+pub mod webhook;
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    net::IpAddr,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use tokio::runtime::Handle;
+
+use super::{
+    error::{Error, ErrorKind},
+    stats::{Incident, IncidentTransition},
+};
+
+/// 默认连续失败多少次后发送告警
+const DEFAULT_ALERT_AFTER_FAILURES: u32 = 3;
+/// 默认是否发送恢复通知
+const DEFAULT_NOTIFY_RECOVERY: bool = true;
+
+/// 通知事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// IP 地址发生变化，且已成功更新 DNS 记录
+    Change,
+    /// 连续失败次数达到告警阈值
+    Failure,
+    /// 告警后恢复正常
+    Recovery,
+    /// 每次检查完成，无论成功与否
+    Check,
+    /// 测试消息
+    Test,
+}
+
+impl EventKind {
+    /// 事件类型名称，用于序列化
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Change => "change",
+            EventKind::Failure => "failure",
+            EventKind::Recovery => "recovery",
+            EventKind::Check => "check",
+            EventKind::Test => "test",
+        }
+    }
+}
+
+impl Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 通知事件
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    /// 域名昵称
+    pub nickname: String,
+    /// DNS 记录名称，未初始化时为空
+    pub record: Option<String>,
+    /// 更新前的 IP 地址
+    pub old_ip: Option<IpAddr>,
+    /// 当前 IP 地址
+    pub new_ip: Option<IpAddr>,
+    /// 事件发生时间
+    pub time: DateTime<Local>,
+    /// 错误原因，仅 [`EventKind::Failure`] 事件存在
+    pub error: Option<String>,
+    /// 错误类型，仅 [`EventKind::Failure`] 事件存在
+    pub error_kind: Option<ErrorKind>,
+    /// 导致错误的 HTTP 状态码，仅访问 Cloudflare 失败时存在
+    pub error_status: Option<u16>,
+    /// 出错的 IP 地址来源名称，仅获取 IP 地址失败时存在
+    pub error_source: Option<String>,
+}
+
+/// 通知渠道
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// 是否接收该类型的事件。默认不接收 [`EventKind::Check`] 事件
+    fn subscribes(&self, kind: EventKind) -> bool {
+        kind != EventKind::Check
+    }
+
+    /// 发送通知
+    async fn send(&self, event: &Event) -> Result<(), Error>;
+}
+
+/// 告警策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlertPolicy {
+    /// 连续失败多少次后发送告警
+    pub alert_after_failures: u32,
+    /// 告警后恢复时是否发送恢复通知
+    pub notify_recovery: bool,
+}
+
+impl Default for AlertPolicy {
+    fn default() -> Self {
+        Self {
+            alert_after_failures: DEFAULT_ALERT_AFTER_FAILURES,
+            notify_recovery: DEFAULT_NOTIFY_RECOVERY,
+        }
+    }
+}
+
+/// 通知渠道配置中通用的告警策略字段，未配置时使用全局配置
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct AlertPolicyConfig {
+    alert_after_failures: Option<u32>,
+    notify_recovery: Option<bool>,
+}
+
+impl AlertPolicyConfig {
+    fn resolve(&self, fallback: AlertPolicy) -> AlertPolicy {
+        AlertPolicy {
+            alert_after_failures: self
+                .alert_after_failures
+                .unwrap_or(fallback.alert_after_failures),
+            notify_recovery: self.notify_recovery.unwrap_or(fallback.notify_recovery),
+        }
+    }
+}
+
+/// 通知配置
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct NotificationConfig {
+    /// 全局告警策略
+    #[serde(flatten)]
+    policy: AlertPolicyConfig,
+    /// Webhook 通知渠道
+    webhook: Option<webhook::WebhookConfig>,
+    /// MQTT 通知渠道
+    mqtt: Option<mqtt::MqttConfig>,
+}
+
+impl NotificationConfig {
+    /// 通过当前配置创建通知分发器
+    pub fn create_dispatcher(&self) -> Result<NotificationDispatcher, Error> {
+        let policy = self.policy.resolve(AlertPolicy::default());
+
+        let mut dispatcher = NotificationDispatcher::default();
+        if let Some(webhook) = &self.webhook {
+            dispatcher.add_channel(
+                Arc::new(webhook.create_notifier()?),
+                webhook.policy().resolve(policy),
+            );
+        }
+        if let Some(mqtt) = &self.mqtt {
+            dispatcher.add_channel(
+                Arc::new(mqtt.create_notifier()?),
+                mqtt.policy().resolve(policy),
+            );
+        }
+
+        Ok(dispatcher)
+    }
+}
+
+/// 单个通知渠道及其告警状态
+#[derive(Debug)]
+struct Channel {
+    notifier: Arc<dyn Notifier>,
+    policy: AlertPolicy,
+    /// 各域名的故障事件状态，以域名昵称为键
+    incidents: Mutex<HashMap<String, Incident>>,
+}
+
+impl Channel {
+    fn transition<F>(&self, nickname: &str, f: F) -> Option<IncidentTransition>
+    where
+        F: FnOnce(&mut Incident, &AlertPolicy) -> Option<IncidentTransition>,
+    {
+        let mut incidents = self.incidents.lock().unwrap();
+        let incident = incidents.entry(nickname.to_string()).or_default();
+        f(incident, &self.policy)
+    }
+}
+
+/// 通知分发器
+///
+/// 根据每个渠道的告警策略决定是否发送通知，通知在后台发送，不会阻塞更新流程。
+#[derive(Debug, Default)]
+pub struct NotificationDispatcher {
+    channels: Vec<Channel>,
+    /// 发送通知使用的运行时，未指定时使用当前运行时
+    runtime: OnceLock<Handle>,
+}
+
+impl NotificationDispatcher {
+    /// 指定发送通知使用的运行时，仅首次指定时生效
+    ///
+    /// 网络命名空间中的更新器任务运行于独立的运行时，通知统一由主运行时发送，
+    /// 避免通知渠道的连接建立在域名所在的网络命名空间中。
+    pub fn set_runtime(&self, runtime: Handle) {
+        let _ = self.runtime.set(runtime);
+    }
+
+    /// 添加通知渠道
+    pub fn add_channel(&mut self, notifier: Arc<dyn Notifier>, policy: AlertPolicy) {
+        self.channels.push(Channel {
+            notifier,
+            policy,
+            incidents: Mutex::new(HashMap::new()),
+        });
+    }
+
+    /// 是否未配置任何通知渠道
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// 更新成功。若 IP 地址发生变化，`old_ip` 为更新前的地址
+    pub fn on_success(
+        &self,
+        nickname: &str,
+        record: Option<&str>,
+        old_ip: Option<IpAddr>,
+        new_ip: IpAddr,
+    ) {
+        let time = Local::now();
+        let event = |kind| Event {
+            kind,
+            nickname: nickname.to_string(),
+            record: record.map(str::to_string),
+            old_ip,
+            new_ip: Some(new_ip),
+            time,
+            error: None,
+            error_kind: None,
+            error_status: None,
+            error_source: None,
+        };
+
+        for channel in &self.channels {
+            let transition = channel.transition(nickname, |incident, _| incident.on_success());
+            if transition == Some(IncidentTransition::Recover) && channel.policy.notify_recovery {
+                send(channel, self.runtime.get(), event(EventKind::Recovery));
+            }
+            if old_ip.is_some() {
+                send(channel, self.runtime.get(), event(EventKind::Change));
+            }
+            send(channel, self.runtime.get(), event(EventKind::Check));
+        }
+    }
+
+    /// 更新失败，`consecutive_failures` 为包含本次在内的连续失败次数
+    pub fn on_failure(
+        &self,
+        nickname: &str,
+        record: Option<&str>,
+        current_ip: Option<IpAddr>,
+        error: &Error,
+        consecutive_failures: u32,
+    ) {
+        for channel in &self.channels {
+            let transition = channel.transition(nickname, |incident, policy| {
+                incident.on_failure(consecutive_failures, policy.alert_after_failures)
+            });
+            let event = |kind| Event {
+                kind,
+                nickname: nickname.to_string(),
+                record: record.map(str::to_string),
+                old_ip: current_ip,
+                new_ip: None,
+                time: Local::now(),
+                error: Some(error.to_string()),
+                error_kind: Some(error.kind()),
+                error_status: error.http_status(),
+                error_source: error.source_name().map(str::to_string),
+            };
+            if transition == Some(IncidentTransition::Alert) {
+                send(channel, self.runtime.get(), event(EventKind::Failure));
+            }
+            send(channel, self.runtime.get(), event(EventKind::Check));
+        }
+    }
+
+    /// 向所有通知渠道发送测试消息并等待发送完成，返回发送失败的渠道数量
+    pub async fn send_test(&self) -> usize {
+        let event = Event {
+            kind: EventKind::Test,
+            nickname: env!("CARGO_PKG_NAME").to_string(),
+            record: None,
+            old_ip: None,
+            new_ip: None,
+            time: Local::now(),
+            error: None,
+            error_kind: None,
+            error_status: None,
+            error_source: None,
+        };
+
+        let mut failures = 0;
+        for channel in &self.channels {
+            match channel.notifier.send(&event).await {
+                Ok(_) => info!("发送 {} 测试消息成功", channel.notifier.name()),
+                Err(err) => {
+                    failures += 1;
+                    warn!("发送 {} 测试消息失败：{}", channel.notifier.name(), err);
+                }
+            }
+        }
+        failures
+    }
+}
+
+/// 在后台发送通知，发送失败仅输出警告
+fn send(channel: &Channel, runtime: Option<&Handle>, event: Event) {
+    if !channel.notifier.subscribes(event.kind) {
+        return;
+    }
+
+    let notifier = channel.notifier.clone();
+    let task = async move {
+        if let Err(err) = notifier.send(&event).await {
+            warn!(
+                "[{}] 发送 {} 通知失败（{}）：{}",
+                event.nickname,
+                notifier.name(),
+                event.kind,
+                err
+            );
+        }
+    };
+    match runtime {
+        Some(runtime) => {
+            runtime.spawn(task);
+        }
+        None => {
+            tokio::spawn(task);
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    fs,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use reqwest::Url;
+use rumqttc::{
+    AsyncClient, Event as MqttEvent, EventLoop, MqttOptions, Packet, QoS, TlsConfiguration,
+    Transport,
+};
+use tokio::{sync::Notify, time::sleep};
+
+use crate::libs::error::Error;
+
+use super::{AlertPolicyConfig, Event, EventKind, Notifier};
+
+/// 默认 IP 地址发布主题
+const DEFAULT_TOPIC: &str = "ddns4cf/{nickname}/ip";
+/// 默认状态发布主题
+const DEFAULT_STATUS_TOPIC: &str = "ddns4cf/{nickname}/status";
+/// 客户端请求队列容量
+const REQUEST_CAPACITY: usize = 32;
+/// 发布失败时最大重试次数
+const PUBLISH_RETRIES: u32 = 3;
+/// 发布失败时重试间隔
+const PUBLISH_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// 连接断开后重连间隔
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// 测试消息等待服务器确认的超时时间
+const TEST_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+/// 心跳间隔
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+
+/// MQTT 通知渠道配置
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct MqttConfig {
+    /// 服务器地址，支持 `mqtt://` 及 `mqtts://`
+    url: String,
+    /// 客户端 id，默认为 `ddns4cf-<pid>`
+    client_id: Option<String>,
+    /// 验证用户名
+    username: Option<String>,
+    /// 验证密码
+    password: Option<String>,
+    /// 自定义 CA 证书路径（PEM），仅 `mqtts://` 可用
+    ca_cert: Option<String>,
+    /// 客户端证书路径（PEM），仅 `mqtts://` 可用
+    client_cert: Option<String>,
+    /// 客户端私钥路径（PEM），仅 `mqtts://` 可用
+    client_key: Option<String>,
+    /// IP 地址发布主题模板，`{nickname}` 将被替换为域名昵称
+    topic: Option<String>,
+    /// 是否以 retain 方式发布
+    #[serde(default)]
+    retain: bool,
+    /// 是否在每次检查后发布 JSON 格式的状态消息
+    #[serde(default)]
+    publish_status: bool,
+    /// 状态发布主题模板，`{nickname}` 将被替换为域名昵称
+    status_topic: Option<String>,
+    /// 告警策略，未配置时使用全局策略
+    #[serde(flatten)]
+    policy: AlertPolicyConfig,
+}
+
+impl MqttConfig {
+    pub(super) fn policy(&self) -> &AlertPolicyConfig {
+        &self.policy
+    }
+
+    pub(super) fn create_notifier(&self) -> Result<Mqtt, Error> {
+        let url = self
+            .url
+            .parse::<Url>()
+            .map_err(|_| Error::config(format!("无效 MQTT 服务器地址：{}", self.url)))?;
+        let Some(host) = url.host_str() else {
+            return Err(Error::config(format!("无效 MQTT 服务器地址：{}", self.url)));
+        };
+
+        let tls = match url.scheme() {
+            "mqtt" | "tcp" => false,
+            "mqtts" | "ssl" => true,
+            scheme => {
+                return Err(Error::config(format!(
+                    "不支持的 MQTT 协议：{}，仅支持 mqtt 及 mqtts",
+                    scheme
+                )))
+            }
+        };
+        let port = url.port().unwrap_or(if tls { 8883 } else { 1883 });
+        let client_id = self
+            .client_id
+            .clone()
+            .unwrap_or_else(|| format!("ddns4cf-{}", std::process::id()));
+
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(KEEP_ALIVE);
+        match (&self.username, &self.password) {
+            (Some(username), password) => {
+                options.set_credentials(username, password.as_deref().unwrap_or_default());
+            }
+            (None, Some(_)) => return Err(Error::config("MQTT 配置了 password 但未配置 username")),
+            (None, None) => {}
+        }
+        if tls {
+            options.set_transport(self.transport()?);
+        }
+
+        let (client, eventloop) = AsyncClient::new(options, REQUEST_CAPACITY);
+
+        Ok(Mqtt {
+            url,
+            client,
+            eventloop: Mutex::new(Some(eventloop)),
+            acked: Arc::new(Notify::new()),
+            topic: self.topic.clone().unwrap_or(DEFAULT_TOPIC.to_string()),
+            status_topic: self.publish_status.then(|| {
+                self.status_topic
+                    .clone()
+                    .unwrap_or(DEFAULT_STATUS_TOPIC.to_string())
+            }),
+            retain: self.retain,
+            published: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// 创建 TLS 传输配置，未指定 CA 证书时使用系统证书
+    fn transport(&self) -> Result<Transport, Error> {
+        let read = |path: &String| {
+            fs::read(path)
+                .map_err(|err| Error::io(format!("读取 MQTT 证书失败：{} {}", err, path), err))
+        };
+
+        let client_auth = match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => Some((read(cert)?, read(key)?)),
+            (None, None) => None,
+            _ => {
+                return Err(Error::config(
+                    "MQTT 客户端证书 client_cert 与私钥 client_key 必须同时配置",
+                ))
+            }
+        };
+
+        match (&self.ca_cert, client_auth) {
+            (None, None) => Ok(Transport::tls_with_default_config()),
+            (Some(ca), client_auth) => Ok(Transport::tls_with_config(TlsConfiguration::Simple {
+                ca: read(ca)?,
+                alpn: None,
+                client_auth,
+            })),
+            (None, Some(_)) => Err(Error::config(
+                "使用 MQTT 客户端证书时必须同时配置 CA 证书 ca_cert",
+            )),
+        }
+    }
+}
+
+/// 通过 MQTT 发布 IP 地址
+///
+/// 所有域名共享同一个连接，连接断开后将自动重连。发布失败不会影响更新流程。
+pub struct Mqtt {
+    url: Url,
+    client: AsyncClient,
+    /// 连接事件循环，首次发送消息时在后台启动
+    eventloop: Mutex<Option<EventLoop>>,
+    /// 收到服务器发布确认时通知
+    acked: Arc<Notify>,
+    topic: String,
+    status_topic: Option<String>,
+    retain: bool,
+    /// 已发布过 IP 地址的域名，首次检查成功时即发布当前地址
+    published: Mutex<HashSet<String>>,
+}
+
+impl Debug for Mqtt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mqtt")
+            .field("url", &self.url)
+            .field("topic", &self.topic)
+            .field("status_topic", &self.status_topic)
+            .field("retain", &self.retain)
+            .finish()
+    }
+}
+
+impl Mqtt {
+    /// 启动连接事件循环
+    fn ensure_connected(&self) {
+        let Some(mut eventloop) = self.eventloop.lock().unwrap().take() else {
+            return;
+        };
+
+        let url = self.url.clone();
+        let acked = self.acked.clone();
+        tokio::spawn(async move {
+            // 每次断开连接期间仅输出一次警告
+            let mut warned = false;
+            loop {
+                match eventloop.poll().await {
+                    Ok(MqttEvent::Incoming(Packet::ConnAck(_))) => {
+                        warned = false;
+                        info!("已连接 MQTT 服务器 {}", url);
+                    }
+                    Ok(MqttEvent::Incoming(Packet::PubAck(_))) => acked.notify_waiters(),
+                    Ok(_) => {}
+                    Err(err) => {
+                        if warned {
+                            debug!("连接 MQTT 服务器 {} 失败：{}", url, err);
+                        } else {
+                            warn!(
+                                "连接 MQTT 服务器 {} 失败：{}。将在 {} 秒后重连",
+                                url,
+                                err,
+                                RECONNECT_DELAY.as_secs()
+                            );
+                            warned = true;
+                        }
+                        sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        });
+    }
+
+    /// 渲染主题模板
+    fn render_topic(template: &str, nickname: &str) -> String {
+        template.replace("{nickname}", nickname)
+    }
+
+    /// 测试消息主题，为 IP 地址主题模板中首个占位符前的固定部分加上 `/test`
+    fn test_topic(&self) -> String {
+        let prefix = match self.topic.find('{') {
+            Some(index) => &self.topic[..index],
+            None => self.topic.as_str(),
+        };
+        format!("{}/test", prefix.trim_end_matches('/'))
+    }
+
+    /// 发布消息，请求队列已满时有限次重试
+    async fn publish(&self, topic: String, payload: Vec<u8>) -> Result<(), Error> {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match self
+                .client
+                .try_publish(&topic, QoS::AtLeastOnce, self.retain, payload.clone())
+            {
+                Ok(_) => return Ok(()),
+                Err(err) if attempts > PUBLISH_RETRIES => {
+                    return Err(Error::network(
+                        format!(
+                            "发布 MQTT 消息至 {} 失败，已重试 {} 次：{}",
+                            topic, PUBLISH_RETRIES, err
+                        ),
+                        err,
+                    ))
+                }
+                Err(_) => sleep(PUBLISH_RETRY_DELAY).await,
+            }
+        }
+    }
+
+    fn status_payload(event: &Event) -> Vec<u8> {
+        let ip = event.new_ip.or(event.old_ip);
+        serde_json::json!({
+            "nickname": event.nickname,
+            "record": event.record,
+            "ip": ip,
+            "success": event.error.is_none(),
+            "error": event.error,
+            "error_kind": event.error_kind.map(|kind| kind.as_str()),
+            "error_status": event.error_status,
+            "error_source": event.error_source,
+            "time": event.time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        })
+        .to_string()
+        .into_bytes()
+    }
+}
+
+#[async_trait]
+impl Notifier for Mqtt {
+    fn name(&self) -> &'static str {
+        "MQTT"
+    }
+
+    fn subscribes(&self, kind: EventKind) -> bool {
+        match kind {
+            EventKind::Change | EventKind::Check | EventKind::Test => true,
+            EventKind::Failure | EventKind::Recovery => false,
+        }
+    }
+
+    async fn send(&self, event: &Event) -> Result<(), Error> {
+        self.ensure_connected();
+
+        match event.kind {
+            EventKind::Change => {
+                let Some(new_ip) = event.new_ip else {
+                    return Ok(());
+                };
+                self.published
+                    .lock()
+                    .unwrap()
+                    .insert(event.nickname.clone());
+                self.publish(
+                    Self::render_topic(&self.topic, &event.nickname),
+                    new_ip.to_string().into_bytes(),
+                )
+                .await
+            }
+            EventKind::Check => {
+                // 首次检查成功时发布当前地址，使 retain 消息尽早可用
+                if let (Some(new_ip), None) = (event.new_ip, &event.error) {
+                    let first = self
+                        .published
+                        .lock()
+                        .unwrap()
+                        .insert(event.nickname.clone());
+                    if first {
+                        self.publish(
+                            Self::render_topic(&self.topic, &event.nickname),
+                            new_ip.to_string().into_bytes(),
+                        )
+                        .await?;
+                    }
+                }
+
+                match &self.status_topic {
+                    Some(status_topic) => {
+                        self.publish(
+                            Self::render_topic(status_topic, &event.nickname),
+                            Self::status_payload(event),
+                        )
+                        .await
+                    }
+                    None => Ok(()),
+                }
+            }
+            EventKind::Test => {
+                let acked = self.acked.notified();
+                tokio::pin!(acked);
+                acked.as_mut().enable();
+
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs())
+                    .unwrap_or_default();
+                self.publish(
+                    self.test_topic(),
+                    format!("ddns4cf 测试消息 {}", timestamp).into_bytes(),
+                )
+                .await?;
+
+                tokio::time::timeout(TEST_ACK_TIMEOUT, acked)
+                    .await
+                    .map_err(|err| {
+                        Error::network(
+                            format!("等待 MQTT 服务器 {} 确认测试消息超时", self.url),
+                            err,
+                        )
+                    })
+            }
+            EventKind::Failure | EventKind::Recovery => Ok(()),
+        }
+    }
+}
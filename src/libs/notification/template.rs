@@ -0,0 +1,353 @@
+use std::fmt::Write;
+
+use crate::libs::error::Error;
+
+use super::{Event, EventKind};
+
+/// 默认 IP 地址变化通知模板
+const DEFAULT_CHANGE_TEMPLATE: &str =
+    "[{{nickname}}] {{record}} IP 地址已由 {{old_ip}} 变更为 {{new_ip}}（{{time}}）";
+/// 默认更新失败告警模板
+const DEFAULT_FAILURE_TEMPLATE: &str = "[{{nickname}}] {{record}} 更新失败：{{error}}（{{time}}）";
+/// 默认恢复通知模板
+const DEFAULT_RECOVERY_TEMPLATE: &str =
+    "[{{nickname}}] {{record}} 已恢复正常，当前 IP 地址为 {{new_ip}}（{{time}}）";
+/// 测试消息模板
+const TEST_TEMPLATE: &str = "{{nickname}} 测试消息（{{time}}）";
+/// `{{time}}` 占位符的时间格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 模板占位符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Nickname,
+    Record,
+    OldIp,
+    NewIp,
+    Time,
+    Error,
+}
+
+impl Placeholder {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nickname" => Some(Self::Nickname),
+            "record" => Some(Self::Record),
+            "old_ip" => Some(Self::OldIp),
+            "new_ip" => Some(Self::NewIp),
+            "time" => Some(Self::Time),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    /// 获取占位符对应的值，事件中不存在的值渲染为空字符串
+    fn value(&self, event: &Event) -> String {
+        match self {
+            Placeholder::Nickname => event.nickname.clone(),
+            Placeholder::Record => event.record.clone().unwrap_or_default(),
+            Placeholder::OldIp => event.old_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+            Placeholder::NewIp => event.new_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+            Placeholder::Time => event.time.format(TIME_FORMAT).to_string(),
+            Placeholder::Error => event.error.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// 通知消息模板
+///
+/// 使用 `{{name}}` 作为占位符，可用的占位符为 `nickname`、`record`、`old_ip`、`new_ip`、`time` 及 `error`。
+/// 模板在读取配置时解析，未知或未闭合的占位符将导致配置读取失败。
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct Template(Vec<Segment>);
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, Error> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                segments.push(Segment::Literal(rest[..start].to_string()));
+            }
+
+            let Some(end) = rest[start + 2..].find("}}") else {
+                return Err(Error::config(format!(
+                    "通知模板中存在未闭合的占位符：{}",
+                    template
+                )));
+            };
+            let name = rest[start + 2..start + 2 + end].trim();
+            let placeholder = Placeholder::from_name(name).ok_or(Error::config(format!(
+                "通知模板中存在未知占位符 {{{{{}}}}}，可用的占位符为：nickname、record、old_ip、new_ip、time、error",
+                name
+            )))?;
+            segments.push(Segment::Placeholder(placeholder));
+
+            rest = &rest[start + 2 + end + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        Ok(Self(segments))
+    }
+
+    /// 渲染模板，仅对占位符的值进行转义，模板本身的内容保持原样
+    pub fn render(&self, event: &Event, escape: Escape) -> String {
+        let mut output = String::with_capacity(128);
+        self.0.iter().for_each(|segment| match segment {
+            Segment::Literal(literal) => output.push_str(literal),
+            Segment::Placeholder(placeholder) => {
+                escape.write(&mut output, &placeholder.value(event))
+            }
+        });
+        output
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+/// 占位符值的转义方式，应与通知渠道的消息格式一致
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Escape {
+    /// 纯文本，不转义
+    #[default]
+    Plain,
+    /// Telegram MarkdownV2
+    MarkdownV2,
+    /// Discord Markdown
+    Discord,
+    /// HTML
+    Html,
+}
+
+impl Escape {
+    fn write(&self, output: &mut String, value: &str) {
+        match self {
+            Escape::Plain => output.push_str(value),
+            Escape::MarkdownV2 => value.chars().for_each(|c| {
+                // https://core.telegram.org/bots/api#markdownv2-style
+                if matches!(
+                    c,
+                    '_' | '*'
+                        | '['
+                        | ']'
+                        | '('
+                        | ')'
+                        | '~'
+                        | '`'
+                        | '>'
+                        | '#'
+                        | '+'
+                        | '-'
+                        | '='
+                        | '|'
+                        | '{'
+                        | '}'
+                        | '.'
+                        | '!'
+                        | '\\'
+                ) {
+                    output.push('\\');
+                }
+                output.push(c);
+            }),
+            Escape::Discord => value.chars().for_each(|c| {
+                if matches!(c, '_' | '*' | '~' | '`' | '|' | '>' | '[' | ']' | '\\') {
+                    output.push('\\');
+                }
+                output.push(c);
+            }),
+            Escape::Html => value.chars().for_each(|c| {
+                let _ = match c {
+                    '&' => output.write_str("&amp;"),
+                    '<' => output.write_str("&lt;"),
+                    '>' => output.write_str("&gt;"),
+                    '"' => output.write_str("&quot;"),
+                    c => output.write_char(c),
+                };
+            }),
+        }
+    }
+}
+
+/// 通知渠道配置中通用的消息模板字段，未配置的事件类型使用默认模板
+#[derive(serde::Deserialize, Debug, Clone, Default)]
+pub struct TemplateConfig {
+    /// IP 地址变化通知模板
+    change: Option<Template>,
+    /// 更新失败告警模板
+    failure: Option<Template>,
+    /// 恢复通知模板
+    recovery: Option<Template>,
+}
+
+impl TemplateConfig {
+    pub(super) fn create_messages(&self, escape: Escape) -> Messages {
+        let template = |template: &Option<Template>, default: &str| {
+            template
+                .clone()
+                .unwrap_or_else(|| Template::parse(default).unwrap())
+        };
+
+        Messages {
+            change: template(&self.change, DEFAULT_CHANGE_TEMPLATE),
+            failure: template(&self.failure, DEFAULT_FAILURE_TEMPLATE),
+            recovery: template(&self.recovery, DEFAULT_RECOVERY_TEMPLATE),
+            test: Template::parse(TEST_TEMPLATE).unwrap(),
+            escape,
+        }
+    }
+}
+
+/// 单个通知渠道的全部消息模板
+#[derive(Debug, Clone)]
+pub struct Messages {
+    change: Template,
+    failure: Template,
+    recovery: Template,
+    test: Template,
+    escape: Escape,
+}
+
+impl Messages {
+    /// 渲染事件对应的通知消息，[`EventKind::Check`] 事件没有消息
+    pub fn render(&self, event: &Event) -> Option<String> {
+        let template = match event.kind {
+            EventKind::Change => &self.change,
+            EventKind::Failure => &self.failure,
+            EventKind::Recovery => &self.recovery,
+            EventKind::Test => &self.test,
+            EventKind::Check => return None,
+        };
+        Some(template.render(event, self.escape))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use crate::libs::notification::{Event, EventKind};
+
+    use super::{Escape, Template, TemplateConfig};
+
+    fn event() -> Event {
+        Event {
+            kind: EventKind::Change,
+            nickname: "home_v4".to_string(),
+            record: Some("my_host.example.com".to_string()),
+            old_ip: Some("1.1.1.1".parse().unwrap()),
+            new_ip: Some("2001:db8::1".parse().unwrap()),
+            time: Local.with_ymd_and_hms(2024, 5, 1, 18, 30, 12).unwrap(),
+            error: Some("<timeout> & *retry*".to_string()),
+            error_kind: None,
+            error_status: None,
+            error_source: None,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let template = Template::parse("{{ nickname }}: {{new_ip}} {single} }}").unwrap();
+        assert_eq!(
+            template.render(&event(), Escape::Plain),
+            "home_v4: 2001:db8::1 {single} }}"
+        );
+        assert_eq!(
+            Template::parse("{{time}}")
+                .unwrap()
+                .render(&event(), Escape::Plain),
+            "2024-05-01 18:30:12"
+        );
+        assert_eq!(
+            Template::parse("").unwrap().render(&event(), Escape::Plain),
+            ""
+        );
+
+        assert!(Template::parse("{{hostname}}").is_err());
+        assert!(Template::parse("{{nickname").is_err());
+        assert!(Template::parse("{{}}").is_err());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = json5::from_str::<TemplateConfig>(r#"{ change: "{{ip}}" }"#);
+        assert!(config.is_err());
+
+        let config = json5::from_str::<TemplateConfig>(r#"{ change: "{{new_ip}}" }"#).unwrap();
+        let messages = config.create_messages(Escape::Plain);
+        assert_eq!(messages.render(&event()).unwrap(), "2001:db8::1");
+    }
+
+    #[test]
+    fn test_escape_markdown_v2() {
+        let template = Template::parse("*{{record}}* {{old_ip}} {{new_ip}}").unwrap();
+        assert_eq!(
+            template.render(&event(), Escape::MarkdownV2),
+            "*my\\_host\\.example\\.com* 1\\.1\\.1\\.1 2001:db8::1"
+        );
+        assert_eq!(
+            Template::parse("{{error}}")
+                .unwrap()
+                .render(&event(), Escape::MarkdownV2),
+            "<timeout\\> & \\*retry\\*"
+        );
+    }
+
+    #[test]
+    fn test_escape_discord() {
+        let template = Template::parse("**{{nickname}}** {{record}} {{error}}").unwrap();
+        assert_eq!(
+            template.render(&event(), Escape::Discord),
+            "**home\\_v4** my\\_host.example.com <timeout\\> & \\*retry\\*"
+        );
+    }
+
+    #[test]
+    fn test_escape_html() {
+        let template = Template::parse("<b>{{nickname}}</b> {{error}}").unwrap();
+        assert_eq!(
+            template.render(&event(), Escape::Html),
+            "<b>home_v4</b> &lt;timeout&gt; &amp; *retry*"
+        );
+        assert_eq!(
+            template.render(&event(), Escape::Plain),
+            "<b>home_v4</b> <timeout> & *retry*"
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let messages = TemplateConfig::default().create_messages(Escape::Plain);
+
+        let mut event = event();
+        assert_eq!(
+            messages.render(&event).unwrap(),
+            "[home_v4] my_host.example.com IP 地址已由 1.1.1.1 变更为 2001:db8::1（2024-05-01 18:30:12）"
+        );
+
+        event.kind = EventKind::Failure;
+        assert!(messages
+            .render(&event)
+            .unwrap()
+            .contains("更新失败：<timeout> & *retry*"));
+
+        event.kind = EventKind::Check;
+        assert!(messages.render(&event).is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{header, Client, Url};
+
+use crate::libs::error::Error;
+
+use super::{
+    template::{Escape, Messages, TemplateConfig},
+    AlertPolicyConfig, Event, Notifier,
+};
+
+/// Webhook 请求超时时间
+const WEBHOOK_TIMEOUT_SECONDS: u64 = 10;
+
+/// Webhook 通知渠道配置
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct WebhookConfig {
+    /// 接收通知的地址
+    url: String,
+    /// 消息模板，未配置的事件类型使用默认模板
+    #[serde(default)]
+    template: TemplateConfig,
+    /// 消息中占位符值的转义方式，默认为 `plain`
+    #[serde(default)]
+    escape: Escape,
+    /// 告警策略，未配置时使用全局策略
+    #[serde(flatten)]
+    policy: AlertPolicyConfig,
+}
+
+impl WebhookConfig {
+    pub(super) fn policy(&self) -> &AlertPolicyConfig {
+        &self.policy
+    }
+
+    pub(super) fn create_notifier(&self) -> Result<Webhook, Error> {
+        let url = self
+            .url
+            .parse::<Url>()
+            .map_err(|_| Error::config(format!("无效 Webhook 地址：{}", self.url)))?;
+        Webhook::new(url, self.template.create_messages(self.escape))
+    }
+}
+
+/// 通过 HTTP POST 将通知事件以 JSON 格式发送至指定地址
+///
+/// 消息负载格式如下，不存在的字段为 `null`：
+///
+/// ```json
+/// {
+///   "event": "change | failure | recovery | test",
+///   "nickname": "test",
+///   "record": "home.example.com",
+///   "old_ip": "1.1.1.1",
+///   "new_ip": "2.2.2.2",
+///   "time": "2024-05-01T18:30:12+08:00",
+///   "error": null,
+///   "error_kind": null,
+///   "error_status": null,
+///   "error_source": null,
+///   "message": "[test] home.example.com IP 地址已由 1.1.1.1 变更为 2.2.2.2（2024-05-01 18:30:12）"
+/// }
+/// ```
+///
+/// 其中 `message` 为通过消息模板渲染的通知文本。
+#[derive(Debug)]
+pub struct Webhook {
+    url: Url,
+    client: Client,
+    messages: Messages,
+}
+
+impl Webhook {
+    pub fn new(url: Url, messages: Messages) -> Result<Self, Error> {
+        Ok(Self {
+            url,
+            messages,
+            client: reqwest::ClientBuilder::new()
+                .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECONDS))
+                .build()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for Webhook {
+    fn name(&self) -> &'static str {
+        "Webhook"
+    }
+
+    async fn send(&self, event: &Event) -> Result<(), Error> {
+        let body = serde_json::json!({
+            "event": event.kind.as_str(),
+            "nickname": event.nickname,
+            "record": event.record,
+            "old_ip": event.old_ip,
+            "new_ip": event.new_ip,
+            "time": event.time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            "error": event.error,
+            "error_kind": event.error_kind.map(|kind| kind.as_str()),
+            "error_status": event.error_status,
+            "error_source": event.error_source,
+            "message": self.messages.render(event),
+        });
+
+        self.client
+            .post(self.url.clone())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| {
+                Error::network(format!("访问 Webhook {} 失败：{}", self.url, err), err)
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use hyper::client::connect::dns::Name;
+use log::warn;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    header, Client,
+};
+
+use super::{error::Error, network::NetworkMode};
+
+/// Cloudflare API 域名
+pub const CLOUDFLARE_API_HOST: &str = "api.cloudflare.com";
+/// 默认 DoH 服务地址，直接使用 IP 地址以避免依赖系统 DNS
+pub const DEFAULT_DOH_URL: &str = "https://1.1.1.1/dns-query";
+
+/// Cloudflare API 域名解析方式
+///
+/// - `pin`：固定解析为指定的 IP 地址，不再查询 DNS
+/// - `doh`：优先使用系统 DNS 解析，失败时改用 DoH 服务解析
+#[derive(serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum CfResolve {
+    Pin(Vec<IpAddr>),
+    Doh(Option<String>),
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// 系统 DNS 解析
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0)).await?;
+            Ok(Box::new(addrs.collect::<Vec<_>>().into_iter()) as Addrs)
+        })
+    }
+}
+
+/// DoH JSON 响应
+#[derive(serde::Deserialize, Debug)]
+struct DohResponse {
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct DohAnswer {
+    data: String,
+}
+
+/// 通过 DoH 服务解析域名
+///
+/// 使用 JSON 格式查询，请求通过绑定的本地地址发出。
+#[derive(Debug, Clone)]
+pub struct DohResolver {
+    url: String,
+    client: Client,
+    /// 绑定 IPv6 本地地址或仅使用 IPv6 时查询 AAAA 记录，否则查询 A 记录
+    ipv6: bool,
+}
+
+impl DohResolver {
+    pub fn new(url: String, bind_address: Option<IpAddr>, ipv6: bool) -> Result<Self, Error> {
+        Ok(Self {
+            url,
+            client: reqwest::ClientBuilder::new()
+                .local_address(bind_address)
+                .build()?,
+            ipv6,
+        })
+    }
+
+    async fn lookup(&self, host: &str) -> Result<Vec<SocketAddr>, BoxError> {
+        let response = self
+            .client
+            .get(&self.url)
+            .query(&[
+                ("name", host),
+                ("type", if self.ipv6 { "AAAA" } else { "A" }),
+            ])
+            .header(header::ACCEPT, "application/dns-json")
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let response: DohResponse = serde_json::from_slice(&response)?;
+
+        // 响应中可能包含 CNAME 记录，仅保留合法的 IP 地址
+        let addrs = response
+            .answer
+            .iter()
+            .filter_map(|answer| answer.data.parse::<IpAddr>().ok())
+            .map(|ip| SocketAddr::new(ip, 0))
+            .collect::<Vec<_>>();
+        if addrs.is_empty() {
+            Err(format!("DoH 服务 {} 未返回 {} 的解析结果", self.url, host).into())
+        } else {
+            Ok(addrs)
+        }
+    }
+}
+
+impl Resolve for DohResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// 按照网络地址族模式过滤 `inner` 的解析结果
+#[derive(Clone)]
+pub struct FamilyResolver {
+    inner: Arc<dyn Resolve>,
+    network: NetworkMode,
+}
+
+impl FamilyResolver {
+    pub fn new(inner: Arc<dyn Resolve>, network: NetworkMode) -> Self {
+        Self { inner, network }
+    }
+}
+
+impl Resolve for FamilyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver
+                .inner
+                .resolve(name.clone())
+                .await?
+                .filter(|addr| resolver.network.allows(&addr.ip()))
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                Err(format!(
+                    "{} 未解析到 {} 模式可用的地址",
+                    name.as_str(),
+                    resolver.network.as_str()
+                )
+                .into())
+            } else {
+                Ok(Box::new(addrs.into_iter()) as Addrs)
+            }
+        })
+    }
+}
+
+/// 优先使用 `primary` 解析，失败时改用 `fallback` 解析
+#[derive(Clone)]
+pub struct FallbackResolver {
+    primary: Arc<dyn Resolve>,
+    fallback: Arc<dyn Resolve>,
+}
+
+impl FallbackResolver {
+    pub fn new(primary: Arc<dyn Resolve>, fallback: Arc<dyn Resolve>) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+impl Resolve for FallbackResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            match resolver.primary.resolve(name.clone()).await {
+                Ok(addrs) => Ok(addrs),
+                Err(err) => {
+                    warn!(
+                        "系统 DNS 解析 {} 失败：{}，改用备用方式解析",
+                        name.as_str(),
+                        err
+                    );
+                    resolver.fallback.resolve(name).await
+                }
+            }
+        })
+    }
+}
+
+impl CfResolve {
+    /// 将域名解析方式应用于 Cloudflare HTTP 客户端，仅使用 `network` 模式可用的地址
+    pub fn apply(
+        &self,
+        builder: reqwest::ClientBuilder,
+        bind_address: Option<IpAddr>,
+        network: NetworkMode,
+    ) -> Result<reqwest::ClientBuilder, Error> {
+        match self {
+            CfResolve::Pin(addresses) => {
+                if addresses.is_empty() {
+                    return Err(Error::config("cf_resolve.pin 至少需要指定一个 IP 地址"));
+                }
+                let addrs = addresses
+                    .iter()
+                    .filter(|ip| network.allows(ip))
+                    .map(|ip| SocketAddr::new(*ip, 443))
+                    .collect::<Vec<_>>();
+                if addrs.is_empty() {
+                    return Err(Error::config(format!(
+                        "cf_resolve.pin 中没有 {} 模式可用的 IP 地址",
+                        network.as_str()
+                    )));
+                }
+                Ok(builder.resolve_to_addrs(CLOUDFLARE_API_HOST, &addrs))
+            }
+            CfResolve::Doh(url) => {
+                let doh = DohResolver::new(
+                    url.clone().unwrap_or(DEFAULT_DOH_URL.to_string()),
+                    bind_address,
+                    matches!(bind_address, Some(IpAddr::V6(_))) || network == NetworkMode::Ipv6Only,
+                )?;
+                let resolver = Arc::new(FallbackResolver::new(
+                    Arc::new(SystemResolver),
+                    Arc::new(doh),
+                ));
+                Ok(builder.dns_resolver(Arc::new(FamilyResolver::new(resolver, network))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, SocketAddr},
+        str::FromStr,
+        sync::Arc,
+    };
+
+    use hyper::client::connect::dns::Name;
+    use reqwest::dns::{Addrs, Resolve, Resolving};
+
+    use super::{FallbackResolver, FamilyResolver};
+    use crate::libs::network::NetworkMode;
+
+    /// 始终解析失败
+    struct BrokenResolver;
+
+    impl Resolve for BrokenResolver {
+        fn resolve(&self, _: Name) -> Resolving {
+            Box::pin(async { Err("resolver is down".into()) })
+        }
+    }
+
+    /// 始终解析为固定地址
+    struct StaticResolver(IpAddr);
+
+    impl Resolve for StaticResolver {
+        fn resolve(&self, _: Name) -> Resolving {
+            let addr = SocketAddr::new(self.0, 0);
+            Box::pin(async move { Ok(Box::new(std::iter::once(addr)) as Addrs) })
+        }
+    }
+
+    async fn resolve(resolver: &FallbackResolver) -> Vec<SocketAddr> {
+        resolver
+            .resolve(Name::from_str("api.cloudflare.com").unwrap())
+            .await
+            .unwrap()
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        let primary = "104.16.132.229".parse().unwrap();
+        let fallback = "104.16.133.229".parse().unwrap();
+
+        let resolver = FallbackResolver::new(
+            Arc::new(StaticResolver(primary)),
+            Arc::new(StaticResolver(fallback)),
+        );
+        assert_eq!(resolve(&resolver).await, vec![SocketAddr::new(primary, 0)]);
+
+        let resolver =
+            FallbackResolver::new(Arc::new(BrokenResolver), Arc::new(StaticResolver(fallback)));
+        assert_eq!(resolve(&resolver).await, vec![SocketAddr::new(fallback, 0)]);
+
+        let resolver = FallbackResolver::new(Arc::new(BrokenResolver), Arc::new(BrokenResolver));
+        assert!(resolver
+            .resolve(Name::from_str("api.cloudflare.com").unwrap())
+            .await
+            .is_err());
+    }
+
+    /// 依次解析为 IPv4 及 IPv6 地址
+    struct DualResolver;
+
+    impl Resolve for DualResolver {
+        fn resolve(&self, _: Name) -> Resolving {
+            let addrs = ["104.16.132.229:0", "[2606:4700::6810:84e5]:0"]
+                .map(|addr| addr.parse::<SocketAddr>().unwrap());
+            Box::pin(async move { Ok(Box::new(addrs.into_iter()) as Addrs) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_family() {
+        let resolve = |network| async move {
+            FamilyResolver::new(Arc::new(DualResolver), network)
+                .resolve(Name::from_str("api.cloudflare.com").unwrap())
+                .await
+                .map(|addrs| addrs.map(|addr| addr.ip()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(resolve(NetworkMode::Dual).await.unwrap().len(), 2);
+        assert!(resolve(NetworkMode::Ipv4Only).await.unwrap()[0].is_ipv4());
+        let ipv6 = resolve(NetworkMode::Ipv6Only).await.unwrap();
+        assert_eq!(ipv6.len(), 1);
+        assert!(ipv6[0].is_ipv6());
+
+        // 没有可用地址时解析失败
+        let resolver = FamilyResolver::new(
+            Arc::new(StaticResolver("104.16.132.229".parse().unwrap())),
+            NetworkMode::Ipv6Only,
+        );
+        assert!(resolver
+            .resolve(Name::from_str("api.cloudflare.com").unwrap())
+            .await
+            .is_err());
+    }
+}
@@ -1,153 +1,1153 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
 
-use futures::future::join_all;
-use log::{error, info};
+use chrono::{DateTime, Local};
+use futures::{
+    future::{join_all, BoxFuture},
+    FutureExt,
+};
+use log::{error, info, warn};
 use smallvec::SmallVec;
 use tokio::{
+    runtime::Handle,
     sync::{
         broadcast::{error::RecvError, Receiver, Sender},
-        Mutex,
+        mpsc::{self, error::TrySendError},
+        watch, Semaphore,
     },
-    time::sleep,
+    task::JoinError,
+    time::{self, interval, sleep, sleep_until, MissedTickBehavior},
 };
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    connectivity::ConnectivityProbe,
+    netns::Netns,
+    supervisor::{panic_message, RestartPolicy, RestartWindow},
+    updater::Updater,
+};
+
+/// 休眠检测的时钟读取间隔
+const SENTINEL_INTERVAL: Duration = Duration::from_secs(60);
+/// 系统时钟比单调时钟多前进超过该时长时视为系统曾休眠
+const SENTINEL_THRESHOLD: Duration = Duration::from_secs(120);
+
+/// 收到系统挂起事件后等待恢复事件的最长时间，超时后视为挂起已取消并恢复更新
+const SUSPEND_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 单个域名初始化占用并发名额的最长时间，超时后转入后台继续初始化并释放名额
+const INIT_PERMIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 日志中下次检查时间的格式
+const NEXT_CHECK_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 格式化下次检查时间，如 `2024-05-01 18:30:12 (+900s)`
+fn format_next_check(next_check: DateTime<Local>, interval: u64) -> String {
+    format!("{} (+{}s)", next_check.format(NEXT_CHECK_FORMAT), interval)
+}
+
+/// 更新器任务的唤醒原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Wake {
+    /// 到达计划的检查时间
+    Scheduled,
+    /// 收到事件触发消息
+    Notify(NotifyKind),
+    /// 系统开始挂起
+    Suspend,
+}
+
+/// 更新器任务句柄，调度器通过句柄向更新器任务发送触发消息
+///
+/// 更新器任务尚未处理的触发消息最多保留一条，期间收到的其他触发消息将被合并。
+/// 系统挂起状态独立于触发消息传递，不会被合并丢失。
+#[derive(Debug, Clone)]
+pub struct UpdaterHandle {
+    trigger_tx: mpsc::Sender<NotifyKind>,
+    suspend_tx: watch::Sender<bool>,
+}
+
+impl UpdaterHandle {
+    fn channel() -> (Self, mpsc::Receiver<NotifyKind>, watch::Receiver<bool>) {
+        let (trigger_tx, trigger_rx) = mpsc::channel(1);
+        let (suspend_tx, suspend_rx) = watch::channel(false);
+        (
+            Self {
+                trigger_tx,
+                suspend_tx,
+            },
+            trigger_rx,
+            suspend_rx,
+        )
+    }
+
+    /// 设置系统是否正在挂起，挂起期间更新器任务取消正在进行的更新并暂停更新
+    pub fn suspend(&self, suspended: bool) {
+        self.suspend_tx.send_replace(suspended);
+    }
+
+    /// 触发更新，返回 `false` 说明更新器任务已退出
+    pub fn trigger(&self, kind: NotifyKind) -> bool {
+        match self.trigger_tx.try_send(kind) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+/// 更新器任务
+///
+/// 每个更新器由唯一的任务持有，任务负责初始化、按计划循环更新及处理触发消息，直至收到终止消息。
+/// 初始化及更新过程中出现 panic 时输出日志并按照重启策略等待后重试，
+/// 时间窗口内 panic 次数超过上限时永久停止更新该域名，避免每次更新均 panic 时无限重试。
+///
+/// 收到触发消息后先静默等待 `quiescence`，静默期间及更新过程中收到的触发消息均合并为一次后续更新。
+/// 静默结束时计划检查时间已临近的，以计划的更新代替事件触发的更新，避免连续更新两次。
+///
+/// 系统挂起期间取消正在进行的更新，直至系统恢复后再继续更新，避免恢复后继续使用挂起前的过期数据。
+///
+/// 配置了网络连通性检查时，事件触发的更新前先检查网络，疑似处于认证页面时跳过本次更新。
+pub struct UpdaterTask {
+    updater: Updater,
+    trigger_rx: mpsc::Receiver<NotifyKind>,
+    suspend_rx: watch::Receiver<bool>,
+    quiescence: Duration,
+    connectivity: Option<Arc<ConnectivityProbe>>,
+    /// 初始化及更新过程中 panic 的次数
+    panics: RestartWindow,
+}
 
-use super::updater::Updater;
+impl UpdaterTask {
+    /// 创建更新器任务及其句柄
+    pub fn new(
+        updater: Updater,
+        quiescence: Duration,
+        connectivity: Option<Arc<ConnectivityProbe>>,
+    ) -> (Self, UpdaterHandle) {
+        let (handle, trigger_rx, suspend_rx) = UpdaterHandle::channel();
+        (
+            Self {
+                updater,
+                trigger_rx,
+                suspend_rx,
+                quiescence,
+                connectivity,
+                panics: RestartWindow::new(RestartPolicy::default()),
+            },
+            handle,
+        )
+    }
+
+    /// 等待下一次唤醒，收到终止消息时返回 `None`
+    ///
+    /// 终止消息优先于触发消息及计划检查处理。
+    async fn next_wake(
+        trigger_rx: &mut mpsc::Receiver<NotifyKind>,
+        deadline: time::Instant,
+        termination: &CancellationToken,
+    ) -> Option<Wake> {
+        tokio::select! {
+            biased;
+            _ = termination.cancelled() => None,
+            Some(kind) = trigger_rx.recv() => Some(Wake::Notify(kind)),
+            _ = sleep_until(deadline) => Some(Wake::Scheduled),
+        }
+    }
+
+    /// 静默等待 `quiescence`，期间收到的触发消息均被合并，收到终止消息时返回 `false`
+    ///
+    /// 静默时长自首条触发消息起计算，不因后续触发消息延长，持续的触发消息不会无限推迟更新。
+    async fn quiesce(
+        trigger_rx: &mut mpsc::Receiver<NotifyKind>,
+        quiescence: Duration,
+        termination: &CancellationToken,
+    ) -> bool {
+        let deadline = sleep(quiescence);
+        tokio::pin!(deadline);
+        loop {
+            tokio::select! {
+                biased;
+                _ = termination.cancelled() => return false,
+                Some(_) = trigger_rx.recv() => {}
+                _ = &mut deadline => return true,
+            }
+        }
+    }
+
+    /// 执行 `future`，系统开始挂起时取消执行并返回 `None`
+    async fn unless_suspended<F: Future>(
+        suspend_rx: &mut watch::Receiver<bool>,
+        future: F,
+    ) -> Option<F::Output> {
+        tokio::select! {
+            biased;
+            Ok(_) = suspend_rx.wait_for(|suspended| *suspended) => None,
+            output = future => Some(output),
+        }
+    }
+
+    /// 暂停更新直至系统恢复，收到终止消息时返回 `false`
+    async fn park(&mut self, termination: &CancellationToken) -> bool {
+        info!("[{}] 系统正在挂起，暂停更新", self.updater.nickname);
+        self.updater
+            .status()
+            .update(|status| status.suspended = true);
+        let resumed = tokio::select! {
+            biased;
+            _ = termination.cancelled() => false,
+            // 发送端已释放时不再可能收到挂起消息，同样视为已恢复
+            _ = self.suspend_rx.wait_for(|suspended| !*suspended) => true,
+        };
+        self.updater
+            .status()
+            .update(|status| status.suspended = false);
+        if resumed {
+            info!("[{}] 系统已恢复，继续更新", self.updater.nickname);
+        }
+        resumed
+    }
+
+    /// 初始化，返回 `false` 说明初始化失败或收到终止消息
+    ///
+    /// 初始化仅在出现不可重试的错误（如 token 无效、记录不存在）时失败，此时永久停止更新该域名。
+    /// 初始化前需获取 `semaphore` 中的名额，以限制同时初始化的域名数量。
+    /// 初始化超过 [`INIT_PERMIT_TIMEOUT`] 后释放名额，在后台继续初始化。
+    async fn init(
+        &mut self,
+        termination: &CancellationToken,
+        semaphore: &Semaphore,
+        ready: &AtomicUsize,
+        total: usize,
+    ) -> bool {
+        loop {
+            let mut permit = tokio::select! {
+                _ = termination.cancelled() => return false,
+                permit = semaphore.acquire() => permit.ok(),
+            };
+            let result = {
+                let nickname = self.updater.nickname.clone();
+                let init = AssertUnwindSafe(self.updater.init()).catch_unwind();
+                let timeout = sleep(INIT_PERMIT_TIMEOUT);
+                tokio::pin!(init, timeout);
+                loop {
+                    tokio::select! {
+                        _ = termination.cancelled() => return false,
+                        result = &mut init => break result,
+                        _ = &mut timeout, if permit.is_some() => {
+                            permit = None;
+                            warn!(
+                                "[{}] 初始化超过 {} 秒，转入后台继续初始化",
+                                nickname,
+                                INIT_PERMIT_TIMEOUT.as_secs()
+                            );
+                        }
+                    }
+                }
+            };
+            drop(permit);
+
+            let payload = match result {
+                Ok(Ok(())) => {
+                    let ready = ready.fetch_add(1, Ordering::SeqCst) + 1;
+                    info!(
+                        "[{}] 初始化完毕，已就绪 {}/{} 个域名，{} 个域名仍在初始化",
+                        self.updater.nickname,
+                        ready,
+                        total,
+                        total - ready
+                    );
+                    return true;
+                }
+                Ok(Err(err)) => {
+                    self.updater.status().update(|status| status.failed = true);
+                    error!("{}。初始化失败，已永久停止更新该域名", err);
+                    return false;
+                }
+                Err(payload) => payload,
+            };
+
+            let reason = panic_message(payload.as_ref());
+            let Some(backoff) = self.record_panic(reason) else {
+                return false;
+            };
+            error!(
+                "[{}] 初始化异常：{}。将在 {} 秒后重试",
+                self.updater.nickname,
+                reason,
+                backoff.as_secs()
+            );
+            tokio::select! {
+                _ = termination.cancelled() => return false,
+                _ = sleep(backoff) => {}
+            }
+        }
+    }
+
+    /// 执行计划的更新，返回是否成功及距下次检查的间隔
+    async fn scheduled_update(&mut self) -> (bool, u64) {
+        let result = AssertUnwindSafe(self.updater.update()).catch_unwind().await;
+        let result = result.map_err(|payload| {
+            let reason = panic_message(payload.as_ref()).to_string();
+            let backoff = self.record_panic(&reason);
+            (reason, backoff)
+        });
+        let updater = &self.updater;
+        match result {
+            Ok(Ok(msg)) => {
+                let interval = updater.refresh_interval;
+                let next_check = updater.schedule_next(Duration::from_secs(interval));
+                info!(
+                    "[{}] {}。下次检查时间：{}",
+                    updater.nickname,
+                    msg,
+                    format_next_check(next_check, interval)
+                );
+                (true, interval)
+            }
+            Ok(Err(err)) => {
+                let interval = updater.retry_delay(&err);
+                let next_check = updater.schedule_next(Duration::from_secs(interval));
+                error!(
+                    "{}。下次重试时间：{}",
+                    err,
+                    format_next_check(next_check, interval)
+                );
+                (false, interval)
+            }
+            Err((reason, Some(backoff))) => {
+                let interval = backoff.as_secs();
+                let next_check = updater.schedule_next(backoff);
+                error!(
+                    "[{}] 更新异常：{}。下次重试时间：{}",
+                    updater.nickname,
+                    reason,
+                    format_next_check(next_check, interval)
+                );
+                (false, interval)
+            }
+            Err((_, None)) => (false, updater.retry_interval),
+        }
+    }
+
+    /// 执行事件触发的更新，不影响计划的下次检查时间
+    async fn notified_update(&mut self) {
+        if let Some(connectivity) = &self.connectivity {
+            if let Err(reason) = connectivity.check().await {
+                warn!(
+                    "[{}] 网络连通性检查未通过，疑似处于认证页面（captive portal），跳过本次事件触发的更新：{}",
+                    self.updater.nickname, reason
+                );
+                return;
+            }
+        }
+
+        let next_check = self
+            .updater
+            .status()
+            .get()
+            .next_check
+            .map(|next_check| format!("。下次检查时间：{}", next_check.format(NEXT_CHECK_FORMAT)))
+            .unwrap_or_default();
+        let result = AssertUnwindSafe(self.updater.update()).catch_unwind().await;
+        match result {
+            Ok(Ok(msg)) => info!("[{}] {}{}", self.updater.nickname, msg, next_check),
+            Ok(Err(err)) => error!("{}{}", err, next_check),
+            Err(payload) => {
+                let reason = panic_message(payload.as_ref());
+                if self.record_panic(reason).is_some() {
+                    error!(
+                        "[{}] 事件触发的更新异常：{}{}",
+                        self.updater.nickname, reason, next_check
+                    );
+                }
+            }
+        }
+    }
+
+    /// 记录一次 panic，返回重试前的等待时间
+    ///
+    /// 时间窗口内 panic 次数已达上限时永久停止更新该域名并返回 `None`。
+    fn record_panic(&mut self, reason: &str) -> Option<Duration> {
+        if let Some(backoff) = self.panics.record() {
+            return Some(backoff);
+        }
+
+        self.updater.status().update(|status| status.failed = true);
+        error!(
+            "[{}] {} 秒内已出现 {} 次异常，永久停止更新该域名！最后一次异常：{}",
+            self.updater.nickname,
+            self.panics.policy().window.as_secs(),
+            self.panics.recent() + 1,
+            reason
+        );
+        None
+    }
+
+    /// 连续失败次数达到上限或异常次数过多时永久停止更新，返回是否已停止
+    fn give_up(&self) -> bool {
+        if self.updater.status().get().failed {
+            return true;
+        }
+        if !self.updater.failure_limit_reached() {
+            return false;
+        }
+
+        let status = self.updater.status();
+        status.update(|status| status.failed = true);
+        let status = status.get();
+        error!(
+            "[{}] 已连续失败 {} 次，永久停止更新该域名！最后一次错误：{}",
+            self.updater.nickname,
+            status.consecutive_failures,
+            status.last_error.as_deref().unwrap_or("未知")
+        );
+        true
+    }
+
+    /// 初始化并循环更新，直至收到终止消息
+    ///
+    /// 返回 `true` 说明初始化失败、连续失败次数达到上限或异常次数过多，已永久停止更新。
+    async fn run(
+        mut self,
+        termination: CancellationToken,
+        semaphore: Arc<Semaphore>,
+        ready: Arc<AtomicUsize>,
+        total: usize,
+    ) -> bool {
+        if !self.init(&termination, &semaphore, &ready, total).await {
+            return self.updater.status().get().failed;
+        }
+        // 初始化完成后将立即更新，无需处理初始化期间收到的触发消息
+        while self.trigger_rx.try_recv().is_ok() {}
+
+        let retry_budget = self.updater.retry_budget().clone();
+        let mut suspend_rx = self.suspend_rx.clone();
+        let mut deadline = time::Instant::now();
+        let mut retrying = false;
+        loop {
+            let wake = tokio::select! {
+                biased;
+                Ok(_) = suspend_rx.wait_for(|suspended| *suspended) => Some(Wake::Suspend),
+                wake = Self::next_wake(&mut self.trigger_rx, deadline, &termination) => wake,
+            };
+            let Some(mut wake) = wake else {
+                break;
+            };
+            if let Wake::Notify(_) = wake {
+                if !Self::quiesce(&mut self.trigger_rx, self.quiescence, &termination).await {
+                    break;
+                }
+                // 计划检查时间已临近，合并为一次计划的更新
+                if deadline <= time::Instant::now() + self.quiescence {
+                    wake = Wake::Scheduled;
+                }
+            }
+
+            match wake {
+                Wake::Scheduled => {
+                    // 仅失败后的重试消耗重试预算
+                    if retrying {
+                        tokio::select! {
+                            _ = termination.cancelled() => break,
+                            _ = retry_budget.acquire() => {},
+                        }
+                    }
+
+                    // 因系统挂起被取消的更新在恢复后立即重新执行
+                    if let Some((success, interval)) =
+                        Self::unless_suspended(&mut suspend_rx, self.scheduled_update()).await
+                    {
+                        retrying = !success;
+                        deadline = time::Instant::now() + Duration::from_secs(interval);
+                    }
+                    if self.give_up() {
+                        return true;
+                    }
+                }
+                Wake::Notify(_) => {
+                    Self::unless_suspended(&mut suspend_rx, self.notified_update()).await;
+                    if self.give_up() {
+                        return true;
+                    }
+                }
+                Wake::Suspend => {
+                    if !self.park(&termination).await {
+                        break;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
 
 /// 自循环定时更新域名调度器
+///
+/// 为每个更新器启动独立的任务，单个域名初始化失败不影响其他域名。
 pub struct LoopingScheduler {
-    updaters: SmallVec<[(Arc<Mutex<Updater>>, Receiver<()>); 4]>,
+    tasks: Vec<UpdaterTask>,
+    termination: CancellationToken,
+    /// 同时初始化的域名数量上限
+    semaphore: Arc<Semaphore>,
+    /// 已完成初始化的域名数量
+    ready: Arc<AtomicUsize>,
 }
 
 impl LoopingScheduler {
     /// 创建自循环定时更新域名调度器
-    pub fn new<I>(updaters: I, termination_tx: &Sender<()>) -> Self
+    pub fn new<I>(tasks: I, init_concurrency: usize, termination: &CancellationToken) -> Self
     where
-        I: IntoIterator<Item = Arc<Mutex<Updater>>>,
+        I: IntoIterator<Item = UpdaterTask>,
     {
-        let updaters = updaters
-            .into_iter()
-            .map(|updater| (updater, termination_tx.subscribe()))
-            .collect::<SmallVec<[(Arc<Mutex<Updater>>, Receiver<()>); 4]>>();
-        Self { updaters }
+        Self {
+            tasks: tasks.into_iter().collect(),
+            termination: termination.clone(),
+            semaphore: Arc::new(Semaphore::new(init_concurrency)),
+            ready: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// 在当前运行时中运行 `tasks`，返回各任务的域名昵称及运行结果
+    async fn run_all(
+        tasks: Vec<UpdaterTask>,
+        termination: CancellationToken,
+        semaphore: Arc<Semaphore>,
+        ready: Arc<AtomicUsize>,
+        total: usize,
+    ) -> Vec<(Arc<str>, Result<bool, JoinError>)> {
+        let handlers = tasks.into_iter().map(|task| {
+            let nickname = task.updater.nickname.clone();
+            let handler = tokio::spawn(task.run(
+                termination.clone(),
+                semaphore.clone(),
+                ready.clone(),
+                total,
+            ));
+            async move { (nickname, handler.await) }
+        });
+        join_all(handlers).await
     }
 
-    /// 启动自循环定时更新
+    /// 启动所有更新器任务，直至全部退出
+    ///
+    /// 配置了网络命名空间的更新器任务按命名空间分组，每个命名空间中的任务由进入该命名空间的独立线程运行。
+    /// 所有域名均因连续失败或异常次数过多永久停止更新时提前退出，以便进程以非零状态码退出。
     pub async fn start(self) {
-        let mut handlers = Vec::with_capacity(self.updaters.len());
-
-        // 启动循环更新器
-        self.updaters
-            .into_iter()
-            .for_each(|(updater, mut termination_rx)| {
-                let handler = tokio::spawn(async move {
-                    loop {
-                        let Ok(mut updater) = updater.try_lock() else {
-                            continue;
-                        };
-
-                        let interval = match updater.update().await {
-                            Ok(msg) => {
-                                info!(
-                                    "[{}] {}。{} 秒后进行下次检查。",
-                                    updater.nickname, msg, updater.refresh_interval
-                                );
-                                updater.refresh_interval
-                            }
-                            Err(err) => {
-                                error!(
-                                    "[{}] {}。将在 {} 秒后重试",
-                                    updater.nickname, err, updater.retry_interval
-                                );
-                                updater.retry_interval
-                            }
-                        };
-
-                        drop(updater);
-
-                        let abort = tokio::select! {
-                            _ = termination_rx.recv() => true,
-                            _ = sleep(Duration::from_secs(interval)) => false,
-                        };
-                        if abort {
-                            break;
-                        }
-                    }
-                });
-                handlers.push(handler);
-            });
+        let total = self.tasks.len();
+        let mut local = Vec::new();
+        let mut namespaces: Vec<(Arc<Netns>, Vec<UpdaterTask>)> = Vec::new();
+        for task in self.tasks {
+            let Some(netns) = task.updater.netns().cloned() else {
+                local.push(task);
+                continue;
+            };
+            match namespaces
+                .iter_mut()
+                .find(|(other, _)| Arc::ptr_eq(other, &netns))
+            {
+                Some((_, tasks)) => tasks.push(task),
+                None => namespaces.push((netns, vec![task])),
+            }
+        }
+
+        let run_all = |tasks| {
+            Self::run_all(
+                tasks,
+                self.termination.clone(),
+                self.semaphore.clone(),
+                self.ready.clone(),
+                total,
+            )
+        };
+        let mut groups: Vec<BoxFuture<'static, Vec<_>>> = vec![Box::pin(run_all(local))];
+        for (netns, tasks) in namespaces {
+            // 通知统一由当前运行时发送
+            tasks
+                .iter()
+                .filter_map(|task| task.updater.notifications())
+                .for_each(|notifications| notifications.set_runtime(Handle::current()));
+            let nicknames = tasks
+                .iter()
+                .map(|task| task.updater.nickname.clone())
+                .collect::<Vec<_>>();
+            let results = netns.spawn(run_all(tasks));
+            groups.push(Box::pin(async move {
+                results.await.unwrap_or_else(|_| {
+                    error!(
+                        "网络命名空间 {} 中的更新器任务未能运行，已停止更新以下域名：{}",
+                        netns.name(),
+                        nicknames.join("、")
+                    );
+                    nicknames
+                        .into_iter()
+                        .map(|nickname| (nickname, Ok(true)))
+                        .collect()
+                })
+            }));
+        }
 
-        join_all(handlers).await;
+        let mut failed = 0;
+        for (nickname, result) in join_all(groups).await.into_iter().flatten() {
+            match result {
+                Ok(true) => failed += 1,
+                Ok(false) => {}
+                Err(err) if err.is_panic() => {
+                    error!("[{}] 更新器任务异常退出：{}", nickname, err)
+                }
+                Err(_) => {}
+            }
+        }
+        if total > 0 && failed == total {
+            error!("所有域名均已因初始化失败、连续失败或异常次数过多永久停止更新，ddns4cf 即将以错误状态退出");
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
 pub enum NotifyKind {
     OsSuspendResume,
+    /// 系统即将挂起
+    OsSuspending,
+    /// 由 [`SleepSentinel`] 检测到系统曾长时间休眠
+    SleepDetected,
 }
 
 /// 基于事件消息的域名更新调度器
+///
+/// 收到事件消息后向所有更新器任务发送触发消息，更新由各更新器任务完成。
+///
+/// 收到系统挂起事件后暂停所有更新器任务，直至收到恢复事件。挂起及恢复事件不保证成对出现：
+/// 未挂起时收到的恢复事件照常触发更新，挂起后超过 [`SUSPEND_TIMEOUT`] 仍未收到恢复事件时自动恢复更新。
 pub struct NotifyScheduler {
-    termination_rx: Receiver<()>,
-    updaters: SmallVec<[Arc<Mutex<Updater>>; 4]>,
+    termination: CancellationToken,
+    handles: SmallVec<[UpdaterHandle; 4]>,
     notify_rx: Receiver<NotifyKind>,
+    suspend_timeout: Duration,
 }
 
 impl NotifyScheduler {
     /// 创建事件消息域名调度器
     pub fn new(
-        updaters: SmallVec<[Arc<Mutex<Updater>>; 4]>,
+        handles: SmallVec<[UpdaterHandle; 4]>,
         notify_rx: Receiver<NotifyKind>,
-        termination_tx: &Sender<()>,
+        termination: &CancellationToken,
     ) -> Self {
         Self {
-            termination_rx: termination_tx.subscribe(),
-            updaters,
+            termination: termination.clone(),
+            handles,
             notify_rx,
+            suspend_timeout: SUSPEND_TIMEOUT,
         }
     }
 
+    fn suspend_all(&self, suspended: bool) {
+        self.handles
+            .iter()
+            .for_each(|handle| handle.suspend(suspended));
+    }
+
     /// 启动消息监听更新
     pub async fn start(mut self) {
+        // 重启后无法得知此前的挂起状态，先恢复所有更新器任务，避免一直处于暂停状态
+        self.suspend_all(false);
+
+        let mut suspended = false;
+        let suspend_timeout = sleep(Duration::ZERO);
+        tokio::pin!(suspend_timeout);
         loop {
-            let abort = tokio::select! {
-                _ = self.termination_rx.recv() => true,
-                result = self.notify_rx.recv() => {
-                    match result {
-                        Ok(kind) => {
-                            match kind {
-                                NotifyKind::OsSuspendResume => info!("接收系统唤醒事件，触发域名刷新"),
-                            };
-                            false
-                        },
-                        Err(error) => match error {
-                            RecvError::Closed => true,
-                            RecvError::Lagged(_) => false
-                        }
-                    }
+            let kind = tokio::select! {
+                _ = self.termination.cancelled() => break,
+                _ = &mut suspend_timeout, if suspended => {
+                    warn!(
+                        "系统挂起事件后 {} 秒仍未收到恢复事件，恢复域名更新",
+                        self.suspend_timeout.as_secs()
+                    );
+                    suspended = false;
+                    self.suspend_all(false);
+                    continue;
+                }
+                result = self.notify_rx.recv() => match result {
+                    Ok(kind) => kind,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => continue,
                 },
             };
-            if abort {
-                break;
+            match kind {
+                NotifyKind::OsSuspending => {
+                    info!("接收系统挂起事件，暂停域名刷新");
+                    suspended = true;
+                    suspend_timeout
+                        .as_mut()
+                        .reset(time::Instant::now() + self.suspend_timeout);
+                    self.suspend_all(true);
+                    continue;
+                }
+                NotifyKind::OsSuspendResume => info!("接收系统唤醒事件，触发域名刷新"),
+                NotifyKind::SleepDetected => info!("检测到系统曾休眠，触发域名刷新"),
+            };
+
+            // 未收到挂起事件时同样恢复，不影响未暂停的更新器任务
+            suspended = false;
+            self.suspend_all(false);
+            self.handles.iter().for_each(|handle| {
+                handle.trigger(kind);
+            });
+        }
+    }
+}
+
+/// 系统休眠检测
+///
+/// 单调时钟在系统休眠期间不前进，而系统时钟照常前进。定时比较两者的前进量，
+/// 系统时钟多前进超过阈值时视为系统曾休眠，发送 [`NotifyKind::SleepDetected`] 触发所有域名更新。
+/// 用于无法接收系统唤醒事件的环境，如未运行 logind 的 Linux 或断电休眠后恢复。
+#[derive(Debug)]
+pub struct SleepSentinel {
+    monotonic: Instant,
+    wall: SystemTime,
+}
+
+impl SleepSentinel {
+    fn new_at(monotonic: Instant, wall: SystemTime) -> Self {
+        Self { monotonic, wall }
+    }
+
+    /// 记录当前时钟，返回系统时钟比单调时钟多前进的时长，未超过阈值时返回 `None`
+    fn check_at(&mut self, monotonic: Instant, wall: SystemTime) -> Option<Duration> {
+        let monotonic_elapsed = monotonic.saturating_duration_since(self.monotonic);
+        // 系统时钟被向后调整时不视为休眠
+        let wall_elapsed = wall.duration_since(self.wall).unwrap_or_default();
+        self.monotonic = monotonic;
+        self.wall = wall;
+
+        let gap = wall_elapsed.saturating_sub(monotonic_elapsed);
+        (gap > SENTINEL_THRESHOLD).then_some(gap)
+    }
+
+    /// 定时检测系统休眠，直至收到终止消息
+    ///
+    /// `listener_active` 为 `true` 时说明系统唤醒事件监听已生效，此时跳过检测以免重复触发更新。
+    pub async fn run(
+        notify_tx: Sender<NotifyKind>,
+        listener_active: Arc<AtomicBool>,
+        termination: CancellationToken,
+    ) {
+        let mut sentinel = Self::new_at(Instant::now(), SystemTime::now());
+        let mut ticker = interval(SENTINEL_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = termination.cancelled() => break,
+                _ = ticker.tick() => {},
             }
 
-            let handlers = self.updaters.iter().cloned().map(|updater| {
-                tokio::spawn(async move {
-                    let Ok(mut updater) = updater.try_lock() else {
-                        return;
-                    };
+            let gap = sentinel.check_at(Instant::now(), SystemTime::now());
+            if listener_active.load(Ordering::Relaxed) {
+                continue;
+            }
+            if let Some(gap) = gap {
+                warn!("检测到系统时钟跳跃 {} 秒，系统可能曾休眠", gap.as_secs());
+                let _ = notify_tx.send(NotifyKind::SleepDetected);
+            }
+        }
+    }
+}
 
-                    match updater.update().await {
-                        Ok(msg) => {
-                            info!("[{}] {}", updater.nickname, msg);
-                        }
-                        Err(err) => {
-                            error!("[{}] {}", updater.nickname, err);
-                        }
-                    };
-                    drop(updater);
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant, SystemTime};
+
+    use std::sync::{atomic::AtomicUsize, Arc};
+
+    use tokio::{sync::Semaphore, time};
+    use tokio_util::sync::CancellationToken;
+
+    use futures::future;
+    use smallvec::smallvec;
+    use tokio::sync::broadcast;
+
+    use super::{NotifyKind, NotifyScheduler, SleepSentinel, UpdaterHandle, UpdaterTask, Wake};
+    use crate::libs::{
+        error::Error,
+        status::UpdaterStatus,
+        supervisor::{RestartPolicy, RestartWindow},
+        updater::tests::{ip, record, updater, MockCfApi},
+    };
+
+    #[tokio::test]
+    async fn test_trigger_coalescing() {
+        let (handle, mut trigger_rx, _) = UpdaterHandle::channel();
+
+        // 更新器任务处理前收到的多条触发消息合并为一条
+        assert!(handle.trigger(NotifyKind::OsSuspendResume));
+        assert!(handle.trigger(NotifyKind::SleepDetected));
+        assert_eq!(trigger_rx.recv().await, Some(NotifyKind::OsSuspendResume));
+        assert!(trigger_rx.try_recv().is_err());
+
+        // 更新器任务退出后触发失败
+        drop(trigger_rx);
+        assert!(!handle.trigger(NotifyKind::OsSuspendResume));
+    }
+
+    #[tokio::test]
+    async fn test_notification_burst() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let updater = updater(&api, (0..10).map(|_| ip("1.1.1.1")));
+        let status = updater.status().clone();
+        let (task, handle) = UpdaterTask::new(updater, Duration::ZERO, None);
+
+        let termination = CancellationToken::new();
+        let running = tokio::spawn(task.run(
+            termination.clone(),
+            Arc::new(Semaphore::new(1)),
+            Arc::new(AtomicUsize::new(0)),
+            1,
+        ));
+
+        // 等待初始化后的首次更新完成
+        let mut status_rx = status.subscribe();
+        time::timeout(
+            Duration::from_secs(5),
+            status_rx.wait_for(|status| status.checks == 1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // 短时间内的大量触发消息合并处理
+        (0..50).for_each(|_| assert!(handle.trigger(NotifyKind::OsSuspendResume)));
+        time::sleep(Duration::from_millis(100)).await;
+        let checks = status.get().checks;
+        assert!((2..=3).contains(&checks), "checks: {}", checks);
+
+        termination.cancel();
+        running.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_consecutive_failures() {
+        let failure = || Err(Error::cloudflare_update_failure(vec![1004], vec![]));
+        let api = MockCfApi::new(
+            [Ok(record("1.1.1.1"))],
+            [failure(), Ok(record("2.2.2.2")), failure(), failure()],
+        );
+        let limited = updater(
+            &api,
+            [
+                ip("1.1.1.1"),
+                ip("2.2.2.2"),
+                ip("2.2.2.2"),
+                ip("3.3.3.3"),
+                ip("4.4.4.4"),
+            ],
+        )
+        .with_max_consecutive_failures(Some(2));
+        let status = limited.status().clone();
+        let (task, handle) = UpdaterTask::new(limited, Duration::ZERO, None);
+
+        let termination = CancellationToken::new();
+        let running = tokio::spawn(task.run(
+            termination.clone(),
+            Arc::new(Semaphore::new(1)),
+            Arc::new(AtomicUsize::new(0)),
+            1,
+        ));
+
+        let mut status_rx = status.subscribe();
+        for checks in 2..=5 {
+            time::timeout(
+                Duration::from_secs(5),
+                status_rx.wait_for(|status| status.checks == checks - 1),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            handle.trigger(NotifyKind::OsSuspendResume);
+        }
+
+        // 成功后重新计数，连续失败次数达到上限后永久停止
+        let failed = time::timeout(Duration::from_secs(5), running).await;
+        assert!(failed.unwrap().unwrap());
+        let status = status.get();
+        assert!(status.failed);
+        assert_eq!(status.checks, 5);
+        assert_eq!(status.failures, 3);
+        assert_eq!(status.consecutive_failures, 2);
+        assert!(!termination.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_panic_limit() {
+        let policy = RestartPolicy {
+            max_restarts: 3,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        };
+
+        // 初始化及更新时 Cloudflare API 均 panic
+        let init_panics = MockCfApi::new([], []);
+        let update_panics = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        for api in [init_panics, update_panics] {
+            let panicking = updater(&api, (0..10).map(|_| ip("2.2.2.2")));
+            let status = panicking.status().clone();
+            let (mut task, _handle) = UpdaterTask::new(panicking, Duration::ZERO, None);
+            task.panics = RestartWindow::new(policy);
+
+            // 时间窗口内 panic 次数超过上限后永久停止，不再无限重试
+            let termination = CancellationToken::new();
+            let running = task.run(
+                termination.clone(),
+                Arc::new(Semaphore::new(1)),
+                Arc::new(AtomicUsize::new(0)),
+                1,
+            );
+            let failed = time::timeout(Duration::from_secs(5), running).await;
+            assert!(failed.unwrap());
+            assert!(status.get().failed);
+            assert!(!termination.is_cancelled());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_init_failure() {
+        // 记录不存在等不可重试的错误导致初始化失败，视为永久停止更新
+        let api = MockCfApi::new(
+            [Err(Error::cloudflare_record_failure(
+                vec![81044],
+                vec!["Record does not exist.".to_string()],
+            ))],
+            [],
+        );
+        let failing = updater(&api, [ip("1.1.1.1")]);
+        let status = failing.status().clone();
+        let (task, _handle) = UpdaterTask::new(failing, Duration::ZERO, None);
+
+        let running = task.run(
+            CancellationToken::new(),
+            Arc::new(Semaphore::new(1)),
+            Arc::new(AtomicUsize::new(0)),
+            1,
+        );
+        assert!(time::timeout(Duration::from_secs(5), running)
+            .await
+            .unwrap());
+        assert!(status.get().failed);
+        assert!(status.get().report().ends_with("已永久停止更新"));
+    }
+
+    #[tokio::test]
+    async fn test_trigger_quiescence() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let updater = updater(&api, (0..10).map(|_| ip("1.1.1.1")));
+        let status = updater.status().clone();
+        let (task, handle) = UpdaterTask::new(updater, Duration::from_millis(200), None);
+
+        let termination = CancellationToken::new();
+        let running = tokio::spawn(task.run(
+            termination.clone(),
+            Arc::new(Semaphore::new(1)),
+            Arc::new(AtomicUsize::new(0)),
+            1,
+        ));
+
+        let mut status_rx = status.subscribe();
+        time::timeout(
+            Duration::from_secs(5),
+            status_rx.wait_for(|status| status.checks == 1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // 多个来源同时发送的大量触发消息在静默期内合并，每轮仅更新一次
+        for round in 2..=3 {
+            let storm = (0..8).map(|_| {
+                let handle = handle.clone();
+                tokio::spawn(async move {
+                    for _ in 0..20 {
+                        assert!(handle.trigger(NotifyKind::OsSuspendResume));
+                        tokio::task::yield_now().await;
+                    }
                 })
             });
-            join_all(handlers).await;
+            futures::future::join_all(storm).await;
+            time::sleep(Duration::from_millis(600)).await;
+            assert_eq!(status.get().checks, round);
         }
+
+        termination.cancel();
+        running.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suspend() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let updater = updater(&api, (0..10).map(|_| ip("1.1.1.1")));
+        let status = updater.status().clone();
+        let (task, handle) = UpdaterTask::new(updater, Duration::ZERO, None);
+
+        let termination = CancellationToken::new();
+        let running = tokio::spawn(task.run(
+            termination.clone(),
+            Arc::new(Semaphore::new(1)),
+            Arc::new(AtomicUsize::new(0)),
+            1,
+        ));
+
+        let status_rx = status.subscribe();
+        let wait_for = |f: fn(&UpdaterStatus) -> bool| {
+            let mut status_rx = status_rx.clone();
+            async move {
+                time::timeout(Duration::from_secs(5), status_rx.wait_for(f))
+                    .await
+                    .unwrap()
+                    .unwrap();
+            }
+        };
+        wait_for(|status| status.checks == 1).await;
+
+        // 挂起期间暂停更新，触发消息不会执行更新
+        handle.suspend(true);
+        wait_for(|status| status.suspended).await;
+        assert!(handle.trigger(NotifyKind::SleepDetected));
+        time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(status.get().checks, 1);
+
+        // 恢复后处理挂起期间的触发消息
+        handle.suspend(false);
+        wait_for(|status| !status.suspended && status.checks == 2).await;
+
+        termination.cancel();
+        running.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unless_suspended() {
+        let (handle, _, mut suspend_rx) = UpdaterHandle::channel();
+        assert_eq!(
+            UpdaterTask::unless_suspended(&mut suspend_rx, async { 1 }).await,
+            Some(1)
+        );
+
+        // 系统开始挂起时取消正在进行的更新
+        let pending = tokio::spawn(async move {
+            UpdaterTask::unless_suspended(&mut suspend_rx, future::pending::<()>()).await
+        });
+        handle.suspend(true);
+        let result = time::timeout(Duration::from_secs(1), pending).await;
+        assert_eq!(result.unwrap().unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_suspend_pairing() {
+        let (handle, mut trigger_rx, suspend_rx) = UpdaterHandle::channel();
+        let (notify_tx, notify_rx) = broadcast::channel(4);
+        let termination = CancellationToken::new();
+        let mut scheduler = NotifyScheduler::new(smallvec![handle], notify_rx, &termination);
+        scheduler.suspend_timeout = Duration::from_millis(100);
+        let running = tokio::spawn(scheduler.start());
+
+        // 未收到挂起事件时的恢复事件照常触发更新
+        notify_tx.send(NotifyKind::OsSuspendResume).unwrap();
+        assert_eq!(trigger_rx.recv().await, Some(NotifyKind::OsSuspendResume));
+        assert!(!*suspend_rx.borrow());
+
+        // 挂起后收到恢复事件
+        notify_tx.send(NotifyKind::OsSuspending).unwrap();
+        let mut waiting = suspend_rx.clone();
+        waiting.wait_for(|suspended| *suspended).await.unwrap();
+        notify_tx.send(NotifyKind::SleepDetected).unwrap();
+        assert_eq!(trigger_rx.recv().await, Some(NotifyKind::SleepDetected));
+        assert!(!*suspend_rx.borrow());
+
+        // 挂起后一直未收到恢复事件，超时后自动恢复
+        notify_tx.send(NotifyKind::OsSuspending).unwrap();
+        waiting.wait_for(|suspended| *suspended).await.unwrap();
+        time::timeout(
+            Duration::from_secs(5),
+            waiting.wait_for(|suspended| !*suspended),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(trigger_rx.try_recv().is_err());
+
+        termination.cancel();
+        running.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_ordering() {
+        let (handle, mut trigger_rx, _) = UpdaterHandle::channel();
+        let termination = CancellationToken::new();
+        let later = time::Instant::now() + Duration::from_secs(3600);
+
+        // 触发消息优先于计划检查
+        handle.trigger(NotifyKind::SleepDetected);
+        assert_eq!(
+            UpdaterTask::next_wake(&mut trigger_rx, time::Instant::now(), &termination).await,
+            Some(Wake::Notify(NotifyKind::SleepDetected))
+        );
+        assert_eq!(
+            UpdaterTask::next_wake(&mut trigger_rx, time::Instant::now(), &termination).await,
+            Some(Wake::Scheduled)
+        );
+
+        // 终止消息优先于尚未处理的触发消息
+        handle.trigger(NotifyKind::SleepDetected);
+        termination.cancel();
+        assert_eq!(
+            UpdaterTask::next_wake(&mut trigger_rx, later, &termination).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_before_spawn() {
+        let termination = CancellationToken::new();
+        termination.cancel();
+
+        // 终止后才启动的任务同样能立即收到终止消息
+        let (_handle, mut trigger_rx, _) = UpdaterHandle::channel();
+        let later = time::Instant::now() + Duration::from_secs(3600);
+        let task = tokio::spawn(async move {
+            UpdaterTask::next_wake(&mut trigger_rx, later, &termination).await
+        });
+        let wake = time::timeout(Duration::from_secs(1), task).await;
+        assert_eq!(wake.unwrap().unwrap(), None);
+    }
+
+    #[test]
+    fn test_sleep_sentinel() {
+        let monotonic = Instant::now();
+        let wall = SystemTime::now();
+        let mut sentinel = SleepSentinel::new_at(monotonic, wall);
+
+        // 正常运行，两个时钟同步前进
+        let monotonic = monotonic + Duration::from_secs(60);
+        let wall = wall + Duration::from_secs(61);
+        assert_eq!(sentinel.check_at(monotonic, wall), None);
+
+        // 休眠一小时，单调时钟不前进
+        let monotonic = monotonic + Duration::from_secs(60);
+        let wall = wall + Duration::from_secs(3660);
+        assert_eq!(
+            sentinel.check_at(monotonic, wall),
+            Some(Duration::from_secs(3600))
+        );
+
+        // 系统时钟向后调整
+        let monotonic = monotonic + Duration::from_secs(60);
+        let wall = wall - Duration::from_secs(3600);
+        assert_eq!(sentinel.check_at(monotonic, wall), None);
     }
 }
@@ -0,0 +1,33 @@
+use std::{fmt::Debug, sync::Arc};
+
+/// 敏感字符串，如 Cloudflare API token
+///
+/// 克隆时共享同一份存储，`Debug` 输出时脱敏，避免意外输出至日志。
+#[derive(serde::Deserialize, Clone)]
+#[serde(transparent)]
+pub struct Secret(Arc<str>);
+
+impl Secret {
+    /// 获取原始内容，仅在构造请求时使用
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// 是否共享同一份存储
+    #[cfg(test)]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(Arc::from(value))
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
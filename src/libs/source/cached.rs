@@ -0,0 +1,127 @@
+use std::{borrow::Cow, net::IpAddr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use log::debug;
+use tokio::{sync::Mutex, time::Instant};
+
+use crate::libs::error::Error;
+
+use super::IpSource;
+
+/// 缓存 IP 地址来源的获取结果，有效期内直接返回上次成功获取的 IP 地址
+///
+/// 获取失败的结果不会被缓存。缓存期间持有锁，多个域名同时获取时仅向来源发起一次请求。
+#[derive(Debug)]
+pub struct CachedSource {
+    source: Arc<dyn IpSource>,
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, IpAddr)>>,
+}
+
+impl CachedSource {
+    pub fn new(source: Arc<dyn IpSource>, ttl: Duration) -> Self {
+        Self {
+            source,
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl IpSource for CachedSource {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched_at, ip)) = *cached {
+            if fetched_at.elapsed() < self.ttl {
+                debug!("使用缓存的 IP 地址 {}，来源 {}", ip, self.source.name());
+                return Ok(ip);
+            }
+        }
+
+        let ip = self.source.ip().await?;
+        *cached = Some((Instant::now(), ip));
+        Ok(ip)
+    }
+
+    fn name(&self) -> &'static str {
+        self.source.name()
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        self.source.info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        net::IpAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+
+    use crate::libs::{error::Error, source::IpSource};
+
+    use super::CachedSource;
+
+    /// 前若干次调用失败，之后返回固定 IP 地址的来源
+    #[derive(Debug, Default)]
+    struct Flaky {
+        failures: usize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl IpSource for Flaky {
+        async fn ip(&self) -> Result<IpAddr, Error> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+            if calls < self.failures {
+                Err(Error::ip_source(self.name(), "unavailable"))
+            } else {
+                Ok(format!("203.0.113.{}", calls).parse().unwrap())
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            "Flaky"
+        }
+
+        fn info(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_source() {
+        let source = Arc::new(Flaky {
+            failures: 1,
+            calls: AtomicUsize::new(0),
+        });
+        let cached = CachedSource::new(source.clone(), Duration::from_millis(100));
+
+        // 失败结果不缓存
+        assert!(cached.ip().await.is_err());
+        let ip = cached.ip().await.unwrap();
+        assert_eq!(ip, "203.0.113.1".parse::<IpAddr>().unwrap());
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+
+        // 有效期内返回缓存
+        assert_eq!(cached.ip().await.unwrap(), ip);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+
+        // 过期后重新获取
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(
+            cached.ip().await.unwrap(),
+            "203.0.113.2".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(source.calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
+
+/// 通过 IPv4 访问的 Cloudflare trace 地址
+const CF_TRACE_IPV4_HOST: &str = "1.1.1.1";
+/// 通过 IPv6 访问的 Cloudflare trace 地址
+const CF_TRACE_IPV6_HOST: &str = "[2606:4700:4700::1111]";
+
+/// 从 Cloudflare `cdn-cgi/trace` 获取 IP 地址
+///
+/// 直接访问 1.1.1.1 的 IP 地址，不依赖 DNS 解析，且请求只会经由指定的地址族发出。
+#[derive(Debug)]
+pub struct CfTrace {
+    family: IpFamily,
+    url: String,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+}
+
+impl CfTrace {
+    /// 创建 Cloudflare trace 来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        family: IpFamily,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            family,
+            url: format!("https://{}/cdn-cgi/trace", Self::host(family)),
+            client,
+            timeout,
+            http_debug,
+        }
+    }
+
+    /// 指定地址族对应的访问地址
+    pub fn host(family: IpFamily) -> &'static str {
+        match family {
+            IpFamily::V4 => CF_TRACE_IPV4_HOST,
+            IpFamily::V6 => CF_TRACE_IPV6_HOST,
+        }
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let request = self.client.get(&self.url).timeout(self.timeout).build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 Cloudflare trace {}", self.url),
+                    err,
+                )
+            })?;
+        let text = std::str::from_utf8(&body).map_err(|err| {
+            Error::parse(
+                format!("解析 Cloudflare trace {} 消息失败：{}", self.url, err),
+                err,
+            )
+        })?;
+
+        let ip_addr = parse_trace(text).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!("Cloudflare trace {} 响应消息中不包含合法 IP 地址", self.url),
+            )
+        })?;
+
+        check_family(
+            self.name(),
+            format_args!("Cloudflare trace {}", self.url),
+            Some(self.family),
+            ip_addr,
+        )
+    }
+}
+
+/// 从 trace 响应中提取 `ip=` 行的 IP 地址
+///
+/// trace 响应为逐行的 `key=value` 文本，如：
+///
+/// ```text
+/// fl=123f45
+/// h=1.1.1.1
+/// ip=203.0.113.1
+/// ts=1700000000.123
+/// ```
+fn parse_trace(text: &str) -> Option<IpAddr> {
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("ip="))
+        .and_then(|ip| parse_ip(ip, false))
+}
+
+#[async_trait]
+impl IpSource for CfTrace {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "Cloudflare Trace"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(&self.url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::parse_trace;
+
+    #[test]
+    fn test_parse_trace() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+
+        let ipv4 = "fl=123f45\nh=1.1.1.1\nip=203.0.113.1\nts=1700000000.123\nvisit_scheme=https\n";
+        assert_eq!(parse_trace(ipv4), Some(ip("203.0.113.1")));
+
+        let ipv6 = "fl=123f45\r\nh=[2606:4700:4700::1111]\r\nip=2001:db8::1\r\nloc=US\r\n";
+        assert_eq!(parse_trace(ipv6), Some(ip("2001:db8::1")));
+
+        // 格式错误的响应
+        assert_eq!(parse_trace(""), None);
+        assert_eq!(parse_trace("<html>error</html>"), None);
+        assert_eq!(parse_trace("fl=123f45\nh=1.1.1.1\n"), None);
+        assert_eq!(parse_trace("ip=not-an-ip\n"), None);
+        assert_eq!(parse_trace("ip=\n"), None);
+        // 仅匹配 `ip=` 行
+        assert_eq!(parse_trace("vip=1.2.3.4\n"), None);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr};
+
+use async_trait::async_trait;
+use log::warn;
+
+use crate::libs::error::Error;
+
+use super::{describe, IpSource};
+
+/// 按顺序依次尝试多个 IP 地址来源，直至其中一个成功
+#[derive(Debug)]
+pub struct Chain {
+    sources: Vec<Box<dyn IpSource>>,
+}
+
+impl Chain {
+    pub fn new(sources: Vec<Box<dyn IpSource>>) -> Self {
+        Self { sources }
+    }
+}
+
+#[async_trait]
+impl IpSource for Chain {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        let mut failures = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            match source.ip().await {
+                Ok(ip) => return Ok(ip),
+                Err(err) => {
+                    let source = describe(source.as_ref());
+                    warn!("IP 地址来源 {} 获取失败，尝试下一个来源：{}", source, err);
+                    failures.push(format!("{}：{}", source, err));
+                }
+            }
+        }
+
+        Err(Error::ip_source(
+            self.name(),
+            format!("所有 IP 地址来源均获取失败：{}", failures.join("；")),
+        ))
+    }
+
+    fn name(&self) -> &'static str {
+        "Chain"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(
+            self.sources
+                .iter()
+                .map(|source| describe(source.as_ref()))
+                .collect::<Vec<_>>()
+                .join(" -> "),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Cow,
+        net::IpAddr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use async_trait::async_trait;
+
+    use crate::libs::{error::Error, source::IpSource};
+
+    use super::Chain;
+
+    /// 返回固定结果并记录调用次数的来源
+    #[derive(Debug)]
+    struct Fixed {
+        name: &'static str,
+        ip: Option<IpAddr>,
+        calls: AtomicUsize,
+    }
+
+    impl Fixed {
+        fn boxed(name: &'static str, ip: Option<&str>) -> Box<dyn IpSource> {
+            Box::new(Self {
+                name,
+                ip: ip.map(|ip| ip.parse().unwrap()),
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl IpSource for Fixed {
+        async fn ip(&self) -> Result<IpAddr, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.ip
+                .ok_or_else(|| Error::ip_source(self.name, format!("{} unavailable", self.name)))
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn info(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain() {
+        let chain = Chain::new(vec![
+            Fixed::boxed("first", None),
+            Fixed::boxed("second", Some("203.0.113.2")),
+            Fixed::boxed("third", Some("203.0.113.3")),
+        ]);
+        assert_eq!(
+            chain.ip().await.unwrap(),
+            "203.0.113.2".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(chain.info().unwrap(), "first -> second -> third");
+
+        let chain = Chain::new(vec![
+            Fixed::boxed("first", None),
+            Fixed::boxed("second", None),
+        ]);
+        let err = chain.ip().await.unwrap_err().to_string();
+        assert!(err.contains("first unavailable"), "{}", err);
+        assert!(err.contains("second unavailable"), "{}", err);
+    }
+}
@@ -0,0 +1,157 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, process::Stdio, time::Duration};
+
+use async_trait::async_trait;
+use tokio::process::Command as Process;
+
+use crate::libs::error::Error;
+
+use super::{parse_ip, IpSource};
+
+/// 通过执行自定义命令获取 IP 地址
+///
+/// 命令的标准输出去除首尾空白后即为 IP 地址，如通过 SSH 查询路由器 WAN 口地址的脚本。
+/// 超时后命令将被终止。
+#[derive(Debug)]
+pub struct Command {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl Command {
+    pub fn new(program: String, args: Vec<String>, timeout: Duration) -> Self {
+        Self {
+            program,
+            args,
+            timeout,
+        }
+    }
+
+    async fn execute(&self) -> Result<IpAddr, Error> {
+        let child = Process::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| Error::io(format!("执行命令 {} 失败：{}", self.program, err), err))?;
+
+        let output = match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(err)) => {
+                return Err(Error::io(
+                    format!("等待命令 {} 结束失败：{}", self.program, err),
+                    err,
+                ))
+            }
+            Err(_) => {
+                return Err(Error::ip_source(
+                    self.name(),
+                    format!(
+                        "命令 {} 执行超过 {} 秒，已终止",
+                        self.program,
+                        self.timeout.as_secs_f64()
+                    ),
+                ))
+            }
+        };
+
+        if !output.status.success() {
+            let status = match output.status.code() {
+                Some(code) => format!("退出码 {}", code),
+                None => "被信号终止".to_string(),
+            };
+            return Err(Error::ip_source(
+                self.name(),
+                format!(
+                    "命令 {} 执行失败（{}）：{}",
+                    self.program,
+                    status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        parse_ip(&stdout, false).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!(
+                    "命令 {} 输出并非合法 IP 地址：{}",
+                    self.program,
+                    stdout.trim()
+                ),
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl IpSource for Command {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.execute().await
+    }
+
+    fn name(&self) -> &'static str {
+        "Command"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(&self.program))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{net::IpAddr, time::Duration};
+
+    use crate::libs::source::IpSource;
+
+    use super::Command;
+
+    fn shell(script: &str, timeout: Duration) -> Command {
+        Command::new(
+            "sh".to_string(),
+            vec!["-c".to_string(), script.to_string()],
+            timeout,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_command() {
+        let timeout = Duration::from_secs(5);
+
+        let ip = shell("echo ' 203.0.113.1'", timeout).ip().await.unwrap();
+        assert_eq!(ip, "203.0.113.1".parse::<IpAddr>().unwrap());
+
+        let err = shell("echo 'no route' >&2; exit 3", timeout)
+            .ip()
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("退出码 3"), "{}", err);
+        assert!(err.contains("no route"), "{}", err);
+
+        let err = shell("echo unknown", timeout)
+            .ip()
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("并非合法 IP 地址"), "{}", err);
+
+        let err = shell("sleep 5", Duration::from_millis(100))
+            .ip()
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("已终止"), "{}", err);
+
+        let err = Command::new("/nonexistent/wanip".to_string(), vec![], timeout)
+            .ip()
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/nonexistent/wanip"), "{}", err);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Debug, net::IpAddr};
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use log::warn;
+
+use crate::libs::error::Error;
+
+use super::{describe, IpSource};
+
+/// 同时查询多个 IP 地址来源，返回达到法定数量的来源一致给出的 IP 地址
+///
+/// 用于避免个别来源返回过时或错误（如运营商级 NAT）地址时记录反复变化。
+/// 获取失败的来源不计票；票数最多的地址未达到法定数量，或多个地址票数并列最多时视为获取失败。
+#[derive(Debug)]
+pub struct Consensus {
+    sources: Vec<Box<dyn IpSource>>,
+    /// 法定数量，即至少需要多少个来源给出相同的地址
+    quorum: usize,
+}
+
+impl Consensus {
+    pub fn new(sources: Vec<Box<dyn IpSource>>, quorum: usize) -> Self {
+        Self { sources, quorum }
+    }
+
+    /// 默认法定数量，即来源数量的一半，向上取整
+    pub fn default_quorum(sources: usize) -> usize {
+        sources.div_ceil(2)
+    }
+}
+
+#[async_trait]
+impl IpSource for Consensus {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        let results = join_all(self.sources.iter().map(|source| source.ip())).await;
+
+        let mut votes: HashMap<IpAddr, usize> = HashMap::new();
+        let mut answers = Vec::with_capacity(results.len());
+        for (source, result) in self.sources.iter().zip(results) {
+            let source = describe(source.as_ref());
+            match result {
+                Ok(ip) => {
+                    *votes.entry(ip).or_default() += 1;
+                    answers.push(format!("{}：{}", source, ip));
+                }
+                Err(err) => {
+                    warn!("IP 地址来源 {} 获取失败，不参与投票：{}", source, err);
+                    answers.push(format!("{}：{}", source, err));
+                }
+            }
+        }
+
+        let most = votes.values().copied().max().unwrap_or(0);
+        let mut leaders = votes.into_iter().filter(|(_, count)| *count == most);
+        match (leaders.next(), leaders.next()) {
+            (Some((ip, count)), None) if count >= self.quorum => Ok(ip),
+            _ => Err(Error::ip_source(
+                self.name(),
+                format!(
+                    "未有 {} 个来源给出相同的 IP 地址：{}",
+                    self.quorum,
+                    answers.join("；")
+                ),
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Consensus"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!(
+            "{}/{}: {}",
+            self.quorum,
+            self.sources.len(),
+            self.sources
+                .iter()
+                .map(|source| describe(source.as_ref()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, net::IpAddr};
+
+    use async_trait::async_trait;
+
+    use crate::libs::{error::Error, source::IpSource};
+
+    use super::Consensus;
+
+    /// 返回固定结果的来源
+    #[derive(Debug)]
+    struct Fixed {
+        name: &'static str,
+        ip: Option<IpAddr>,
+    }
+
+    impl Fixed {
+        fn boxed(name: &'static str, ip: Option<&str>) -> Box<dyn IpSource> {
+            Box::new(Self {
+                name,
+                ip: ip.map(|ip| ip.parse().unwrap()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl IpSource for Fixed {
+        async fn ip(&self) -> Result<IpAddr, Error> {
+            self.ip
+                .ok_or_else(|| Error::ip_source(self.name, format!("{} unavailable", self.name)))
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn info(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_default_quorum() {
+        assert_eq!(Consensus::default_quorum(1), 1);
+        assert_eq!(Consensus::default_quorum(2), 1);
+        assert_eq!(Consensus::default_quorum(3), 2);
+        assert_eq!(Consensus::default_quorum(4), 2);
+    }
+
+    #[tokio::test]
+    async fn test_consensus() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+
+        // 多数来源一致，少数来源返回运营商级 NAT 地址
+        let consensus = Consensus::new(
+            vec![
+                Fixed::boxed("first", Some("100.64.0.1")),
+                Fixed::boxed("second", Some("203.0.113.1")),
+                Fixed::boxed("third", Some("203.0.113.1")),
+            ],
+            2,
+        );
+        assert_eq!(consensus.ip().await.unwrap(), ip("203.0.113.1"));
+        assert_eq!(consensus.info().unwrap(), "2/3: first, second, third");
+
+        // 获取失败的来源不计票
+        let consensus = Consensus::new(
+            vec![
+                Fixed::boxed("first", None),
+                Fixed::boxed("second", Some("203.0.113.1")),
+                Fixed::boxed("third", Some("203.0.113.1")),
+            ],
+            2,
+        );
+        assert_eq!(consensus.ip().await.unwrap(), ip("203.0.113.1"));
+
+        // 未达到法定数量
+        let consensus = Consensus::new(
+            vec![
+                Fixed::boxed("first", None),
+                Fixed::boxed("second", Some("203.0.113.1")),
+                Fixed::boxed("third", Some("203.0.113.3")),
+            ],
+            2,
+        );
+        let err = consensus.ip().await.unwrap_err().to_string();
+        assert!(err.contains("first：first unavailable"), "{}", err);
+        assert!(err.contains("second：203.0.113.1"), "{}", err);
+        assert!(err.contains("third：203.0.113.3"), "{}", err);
+
+        // 票数并列最多
+        let consensus = Consensus::new(
+            vec![
+                Fixed::boxed("first", Some("203.0.113.1")),
+                Fixed::boxed("second", Some("203.0.113.2")),
+            ],
+            1,
+        );
+        assert!(consensus.ip().await.is_err());
+    }
+}
@@ -0,0 +1,391 @@
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::libs::error::Error;
+
+use super::{connect_udp, parse_ip, with_default_port, IpFamily, IpSource};
+
+/// 默认 DNS 服务器
+pub const DEFAULT_DNS_RESOLVER: &str = "resolver1.opendns.com:53";
+/// 默认查询域名，OpenDNS 会将其解析为请求方的 IP 地址
+pub const DEFAULT_DNS_QUERY_NAME: &str = "myip.opendns.com";
+
+/// DNS 服务默认端口
+const DNS_PORT: u16 = 53;
+/// A 记录
+const TYPE_A: u16 = 1;
+/// TXT 记录
+const TYPE_TXT: u16 = 16;
+/// AAAA 记录
+const TYPE_AAAA: u16 = 28;
+/// IN 类
+const CLASS_IN: u16 = 1;
+/// DNS 消息头长度
+const HEADER_LEN: usize = 12;
+/// UDP 响应的最大长度
+const MAX_RESPONSE_LEN: usize = 512;
+
+/// 通过 DNS 查询获取 IP 地址
+///
+/// 向指定的 DNS 服务器查询特殊域名，由服务器返回请求方的 IP 地址，如：
+///
+/// - `myip.opendns.com` A/AAAA 记录，向 `resolver1.opendns.com` 查询
+/// - `o-o.myaddr.l.google.com` TXT 记录，向 `ns1.google.com` 查询
+///
+/// 查询地址族由 `family` 决定，同时决定访问 DNS 服务器所用的地址族。
+#[derive(Debug)]
+pub struct DnsQuery {
+    resolver: String,
+    name: String,
+    family: IpFamily,
+    txt: bool,
+    bind_address: Option<IpAddr>,
+    timeout: Duration,
+}
+
+impl DnsQuery {
+    pub fn new(
+        resolver: String,
+        name: String,
+        family: IpFamily,
+        txt: bool,
+        bind_address: Option<IpAddr>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            resolver,
+            name,
+            family,
+            txt,
+            bind_address,
+            timeout,
+        }
+    }
+
+    fn record_type(&self) -> u16 {
+        match (self.txt, self.family) {
+            (true, _) => TYPE_TXT,
+            (false, IpFamily::V4) => TYPE_A,
+            (false, IpFamily::V6) => TYPE_AAAA,
+        }
+    }
+
+    async fn query(&self) -> Result<IpAddr, Error> {
+        let id = query_id();
+        let record_type = self.record_type();
+        let query = build_query(id, &self.name, record_type)
+            .map_err(|message| Error::ip_source(self.name(), message))?;
+
+        let socket = connect_udp(&self.resolver, self.family, self.bind_address)
+            .await
+            .map_err(|err| {
+                Error::io(
+                    format!("连接 DNS 服务器 {} 失败：{}", self.resolver, err),
+                    err,
+                )
+            })?;
+        socket.send(&query).await.map_err(|err| {
+            Error::io(
+                format!("发送 DNS 查询至 {} 失败：{}", self.resolver, err),
+                err,
+            )
+        })?;
+
+        let mut buffer = [0; MAX_RESPONSE_LEN];
+        let len = tokio::time::timeout(self.timeout, socket.recv(&mut buffer))
+            .await
+            .map_err(|_| {
+                Error::ip_source(
+                    self.name(),
+                    format!("DNS 服务器 {} 响应超时", self.resolver),
+                )
+            })?
+            .map_err(|err| {
+                Error::io(
+                    format!("接收 DNS 服务器 {} 响应失败：{}", self.resolver, err),
+                    err,
+                )
+            })?;
+
+        parse_response(&buffer[..len], id, record_type, self.family).map_err(|message| {
+            Error::ip_source(
+                self.name(),
+                format!(
+                    "向 DNS 服务器 {} 查询 {} 失败：{}",
+                    self.resolver, self.name, message
+                ),
+            )
+        })
+    }
+}
+
+/// 补全 DNS 服务器地址的端口，未指定端口时使用 53
+pub fn resolver_address(resolver: &str) -> String {
+    with_default_port(resolver, DNS_PORT)
+}
+
+#[async_trait]
+impl IpSource for DnsQuery {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.query().await
+    }
+
+    fn name(&self) -> &'static str {
+        "DNS Query"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("{} @{}", self.name, self.resolver)))
+    }
+}
+
+/// 生成查询 id，无需密码学安全，仅用于匹配响应
+fn query_id() -> u16 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or_default();
+    (nanos ^ (nanos >> 16)) as u16
+}
+
+/// 构造仅包含一个问题的 DNS 查询消息
+fn build_query(id: u16, name: &str, record_type: u16) -> Result<Vec<u8>, String> {
+    let mut message = Vec::with_capacity(HEADER_LEN + name.len() + 6);
+    message.extend_from_slice(&id.to_be_bytes());
+    // RD 标志位
+    message.extend_from_slice(&0x0100u16.to_be_bytes());
+    // QDCOUNT、ANCOUNT、NSCOUNT、ARCOUNT
+    message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+
+    let name = name.trim_end_matches('.');
+    if name.is_empty() || name.len() > 253 {
+        return Err(format!("无效的查询域名：{}", name));
+    }
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("无效的查询域名：{}", name));
+        }
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+
+    message.extend_from_slice(&record_type.to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+/// 跳过位于 `offset` 处的域名，返回域名之后的偏移量
+fn skip_name(message: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let len = *message.get(offset)? as usize;
+        match len {
+            0 => return Some(offset + 1),
+            // 压缩指针，占两个字节且指针之后不再有其他标签
+            len if len & 0xc0 == 0xc0 => {
+                message.get(offset + 1)?;
+                return Some(offset + 2);
+            }
+            len => offset += len + 1,
+        }
+    }
+}
+
+fn read_u16(message: &[u8], offset: usize) -> Option<u16> {
+    let bytes = message.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// 解析 DNS 响应，返回首个属于 `family` 地址族的地址
+fn parse_response(
+    message: &[u8],
+    id: u16,
+    record_type: u16,
+    family: IpFamily,
+) -> Result<IpAddr, String> {
+    let malformed = || "响应消息格式错误".to_string();
+
+    if message.len() < HEADER_LEN {
+        return Err(malformed());
+    }
+    if read_u16(message, 0) != Some(id) {
+        return Err("响应消息 id 不匹配".to_string());
+    }
+    let flags = read_u16(message, 2).ok_or_else(malformed)?;
+    if flags & 0x8000 == 0 {
+        return Err(malformed());
+    }
+    if flags & 0x0200 != 0 {
+        return Err("响应消息被截断".to_string());
+    }
+    match flags & 0x000f {
+        0 => {}
+        3 => return Err("域名不存在(NXDOMAIN)".to_string()),
+        rcode => return Err(format!("服务器返回错误码 {}", rcode)),
+    }
+
+    let questions = read_u16(message, 4).ok_or_else(malformed)?;
+    let answers = read_u16(message, 6).ok_or_else(malformed)?;
+
+    let mut offset = HEADER_LEN;
+    for _ in 0..questions {
+        offset = skip_name(message, offset).ok_or_else(malformed)? + 4;
+    }
+
+    for _ in 0..answers {
+        offset = skip_name(message, offset).ok_or_else(malformed)?;
+        let r#type = read_u16(message, offset).ok_or_else(malformed)?;
+        let len = read_u16(message, offset + 8).ok_or_else(malformed)? as usize;
+        let data_offset = offset + 10;
+        let data = message
+            .get(data_offset..data_offset + len)
+            .ok_or_else(malformed)?;
+        offset = data_offset + len;
+
+        if r#type != record_type {
+            continue;
+        }
+        let ip = match r#type {
+            TYPE_A => <[u8; 4]>::try_from(data)
+                .ok()
+                .map(|octets| IpAddr::V4(Ipv4Addr::from(octets))),
+            TYPE_AAAA => <[u8; 16]>::try_from(data)
+                .ok()
+                .map(|octets| IpAddr::V6(Ipv6Addr::from(octets))),
+            TYPE_TXT => parse_txt(data),
+            _ => None,
+        };
+        if let Some(ip) = ip.filter(|ip| family.matches(ip)) {
+            return Ok(ip);
+        }
+    }
+
+    Err("响应中不包含合法 IP 地址".to_string())
+}
+
+/// 拼接 TXT 记录中的字符串并解析为 IP 地址
+fn parse_txt(data: &[u8]) -> Option<IpAddr> {
+    let mut text = Vec::with_capacity(data.len());
+    let mut offset = 0;
+    while offset < data.len() {
+        let len = data[offset] as usize;
+        text.extend_from_slice(data.get(offset + 1..offset + 1 + len)?);
+        offset += len + 1;
+    }
+    parse_ip(std::str::from_utf8(&text).ok()?, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::libs::source::IpFamily;
+
+    use super::{build_query, parse_response, resolver_address, TYPE_A, TYPE_AAAA, TYPE_TXT};
+
+    /// 构造对 `query` 的响应，`answers` 为记录类型及数据
+    fn response(query: &[u8], rcode: u16, answers: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut message = query.to_vec();
+        message[2..4].copy_from_slice(&(0x8180 | rcode).to_be_bytes());
+        message[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for (r#type, data) in answers {
+            // 指向问题中域名的压缩指针
+            message.extend_from_slice(&[0xc0, 0x0c]);
+            message.extend_from_slice(&r#type.to_be_bytes());
+            message.extend_from_slice(&[0, 1, 0, 0, 0, 0]);
+            message.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            message.extend_from_slice(data);
+        }
+        message
+    }
+
+    #[test]
+    fn test_resolver_address() {
+        assert_eq!(resolver_address("1.1.1.1"), "1.1.1.1:53");
+        assert_eq!(resolver_address("1.1.1.1:5353"), "1.1.1.1:5353");
+        assert_eq!(resolver_address("2001:db8::1"), "[2001:db8::1]:53");
+        assert_eq!(resolver_address("[2001:db8::1]"), "[2001:db8::1]:53");
+        assert_eq!(resolver_address("[2001:db8::1]:5353"), "[2001:db8::1]:5353");
+        assert_eq!(resolver_address("ns1.google.com"), "ns1.google.com:53");
+        assert_eq!(
+            resolver_address("resolver1.opendns.com:53"),
+            "resolver1.opendns.com:53"
+        );
+    }
+
+    #[test]
+    fn test_build_query() {
+        let query = build_query(0x1234, "myip.opendns.com.", TYPE_A).unwrap();
+        assert_eq!(
+            query,
+            [
+                &[0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0][..],
+                b"\x04myip\x07opendns\x03com\x00",
+                &[0, 1, 0, 1],
+            ]
+            .concat()
+        );
+
+        assert!(build_query(1, "", TYPE_A).is_err());
+        assert!(build_query(1, "a..b", TYPE_A).is_err());
+        assert!(build_query(1, &"a".repeat(64), TYPE_A).is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+
+        let query = build_query(7, "myip.opendns.com", TYPE_A).unwrap();
+        let message = response(&query, 0, &[(TYPE_A, &[203, 0, 113, 1])]);
+        assert_eq!(
+            parse_response(&message, 7, TYPE_A, IpFamily::V4),
+            Ok(ip("203.0.113.1"))
+        );
+        // id 不匹配
+        assert!(parse_response(&message, 8, TYPE_A, IpFamily::V4).is_err());
+
+        let query = build_query(7, "myip.opendns.com", TYPE_AAAA).unwrap();
+        let v6 = ip("2001:db8::1");
+        let IpAddr::V6(octets) = v6 else {
+            unreachable!()
+        };
+        let message = response(&query, 0, &[(TYPE_AAAA, &octets.octets())]);
+        assert_eq!(parse_response(&message, 7, TYPE_AAAA, IpFamily::V6), Ok(v6));
+
+        // Google 的 TXT 记录，跳过非 IP 地址的记录
+        let query = build_query(7, "o-o.myaddr.l.google.com", TYPE_TXT).unwrap();
+        let message = response(
+            &query,
+            0,
+            &[
+                (TYPE_TXT, b"\x14edns0-client-subnet "),
+                (TYPE_TXT, b"\x0b203.0.113.1"),
+            ],
+        );
+        assert_eq!(
+            parse_response(&message, 7, TYPE_TXT, IpFamily::V4),
+            Ok(ip("203.0.113.1"))
+        );
+        assert!(parse_response(&message, 7, TYPE_TXT, IpFamily::V6).is_err());
+
+        // NXDOMAIN
+        let message = response(&query, 3, &[]);
+        assert!(parse_response(&message, 7, TYPE_TXT, IpFamily::V4)
+            .unwrap_err()
+            .contains("NXDOMAIN"));
+
+        // 格式错误的响应
+        let query = build_query(7, "myip.opendns.com", TYPE_A).unwrap();
+        let mut message = response(&query, 0, &[(TYPE_A, &[203, 0, 113])]);
+        assert!(parse_response(&message, 7, TYPE_A, IpFamily::V4).is_err());
+        message.truncate(message.len() - 2);
+        assert!(parse_response(&message, 7, TYPE_A, IpFamily::V4).is_err());
+        assert!(parse_response(&[0, 7], 7, TYPE_A, IpFamily::V4).is_err());
+    }
+}
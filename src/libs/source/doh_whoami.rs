@@ -0,0 +1,196 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{header, Client, Url};
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
+
+/// 默认 DoH 服务地址
+pub const DEFAULT_DOH_WHOAMI_URL: &str = "https://cloudflare-dns.com/dns-query";
+/// 查询域名，Cloudflare 解析器以 TXT 记录返回其观察到的请求方 IP 地址
+const WHOAMI_NAME: &str = "whoami.cloudflare";
+
+/// 通过 DoH 查询 `whoami.cloudflare` TXT 记录获取 IP 地址
+///
+/// 查询经由 HTTPS 发出，适用于仅允许访问 DNS 服务、无法访问其他 IP 查询网站的环境。
+/// 解析器返回的是 DoH 请求方的地址，因此客户端需绑定 `family` 对应地址族的本地地址，使请求只经由该地址族发出。
+#[derive(Debug)]
+pub struct DohWhoami {
+    url: Url,
+    family: IpFamily,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+}
+
+impl DohWhoami {
+    /// 创建 DoH whoami 来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        url: Url,
+        family: IpFamily,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            url,
+            family,
+            client,
+            timeout,
+            http_debug,
+        }
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let request = self
+            .client
+            .get(self.url.as_ref())
+            .query(&[
+                ("name", WHOAMI_NAME),
+                ("type", "TXT"),
+                ("ct", "application/dns-json"),
+            ])
+            .header(header::ACCEPT, "application/dns-json")
+            .timeout(self.timeout)
+            .build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 DoH 服务 {}", self.url),
+                    err,
+                )
+            })?;
+
+        let ip_addr = parse_answer(&body).map_err(|reason| {
+            Error::ip_source(self.name(), format!("DoH 服务 {} {}", self.url, reason))
+        })?;
+        check_family(
+            self.name(),
+            format_args!("DoH 服务 {}", self.url),
+            Some(self.family),
+            ip_addr,
+        )
+    }
+}
+
+/// DoH JSON 响应
+#[derive(serde::Deserialize, Debug)]
+struct DohResponse {
+    /// DNS 响应码，`0` 为 NOERROR
+    #[serde(rename = "Status")]
+    status: u16,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct DohAnswer {
+    /// 记录类型，`16` 为 TXT
+    r#type: u16,
+    data: String,
+}
+
+/// TXT 记录类型
+const TYPE_TXT: u16 = 16;
+
+/// 从 DoH JSON 响应中提取首个 TXT 记录中的 IP 地址
+///
+/// 响应形如：
+///
+/// ```json
+/// {"Status":0,"Answer":[{"name":"whoami.cloudflare","type":16,"TTL":0,"data":"\"203.0.113.1\""}]}
+/// ```
+///
+/// TXT 记录内容带有引号，较长时可能被拆分为多个带引号的字符串，此处拼接后再解析。
+fn parse_answer(body: &[u8]) -> Result<IpAddr, String> {
+    let response: DohResponse =
+        serde_json::from_slice(body).map_err(|err| format!("响应消息解析失败：{}", err))?;
+    if response.status != 0 {
+        return Err(format!("查询失败，响应码 {}", response.status));
+    }
+
+    let data = response
+        .answer
+        .iter()
+        .find(|answer| answer.r#type == TYPE_TXT)
+        .map(|answer| answer.data.as_str())
+        .ok_or("未返回 TXT 记录")?;
+    let text = unquote(data);
+    parse_ip(&text, false).ok_or_else(|| format!("TXT 记录 {} 并非合法 IP 地址", data))
+}
+
+/// 去除 TXT 记录各字符串的引号并拼接，如 `"203.0." "113.1"` 转换为 `203.0.113.1`
+fn unquote(data: &str) -> String {
+    let data = data.trim();
+    if !data.starts_with('"') {
+        return data.to_string();
+    }
+    data.split('"')
+        .enumerate()
+        // 引号内的部分位于奇数位置
+        .filter(|(index, _)| index % 2 == 1)
+        .map(|(_, part)| part)
+        .collect()
+}
+
+#[async_trait]
+impl IpSource for DohWhoami {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "DoH Whoami"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("{} {}", self.url, self.family.as_str())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::{parse_answer, unquote};
+
+    #[test]
+    fn test_unquote() {
+        assert_eq!(unquote("\"203.0.113.1\""), "203.0.113.1");
+        assert_eq!(unquote("\"2001:db8:\" \"1::1\""), "2001:db8:1::1");
+        assert_eq!(unquote("203.0.113.1"), "203.0.113.1");
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+
+        let ipv4 = br#"{"Status":0,"TC":false,"RD":true,"RA":true,"AD":false,"CD":false,"Question":[{"name":"whoami.cloudflare","type":16}],"Answer":[{"name":"whoami.cloudflare","type":16,"TTL":0,"data":"\"203.0.113.1\""}]}"#;
+        assert_eq!(parse_answer(ipv4), Ok(ip("203.0.113.1")));
+
+        let ipv6 = br#"{"Status":0,"Answer":[{"name":"whoami.cloudflare","type":16,"TTL":0,"data":"\"2001:db8::1\""}]}"#;
+        assert_eq!(parse_answer(ipv6), Ok(ip("2001:db8::1")));
+
+        // 跳过非 TXT 记录
+        let cname = br#"{"Status":0,"Answer":[{"name":"whoami.cloudflare","type":5,"TTL":0,"data":"example.com."},{"name":"whoami.cloudflare","type":16,"TTL":0,"data":"\"203.0.113.2\""}]}"#;
+        assert_eq!(parse_answer(cname), Ok(ip("203.0.113.2")));
+
+        assert!(parse_answer(br#"{"Status":3}"#)
+            .unwrap_err()
+            .contains("响应码 3"));
+        assert!(parse_answer(br#"{"Status":0,"Answer":[]}"#).is_err());
+        assert!(
+            parse_answer(br#"{"Status":0,"Answer":[{"type":16,"data":"\"not an ip\""}]}"#).is_err()
+        );
+        assert!(parse_answer(b"<html>").is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{header, Client, Url};
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{parse_ip, request_error, IpFamily, IpSource};
+
+/// 默认 FRITZ!Box 地址
+pub const DEFAULT_FRITZBOX_URL: &str = "http://fritz.box:49000";
+/// IGD 服务的控制地址，该服务无需认证
+const CONTROL_PATH: &str = "/igdupnp/control/WANIPConn1";
+/// IGD WANIPConnection 服务类型
+const SERVICE: &str = "urn:schemas-upnp-org:service:WANIPConnection:1";
+
+/// 通过 FRITZ!Box 的 TR-064/UPnP IGD 服务获取外部 IP 地址
+///
+/// 向 `<url>/igdupnp/control/WANIPConn1` 发送 SOAP 请求，IPv4 调用 `GetExternalIPAddress`，
+/// IPv6 调用 `X_AVM_DE_GetExternalIPv6Address`。不进行 SSDP 发现，路由器地址由配置指定。
+#[derive(Debug)]
+pub struct FritzBox {
+    /// 路由器地址，如 `http://fritz.box:49000`
+    url: Url,
+    /// 控制地址
+    control: Url,
+    family: IpFamily,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+}
+
+impl FritzBox {
+    /// 创建 FRITZ!Box 来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        url: Url,
+        family: IpFamily,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        let mut control = url.clone();
+        control.set_path(CONTROL_PATH);
+        control.set_query(None);
+        Self {
+            url,
+            control,
+            family,
+            client,
+            timeout,
+            http_debug,
+        }
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let (action, _) = action(self.family);
+        let request = self
+            .client
+            .post(self.control.as_ref())
+            .header(header::CONTENT_TYPE, r#"text/xml; charset="utf-8""#)
+            .header("SOAPAction", format!("{}#{}", SERVICE, action))
+            .body(envelope(action))
+            .timeout(self.timeout)
+            .build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 FRITZ!Box {}", self.url),
+                    err,
+                )
+            })?;
+
+        parse_response(&String::from_utf8_lossy(&body), self.family).map_err(|message| {
+            Error::ip_source(self.name(), format!("FRITZ!Box {} {}", self.url, message))
+        })
+    }
+}
+
+/// 地址族对应的 SOAP 操作及响应中的地址元素名称
+fn action(family: IpFamily) -> (&'static str, &'static str) {
+    match family {
+        IpFamily::V4 => ("GetExternalIPAddress", "NewExternalIPAddress"),
+        IpFamily::V6 => ("X_AVM_DE_GetExternalIPv6Address", "NewExternalIPv6Address"),
+    }
+}
+
+/// 构造无参数的 SOAP 请求体
+fn envelope(action: &str) -> String {
+    format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="utf-8"?>"#,
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">"#,
+            r#"<s:Body><u:{action} xmlns:u="{service}"/></s:Body>"#,
+            r#"</s:Envelope>"#
+        ),
+        action = action,
+        service = SERVICE
+    )
+}
+
+/// 获取首个名称为 `name`（忽略命名空间前缀）的元素的文本内容
+fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = rest.find('>')?;
+        let tag = &rest[..end];
+        let qualified = tag
+            .trim_end_matches('/')
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        if qualified.rsplit(':').next() == Some(name) {
+            // 自闭合的元素视为空内容
+            if tag.ends_with('/') {
+                return Some("");
+            }
+            let content = &rest[end + 1..];
+            return content.find("</").map(|close| content[..close].trim());
+        }
+        rest = &rest[end + 1..];
+    }
+    None
+}
+
+/// 解析 SOAP 响应，SOAP 错误时返回其中的错误码及描述
+fn parse_response(xml: &str, family: IpFamily) -> Result<IpAddr, String> {
+    if let Some(code) = element(xml, "errorCode") {
+        let description = element(xml, "errorDescription").unwrap_or_default();
+        return Err(format!("返回 SOAP 错误 {}：{}", code, description));
+    }
+
+    let (_, name) = action(family);
+    let Some(address) = element(xml, name) else {
+        return Err(format!("响应消息中不存在 {}", name));
+    };
+    if address.is_empty() {
+        return Err(format!("尚无外部 {} 地址，请检查拨号状态", family.as_str()));
+    }
+    match parse_ip(address, false) {
+        Some(ip) if family.matches(&ip) => Ok(ip),
+        _ => Err(format!(
+            "返回的地址 {} 并非合法 {} 地址",
+            address,
+            family.as_str()
+        )),
+    }
+}
+
+#[async_trait]
+impl IpSource for FritzBox {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "FRITZ!Box"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("{} {}", self.url, self.family.as_str())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use crate::libs::source::IpFamily;
+
+    use super::{element, envelope, parse_response};
+
+    /// `GetExternalIPAddress` 的响应，格式依照 FRITZ!Box 的 IGD 服务
+    const IPV4_RESPONSE: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:GetExternalIPAddressResponse xmlns:u="urn:schemas-upnp-org:service:WANIPConnection:1">
+<NewExternalIPAddress>203.0.113.5</NewExternalIPAddress>
+</u:GetExternalIPAddressResponse>
+</s:Body>
+</s:Envelope>"#;
+
+    /// `X_AVM_DE_GetExternalIPv6Address` 的响应，格式依照 FRITZ!Box 的 IGD 服务
+    const IPV6_RESPONSE: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<u:X_AVM_DE_GetExternalIPv6AddressResponse xmlns:u="urn:schemas-upnp-org:service:WANIPConnection:1">
+<NewExternalIPv6Address>2001:db8:1234::1</NewExternalIPv6Address>
+<NewPrefixLength>64</NewPrefixLength>
+<NewValidLifetime>7200</NewValidLifetime>
+<NewPreferedLifetime>3600</NewPreferedLifetime>
+</u:X_AVM_DE_GetExternalIPv6AddressResponse>
+</s:Body>
+</s:Envelope>"#;
+
+    /// 调用不存在的操作时的 SOAP 错误
+    const FAULT_RESPONSE: &str = r#"<?xml version="1.0"?>
+<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">
+<s:Body>
+<s:Fault>
+<faultcode>s:Client</faultcode>
+<faultstring>UPnPError</faultstring>
+<detail>
+<UPnPError xmlns="urn:schemas-upnp-org:control-1-0">
+<errorCode>401</errorCode>
+<errorDescription>Invalid Action</errorDescription>
+</UPnPError>
+</detail>
+</s:Fault>
+</s:Body>
+</s:Envelope>"#;
+
+    #[test]
+    fn test_envelope() {
+        let body = envelope("GetExternalIPAddress");
+        assert_eq!(
+            body,
+            concat!(
+                r#"<?xml version="1.0" encoding="utf-8"?>"#,
+                r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/">"#,
+                r#"<s:Body><u:GetExternalIPAddress xmlns:u="urn:schemas-upnp-org:service:WANIPConnection:1"/></s:Body>"#,
+                r#"</s:Envelope>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_element() {
+        assert_eq!(element("<a><b>1</b></a>", "b"), Some("1"));
+        assert_eq!(element("<a><x:b> 1 </x:b></a>", "b"), Some("1"));
+        assert_eq!(element("<a><b/></a>", "b"), Some(""));
+        assert_eq!(element(r#"<u:b xmlns:u="urn:x:y">2</u:b>"#, "b"), Some("2"));
+        assert_eq!(element(r#"<u:a xmlns:u="urn:x:b">2</u:a>"#, "b"), None);
+        assert_eq!(element("<a><bc>1</bc></a>", "b"), None);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(
+            parse_response(IPV4_RESPONSE, IpFamily::V4),
+            Ok("203.0.113.5".parse::<IpAddr>().unwrap())
+        );
+        assert_eq!(
+            parse_response(IPV6_RESPONSE, IpFamily::V6),
+            Ok("2001:db8:1234::1".parse::<IpAddr>().unwrap())
+        );
+
+        // 断线时地址为空
+        let offline = IPV4_RESPONSE.replace("203.0.113.5", "");
+        assert!(parse_response(&offline, IpFamily::V4)
+            .unwrap_err()
+            .contains("拨号状态"));
+        // 地址族与响应不符
+        assert!(parse_response(IPV4_RESPONSE, IpFamily::V6).is_err());
+        let err = parse_response(FAULT_RESPONSE, IpFamily::V6).unwrap_err();
+        assert!(
+            err.contains("401") && err.contains("Invalid Action"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Client, Url};
+use simd_json::BorrowedValue;
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
+
+/// 从返回 JSON 的 HTTP 接口获取 IP 地址
+///
+/// 如 `{"ip":"1.2.3.4"}`、ipinfo.io 及 ifconfig.co/json 等，
+/// 通过以 `.` 分隔的字段路径定位 IP 地址，路径中的数字用于访问数组元素，如 `data.ips.0`。
+#[derive(Debug)]
+pub struct HttpJson {
+    url: Url,
+    path: Vec<String>,
+    headers: HeaderMap,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+    /// 仅接受该地址族的 IP 地址
+    family: Option<IpFamily>,
+}
+
+impl HttpJson {
+    /// 创建 JSON 接口来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        url: Url,
+        path: Vec<String>,
+        headers: HeaderMap,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            url,
+            path,
+            headers,
+            client,
+            timeout,
+            http_debug,
+            family: None,
+        }
+    }
+
+    /// 仅接受地址族 `family` 的 IP 地址
+    pub fn with_family(mut self, family: Option<IpFamily>) -> Self {
+        self.family = family;
+        self
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let request = self
+            .client
+            .get(self.url.as_ref())
+            .headers(self.headers.clone())
+            .timeout(self.timeout)
+            .build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 JSON 接口 {}", self.url),
+                    err,
+                )
+            })?;
+
+        let mut body = body.to_vec();
+        let document = simd_json::to_borrowed_value(&mut body).map_err(|err| {
+            Error::parse(
+                format!("解析 JSON 接口 {} 消息失败：{}", self.url, err),
+                err,
+            )
+        })?;
+
+        let text = extract(&document, &self.path).map_err(|message| {
+            Error::ip_source(
+                self.name(),
+                format!("JSON 接口 {} 响应消息{}", self.url, message),
+            )
+        })?;
+        let ip_addr = parse_ip(text, false).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!(
+                    "JSON 接口 {} 响应消息中字段 {} 并非合法 IP 地址：{}",
+                    self.url,
+                    self.path.join("."),
+                    text
+                ),
+            )
+        })?;
+
+        check_family(
+            self.name(),
+            format_args!("JSON 接口 {}", self.url),
+            self.family,
+            ip_addr,
+        )
+    }
+}
+
+/// 解析以 `.` 分隔的字段路径，拒绝空路径及空字段
+pub fn parse_path(path: &str) -> Option<Vec<String>> {
+    let segments = path.split('.').map(str::to_string).collect::<Vec<_>>();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        None
+    } else {
+        Some(segments)
+    }
+}
+
+/// 按字段路径获取字符串字段
+fn extract<'a>(document: &'a BorrowedValue<'_>, path: &[String]) -> Result<&'a str, String> {
+    let mut value = document;
+    for (depth, segment) in path.iter().enumerate() {
+        let next = match value {
+            BorrowedValue::Object(object) => object.get(segment.as_str()),
+            BorrowedValue::Array(array) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| array.get(index)),
+            _ => None,
+        };
+        value = next.ok_or_else(|| format!("中不存在字段 {}", path[..=depth].join(".")))?;
+    }
+
+    match value {
+        BorrowedValue::String(text) => Ok(text),
+        _ => Err(format!("中字段 {} 并非字符串", path.join("."))),
+    }
+}
+
+#[async_trait]
+impl IpSource for HttpJson {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "HTTP JSON"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("{} {}", self.url, self.path.join("."))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::IpAddr, time::Duration};
+
+    use reqwest::{header::HeaderMap, Client};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use crate::libs::source::{IpFamily, IpSource};
+
+    use super::{extract, parse_path, HttpJson};
+
+    fn extract_text(json: &str, path: &str) -> Result<String, String> {
+        let mut json = json.as_bytes().to_vec();
+        let document = simd_json::to_borrowed_value(&mut json).unwrap();
+        extract(&document, &parse_path(path).unwrap()).map(str::to_string)
+    }
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(
+            extract_text(r#"{"ip":"203.0.113.1"}"#, "ip").as_deref(),
+            Ok("203.0.113.1")
+        );
+        assert_eq!(
+            extract_text(
+                r#"{"data":{"client":{"ip":"2001:db8::1","port":443}}}"#,
+                "data.client.ip"
+            )
+            .as_deref(),
+            Ok("2001:db8::1")
+        );
+
+        // 数组
+        let json = r#"{"data":{"ips":[{"ip":"203.0.113.1"},{"ip":"203.0.113.2"}]}}"#;
+        assert_eq!(
+            extract_text(json, "data.ips.1.ip").as_deref(),
+            Ok("203.0.113.2")
+        );
+        assert_eq!(
+            extract_text(r#"["203.0.113.3"]"#, "0").as_deref(),
+            Ok("203.0.113.3")
+        );
+        assert!(extract_text(json, "data.ips.2.ip")
+            .unwrap_err()
+            .contains("data.ips.2"));
+        assert!(extract_text(json, "data.ips.first").is_err());
+
+        // 字段缺失或类型错误
+        assert!(extract_text(r#"{"data":{}}"#, "data.client.ip")
+            .unwrap_err()
+            .contains("data.client"));
+        assert!(extract_text(r#"{"ip":null}"#, "ip")
+            .unwrap_err()
+            .contains("并非字符串"));
+        assert!(extract_text(r#"{"ip":{"v4":"1.2.3.4"}}"#, "ip").is_err());
+        assert!(extract_text(r#"{"ip":"1.2.3.4"}"#, "ip.v4").is_err());
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(
+            parse_path("data.client.ip"),
+            Some(vec![
+                "data".to_string(),
+                "client".to_string(),
+                "ip".to_string()
+            ])
+        );
+        assert_eq!(parse_path(""), None);
+        assert_eq!(parse_path("data..ip"), None);
+        assert_eq!(parse_path("ip."), None);
+    }
+
+    /// 地址族不符的响应被拒绝
+    #[tokio::test]
+    async fn test_family() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/json", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for body in [r#"{"ip":"2001:db8::1"}"#, r#"{"ip":"203.0.113.1"}"#] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let source = HttpJson::new(
+            url.parse().unwrap(),
+            vec!["ip".to_string()],
+            HeaderMap::new(),
+            Client::new(),
+            Duration::from_secs(5),
+            None,
+        )
+        .with_family(Some(IpFamily::V4));
+        let err = source.ip().await.unwrap_err().to_string();
+        assert!(err.contains("不属于地址族 v4"), "{}", err);
+        assert_eq!(
+            source.ip().await.unwrap(),
+            "203.0.113.1".parse::<IpAddr>().unwrap()
+        );
+    }
+}
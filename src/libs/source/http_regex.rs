@@ -0,0 +1,165 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::{header::HeaderMap, Client, Url};
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
+
+/// 从任意 HTTP 页面中通过正则表达式提取 IP 地址
+///
+/// 适用于路由器状态页等 HTML 页面，使用首个匹配中第一个捕获组的内容作为 IP 地址。
+#[derive(Debug)]
+pub struct HttpRegex {
+    url: Url,
+    regex: Regex,
+    headers: HeaderMap,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+    /// 仅接受该地址族的 IP 地址
+    family: Option<IpFamily>,
+}
+
+impl HttpRegex {
+    /// 创建正则提取来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        url: Url,
+        regex: Regex,
+        headers: HeaderMap,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            url,
+            regex,
+            headers,
+            client,
+            timeout,
+            http_debug,
+            family: None,
+        }
+    }
+
+    /// 仅接受地址族 `family` 的 IP 地址
+    pub fn with_family(mut self, family: Option<IpFamily>) -> Self {
+        self.family = family;
+        self
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let request = self
+            .client
+            .get(self.url.as_ref())
+            .headers(self.headers.clone())
+            .timeout(self.timeout)
+            .build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问页面 {}", self.url),
+                    err,
+                )
+            })?;
+        let text = String::from_utf8_lossy(&body);
+
+        let captured = extract(&self.regex, &text).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!("页面 {} 中未匹配到 {}", self.url, self.regex),
+            )
+        })?;
+        let ip_addr = parse_ip(captured, false).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!(
+                    "页面 {} 中匹配到的内容并非合法 IP 地址：{}",
+                    self.url, captured
+                ),
+            )
+        })?;
+
+        check_family(
+            self.name(),
+            format_args!("页面 {}", self.url),
+            self.family,
+            ip_addr,
+        )
+    }
+}
+
+/// 编译正则表达式，要求至少包含一个捕获组
+pub fn compile(pattern: &str) -> Result<Regex, String> {
+    let regex =
+        Regex::new(pattern).map_err(|err| format!("无效正则表达式 {}：{}", pattern, err))?;
+    // 第 0 组为整个匹配
+    if regex.captures_len() < 2 {
+        return Err(format!("正则表达式 {} 必须包含一个捕获组", pattern));
+    }
+    Ok(regex)
+}
+
+/// 提取首个匹配中第一个捕获组的内容
+fn extract<'a>(regex: &Regex, text: &'a str) -> Option<&'a str> {
+    regex
+        .captures(text)
+        .and_then(|captures| captures.get(1))
+        .map(|group| group.as_str())
+}
+
+#[async_trait]
+impl IpSource for HttpRegex {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "HTTP Regex"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(self.url.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, extract};
+
+    #[test]
+    fn test_compile() {
+        assert!(compile(r"WAN IP: (\S+)").is_ok());
+        assert!(compile(r"WAN IP: (?P<ip>\S+)").is_ok());
+        assert!(compile(r"WAN IP: \S+").is_err());
+        assert!(compile(r"WAN IP: (?:\S+)").is_err());
+        assert!(compile(r"WAN IP: (\S+").is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let regex = compile(r#"<td id="wan_ip">\s*([0-9a-fA-F.:]+)\s*</td>"#).unwrap();
+        let page = r#"
+            <table>
+              <tr><td>LAN</td><td id="lan_ip">192.168.1.1</td></tr>
+              <tr><td>WAN</td><td id="wan_ip"> 203.0.113.1 </td></tr>
+              <tr><td>WAN</td><td id="wan_ip">203.0.113.2</td></tr>
+            </table>
+        "#;
+        assert_eq!(extract(&regex, page), Some("203.0.113.1"));
+        assert_eq!(extract(&regex, "<html></html>"), None);
+
+        // 可选捕获组未参与匹配
+        let regex = compile(r"ip=(\d+\.\d+\.\d+\.\d+)?;").unwrap();
+        assert_eq!(extract(&regex, "ip=;"), None);
+    }
+}
@@ -0,0 +1,102 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
+
+/// icanhazip IPv4 查询域名
+const ICANHAZIP_IPV4_HOST: &str = "ipv4.icanhazip.com";
+/// icanhazip IPv6 查询域名
+const ICANHAZIP_IPV6_HOST: &str = "ipv6.icanhazip.com";
+
+/// 从 [icanhazip](https://icanhazip.com) 获取 IP 地址
+#[derive(Debug)]
+pub struct Icanhazip {
+    family: IpFamily,
+    url: String,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+}
+
+impl Icanhazip {
+    /// 创建 icanhazip 来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        family: IpFamily,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            family,
+            url: format!("https://{}", Self::host(family)),
+            client,
+            timeout,
+            http_debug,
+        }
+    }
+
+    /// 指定地址族对应的查询域名
+    pub fn host(family: IpFamily) -> &'static str {
+        match family {
+            IpFamily::V4 => ICANHAZIP_IPV4_HOST,
+            IpFamily::V6 => ICANHAZIP_IPV6_HOST,
+        }
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let request = self.client.get(&self.url).timeout(self.timeout).build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 icanhazip {}", self.url),
+                    err,
+                )
+            })?;
+        let text = std::str::from_utf8(&body).map_err(|err| {
+            Error::parse(
+                format!("解析 icanhazip {} 消息失败：{}", self.url, err),
+                err,
+            )
+        })?;
+
+        let ip_addr = parse_ip(text, false).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!("icanhazip {} 响应消息并非合法 IP 地址", self.url),
+            )
+        })?;
+
+        check_family(
+            self.name(),
+            format_args!("icanhazip {}", self.url),
+            Some(self.family),
+            ip_addr,
+        )
+    }
+}
+
+#[async_trait]
+impl IpSource for Icanhazip {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "icanhazip"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.family.as_str()))
+    }
+}
@@ -0,0 +1,99 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::Client;
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+};
+
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
+
+/// ipify IPv4 查询域名
+const IPIFY_IPV4_HOST: &str = "api.ipify.org";
+/// ipify IPv6 查询域名
+const IPIFY_IPV6_HOST: &str = "api6.ipify.org";
+
+/// 从 [ipify](https://www.ipify.org) 获取 IP 地址
+#[derive(Debug)]
+pub struct Ipify {
+    family: IpFamily,
+    url: String,
+    client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
+}
+
+impl Ipify {
+    /// 创建 ipify 来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        family: IpFamily,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
+            family,
+            url: format!("https://{}", Self::host(family)),
+            client,
+            timeout,
+            http_debug,
+        }
+    }
+
+    /// 指定地址族对应的查询域名
+    pub fn host(family: IpFamily) -> &'static str {
+        match family {
+            IpFamily::V4 => IPIFY_IPV4_HOST,
+            IpFamily::V6 => IPIFY_IPV6_HOST,
+        }
+    }
+
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let request = self.client.get(&self.url).timeout(self.timeout).build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 ipify {}", self.url),
+                    err,
+                )
+            })?;
+        let text = std::str::from_utf8(&body).map_err(|err| {
+            Error::parse(format!("解析 ipify {} 消息失败：{}", self.url, err), err)
+        })?;
+
+        let ip_addr = parse_ip(text, false).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!("ipify {} 响应消息并非合法 IP 地址", self.url),
+            )
+        })?;
+
+        check_family(
+            self.name(),
+            format_args!("ipify {}", self.url),
+            Some(self.family),
+            ip_addr,
+        )
+    }
+}
+
+#[async_trait]
+impl IpSource for Ipify {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.send().await
+    }
+
+    fn name(&self) -> &'static str {
+        "ipify"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.family.as_str()))
+    }
+}
@@ -4,65 +4,163 @@ use std::{
 };
 
 use async_trait::async_trait;
+use log::warn;
 
 use crate::libs::error::Error;
 
+#[cfg(target_os = "windows")]
+mod adapters;
+#[cfg(any(target_os = "macos", all(test, target_os = "linux")))]
+mod ifconfig;
+#[cfg(target_os = "linux")]
+mod netlink;
+
+#[cfg(target_os = "windows")]
+use super::parse_ip;
 use super::IpSource;
 
-/// Linux 和 Windows 专用，使用本机命令获取 IPv6 地址。
+/// Linux、macOS 和 Windows 专用，使用本机命令获取 IPv6 地址。
 /// 可以指定需要获取的网卡接口的名称，若未指定，则使用第一个符合匹配要求的 IPv6 地址。
 ///
 /// - 针对 Linux 系统
 ///
-/// 使用 `ip -6 -j addr` 命令，对于所输出的结果中匹配以下规则：
+/// 通过 netlink 获取网卡及地址，netlink 不可用时改用 `ip -6 -j addr` 命令，对于所获取的结果中匹配以下规则：
 ///
 /// - `operstate` 为 `UP`
 /// - `scope` 为 `global`
 /// - `dynamic` 为 `true`
 /// - `mngtmpaddr` 为 `true`
 /// - `noprefixroute` 为 `true`
+/// - `temporary` 为 `false`
+///
+/// 除前两项外的规则均可通过 [`AddressRules`] 调整，将会使用首个匹配规则的地址
+///
+/// - 针对 macOS 系统
 ///
-/// 将会使用首个匹配规则的地址
+/// 通过 `ifconfig -a -L inet6` 命令获取网卡及地址，匹配处于 `UP` 状态的网卡中首个非链路本地、非回环的地址，
+/// 临时地址（`temporary`）默认被跳过，即使用 `secured` 或基于 EUI-64 生成的地址。
+/// [`AddressRules`] 中仅 `allow_temporary` 有效。
 ///
 /// - 针对 Windows 系统
 ///
-/// 使用基于 Powershell 的命令 `Get-NetIPAddress -AddressFamily IPv6 -PolicyStore ActiveStore [-InterfaceAlias <interface_name>] | ConvertTo-JSON`。
+/// 通过 IP Helper API 的 `GetAdaptersAddresses` 获取地址，网卡接口名称即网卡别名，匹配时忽略大小写。
+/// API 调用失败时改用基于 Powershell 的命令 `Get-NetIPAddress -AddressFamily IPv6 -PolicyStore ActiveStore [-InterfaceAlias <interface_name>] | ConvertTo-JSON`。
 ///
 /// 将会使用首个非本地、非回环地址、非多播、非未指定的地址
 #[derive(Debug)]
-pub struct LocalIPv6(Option<Cow<'static, str>>);
+pub struct LocalIPv6 {
+    interface_name: Option<Cow<'static, str>>,
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    rules: AddressRules,
+}
+
+/// Linux 下选取 IPv6 地址时的匹配规则，macOS 下仅使用 `allow_temporary`，Windows 下不使用
+///
+/// 默认要求 `dynamic`、`mngtmpaddr` 及 `noprefixroute` 均为 `true`，且不使用临时地址。
+/// 使用 systemd-networkd 或静态配置的 IPv6 地址通常不带有这些标志，需要放宽对应规则。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+pub struct AddressRules {
+    /// 是否要求 `dynamic` 为 `true`
+    pub require_dynamic: bool,
+    /// 是否要求 `mngtmpaddr` 为 `true`
+    pub require_mngtmpaddr: bool,
+    /// 是否要求 `noprefixroute` 为 `true`
+    pub require_noprefixroute: bool,
+    /// 是否允许使用临时地址
+    pub allow_temporary: bool,
+}
+
+impl Default for AddressRules {
+    fn default() -> Self {
+        Self {
+            require_dynamic: true,
+            require_mngtmpaddr: true,
+            require_noprefixroute: true,
+            allow_temporary: false,
+        }
+    }
+}
+
+impl AddressRules {
+    /// 地址是否符合规则，`scope` 不为 `global` 的地址始终不符合
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn matches(&self, address: &Address) -> bool {
+        address.global
+            && (self.allow_temporary || !address.temporary)
+            && (!self.require_dynamic || address.dynamic)
+            && (!self.require_mngtmpaddr || address.mngtmpaddr)
+            && (!self.require_noprefixroute || address.noprefixroute)
+    }
+}
 
 impl LocalIPv6 {
-    pub fn new(interface_name: Option<Cow<'static, str>>) -> Self {
-        Self(interface_name)
+    pub fn new(interface_name: Option<Cow<'static, str>>, rules: AddressRules) -> Self {
+        Self {
+            interface_name,
+            rules,
+        }
     }
 
     #[cfg(target_os = "linux")]
     async fn ip_linux(&self) -> Result<IpAddr, Error> {
-        use serde::Deserialize;
-        use smallvec::SmallVec;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        /// netlink 不可用时仅提示一次
+        static NETLINK_WARNED: AtomicBool = AtomicBool::new(false);
+
+        let interfaces = match tokio::task::spawn_blocking(netlink::interfaces).await {
+            Ok(Ok(interfaces)) => interfaces,
+            result => {
+                if !NETLINK_WARNED.swap(true, Ordering::Relaxed) {
+                    let err = match result {
+                        Ok(Err(err)) => err.to_string(),
+                        Err(err) => err.to_string(),
+                        Ok(Ok(_)) => unreachable!(),
+                    };
+                    warn!("通过 netlink 获取网卡地址失败，改用 ip 命令获取：{}", err);
+                }
+                self.interfaces_command().await?
+            }
+        };
+
+        select(&interfaces, self.interface_name.as_deref(), &self.rules)
+            .map(IpAddr::V6)
+            .ok_or(Error::ip_source(self.name(), "未匹配到合法的 IPv6 地址"))
+    }
+
+    #[cfg(target_os = "macos")]
+    async fn ip_macos(&self) -> Result<IpAddr, Error> {
         use tokio::process::Command;
 
-        #[derive(Deserialize)]
-        struct Interface<'a> {
-            ifname: &'a str,
-            operstate: &'a str,
-            addr_info: Vec<AddrInfo<'a>>,
-        }
+        let output = Command::new("ifconfig")
+            .arg("-a")
+            .arg("-L")
+            .arg("inet6")
+            .output()
+            .await;
+        let output = match output {
+            Ok(output) => output,
+            Err(err) => return Err(Error::io(format!("执行命令时发生错误：{err}"), err)),
+        };
 
-        #[derive(Deserialize)]
-        struct AddrInfo<'a> {
-            local: Ipv6Addr,
-            scope: &'a str,
-            #[serde(default)]
-            temporary: bool,
-            #[serde(default)]
-            dynamic: bool,
-            #[serde(default)]
-            mngtmpaddr: bool,
-            #[serde(default)]
-            noprefixroute: bool,
-        }
+        let interfaces = ifconfig::parse(&String::from_utf8_lossy(&output.stdout));
+        // macOS 的地址不存在 Linux 专有的标志位
+        let rules = AddressRules {
+            require_dynamic: false,
+            require_mngtmpaddr: false,
+            require_noprefixroute: false,
+            ..self.rules
+        };
+        select(&interfaces, self.interface_name.as_deref(), &rules)
+            .map(IpAddr::V6)
+            .ok_or(Error::ip_source(self.name(), "未匹配到合法的 IPv6 地址"))
+    }
+
+    /// 通过 `ip -6 -j addr` 命令获取网卡及地址
+    #[cfg(target_os = "linux")]
+    async fn interfaces_command(&self) -> Result<Vec<Interface>, Error> {
+        use tokio::process::Command;
 
         let output = Command::new("ip")
             .arg("-6")
@@ -73,52 +171,57 @@ impl LocalIPv6 {
 
         let mut output = match output {
             Ok(output) => output,
-            Err(err) => return Err(Error::new_string(format!("执行命令时发生错误：{err}"))),
+            Err(err) => return Err(Error::io(format!("执行命令时发生错误：{err}"), err)),
         };
 
-        let interfaces = match simd_json::from_slice::<SmallVec<[Interface; 8]>>(&mut output.stdout)
-        {
-            Ok(interfaces) => interfaces,
-            Err(err) => return Err(Error::new_string(format!("解析 JSON 时发生错误：{err}"))),
-        };
-
-        let ip = interfaces
-            .into_iter()
-            .find(|interface| {
-                let matched_name = match self.0.as_ref() {
-                    Some(interface_name) => &interface.ifname == &*interface_name,
-                    None => true,
-                };
-                matched_name && interface.operstate == "UP"
-            })
-            .and_then(|interface| {
-                interface.addr_info.into_iter().find(|info| {
-                    info.scope == "global"
-                        && !info.temporary
-                        && info.dynamic
-                        && info.mngtmpaddr
-                        && info.noprefixroute
-                })
-            })
-            .map(|info| IpAddr::V6(info.local));
-
-        ip.ok_or(Error::new_str("未匹配到合法的 IPv6 地址"))
+        parse_command_output(&mut output.stdout)
     }
 
     #[cfg(target_os = "windows")]
     async fn ip_windows(&self) -> Result<IpAddr, Error> {
-        use std::str::FromStr;
+        use std::sync::atomic::{AtomicBool, Ordering};
 
-        use serde::{Deserialize, Serialize};
-        use tokio::process::Command;
+        /// IP Helper API 不可用时仅提示一次
+        static ADAPTERS_WARNED: AtomicBool = AtomicBool::new(false);
 
-        #[derive(Serialize, Deserialize)]
-        struct NetIPAddress<'a> {
-            #[serde(rename = "IPAddress")]
-            ip_address: &'a str,
-        }
+        let interface_name = self.interface_name.as_ref().map(|name| name.to_string());
+        let addresses = match tokio::task::spawn_blocking(move || {
+            adapters::addresses(interface_name.as_deref())
+        })
+        .await
+        {
+            Ok(Ok(addresses)) => addresses,
+            result => {
+                if !ADAPTERS_WARNED.swap(true, Ordering::Relaxed) {
+                    let err = match result {
+                        Ok(Err(err)) => err.to_string(),
+                        Err(err) => err.to_string(),
+                        Ok(Ok(_)) => unreachable!(),
+                    };
+                    warn!(
+                        "通过 GetAdaptersAddresses 获取网卡地址失败，改用 PowerShell 获取：{}",
+                        err
+                    );
+                }
+                self.addresses_powershell().await?
+            }
+        };
 
-        const EMPTY_LIST: Vec<NetIPAddress> = Vec::new();
+        prefer_valid(addresses.into_iter().filter(|(address, _)| {
+            !address.is_loopback()
+                && !address.is_unspecified()
+                && !address.is_multicast()
+                && !address.is_unicast_link_local()
+                && !address.is_unique_local()
+        }))
+        .map(IpAddr::V6)
+        .ok_or(Error::ip_source(self.name(), "未匹配到合法的 IPv6 地址"))
+    }
+
+    /// 通过 PowerShell 的 `Get-NetIPAddress` 命令获取地址
+    #[cfg(target_os = "windows")]
+    async fn addresses_powershell(&self) -> Result<Vec<(Ipv6Addr, Lifetime)>, Error> {
+        use tokio::process::Command;
 
         let mut command = Command::new("powershell");
         command
@@ -135,7 +238,7 @@ impl LocalIPv6 {
             .arg("IPv6")
             .arg("-PolicyStore")
             .arg("ActiveStore");
-        if let Some(interface_name) = self.0.as_ref() {
+        if let Some(interface_name) = self.interface_name.as_ref() {
             command.arg("-InterfaceAlias").arg(interface_name.as_ref());
         };
         command.arg("| ConvertTo-JSON");
@@ -143,7 +246,7 @@ impl LocalIPv6 {
         let output = command.output().await;
         let output = match output {
             Ok(output) => output,
-            Err(err) => return Err(Error::new_string(format!("执行命令时发生错误：{err}"))),
+            Err(err) => return Err(Error::io(format!("执行命令时发生错误：{err}"), err)),
         };
         let mut output = String::from_utf16_lossy(unsafe {
             std::slice::from_raw_parts(
@@ -152,25 +255,226 @@ impl LocalIPv6 {
             )
         });
 
-        let addresses = unsafe {
-            simd_json::from_str::<Vec<NetIPAddress>>(output.as_mut_str()).unwrap_or(EMPTY_LIST)
-        };
+        Ok(parse_powershell_output(output.as_mut_str()))
+    }
+}
 
-        let address = addresses
-            .into_iter()
-            .filter_map(|NetIPAddress { ip_address }| Ipv6Addr::from_str(&ip_address).ok())
-            .filter(|address| {
-                !address.is_loopback()
-                    && !address.is_unspecified()
-                    && !address.is_multicast()
-                    && !address.is_unicast_link_local()
-                    && !address.is_unique_local()
-            })
-            .next()
-            .map(|address| IpAddr::V6(address));
+/// 解析 `Get-NetIPAddress | ConvertTo-JSON` 的输出，解析失败时视为没有地址
+///
+/// `AddressState` 为 `Deprecated` 或 `PreferredLifetime` 为 0 的地址视为已弃用。
+#[cfg(target_os = "windows")]
+fn parse_powershell_output(output: &mut str) -> Vec<(Ipv6Addr, Lifetime)> {
+    use serde::Deserialize;
+
+    /// `AddressState` 中表示已弃用的值
+    const DEPRECATED: u32 = 3;
+
+    #[derive(Deserialize)]
+    struct NetIPAddress<'a> {
+        #[serde(rename = "IPAddress")]
+        ip_address: &'a str,
+        #[serde(rename = "AddressState", default)]
+        address_state: Option<AddressState<'a>>,
+        #[serde(rename = "PreferredLifetime", default)]
+        preferred_lifetime: Option<TimeSpan>,
+        #[serde(rename = "ValidLifetime", default)]
+        valid_lifetime: Option<TimeSpan>,
+    }
+
+    /// 地址状态，不同版本的 PowerShell 可能输出数值或名称
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum AddressState<'a> {
+        Code(u32),
+        Name(&'a str),
+    }
+
+    #[derive(Deserialize)]
+    struct TimeSpan {
+        #[serde(rename = "TotalSeconds")]
+        total_seconds: f64,
+    }
+
+    let addresses = unsafe { simd_json::from_str::<Vec<NetIPAddress>>(output) }.unwrap_or_default();
+
+    addresses
+        .into_iter()
+        .filter_map(|address| {
+            let Some(IpAddr::V6(ip)) = parse_ip(address.ip_address, false) else {
+                return None;
+            };
+            let deprecated = match address.address_state {
+                Some(AddressState::Code(code)) => code == DEPRECATED,
+                Some(AddressState::Name(name)) => name.eq_ignore_ascii_case("Deprecated"),
+                None => false,
+            } || address
+                .preferred_lifetime
+                .is_some_and(|lifetime| lifetime.total_seconds <= 0.0);
+            Some((
+                ip,
+                Lifetime {
+                    deprecated,
+                    valid: address
+                        .valid_lifetime
+                        .map(|lifetime| lifetime.total_seconds.min(u32::MAX as f64) as u32)
+                        .unwrap_or(u32::MAX),
+                },
+            ))
+        })
+        .collect()
+}
 
-        address.ok_or(Error::new_str("未匹配到合法的 IPv6 地址"))
+/// 解析 `ip -6 -j addr` 命令的输出
+#[cfg(target_os = "linux")]
+fn parse_command_output(output: &mut [u8]) -> Result<Vec<Interface>, Error> {
+    use serde::Deserialize;
+    use smallvec::SmallVec;
+
+    #[derive(Deserialize)]
+    struct CommandInterface<'a> {
+        ifname: &'a str,
+        operstate: &'a str,
+        addr_info: Vec<AddrInfo<'a>>,
+    }
+
+    #[derive(Deserialize)]
+    struct AddrInfo<'a> {
+        local: Ipv6Addr,
+        scope: &'a str,
+        #[serde(default)]
+        temporary: bool,
+        #[serde(default)]
+        dynamic: bool,
+        #[serde(default)]
+        mngtmpaddr: bool,
+        #[serde(default)]
+        noprefixroute: bool,
+        #[serde(default)]
+        deprecated: bool,
+        preferred_life_time: Option<u32>,
+        valid_life_time: Option<u32>,
     }
+
+    let interfaces = match simd_json::from_slice::<SmallVec<[CommandInterface; 8]>>(output) {
+        Ok(interfaces) => interfaces,
+        Err(err) => return Err(Error::parse(format!("解析 JSON 时发生错误：{err}"), err)),
+    };
+
+    Ok(interfaces
+        .into_iter()
+        .map(|interface| Interface {
+            name: interface.ifname.to_string(),
+            up: interface.operstate == "UP",
+            addresses: interface
+                .addr_info
+                .into_iter()
+                .map(|info| Address {
+                    local: info.local,
+                    global: info.scope == "global",
+                    temporary: info.temporary,
+                    dynamic: info.dynamic,
+                    mngtmpaddr: info.mngtmpaddr,
+                    noprefixroute: info.noprefixroute,
+                    lifetime: Lifetime {
+                        deprecated: info.deprecated || info.preferred_life_time == Some(0),
+                        valid: info.valid_life_time.unwrap_or(u32::MAX),
+                    },
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+/// 网卡及其 IPv6 地址
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Interface {
+    name: String,
+    /// `operstate` 是否为 `UP`
+    up: bool,
+    addresses: Vec<Address>,
+}
+
+/// IPv6 地址及其标志位
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Address {
+    local: Ipv6Addr,
+    /// `scope` 是否为 `global`
+    global: bool,
+    temporary: bool,
+    dynamic: bool,
+    mngtmpaddr: bool,
+    noprefixroute: bool,
+    lifetime: Lifetime,
+}
+
+/// 地址的弃用状态及剩余有效期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lifetime {
+    /// 是否已弃用，即首选有效期已结束
+    deprecated: bool,
+    /// 剩余有效期，单位秒，`u32::MAX` 表示永久有效
+    valid: u32,
+}
+
+impl Lifetime {
+    /// 未弃用且永久有效
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    const FOREVER: Self = Self {
+        deprecated: false,
+        valid: u32::MAX,
+    };
+}
+
+/// 选取首个未弃用的地址。全部已弃用时选取剩余有效期最长的地址，并输出警告
+///
+/// 运营商更换前缀后，旧地址在有效期结束前仍保留在网卡上并被标记为已弃用。
+fn prefer_valid(candidates: impl IntoIterator<Item = (Ipv6Addr, Lifetime)>) -> Option<Ipv6Addr> {
+    let mut fallback: Option<(Ipv6Addr, Lifetime)> = None;
+    for (address, lifetime) in candidates {
+        if !lifetime.deprecated {
+            return Some(address);
+        }
+        if fallback.is_none_or(|(_, longest)| lifetime.valid > longest.valid) {
+            fallback = Some((address, lifetime));
+        }
+    }
+
+    fallback.map(|(address, lifetime)| {
+        warn!(
+            "所有候选 IPv6 地址均已弃用，使用剩余有效期最长的地址 {}，剩余 {} 秒",
+            address, lifetime.valid
+        );
+        address
+    })
+}
+
+/// 选取首个匹配名称且处于 `UP` 状态的网卡中，首个符合规则且未弃用的地址
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn select(
+    interfaces: &[Interface],
+    interface_name: Option<&str>,
+    rules: &AddressRules,
+) -> Option<Ipv6Addr> {
+    interfaces
+        .iter()
+        .find(|interface| {
+            let matched_name = match interface_name {
+                Some(interface_name) => interface.name == interface_name,
+                None => true,
+            };
+            matched_name && interface.up
+        })
+        .and_then(|interface| {
+            prefer_valid(
+                interface
+                    .addresses
+                    .iter()
+                    .filter(|address| rules.matches(address))
+                    .map(|address| (address.local, address.lifetime)),
+            )
+        })
 }
 
 #[async_trait]
@@ -180,11 +484,15 @@ impl IpSource for LocalIPv6 {
         {
             return self.ip_linux().await;
         }
+        #[cfg(target_os = "macos")]
+        {
+            return self.ip_macos().await;
+        }
         #[cfg(target_os = "windows")]
         {
             return self.ip_windows().await;
         }
-        #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+        #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
         {
             unimplemented!()
         }
@@ -195,10 +503,9 @@ impl IpSource for LocalIPv6 {
     }
 
     fn info(&self) -> Option<Cow<'_, str>> {
-        match self.0.as_ref() {
-            Some(interface_name) => Some(Cow::Owned(format!("指定网卡接口 {}", interface_name))),
-            None => None,
-        }
+        self.interface_name
+            .as_ref()
+            .map(|interface_name| Cow::Owned(format!("指定网卡接口 {}", interface_name)))
     }
 }
 
@@ -206,12 +513,226 @@ impl IpSource for LocalIPv6 {
 mod tests {
     use crate::libs::{
         error::Error,
-        source::{local_ipv6::LocalIPv6, IpSource},
+        source::{
+            local_ipv6::{AddressRules, LocalIPv6},
+            IpSource,
+        },
     };
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_select() {
+        use super::{select, Address, AddressRules, Interface, Lifetime};
+
+        let rules = AddressRules::default();
+
+        let address = |ip: &str, temporary: bool| Address {
+            local: ip.parse().unwrap(),
+            global: true,
+            temporary,
+            dynamic: true,
+            mngtmpaddr: true,
+            noprefixroute: true,
+            lifetime: Lifetime::FOREVER,
+        };
+        let interfaces = vec![
+            Interface {
+                name: "lo".to_string(),
+                up: false,
+                addresses: vec![Address {
+                    global: false,
+                    dynamic: false,
+                    ..address("::1", false)
+                }],
+            },
+            Interface {
+                name: "eth0".to_string(),
+                up: true,
+                addresses: vec![
+                    address("2001:db8::aaaa", true),
+                    Address {
+                        mngtmpaddr: false,
+                        ..address("2001:db8::bbbb", false)
+                    },
+                    Address {
+                        global: false,
+                        ..address("fe80::1", false)
+                    },
+                    address("2001:db8::1", false),
+                    address("2001:db8::2", false),
+                ],
+            },
+            Interface {
+                name: "eth1".to_string(),
+                up: true,
+                addresses: vec![address("2001:db8:1::1", false)],
+            },
+            Interface {
+                name: "eth2".to_string(),
+                up: false,
+                addresses: vec![address("2001:db8:2::1", false)],
+            },
+        ];
+
+        let ip = |text: &str| Some(text.parse().unwrap());
+        assert_eq!(select(&interfaces, None, &rules), ip("2001:db8::1"));
+        assert_eq!(
+            select(&interfaces, Some("eth1"), &rules),
+            ip("2001:db8:1::1")
+        );
+        // 网卡未处于 UP 状态
+        assert_eq!(select(&interfaces, Some("eth2"), &rules), None);
+        assert_eq!(select(&interfaces, Some("wlan0"), &rules), None);
+        // 仅检查首个 UP 状态的网卡
+        assert_eq!(select(&interfaces[..2], None, &rules), ip("2001:db8::1"));
+        let without_match = vec![interfaces[0].clone(), interfaces[1].clone()]
+            .into_iter()
+            .map(|mut interface| {
+                interface.addresses.retain(|address| address.temporary);
+                interface
+            })
+            .chain(std::iter::once(interfaces[2].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(select(&without_match, None, &rules), None);
+        // 允许使用临时地址
+        let rules = AddressRules {
+            allow_temporary: true,
+            ..rules
+        };
+        assert_eq!(select(&without_match, None, &rules), ip("2001:db8::aaaa"));
+    }
+
+    /// 使用 systemd-networkd 的主机上 `ip -6 -j addr` 的输出，
+    /// SLAAC 地址仅带有 `dynamic` 标志，静态地址不带任何标志
+    #[cfg(target_os = "linux")]
+    const NETWORKD_OUTPUT: &str = r#"[{"ifindex":1,"ifname":"lo","flags":["LOOPBACK","UP","LOWER_UP"],"mtu":65536,"qdisc":"noqueue","operstate":"UNKNOWN","group":"default","txqlen":1000,"addr_info":[{"family":"inet6","local":"::1","prefixlen":128,"scope":"host","noprefixroute":true,"valid_life_time":4294967295,"preferred_life_time":4294967295}]},{"ifindex":2,"ifname":"enp1s0","flags":["BROADCAST","MULTICAST","UP","LOWER_UP"],"mtu":1500,"qdisc":"fq_codel","operstate":"UP","group":"default","txqlen":1000,"addr_info":[{"family":"inet6","local":"2001:db8:1234:5678:5054:ff:fe12:3456","prefixlen":64,"scope":"global","dynamic":true,"protocol":"kernel_ra","valid_life_time":86331,"preferred_life_time":14331},{"family":"inet6","local":"fe80::5054:ff:fe12:3456","prefixlen":64,"scope":"link","protocol":"kernel_ll","valid_life_time":4294967295,"preferred_life_time":4294967295}]},{"ifindex":3,"ifname":"enp2s0","flags":["BROADCAST","MULTICAST","UP","LOWER_UP"],"mtu":1500,"qdisc":"fq_codel","operstate":"UP","group":"default","txqlen":1000,"addr_info":[{"family":"inet6","local":"2001:db8:abcd::10","prefixlen":64,"scope":"global","protocol":"static","valid_life_time":4294967295,"preferred_life_time":4294967295},{"family":"inet6","local":"fe80::5054:ff:feab:cdef","prefixlen":64,"scope":"link","protocol":"kernel_ll","valid_life_time":4294967295,"preferred_life_time":4294967295}]}]"#;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_select_networkd() {
+        use super::{parse_command_output, select, AddressRules};
+
+        let interfaces = parse_command_output(&mut NETWORKD_OUTPUT.as_bytes().to_vec()).unwrap();
+        assert_eq!(interfaces.len(), 3);
+        assert!(!interfaces[0].up);
+
+        let ip = |text: &str| Some(text.parse().unwrap());
+        // 默认规则无法匹配
+        let rules = AddressRules::default();
+        assert_eq!(select(&interfaces, None, &rules), None);
+        assert_eq!(select(&interfaces, Some("enp2s0"), &rules), None);
+
+        // SLAAC 地址仅需放宽 mngtmpaddr 及 noprefixroute
+        let rules = AddressRules {
+            require_mngtmpaddr: false,
+            require_noprefixroute: false,
+            ..AddressRules::default()
+        };
+        assert_eq!(
+            select(&interfaces, None, &rules),
+            ip("2001:db8:1234:5678:5054:ff:fe12:3456")
+        );
+        assert_eq!(select(&interfaces, Some("enp2s0"), &rules), None);
+
+        // 静态地址需放宽所有标志
+        let rules = AddressRules {
+            require_dynamic: false,
+            ..rules
+        };
+        assert_eq!(
+            select(&interfaces, Some("enp2s0"), &rules),
+            ip("2001:db8:abcd::10")
+        );
+    }
+
+    /// 更换前缀后旧地址仍保留在网卡上并被标记为已弃用
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_select_deprecated() {
+        use super::{parse_command_output, select, AddressRules};
+
+        let output = |addresses: &[(&str, bool, u32, u32)]| {
+            let addr_info = addresses
+                .iter()
+                .map(|(local, deprecated, preferred, valid)| {
+                    format!(
+                        r#"{{"family":"inet6","local":"{}","prefixlen":64,"scope":"global","dynamic":true,"mngtmpaddr":true,"noprefixroute":true,{}"valid_life_time":{},"preferred_life_time":{}}}"#,
+                        local,
+                        if *deprecated { r#""deprecated":true,"# } else { "" },
+                        valid,
+                        preferred
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            let mut output = format!(
+                r#"[{{"ifindex":2,"ifname":"eth0","operstate":"UP","addr_info":[{}]}}]"#,
+                addr_info
+            )
+            .into_bytes();
+            parse_command_output(&mut output).unwrap()
+        };
+        let rules = AddressRules::default();
+        let ip = |text: &str| Some(text.parse().unwrap());
+
+        let interfaces = output(&[
+            ("2001:db8:1::1", true, 0, 5000),
+            ("2001:db8:2::1", false, 14400, 86400),
+        ]);
+        assert!(interfaces[0].addresses[0].lifetime.deprecated);
+        assert_eq!(select(&interfaces, None, &rules), ip("2001:db8:2::1"));
+
+        // 未输出 deprecated 标志时通过首选有效期判断
+        let interfaces = output(&[
+            ("2001:db8:1::1", false, 0, 5000),
+            ("2001:db8:2::1", false, 14400, 86400),
+        ]);
+        assert_eq!(select(&interfaces, None, &rules), ip("2001:db8:2::1"));
+
+        // 全部已弃用时使用剩余有效期最长的地址
+        let interfaces = output(&[
+            ("2001:db8:a::1", true, 0, 600),
+            ("2001:db8:b::1", true, 0, 7200),
+            ("2001:db8:c::1", true, 0, 3600),
+        ]);
+        assert_eq!(select(&interfaces, None, &rules), ip("2001:db8:b::1"));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_parse_powershell_output() {
+        use super::{parse_powershell_output, prefer_valid, Lifetime};
+
+        let mut output = r#"[
+            {"IPAddress":"2001:db8:1::1","AddressState":3,"PreferredLifetime":{"TotalSeconds":0},"ValidLifetime":{"TotalSeconds":5000}},
+            {"IPAddress":"fe80::1%12","AddressState":4,"PreferredLifetime":{"TotalSeconds":4294967295},"ValidLifetime":{"TotalSeconds":4294967295}},
+            {"IPAddress":"2001:db8:2::1","AddressState":4,"PreferredLifetime":{"TotalSeconds":14400},"ValidLifetime":{"TotalSeconds":86400}}
+        ]"#
+        .to_string();
+        let addresses = parse_powershell_output(output.as_mut_str());
+        assert_eq!(
+            addresses[0],
+            (
+                "2001:db8:1::1".parse().unwrap(),
+                Lifetime {
+                    deprecated: true,
+                    valid: 5000
+                }
+            )
+        );
+        assert_eq!(
+            prefer_valid(
+                addresses
+                    .into_iter()
+                    .filter(|(address, _)| !address.is_unicast_link_local())
+            ),
+            Some("2001:db8:2::1".parse().unwrap())
+        );
+    }
+
     #[tokio::test]
     async fn test_local_ipv6() -> Result<(), Error> {
-        let ip_source = LocalIPv6::new(None);
+        let ip_source = LocalIPv6::new(None, AddressRules::default());
 
         let ip = ip_source.ip().await?;
         println!("{}", ip);
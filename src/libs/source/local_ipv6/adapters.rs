@@ -0,0 +1,105 @@
+//! 通过 IP Helper API 的 `GetAdaptersAddresses` 获取网卡的 IPv6 地址，无需启动 PowerShell
+
+use std::{io, net::Ipv6Addr};
+
+use windows::Win32::{
+    Foundation::{ERROR_BUFFER_OVERFLOW, ERROR_NO_DATA, ERROR_SUCCESS, WIN32_ERROR},
+    NetworkManagement::IpHelper::{
+        GetAdaptersAddresses, GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER,
+        GAA_FLAG_SKIP_MULTICAST, IP_ADAPTER_ADDRESSES_LH,
+    },
+    Networking::WinSock::{IpDadStateDeprecated, AF_INET6, SOCKADDR_IN6},
+};
+
+use super::Lifetime;
+
+/// 初始缓冲区大小，微软建议为 15 KB
+const INITIAL_BUFFER_LEN: u32 = 15 * 1024;
+/// 缓冲区不足时的最大重试次数
+const MAX_ATTEMPTS: usize = 3;
+
+/// 获取单播 IPv6 地址及其有效期，`interface_name` 不为空时仅获取别名匹配的网卡地址，匹配时忽略大小写
+///
+/// `DadState` 为 `IpDadStateDeprecated` 或首选有效期为 0 的地址视为已弃用。
+pub fn addresses(interface_name: Option<&str>) -> io::Result<Vec<(Ipv6Addr, Lifetime)>> {
+    let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER;
+    let interface_name = interface_name.map(str::to_lowercase);
+
+    // 使用 u64 保证结构体对齐
+    let mut buffer = Vec::<u64>::new();
+    let mut size = INITIAL_BUFFER_LEN;
+    for attempt in 1..=MAX_ATTEMPTS {
+        buffer = vec![0; (size as usize).div_ceil(8)];
+        let result = WIN32_ERROR(unsafe {
+            GetAdaptersAddresses(
+                AF_INET6.0 as u32,
+                flags,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                &mut size,
+            )
+        });
+
+        if result == ERROR_SUCCESS {
+            break;
+        } else if result == ERROR_NO_DATA {
+            // 没有任何包含 IPv6 地址的网卡
+            return Ok(Vec::new());
+        } else if result == ERROR_BUFFER_OVERFLOW && attempt < MAX_ATTEMPTS {
+            continue;
+        } else {
+            return Err(io::Error::from_raw_os_error(result.0 as i32));
+        }
+    }
+
+    let mut addresses = Vec::new();
+    let mut adapter = buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH;
+    while let Some(current) = unsafe { adapter.as_ref() } {
+        adapter = current.Next;
+
+        if let Some(interface_name) = interface_name.as_ref() {
+            let alias = if current.FriendlyName.is_null() {
+                String::new()
+            } else {
+                unsafe { current.FriendlyName.to_string() }.unwrap_or_default()
+            };
+            if alias.to_lowercase() != *interface_name {
+                continue;
+            }
+        }
+
+        let mut unicast = current.FirstUnicastAddress;
+        while let Some(address) = unsafe { unicast.as_ref() } {
+            unicast = address.Next;
+
+            let sockaddr = address.Address.lpSockaddr;
+            if sockaddr.is_null() || unsafe { (*sockaddr).sa_family } != AF_INET6 {
+                continue;
+            }
+            let sockaddr = unsafe { &*(sockaddr as *const SOCKADDR_IN6) };
+            addresses.push((
+                Ipv6Addr::from(unsafe { sockaddr.sin6_addr.u.Byte }),
+                Lifetime {
+                    deprecated: address.DadState == IpDadStateDeprecated
+                        || address.PreferredLifetime == 0,
+                    valid: address.ValidLifetime,
+                },
+            ));
+        }
+    }
+
+    Ok(addresses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::addresses;
+
+    #[test]
+    fn test_addresses() {
+        addresses(None).unwrap();
+        assert!(addresses(Some("ddns4cf-nonexistent-interface"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! 解析 macOS 下 `ifconfig -a -L inet6` 命令的输出
+//!
+//! 输出中每个网卡以不带缩进的 `en0: flags=8863<UP,...> mtu 1500` 行开始，
+//! 其后缩进的 `inet6` 行为该网卡的地址，地址之后为前缀长度、标志位及 `-L` 输出的有效期。
+
+use std::net::Ipv6Addr;
+
+use super::{Address, Interface, Lifetime};
+
+/// 解析所有包含全局 IPv6 地址的网卡，顺序与命令输出一致
+///
+/// macOS 下 `lo0`、`awdl0`、`utun0` 等网卡同样处于 `UP` 状态但仅有回环或链路本地地址，
+/// 保留这些网卡将导致未指定网卡名称时选中错误的网卡。
+///
+/// 尚未完成重复地址检测（`tentative`）、地址冲突（`duplicated`）或已脱离链路（`detached`）的地址不可用，将被忽略。
+pub fn parse(output: &str) -> Vec<Interface> {
+    let mut interfaces: Vec<Interface> = Vec::new();
+    for line in output.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            if let Some(interface) = parse_header(line) {
+                interfaces.push(interface);
+            }
+            continue;
+        }
+
+        let Some(interface) = interfaces.last_mut() else {
+            continue;
+        };
+        if let Some(address) = parse_address(line) {
+            interface.addresses.push(address);
+        }
+    }
+
+    interfaces.retain(|interface| interface.addresses.iter().any(|address| address.global));
+    interfaces
+}
+
+/// 解析 `en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500`
+fn parse_header(line: &str) -> Option<Interface> {
+    let (name, rest) = line.split_once(": ")?;
+    let up = rest
+        .split_once('<')
+        .and_then(|(_, flags)| flags.split_once('>'))
+        .map(|(flags, _)| flags.split(',').any(|flag| flag == "UP"))
+        .unwrap_or(false);
+
+    Some(Interface {
+        name: name.to_string(),
+        up,
+        addresses: Vec::new(),
+    })
+}
+
+/// 解析 `inet6 2001:db8::1 prefixlen 64 autoconf secured pltime 604775 vltime 2591975`
+fn parse_address(line: &str) -> Option<Address> {
+    let mut tokens = line.split_whitespace();
+    if tokens.next()? != "inet6" {
+        return None;
+    }
+    // 链路本地地址带有 `%en0` 形式的区域标识
+    let local = tokens.next()?.split('%').next()?.parse::<Ipv6Addr>().ok()?;
+
+    let mut temporary = false;
+    let mut autoconf = false;
+    let mut deprecated = false;
+    let mut preferred = None;
+    let mut valid = None;
+    while let Some(token) = tokens.next() {
+        match token {
+            "temporary" => temporary = true,
+            "autoconf" => autoconf = true,
+            "deprecated" => deprecated = true,
+            "tentative" | "duplicated" | "detached" => return None,
+            "pltime" => preferred = tokens.next().map(parse_lifetime),
+            "vltime" => valid = tokens.next().map(parse_lifetime),
+            // 带有参数的字段
+            "prefixlen" | "scopeid" => {
+                tokens.next();
+            }
+            _ => {}
+        }
+    }
+
+    Some(Address {
+        local,
+        global: !local.is_loopback() && !local.is_unicast_link_local(),
+        temporary,
+        dynamic: autoconf,
+        mngtmpaddr: false,
+        noprefixroute: false,
+        lifetime: Lifetime {
+            deprecated: deprecated || preferred == Some(0),
+            valid: valid.unwrap_or(Lifetime::FOREVER.valid),
+        },
+    })
+}
+
+/// 有效期为秒数，永久有效时为 `infty`
+fn parse_lifetime(value: &str) -> u32 {
+    value.parse().unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::libs::source::local_ipv6::{select, AddressRules, Lifetime};
+
+    use super::parse;
+
+    const OUTPUT: &str = "\
+lo0: flags=8049<UP,LOOPBACK,RUNNING,MULTICAST> mtu 16384
+\toptions=1203<RXCSUM,TXCSUM,TXSTATUS,SW_TIMESTAMP>
+\tinet6 ::1 prefixlen 128 pltime infty vltime infty
+\tinet6 fe80::1%lo0 prefixlen 64 scopeid 0x1 pltime infty vltime infty
+\tnd6 options=201<PERFORMNUD,DAD>
+gif0: flags=8010<POINTOPOINT,MULTICAST> mtu 1280
+en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+\toptions=6463<RXCSUM,TXCSUM,TSO4,TSO6,CHANNEL_IO,PARTIAL_CSUM,ZEROINVERT_CSUM>
+\tinet6 fe80::18f2:2c1f:a2b3:c4d5%en0 prefixlen 64 secured scopeid 0x6 pltime infty vltime infty
+\tinet6 2001:db8:1234:5678:a9b8:c7d6:e5f4:3210 prefixlen 64 autoconf temporary pltime 85947 vltime 604775
+\tinet6 2001:db8:1234:5678:1c2b:3d4e:5f60:7182 prefixlen 64 autoconf secured pltime 604775 vltime 2591975
+\tnd6 options=201<PERFORMNUD,DAD>
+\tmedia: autoselect
+\tstatus: active
+en1: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+\tinet6 2001:db8:aaaa::1 prefixlen 64 tentative pltime infty vltime infty
+\tinet6 2001:db8:aaaa::2 prefixlen 64 deprecated pltime 0 vltime 3600
+\tinet6 2001:db8:aaaa::3 prefixlen 64 pltime infty vltime infty
+en2: flags=8822<BROADCAST,SMART,SIMPLEX,MULTICAST> mtu 1500
+\tinet6 2001:db8:bbbb::1 prefixlen 64 autoconf secured pltime 604775 vltime 2591975
+";
+
+    #[test]
+    fn test_parse() {
+        let interfaces = parse(OUTPUT);
+        assert_eq!(
+            interfaces
+                .iter()
+                .map(|interface| (interface.name.as_str(), interface.up))
+                .collect::<Vec<_>>(),
+            [("en0", true), ("en1", true), ("en2", false)]
+        );
+
+        let en0 = &interfaces[0];
+        assert_eq!(en0.addresses.len(), 3);
+        assert!(!en0.addresses[0].global);
+        assert!(en0.addresses[1].temporary && en0.addresses[1].dynamic);
+        assert_eq!(
+            en0.addresses[2].lifetime,
+            Lifetime {
+                deprecated: false,
+                valid: 2591975
+            }
+        );
+
+        // 未完成重复地址检测的地址被忽略
+        let en1 = &interfaces[1];
+        assert_eq!(en1.addresses.len(), 2);
+        assert!(en1.addresses[0].lifetime.deprecated);
+        assert_eq!(en1.addresses[1].lifetime, Lifetime::FOREVER);
+    }
+
+    #[test]
+    fn test_select() {
+        let interfaces = parse(OUTPUT);
+        let rules = AddressRules {
+            require_dynamic: false,
+            require_mngtmpaddr: false,
+            require_noprefixroute: false,
+            ..AddressRules::default()
+        };
+        let ip = |text: &str| Some(text.parse().unwrap());
+
+        // 跳过仅有回环及链路本地地址的 lo0，跳过临时地址
+        assert_eq!(
+            select(&interfaces, None, &rules),
+            ip("2001:db8:1234:5678:1c2b:3d4e:5f60:7182")
+        );
+        assert_eq!(select(&interfaces, Some("lo0"), &rules), None);
+        // 跳过已弃用地址
+        assert_eq!(
+            select(&interfaces, Some("en1"), &rules),
+            ip("2001:db8:aaaa::3")
+        );
+        assert_eq!(select(&interfaces, Some("en2"), &rules), None);
+
+        let rules = AddressRules {
+            allow_temporary: true,
+            ..rules
+        };
+        assert_eq!(
+            select(&interfaces, Some("en0"), &rules),
+            ip("2001:db8:1234:5678:a9b8:c7d6:e5f4:3210")
+        );
+    }
+}
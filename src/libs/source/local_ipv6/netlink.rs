@@ -0,0 +1,388 @@
+//! 通过 netlink 直接从内核获取网卡及 IPv6 地址，不依赖 iproute2
+//!
+//! 依次发送 `RTM_GETLINK` 及 `RTM_GETADDR` 请求，获取的信息与 `ip -6 -j addr` 一致。
+
+use std::{
+    io, mem,
+    net::Ipv6Addr,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use super::{Address, Interface, Lifetime};
+
+/// `nlmsghdr` 长度
+const NLMSG_HEADER_LEN: usize = 16;
+/// `ifinfomsg` 长度
+const IFINFOMSG_LEN: usize = 16;
+/// `ifaddrmsg` 长度
+const IFADDRMSG_LEN: usize = 8;
+/// 接收缓冲区大小
+const RECEIVE_BUFFER_LEN: usize = 32 * 1024;
+
+/// 获取所有包含 IPv6 地址的网卡，顺序与 `ip -6 addr` 输出一致
+pub fn interfaces() -> io::Result<Vec<Interface>> {
+    let socket = NetlinkSocket::open()?;
+
+    let mut interfaces = socket
+        .dump(libc::RTM_GETLINK, libc::AF_UNSPEC as u8, IFINFOMSG_LEN, 1)?
+        .iter()
+        .filter_map(|(r#type, payload)| {
+            (*r#type == libc::RTM_NEWLINK)
+                .then(|| parse_link(payload))
+                .flatten()
+        })
+        .collect::<Vec<_>>();
+
+    for (r#type, payload) in socket
+        .dump(libc::RTM_GETADDR, libc::AF_INET6 as u8, IFADDRMSG_LEN, 2)?
+        .iter()
+    {
+        if *r#type != libc::RTM_NEWADDR {
+            continue;
+        }
+        let Some((index, address)) = parse_address(payload) else {
+            continue;
+        };
+        if let Some((_, interface)) = interfaces.iter_mut().find(|(i, _)| *i == index) {
+            interface.addresses.push(address);
+        }
+    }
+
+    Ok(interfaces
+        .into_iter()
+        .map(|(_, interface)| interface)
+        .filter(|interface| !interface.addresses.is_empty())
+        .collect())
+}
+
+struct NetlinkSocket(OwnedFd);
+
+impl NetlinkSocket {
+    fn open() -> io::Result<Self> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    /// 发送 dump 请求，返回所有响应消息的类型及消息体
+    fn dump(
+        &self,
+        r#type: u16,
+        family: u8,
+        payload_len: usize,
+        sequence: u32,
+    ) -> io::Result<Vec<(u16, Vec<u8>)>> {
+        let len = NLMSG_HEADER_LEN + payload_len;
+        let mut request = vec![0; len];
+        request[0..4].copy_from_slice(&(len as u32).to_ne_bytes());
+        request[4..6].copy_from_slice(&r#type.to_ne_bytes());
+        request[6..8]
+            .copy_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+        request[8..12].copy_from_slice(&sequence.to_ne_bytes());
+        request[NLMSG_HEADER_LEN] = family;
+
+        let mut address: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as u16;
+        let sent = unsafe {
+            libc::sendto(
+                self.0.as_raw_fd(),
+                request.as_ptr().cast(),
+                request.len(),
+                0,
+                (&address as *const libc::sockaddr_nl).cast(),
+                mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut messages = Vec::new();
+        let mut buffer = vec![0u8; RECEIVE_BUFFER_LEN];
+        loop {
+            let len = unsafe {
+                libc::recv(
+                    self.0.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    0,
+                )
+            };
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if parse_messages(&buffer[..len as usize], sequence, &mut messages)? {
+                return Ok(messages);
+            }
+        }
+    }
+}
+
+/// 解析一次接收到的所有消息，收到 `NLMSG_DONE` 时返回 `true`
+fn parse_messages(
+    mut buffer: &[u8],
+    sequence: u32,
+    messages: &mut Vec<(u16, Vec<u8>)>,
+) -> io::Result<bool> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "netlink 消息格式错误");
+
+    while buffer.len() >= NLMSG_HEADER_LEN {
+        let len = u32::from_ne_bytes(buffer[0..4].try_into().unwrap()) as usize;
+        let r#type = u16::from_ne_bytes(buffer[4..6].try_into().unwrap());
+        let seq = u32::from_ne_bytes(buffer[8..12].try_into().unwrap());
+        if len < NLMSG_HEADER_LEN || len > buffer.len() {
+            return Err(malformed());
+        }
+        let payload = &buffer[NLMSG_HEADER_LEN..len];
+        buffer = buffer.get(align(len)..).unwrap_or_default();
+
+        if seq != sequence {
+            continue;
+        }
+        match r#type as i32 {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                let code = payload
+                    .get(0..4)
+                    .map(|code| i32::from_ne_bytes(code.try_into().unwrap()))
+                    .ok_or_else(malformed)?;
+                if code != 0 {
+                    return Err(io::Error::from_raw_os_error(-code));
+                }
+            }
+            _ => messages.push((r#type, payload.to_vec())),
+        }
+    }
+
+    Ok(false)
+}
+
+/// netlink 消息及属性均按 4 字节对齐
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// 遍历 `rtattr` 属性，返回属性类型及数据
+fn attributes(mut buffer: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        let len = u16::from_ne_bytes(buffer.get(0..2)?.try_into().unwrap()) as usize;
+        let r#type = u16::from_ne_bytes(buffer.get(2..4)?.try_into().unwrap());
+        if len < 4 {
+            return None;
+        }
+        let data = buffer.get(4..len)?;
+        buffer = buffer.get(align(len)..).unwrap_or_default();
+        Some((r#type, data))
+    })
+}
+
+/// 解析 `RTM_NEWLINK` 消息，返回网卡序号及网卡信息
+fn parse_link(payload: &[u8]) -> Option<(i32, Interface)> {
+    let index = i32::from_ne_bytes(payload.get(4..8)?.try_into().unwrap());
+
+    let mut name = None;
+    let mut up = false;
+    for (r#type, data) in attributes(payload.get(IFINFOMSG_LEN..)?) {
+        match r#type {
+            libc::IFLA_IFNAME => {
+                let data = data.split(|byte| *byte == 0).next().unwrap_or_default();
+                name = Some(String::from_utf8_lossy(data).into_owned());
+            }
+            libc::IFLA_OPERSTATE => up = data.first() == Some(&(libc::IF_OPER_UP as u8)),
+            _ => {}
+        }
+    }
+
+    Some((
+        index,
+        Interface {
+            name: name?,
+            up,
+            addresses: Vec::new(),
+        },
+    ))
+}
+
+/// 解析 `RTM_NEWADDR` 消息，返回网卡序号及地址信息
+fn parse_address(payload: &[u8]) -> Option<(i32, Address)> {
+    let header = payload.get(..IFADDRMSG_LEN)?;
+    if header[0] != libc::AF_INET6 as u8 {
+        return None;
+    }
+    // `ifa_flags` 仅有 8 位，存在 `IFA_FLAGS` 属性时以属性为准
+    let mut flags = header[2] as u32;
+    let scope = header[3];
+    let index = u32::from_ne_bytes(header[4..8].try_into().unwrap()) as i32;
+
+    let mut address = None;
+    let mut local = None;
+    let mut lifetime = Lifetime::FOREVER;
+    for (r#type, data) in attributes(&payload[IFADDRMSG_LEN..]) {
+        match r#type {
+            libc::IFA_ADDRESS => address = <[u8; 16]>::try_from(data).ok().map(Ipv6Addr::from),
+            libc::IFA_LOCAL => local = <[u8; 16]>::try_from(data).ok().map(Ipv6Addr::from),
+            libc::IFA_FLAGS => {
+                flags = u32::from_ne_bytes(data.get(0..4)?.try_into().unwrap());
+            }
+            // `ifa_cacheinfo` 的前两个字段为首选有效期及有效期
+            libc::IFA_CACHEINFO => {
+                let preferred = u32::from_ne_bytes(data.get(0..4)?.try_into().unwrap());
+                lifetime.valid = u32::from_ne_bytes(data.get(4..8)?.try_into().unwrap());
+                lifetime.deprecated = preferred == 0;
+            }
+            _ => {}
+        }
+    }
+
+    Some((
+        index,
+        Address {
+            local: local.or(address)?,
+            global: scope == libc::RT_SCOPE_UNIVERSE,
+            temporary: flags & libc::IFA_F_TEMPORARY != 0,
+            dynamic: flags & libc::IFA_F_PERMANENT == 0,
+            mngtmpaddr: flags & libc::IFA_F_MANAGETEMPADDR != 0,
+            noprefixroute: flags & libc::IFA_F_NOPREFIXROUTE != 0,
+            lifetime: Lifetime {
+                deprecated: lifetime.deprecated || flags & libc::IFA_F_DEPRECATED != 0,
+                ..lifetime
+            },
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv6Addr;
+
+    use super::{align, parse_address, parse_link, parse_messages, Address, Lifetime};
+
+    fn attribute(r#type: u16, data: &[u8]) -> Vec<u8> {
+        let mut attribute = Vec::new();
+        attribute.extend_from_slice(&((4 + data.len()) as u16).to_ne_bytes());
+        attribute.extend_from_slice(&r#type.to_ne_bytes());
+        attribute.extend_from_slice(data);
+        attribute.resize(align(attribute.len()), 0);
+        attribute
+    }
+
+    fn message(r#type: u16, sequence: u32, payload: &[u8]) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&((16 + payload.len()) as u32).to_ne_bytes());
+        message.extend_from_slice(&r#type.to_ne_bytes());
+        message.extend_from_slice(&[0, 0]);
+        message.extend_from_slice(&sequence.to_ne_bytes());
+        message.extend_from_slice(&[0, 0, 0, 0]);
+        message.extend_from_slice(payload);
+        message.resize(align(message.len()), 0);
+        message
+    }
+
+    #[test]
+    fn test_parse_link() {
+        let mut payload = vec![0; 16];
+        payload[4..8].copy_from_slice(&2i32.to_ne_bytes());
+        payload.extend(attribute(libc::IFLA_IFNAME, b"eth0\0"));
+        payload.extend(attribute(libc::IFLA_OPERSTATE, &[libc::IF_OPER_UP as u8]));
+
+        let (index, interface) = parse_link(&payload).unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(interface.name, "eth0");
+        assert!(interface.up);
+
+        // 缺少网卡名称
+        let mut payload = vec![0; 16];
+        payload.extend(attribute(libc::IFLA_OPERSTATE, &[2]));
+        assert!(parse_link(&payload).is_none());
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let ip = "2001:db8::1".parse::<Ipv6Addr>().unwrap();
+        let mut payload = vec![libc::AF_INET6 as u8, 64, 0, libc::RT_SCOPE_UNIVERSE];
+        payload.extend_from_slice(&2u32.to_ne_bytes());
+        payload.extend(attribute(libc::IFA_ADDRESS, &ip.octets()));
+        payload.extend(attribute(
+            libc::IFA_FLAGS,
+            &(libc::IFA_F_MANAGETEMPADDR | libc::IFA_F_NOPREFIXROUTE).to_ne_bytes(),
+        ));
+
+        assert_eq!(
+            parse_address(&payload),
+            Some((
+                2,
+                Address {
+                    local: ip,
+                    global: true,
+                    temporary: false,
+                    dynamic: true,
+                    mngtmpaddr: true,
+                    noprefixroute: true,
+                    lifetime: Lifetime::FOREVER,
+                }
+            ))
+        );
+
+        // 首选有效期为 0 的已弃用地址
+        let mut cacheinfo = Vec::new();
+        [0u32, 3600, 0, 0]
+            .iter()
+            .for_each(|value| cacheinfo.extend_from_slice(&value.to_ne_bytes()));
+        let mut deprecated = payload.clone();
+        deprecated.extend(attribute(libc::IFA_CACHEINFO, &cacheinfo));
+        let (_, address) = parse_address(&deprecated).unwrap();
+        assert_eq!(
+            address.lifetime,
+            Lifetime {
+                deprecated: true,
+                valid: 3600
+            }
+        );
+
+        // 仅有 8 位标志位的永久链路本地地址
+        let link_local = "fe80::1".parse::<Ipv6Addr>().unwrap();
+        let mut payload = vec![libc::AF_INET6 as u8, 64, libc::IFA_F_PERMANENT as u8, 253];
+        payload.extend_from_slice(&2u32.to_ne_bytes());
+        payload.extend(attribute(libc::IFA_ADDRESS, &link_local.octets()));
+        let (_, address) = parse_address(&payload).unwrap();
+        assert!(!address.global && !address.dynamic);
+
+        // IPv4 地址
+        let mut payload = vec![libc::AF_INET as u8, 24, 0, 0, 2, 0, 0, 0];
+        payload.extend(attribute(libc::IFA_ADDRESS, &[192, 168, 1, 2]));
+        assert!(parse_address(&payload).is_none());
+    }
+
+    #[test]
+    fn test_parse_messages() {
+        let mut buffer = message(libc::RTM_NEWADDR, 7, &[1, 2, 3, 4]);
+        buffer.extend(message(libc::RTM_NEWADDR, 6, &[5]));
+        let mut messages = Vec::new();
+        assert!(!parse_messages(&buffer, 7, &mut messages).unwrap());
+        assert_eq!(messages, [(libc::RTM_NEWADDR, vec![1, 2, 3, 4])]);
+
+        let buffer = message(libc::NLMSG_DONE as u16, 7, &[0, 0, 0, 0]);
+        assert!(parse_messages(&buffer, 7, &mut messages).unwrap());
+
+        let buffer = message(libc::NLMSG_ERROR as u16, 7, &(-libc::EPERM).to_ne_bytes());
+        assert_eq!(
+            parse_messages(&buffer, 7, &mut messages)
+                .unwrap_err()
+                .raw_os_error(),
+            Some(libc::EPERM)
+        );
+
+        let mut buffer = message(libc::RTM_NEWADDR, 7, &[1, 2, 3, 4]);
+        buffer[0] = 64;
+        assert!(parse_messages(&buffer, 7, &mut messages).is_err());
+    }
+}
@@ -1,13 +1,213 @@
-#[cfg(any(target_os = "linux", target_os = "windows"))]
+pub mod cached;
+pub mod cf_trace;
+pub mod chain;
+pub mod command;
+pub mod consensus;
+pub mod dns;
+pub mod doh_whoami;
+pub mod fritzbox;
+pub mod http_json;
+pub mod http_regex;
+pub mod icanhazip;
+pub mod ipify;
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 pub mod local_ipv6;
+pub mod prefix_compose;
+pub mod routeros;
 pub mod standalone;
+pub mod stun;
+pub mod ubus;
+#[cfg(target_os = "linux")]
+pub mod wan_ipv4;
 
-use std::{borrow::Cow, fmt::Debug, net::IpAddr};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
 use async_trait::async_trait;
+use tokio::net::UdpSocket;
 
 use super::error::Error;
 
+/// 公共 IP 查询服务使用的地址族
+#[derive(serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    /// 查询 IPv4 地址
+    #[default]
+    V4,
+    /// 查询 IPv6 地址
+    V6,
+}
+
+impl IpFamily {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpFamily::V4 => "v4",
+            IpFamily::V6 => "v6",
+        }
+    }
+
+    /// IP 地址是否属于该地址族
+    pub fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpFamily::V4 => ip.is_ipv4(),
+            IpFamily::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+/// 未手动绑定本地地址时绑定地址族 `family` 的任意地址，使 HTTP 请求只经由该地址族发出
+///
+/// 绑定任意地址后连接仅尝试该地址族的目标地址，基于 HTTP 的来源指定地址族时均应使用。
+pub fn bind_family(bind_address: Option<IpAddr>, family: Option<IpFamily>) -> Option<IpAddr> {
+    match (bind_address, family) {
+        (None, Some(IpFamily::V4)) => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+        (None, Some(IpFamily::V6)) => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        (bind_address, _) => bind_address,
+    }
+}
+
+/// 检查基于 HTTP 的来源返回的地址是否属于地址族 `family`，`target` 为所访问的服务
+///
+/// 双栈网络下请求可能经由另一地址族发出，此时返回的地址不可使用。
+fn check_family(
+    name: &'static str,
+    target: impl std::fmt::Display,
+    family: Option<IpFamily>,
+    ip: IpAddr,
+) -> Result<IpAddr, Error> {
+    match family {
+        Some(family) if !family.matches(&ip) => Err(Error::ip_source(
+            name,
+            format!(
+                "{} 返回的 IP 地址 {} 不属于地址族 {}",
+                target,
+                ip,
+                family.as_str()
+            ),
+        )),
+        _ => Ok(ip),
+    }
+}
+
+/// 解析 IP 地址来源返回的文本
+///
+/// 去除首尾空白及 IPv6 地址末尾的 `%zone` 区域标识后解析，`allow_link_local` 为 `false` 时拒绝链路本地地址。
+pub fn parse_ip(text: &str, allow_link_local: bool) -> Option<IpAddr> {
+    let text = text.trim();
+    let text = match text.split_once('%') {
+        Some((address, _)) if address.contains(':') => address,
+        _ => text,
+    };
+
+    let ip = text.parse::<IpAddr>().ok()?;
+    if !allow_link_local && is_link_local(&ip) {
+        None
+    } else {
+        Some(ip)
+    }
+}
+
+/// 是否为链路本地地址，即 `169.254.0.0/16` 或 `fe80::/10`
+fn is_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => ip.segments()[0] & 0xffc0 == 0xfe80,
+    }
+}
+
+/// 不可公开发布的地址类别，可公开发布时返回 `None`
+///
+/// 包括未指定地址（`0.0.0.0`、`::`）、环回地址、链路本地地址、私有地址（RFC 1918）、
+/// 运营商级 NAT 地址（`100.64.0.0/10`）及唯一本地地址（`fc00::/7`）。
+/// IPv4 映射的 IPv6 地址（如监听 `[::]` 的回显服务器返回的 `::ffff:10.0.0.1`）按对应的 IPv4 地址判断。
+pub fn non_public_kind(ip: &IpAddr) -> Option<&'static str> {
+    let ip = &ip.to_canonical();
+    match ip {
+        _ if ip.is_unspecified() => Some("未指定地址"),
+        _ if ip.is_loopback() => Some("环回地址"),
+        _ if is_link_local(ip) => Some("链路本地地址"),
+        IpAddr::V4(ip) if ip.is_private() => Some("私有地址"),
+        IpAddr::V4(ip) if wan_ipv4::is_cgnat(ip) => Some("运营商级 NAT 地址"),
+        IpAddr::V6(ip) if ip.segments()[0] & 0xfe00 == 0xfc00 => Some("唯一本地地址"),
+        _ => None,
+    }
+}
+
+/// 为 `host[:port]` 形式的地址补全默认端口
+///
+/// IPv6 地址可带或不带方括号，补全后统一为 `[ip]:port` 形式。
+pub fn with_default_port(address: &str, port: u16) -> String {
+    if address.parse::<SocketAddr>().is_ok() {
+        address.to_string()
+    } else if let Ok(ip) = address.trim_matches(['[', ']']).parse::<IpAddr>() {
+        SocketAddr::new(ip, port).to_string()
+    } else if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, port)
+    }
+}
+
+/// 创建连接至 `remote` 的 UDP 套接字
+///
+/// `remote` 为 `host:port` 形式，解析后使用首个属于 `family` 地址族的地址。
+/// 指定 `bind_address` 时从该地址发出，否则绑定到对应地址族的未指定地址。
+pub async fn connect_udp(
+    remote: &str,
+    family: IpFamily,
+    bind_address: Option<IpAddr>,
+) -> io::Result<UdpSocket> {
+    let remote = tokio::net::lookup_host(remote)
+        .await?
+        .find(|address| family.matches(&address.ip()))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} 没有 {} 地址", remote, family.as_str()),
+            )
+        })?;
+    let local = bind_address.unwrap_or(match family {
+        IpFamily::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpFamily::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    });
+
+    let socket = UdpSocket::bind(SocketAddr::new(local, 0)).await?;
+    socket.connect(remote).await?;
+    Ok(socket)
+}
+
+/// 来源名称及附加信息，如 `Standalone Server(http://127.0.0.1/ip)`
+fn describe(source: &dyn IpSource) -> String {
+    match source.info() {
+        Some(info) => format!("{}({})", source.name(), info),
+        None => source.name().to_string(),
+    }
+}
+
+/// HTTP 请求失败时的错误，请求超时时注明来源名称及超时时间
+fn request_error(name: &str, timeout: Duration, target: String, err: reqwest::Error) -> Error {
+    if err.is_timeout() {
+        Error::network(
+            format!(
+                "IP 地址来源 {} {} 超时（{} 秒）",
+                name,
+                target,
+                timeout.as_secs()
+            ),
+            err,
+        )
+    } else {
+        Error::network(format!("{} 失败：{}", target, err), err)
+    }
+}
+
 /// IP 地址来源
 #[async_trait]
 pub trait IpSource: Debug + Send + Sync {
@@ -18,3 +218,117 @@ pub trait IpSource: Debug + Send + Sync {
     /// 获取当前运行机器所处于的 IPv4 地址
     async fn ip(&self) -> Result<IpAddr, Error>;
 }
+
+/// 共享的 IP 地址来源，多个域名使用同一来源实例
+#[async_trait]
+impl IpSource for Arc<dyn IpSource> {
+    fn name(&self) -> &'static str {
+        self.as_ref().name()
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        self.as_ref().info()
+    }
+
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.as_ref().ip().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::{bind_family, check_family, non_public_kind, parse_ip, IpFamily};
+
+    #[test]
+    fn test_parse_ip() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+
+        assert_eq!(parse_ip(" 1.2.3.4\n", false), Some(ip("1.2.3.4")));
+        assert_eq!(parse_ip("2001:db8::1%eth0", false), Some(ip("2001:db8::1")));
+        assert_eq!(
+            parse_ip("2001:db8::1%12\r\n", false),
+            Some(ip("2001:db8::1"))
+        );
+
+        // 链路本地地址
+        assert_eq!(parse_ip("fe80::1%eth0", false), None);
+        assert_eq!(parse_ip("fe80::1%eth0", true), Some(ip("fe80::1")));
+        assert_eq!(parse_ip("169.254.1.1", false), None);
+        assert_eq!(parse_ip("169.254.1.1", true), Some(ip("169.254.1.1")));
+
+        // IPv4 地址不允许携带区域标识
+        assert_eq!(parse_ip("1.2.3.4%eth0", true), None);
+        assert_eq!(parse_ip("<html>", true), None);
+    }
+
+    #[test]
+    fn test_non_public_kind() {
+        let kind = |text: &str| non_public_kind(&text.parse().unwrap());
+
+        assert_eq!(kind("1.1.1.1"), None);
+        assert_eq!(kind("100.63.255.255"), None);
+        assert_eq!(kind("100.128.0.1"), None);
+        assert_eq!(kind("172.32.0.1"), None);
+        assert_eq!(kind("10.1.2.3"), Some("私有地址"));
+        assert_eq!(kind("172.16.0.1"), Some("私有地址"));
+        assert_eq!(kind("192.168.1.2"), Some("私有地址"));
+        assert_eq!(kind("100.64.0.1"), Some("运营商级 NAT 地址"));
+        assert_eq!(kind("100.127.255.254"), Some("运营商级 NAT 地址"));
+        assert_eq!(kind("127.0.0.1"), Some("环回地址"));
+        assert_eq!(kind("169.254.1.1"), Some("链路本地地址"));
+
+        assert_eq!(kind("2606:4700::1111"), None);
+        assert_eq!(kind("fbff::1"), None);
+        assert_eq!(kind("fe00::1"), None);
+        assert_eq!(kind("::1"), Some("环回地址"));
+        assert_eq!(kind("fe80::1"), Some("链路本地地址"));
+        assert_eq!(kind("fc00::1"), Some("唯一本地地址"));
+        assert_eq!(kind("fd12:3456::1"), Some("唯一本地地址"));
+
+        // 未指定地址
+        assert_eq!(kind("0.0.0.0"), Some("未指定地址"));
+        assert_eq!(kind("::"), Some("未指定地址"));
+
+        // IPv4 映射的 IPv6 地址按对应的 IPv4 地址判断
+        assert_eq!(kind("::ffff:10.0.0.1"), Some("私有地址"));
+        assert_eq!(kind("::ffff:127.0.0.1"), Some("环回地址"));
+        assert_eq!(kind("::ffff:100.64.0.1"), Some("运营商级 NAT 地址"));
+        assert_eq!(kind("::ffff:0.0.0.0"), Some("未指定地址"));
+        assert_eq!(kind("::ffff:1.1.1.1"), None);
+    }
+
+    #[test]
+    fn test_bind_family() {
+        let bound = "192.168.1.2".parse::<IpAddr>().unwrap();
+        assert_eq!(bind_family(None, None), None);
+        assert_eq!(
+            bind_family(None, Some(IpFamily::V4)),
+            Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+        );
+        assert_eq!(
+            bind_family(None, Some(IpFamily::V6)),
+            Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED))
+        );
+        // 手动绑定的本地地址优先
+        assert_eq!(bind_family(Some(bound), Some(IpFamily::V6)), Some(bound));
+    }
+
+    #[test]
+    fn test_check_family() {
+        let ipv4 = "203.0.113.1".parse::<IpAddr>().unwrap();
+        let ipv6 = "2001:db8::1".parse::<IpAddr>().unwrap();
+
+        assert_eq!(check_family("Test", "服务", None, ipv6).unwrap(), ipv6);
+        assert_eq!(
+            check_family("Test", "服务", Some(IpFamily::V4), ipv4).unwrap(),
+            ipv4
+        );
+        let err = check_family("Test", "服务", Some(IpFamily::V4), ipv6)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("2001:db8::1 不属于地址族 v4"), "{}", err);
+        assert!(check_family("Test", "服务", Some(IpFamily::V6), ipv4).is_err());
+    }
+}
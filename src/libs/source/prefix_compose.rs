@@ -0,0 +1,165 @@
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    net::{IpAddr, Ipv6Addr},
+};
+
+use async_trait::async_trait;
+
+use crate::libs::error::Error;
+
+use super::{describe, IpSource};
+
+/// 以内部来源获取的 IPv6 地址前缀拼接固定后缀
+///
+/// 运营商定期更换前缀而主机接口标识固定时，可由一台设备检测前缀，
+/// 为局域网内后缀已知的其他主机更新 AAAA 记录。
+#[derive(Debug)]
+pub struct PrefixCompose {
+    source: Box<dyn IpSource>,
+    /// 前缀长度，单位位
+    prefix_len: u8,
+    /// 后缀，仅低 `128 - prefix_len` 位有效
+    suffix: Ipv6Addr,
+}
+
+impl PrefixCompose {
+    pub fn new(source: Box<dyn IpSource>, prefix_len: u8, suffix: Ipv6Addr) -> Self {
+        Self {
+            source,
+            prefix_len,
+            suffix,
+        }
+    }
+}
+
+/// 前缀长度为 `prefix_len` 时前缀部分的掩码
+fn prefix_mask(prefix_len: u8) -> u128 {
+    match prefix_len {
+        0 => 0,
+        len => u128::MAX << (128 - u32::from(len.min(128))),
+    }
+}
+
+/// 后缀是否仅占用低 `128 - prefix_len` 位
+pub fn suffix_fits(prefix_len: u8, suffix: &Ipv6Addr) -> bool {
+    u128::from(*suffix) & prefix_mask(prefix_len) == 0
+}
+
+/// 取 `prefix` 的高 `prefix_len` 位与 `suffix` 的低 `128 - prefix_len` 位拼接
+fn compose(prefix: &Ipv6Addr, prefix_len: u8, suffix: &Ipv6Addr) -> Ipv6Addr {
+    let mask = prefix_mask(prefix_len);
+    Ipv6Addr::from((u128::from(*prefix) & mask) | (u128::from(*suffix) & !mask))
+}
+
+#[async_trait]
+impl IpSource for PrefixCompose {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        match self.source.ip().await? {
+            IpAddr::V6(prefix) => Ok(IpAddr::V6(compose(&prefix, self.prefix_len, &self.suffix))),
+            IpAddr::V4(ip) => Err(Error::ip_source(
+                self.name(),
+                format!(
+                    "IP 地址来源 {} 返回的 {} 并非 IPv6 地址，无法拼接后缀",
+                    describe(self.source.as_ref()),
+                    ip
+                ),
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Prefix Compose"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!(
+            "{} /{} + {}",
+            describe(self.source.as_ref()),
+            self.prefix_len,
+            self.suffix
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, net::IpAddr};
+
+    use async_trait::async_trait;
+
+    use crate::libs::{error::Error, source::IpSource};
+
+    use super::{compose, suffix_fits, PrefixCompose};
+
+    #[derive(Debug)]
+    struct Fixed(IpAddr);
+
+    #[async_trait]
+    impl IpSource for Fixed {
+        async fn ip(&self) -> Result<IpAddr, Error> {
+            Ok(self.0)
+        }
+
+        fn name(&self) -> &'static str {
+            "Fixed"
+        }
+
+        fn info(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_compose() {
+        let ip = |text: &str| text.parse().unwrap();
+
+        assert_eq!(
+            compose(&ip("2001:db8:1234:5678:aaaa::1"), 64, &ip("::1:2:3:4")),
+            ip("2001:db8:1234:5678:1:2:3:4")
+        );
+        // /56 前缀，后缀占用第 4 组的低 8 位
+        assert_eq!(
+            compose(&ip("2001:db8:1234:56ff::1"), 56, &ip("::12:1:2:3:4")),
+            ip("2001:db8:1234:5612:1:2:3:4")
+        );
+        // 前缀长度不在组边界
+        assert_eq!(
+            compose(&ip("2001:db8:ffff::"), 36, &ip("::1")),
+            ip("2001:db8:f000::1")
+        );
+        assert_eq!(
+            compose(&ip("2001:db8::1"), 128, &ip("::")),
+            ip("2001:db8::1")
+        );
+        assert_eq!(compose(&ip("2001:db8::1"), 0, &ip("::2")), ip("::2"));
+    }
+
+    #[test]
+    fn test_suffix_fits() {
+        let ip = |text: &str| text.parse().unwrap();
+
+        assert!(suffix_fits(64, &ip("::ffff:ffff:ffff:ffff")));
+        assert!(!suffix_fits(64, &ip("::1:0:0:0:0")));
+        assert!(suffix_fits(56, &ip("::ff:0:0:0:1")));
+        assert!(!suffix_fits(56, &ip("::100:0:0:0:1")));
+        assert!(suffix_fits(128, &ip("::")));
+    }
+
+    #[tokio::test]
+    async fn test_prefix_compose() {
+        let source = |ip: &str| {
+            PrefixCompose::new(
+                Box::new(Fixed(ip.parse().unwrap())),
+                64,
+                "::1234".parse().unwrap(),
+            )
+        };
+
+        assert_eq!(
+            source("2001:db8::abcd").ip().await.unwrap(),
+            "2001:db8::1234".parse::<IpAddr>().unwrap()
+        );
+        assert!(source("203.0.113.1").ip().await.is_err());
+    }
+}
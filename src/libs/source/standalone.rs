@@ -1,64 +1,168 @@
-use std::{borrow::Cow, fmt::Debug, net::IpAddr, str::FromStr};
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Write},
+    net::IpAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
-use reqwest::{Client, Url};
+use hmac::{Hmac, Mac};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, Method, Url,
+};
+use sha2::Sha256;
 
-use crate::libs::error::Error;
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+    secret::Secret,
+};
 
-use super::IpSource;
+use super::{check_family, parse_ip, request_error, IpFamily, IpSource};
 
 /// 从 独立服务器获取 IP 地址
+///
+/// 每次请求均附加配置的请求头，如访问认证代理所需的 `Authorization`。
+/// 默认使用 `GET` 请求，也可使用 `POST` 或 `PUT` 请求并附带固定的请求体，响应均为纯文本 IP 地址。
+/// 配置共享密钥后每次请求均附加签名，签名方式见 [`sign`]。
+/// 指定地址族后，响应的 IP 地址不属于该地址族时视为获取失败。
 #[derive(Debug)]
 pub struct Standalone {
     url: Url,
+    method: Method,
+    headers: HeaderMap,
+    body: Option<String>,
+    /// 请求签名使用的共享密钥
+    secret: Option<Secret>,
+    /// 期望的地址族，为 `None` 时接受任意地址族
+    family: Option<IpFamily>,
     client: Client,
+    /// 单次请求的超时时间
+    timeout: Duration,
+    http_debug: Option<HttpDebug>,
 }
 
 impl Standalone {
-    pub fn new(url: Url, bind_address: Option<IpAddr>) -> Result<Self, reqwest::Error> {
-        Ok(Self {
+    /// 创建独立服务器来源，`client` 由 [`ClientRegistry`](crate::libs::http::ClientRegistry) 提供
+    pub fn new(
+        url: Url,
+        method: Method,
+        headers: HeaderMap,
+        body: Option<String>,
+        client: Client,
+        timeout: Duration,
+        http_debug: Option<HttpDebug>,
+    ) -> Self {
+        Self {
             url,
-            client: reqwest::ClientBuilder::new()
-                .local_address(bind_address)
-                .build()?,
-        })
+            method,
+            headers,
+            body,
+            secret: None,
+            family: None,
+            client,
+            timeout,
+            http_debug,
+        }
+    }
+
+    /// 使用共享密钥对每次请求签名
+    pub fn with_secret(mut self, secret: Option<Secret>) -> Self {
+        self.secret = secret;
+        self
+    }
+
+    /// 仅接受地址族 `family` 的 IP 地址
+    pub fn with_family(mut self, family: Option<IpFamily>) -> Self {
+        self.family = family;
+        self
     }
 
-    async fn send<T>(&self) -> Result<T, Error>
-    where
-        T: FromStr,
-    {
-        let text = self
+    async fn send(&self) -> Result<IpAddr, Error> {
+        let mut request = self
             .client
-            .get(self.url.as_ref())
-            .send()
+            .request(self.method.clone(), self.url.as_ref())
+            .headers(self.headers.clone())
+            .timeout(self.timeout);
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+        if let Some(secret) = &self.secret {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default();
+            let mut signature =
+                HeaderValue::from_str(&sign(secret.expose(), timestamp, self.url.path()))
+                    .expect("hex signature is a valid header value");
+            signature.set_sensitive(true);
+            request = request
+                .header(TIMESTAMP, timestamp)
+                .header(SIGNATURE, signature);
+        }
+        let request = request.build()?;
+        let (_, _, body) = http_debug::execute(&self.client, request, self.http_debug.as_ref())
             .await
-            .or_else(|err| {
-                Err(Error::new_string(format!(
-                    "访问独立服务器 {} 失败：{}",
-                    self.url, err
-                )))
-            })?
-            .text()
-            .await
-            .or_else(|err| {
-                Err(Error::new_string(format!(
-                    "解析独立服务器 {} 消息失败：{}",
-                    self.url, err
-                )))
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问独立服务器 {}", self.url),
+                    err,
+                )
             })?;
-
-        let ip_addr = text.parse::<T>().or_else(|_| {
-            Err(Error::new_string(format!(
-                "独立服务器 {} 响应消息并非合法 IP 地址",
-                self.url
-            )))
+        let text = std::str::from_utf8(&body).map_err(|err| {
+            Error::parse(
+                format!("解析独立服务器 {} 消息失败：{}", self.url, err),
+                err,
+            )
         })?;
 
-        Ok(ip_addr)
+        let ip_addr = parse_ip(text, false).ok_or_else(|| {
+            Error::ip_source(
+                self.name(),
+                format!("独立服务器 {} 响应消息并非合法 IP 地址", self.url),
+            )
+        })?;
+        check_family(
+            self.name(),
+            format_args!("独立服务器 {}", self.url),
+            self.family,
+            ip_addr,
+        )
     }
 }
 
+/// 请求时的 Unix 时间戳，单位秒
+const TIMESTAMP: HeaderName = HeaderName::from_static("x-timestamp");
+/// 请求签名
+const SIGNATURE: HeaderName = HeaderName::from_static("x-signature");
+
+/// 计算请求签名
+///
+/// 签名为以共享密钥为密钥，对 `X-Timestamp` 的十进制字符串与 URL 路径（不含查询参数）直接拼接后的内容
+/// 计算的 HMAC-SHA256，以小写十六进制输出。如请求 `http://example.com/ip?v=4` 时时间戳为 `1700000000`，
+/// 则签名内容为 `1700000000/ip`。
+///
+/// 服务端验证时应：
+/// - 使用相同方式计算签名，并以常量时间与 `X-Signature` 比较
+/// - 拒绝 `X-Timestamp` 与当前时间相差过大（如超过 5 分钟）的请求，避免签名被重放
+fn sign(secret: &str, timestamp: u64, path: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(path.as_bytes());
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
 #[async_trait]
 impl IpSource for Standalone {
     async fn ip(&self) -> Result<IpAddr, Error> {
@@ -73,3 +177,155 @@ impl IpSource for Standalone {
         Some(Cow::Owned(self.url.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{header::HeaderMap, Client, Method};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        task::JoinHandle,
+    };
+
+    use crate::libs::{
+        secret::Secret,
+        source::{IpFamily, IpSource},
+    };
+
+    use super::{sign, Standalone};
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("secret", 1700000000, "/ip"),
+            "1abfaef8ac4058ec76a67b31052f67f5a7adbd8cbcd1c1baf0800a9319433a0b"
+        );
+        // 时间戳、路径或密钥不同时签名不同
+        assert_ne!(
+            sign("secret", 1700000001, "/ip"),
+            sign("secret", 1700000000, "/ip")
+        );
+        assert_ne!(
+            sign("secret", 1700000000, "/ip6"),
+            sign("secret", 1700000000, "/ip")
+        );
+        assert_ne!(
+            sign("other", 1700000000, "/ip"),
+            sign("secret", 1700000000, "/ip")
+        );
+    }
+
+    /// 接受一次请求并返回 `ip` 的服务器，返回请求地址及小写的请求内容
+    async fn serve_once(ip: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ip?v=4", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                ip.len(),
+                ip
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap().to_ascii_lowercase()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn test_signed_request() {
+        let (url, server) = serve_once("203.0.113.1").await;
+        let secret = json5::from_str::<Secret>("'secret'").unwrap();
+        let source = Standalone::new(
+            url.parse().unwrap(),
+            Method::GET,
+            HeaderMap::new(),
+            None,
+            Client::new(),
+            Duration::from_secs(5),
+            None,
+        )
+        .with_secret(Some(secret));
+        assert_eq!(source.ip().await.unwrap().to_string(), "203.0.113.1");
+
+        let request = server.await.unwrap();
+        let header = |name: &str| {
+            request
+                .lines()
+                .find_map(|line| line.strip_prefix(name))
+                .map(|value| value.trim().to_string())
+                .unwrap()
+        };
+        let timestamp = header("x-timestamp:").parse().unwrap();
+        // 签名不包含查询参数
+        assert_eq!(header("x-signature:"), sign("secret", timestamp, "/ip"));
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        // 接受连接但从不响应的服务器
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ip", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+
+        let source = Standalone::new(
+            url.parse().unwrap(),
+            Method::GET,
+            HeaderMap::new(),
+            None,
+            Client::new(),
+            Duration::from_secs(1),
+            None,
+        );
+        let err = source.ip().await.unwrap_err().to_string();
+        assert!(err.contains("Standalone Server"), "{}", err);
+        assert!(err.contains("超时（1 秒）"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_family() {
+        let source = |url: &str, family| {
+            Standalone::new(
+                url.parse().unwrap(),
+                Method::GET,
+                HeaderMap::new(),
+                None,
+                Client::new(),
+                Duration::from_secs(5),
+                None,
+            )
+            .with_family(family)
+        };
+
+        let (url, _) = serve_once("203.0.113.1").await;
+        let err = source(&url, Some(IpFamily::V6))
+            .ip()
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("不属于地址族 v6"), "{}", err);
+
+        let (url, _) = serve_once("203.0.113.1").await;
+        assert_eq!(
+            source(&url, Some(IpFamily::V4))
+                .ip()
+                .await
+                .unwrap()
+                .to_string(),
+            "203.0.113.1"
+        );
+    }
+}
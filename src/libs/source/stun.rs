@@ -0,0 +1,401 @@
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    hash::{BuildHasher, Hasher},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::libs::error::Error;
+
+use super::{connect_udp, with_default_port, IpFamily, IpSource};
+
+/// 默认 STUN 服务器
+pub const DEFAULT_STUN_SERVERS: [&str; 2] = ["stun.l.google.com:19302", "stun1.l.google.com:19302"];
+/// 单个 STUN 服务器的最长等待时间，超时后尝试下一个服务器
+const STUN_SERVER_TIMEOUT: Duration = Duration::from_secs(3);
+/// STUN 服务默认端口
+const STUN_PORT: u16 = 3478;
+
+/// Binding 请求
+const BINDING_REQUEST: u16 = 0x0001;
+/// Binding 成功响应
+const BINDING_SUCCESS: u16 = 0x0101;
+/// 固定的 magic cookie
+const MAGIC_COOKIE: u32 = 0x2112_a442;
+/// MAPPED-ADDRESS 属性，仅用于兼容 RFC 3489 服务器
+const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+/// XOR-MAPPED-ADDRESS 属性
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+/// STUN 消息头长度
+const HEADER_LEN: usize = 20;
+/// UDP 响应的最大长度
+const MAX_RESPONSE_LEN: usize = 576;
+
+/// 通过 STUN 协议获取 IP 地址
+///
+/// 从绑定的本地地址向 STUN 服务器发送 Binding 请求，解析响应中的映射地址，
+/// 适用于仅允许 UDP 出站的 NAT 环境。依次尝试配置的服务器，直至其中一个成功响应。
+#[derive(Debug)]
+pub struct Stun {
+    servers: Vec<String>,
+    family: IpFamily,
+    bind_address: Option<IpAddr>,
+    timeout: Duration,
+}
+
+impl Stun {
+    pub fn new(
+        servers: Vec<String>,
+        family: IpFamily,
+        bind_address: Option<IpAddr>,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            servers,
+            family,
+            bind_address,
+            timeout: timeout.min(STUN_SERVER_TIMEOUT),
+        }
+    }
+
+    async fn binding(&self, server: &str) -> Result<IpAddr, String> {
+        let transaction_id = transaction_id();
+        let request = encode_request(&transaction_id);
+
+        let socket = connect_udp(server, self.family, self.bind_address)
+            .await
+            .map_err(|err| format!("连接失败：{}", err))?;
+        socket
+            .send(&request)
+            .await
+            .map_err(|err| format!("发送请求失败：{}", err))?;
+
+        let mut buffer = [0; MAX_RESPONSE_LEN];
+        let len = tokio::time::timeout(self.timeout, socket.recv(&mut buffer))
+            .await
+            .map_err(|_| "响应超时".to_string())?
+            .map_err(|err| format!("接收响应失败：{}", err))?;
+
+        let ip = decode_response(&buffer[..len], &transaction_id)?;
+        if self.family.matches(&ip) {
+            Ok(ip)
+        } else {
+            Err(format!(
+                "返回的 IP 地址 {} 并非 {} 地址",
+                ip,
+                self.family.as_str()
+            ))
+        }
+    }
+
+    async fn query(&self) -> Result<IpAddr, Error> {
+        let mut failures = Vec::with_capacity(self.servers.len());
+        for server in &self.servers {
+            match self.binding(server).await {
+                Ok(ip) => return Ok(ip),
+                Err(message) => failures.push(format!("{} {}", server, message)),
+            }
+        }
+
+        Err(Error::ip_source(
+            self.name(),
+            format!("所有 STUN 服务器均请求失败：{}", failures.join("；")),
+        ))
+    }
+}
+
+#[async_trait]
+impl IpSource for Stun {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.query().await
+    }
+
+    fn name(&self) -> &'static str {
+        "STUN"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(self.servers.join(", ")))
+    }
+}
+
+/// 补全 STUN 服务器地址的端口，未指定端口时使用 3478
+pub fn server_address(server: &str) -> String {
+    with_default_port(server, STUN_PORT)
+}
+
+/// 生成 96 位事务 id，仅用于匹配响应
+fn transaction_id() -> [u8; 12] {
+    let state = std::collections::hash_map::RandomState::new();
+    let mut hasher = state.build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default(),
+    );
+    let high = hasher.finish();
+    hasher.write_u64(high);
+    let low = hasher.finish();
+
+    let mut id = [0; 12];
+    id[..8].copy_from_slice(&high.to_be_bytes());
+    id[8..].copy_from_slice(&low.to_be_bytes()[..4]);
+    id
+}
+
+/// 构造不携带任何属性的 Binding 请求
+fn encode_request(transaction_id: &[u8; 12]) -> [u8; HEADER_LEN] {
+    let mut message = [0; HEADER_LEN];
+    message[0..2].copy_from_slice(&BINDING_REQUEST.to_be_bytes());
+    // 属性长度为 0
+    message[4..8].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    message[8..].copy_from_slice(transaction_id);
+    message
+}
+
+/// 解析 Binding 成功响应，优先使用 XOR-MAPPED-ADDRESS 属性
+fn decode_response(message: &[u8], transaction_id: &[u8; 12]) -> Result<IpAddr, String> {
+    let malformed = || "响应消息格式错误".to_string();
+
+    let header = message.get(..HEADER_LEN).ok_or_else(malformed)?;
+    let r#type = u16::from_be_bytes([header[0], header[1]]);
+    let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+    if header[4..8] != MAGIC_COOKIE.to_be_bytes() {
+        return Err(malformed());
+    }
+    if header[8..] != transaction_id[..] {
+        return Err("响应消息事务 id 不匹配".to_string());
+    }
+    if r#type != BINDING_SUCCESS {
+        return Err(format!("服务器返回非成功响应 {:#06x}", r#type));
+    }
+
+    let attributes = message
+        .get(HEADER_LEN..HEADER_LEN + len)
+        .ok_or_else(malformed)?;
+    let mut mapped = None;
+    let mut offset = 0;
+    while offset < attributes.len() {
+        let header = attributes.get(offset..offset + 4).ok_or_else(malformed)?;
+        let r#type = u16::from_be_bytes([header[0], header[1]]);
+        let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let value = attributes
+            .get(offset + 4..offset + 4 + len)
+            .ok_or_else(malformed)?;
+        // 属性按 4 字节对齐
+        offset += 4 + len.div_ceil(4) * 4;
+
+        match r#type {
+            ATTR_XOR_MAPPED_ADDRESS => {
+                return decode_address(value, Some(transaction_id)).ok_or_else(malformed)
+            }
+            ATTR_MAPPED_ADDRESS => {
+                mapped = Some(decode_address(value, None).ok_or_else(malformed)?)
+            }
+            _ => {}
+        }
+    }
+
+    mapped.ok_or_else(|| "响应中不包含映射地址".to_string())
+}
+
+/// 解析地址属性，`transaction_id` 不为空时按 XOR-MAPPED-ADDRESS 还原地址
+fn decode_address(value: &[u8], transaction_id: Option<&[u8; 12]>) -> Option<IpAddr> {
+    let family = *value.get(1)?;
+    let address = value.get(4..)?;
+
+    let mut mask = [0; 16];
+    if let Some(transaction_id) = transaction_id {
+        mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        mask[4..].copy_from_slice(transaction_id);
+    }
+
+    match (family, address.len()) {
+        (0x01, 4) => {
+            let mut octets = [0; 4];
+            octets
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, octet)| *octet = address[i] ^ mask[i]);
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        (0x02, 16) => {
+            let mut octets = [0; 16];
+            octets
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, octet)| *octet = address[i] ^ mask[i]);
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::IpAddr, time::Duration};
+
+    use crate::libs::source::{IpFamily, IpSource};
+
+    use super::{
+        decode_response, encode_request, server_address, transaction_id, Stun, ATTR_MAPPED_ADDRESS,
+        ATTR_XOR_MAPPED_ADDRESS, MAGIC_COOKIE,
+    };
+
+    const TRANSACTION_ID: [u8; 12] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+
+    /// 构造携带指定属性的 Binding 响应
+    fn response(r#type: u16, attributes: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (attribute, value) in attributes {
+            body.extend_from_slice(&attribute.to_be_bytes());
+            body.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            body.extend_from_slice(value);
+            body.resize(body.len().div_ceil(4) * 4, 0);
+        }
+
+        let mut message = Vec::new();
+        message.extend_from_slice(&r#type.to_be_bytes());
+        message.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        message.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        message.extend_from_slice(&TRANSACTION_ID);
+        message.extend_from_slice(&body);
+        message
+    }
+
+    #[test]
+    fn test_encode_request() {
+        assert_eq!(
+            encode_request(&TRANSACTION_ID),
+            [0, 1, 0, 0, 0x21, 0x12, 0xa4, 0x42, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+        );
+        assert_ne!(transaction_id(), transaction_id());
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let ip = |text: &str| text.parse::<IpAddr>().unwrap();
+
+        // 192.0.2.1:32853 与 magic cookie 异或
+        let xor_v4 = vec![0, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43];
+        // 携带未知属性及奇数长度属性，验证对齐
+        let message = response(
+            0x0101,
+            &[
+                (0x8022, b"test".to_vec()),
+                (0x8028, vec![0xff]),
+                (ATTR_XOR_MAPPED_ADDRESS, xor_v4.clone()),
+            ],
+        );
+        assert_eq!(
+            decode_response(&message, &TRANSACTION_ID),
+            Ok(ip("192.0.2.1"))
+        );
+
+        // 2001:db8:1234:5678:11:2233:4455:6677 与 magic cookie 及事务 id 异或
+        let v6 = ip("2001:db8:1234:5678:11:2233:4455:6677");
+        let IpAddr::V6(v6_octets) = v6 else {
+            unreachable!()
+        };
+        let mut mask = MAGIC_COOKIE.to_be_bytes().to_vec();
+        mask.extend_from_slice(&TRANSACTION_ID);
+        let mut xor_v6 = vec![0, 0x02, 0xa1, 0x47];
+        xor_v6.extend(v6_octets.octets().iter().zip(&mask).map(|(a, b)| a ^ b));
+        let message = response(0x0101, &[(ATTR_XOR_MAPPED_ADDRESS, xor_v6)]);
+        assert_eq!(decode_response(&message, &TRANSACTION_ID), Ok(v6));
+
+        // 仅携带 MAPPED-ADDRESS 的旧版服务器
+        let message = response(
+            0x0101,
+            &[(
+                ATTR_MAPPED_ADDRESS,
+                vec![0, 0x01, 0x80, 0x55, 203, 0, 113, 1],
+            )],
+        );
+        assert_eq!(
+            decode_response(&message, &TRANSACTION_ID),
+            Ok(ip("203.0.113.1"))
+        );
+
+        // 事务 id 不匹配
+        let message = response(0x0101, &[(ATTR_XOR_MAPPED_ADDRESS, xor_v4.clone())]);
+        assert!(decode_response(&message, &[0; 12]).is_err());
+        // 错误响应
+        let message = response(0x0111, &[]);
+        assert!(decode_response(&message, &TRANSACTION_ID).is_err());
+        // 缺少映射地址
+        let message = response(0x0101, &[]);
+        assert!(decode_response(&message, &TRANSACTION_ID).is_err());
+        // 截断的消息
+        let message = response(0x0101, &[(ATTR_XOR_MAPPED_ADDRESS, xor_v4)]);
+        assert!(decode_response(&message[..message.len() - 2], &TRANSACTION_ID).is_err());
+        assert!(decode_response(&message[..10], &TRANSACTION_ID).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        // 本地 STUN 服务器，返回请求方的地址
+        let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buffer = [0; 64];
+            let (len, peer) = server.recv_from(&mut buffer).await.unwrap();
+            assert_eq!(len, 20);
+            let IpAddr::V4(ip) = peer.ip() else {
+                unreachable!()
+            };
+            let mut value = vec![0, 0x01];
+            value.extend_from_slice(&(peer.port() ^ 0x2112).to_be_bytes());
+            value.extend(
+                ip.octets()
+                    .iter()
+                    .zip(MAGIC_COOKIE.to_be_bytes())
+                    .map(|(a, b)| a ^ b),
+            );
+
+            let mut message = vec![0x01, 0x01, 0, 12];
+            message.extend_from_slice(&buffer[4..20]);
+            message.extend_from_slice(&ATTR_XOR_MAPPED_ADDRESS.to_be_bytes());
+            message.extend_from_slice(&8u16.to_be_bytes());
+            message.extend_from_slice(&value);
+            server.send_to(&message, peer).await.unwrap();
+        });
+
+        // 首个服务器无响应，应尝试下一个服务器
+        let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let stun = Stun::new(
+            vec![
+                silent.local_addr().unwrap().to_string(),
+                address.to_string(),
+            ],
+            IpFamily::V4,
+            None,
+            Duration::from_millis(200),
+        );
+        assert_eq!(
+            stun.ip().await.unwrap(),
+            "127.0.0.1".parse::<IpAddr>().unwrap()
+        );
+
+        let stun = Stun::new(
+            vec![silent.local_addr().unwrap().to_string()],
+            IpFamily::V4,
+            None,
+            Duration::from_millis(200),
+        );
+        assert!(stun.ip().await.is_err());
+    }
+
+    #[test]
+    fn test_server_address() {
+        assert_eq!(server_address("stun.example.com"), "stun.example.com:3478");
+        assert_eq!(
+            server_address("stun.l.google.com:19302"),
+            "stun.l.google.com:19302"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::{borrow::Cow, fmt::Debug, net::IpAddr, process::Stdio, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{header, Client, Url};
+use serde_json::{json, Value};
+use tokio::process::Command;
+
+use crate::libs::{
+    error::Error,
+    http_debug::{self, HttpDebug},
+    secret::Secret,
+};
+
+use super::{parse_ip, request_error, IpFamily, IpSource};
+
+/// ubus 的访问方式
+#[derive(Debug)]
+pub enum UbusTransport {
+    /// 在本机执行 `ubus call` 命令
+    Local,
+    /// 通过 ubus JSON-RPC 接口（通常为 `http://<router>/ubus`）远程调用
+    Remote {
+        url: Url,
+        /// rpcd 会话 ID
+        session: Secret,
+        client: Client,
+        http_debug: Option<HttpDebug>,
+    },
+}
+
+/// 通过 OpenWrt ubus 获取网络接口状态中的地址
+///
+/// 调用 `network.interface.<interface>` 的 `status` 方法，按地址族读取 `ipv4-address[0].address`
+/// 或 `ipv6-address[0].address`。接口名称为 OpenWrt 的逻辑接口名称，如 `wan`、`wan6`，而非网卡名称。
+#[derive(Debug)]
+pub struct Ubus {
+    interface: String,
+    family: IpFamily,
+    transport: UbusTransport,
+    /// 单次请求或命令的超时时间
+    timeout: Duration,
+}
+
+impl Ubus {
+    pub fn new(
+        interface: String,
+        family: IpFamily,
+        transport: UbusTransport,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            interface,
+            family,
+            transport,
+            timeout,
+        }
+    }
+
+    /// ubus 对象名称
+    fn object(&self) -> String {
+        format!("network.interface.{}", self.interface)
+    }
+
+    /// 执行 `ubus call network.interface.<interface> status`
+    async fn call_local(&self) -> Result<Vec<u8>, Error> {
+        let child = Command::new("ubus")
+            .args(["call", &self.object(), "status"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| Error::io(format!("执行命令时发生错误：{err}"), err))?;
+
+        let output = match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(err)) => return Err(Error::io(format!("等待命令结束失败：{err}"), err)),
+            Err(_) => {
+                return Err(Error::ip_source(
+                    self.name(),
+                    format!(
+                        "ubus 命令执行超过 {} 秒，已终止",
+                        self.timeout.as_secs_f64()
+                    ),
+                ))
+            }
+        };
+        if !output.status.success() {
+            // 接口不存在时输出 `Command failed: Not found`
+            return Err(Error::ip_source(
+                self.name(),
+                format!(
+                    "ubus 调用 {} 失败：{}",
+                    self.object(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            ));
+        }
+
+        Ok(output.stdout)
+    }
+
+    /// 通过 JSON-RPC 接口调用 `status` 方法
+    async fn call_remote(
+        &self,
+        url: &Url,
+        session: &Secret,
+        client: &Client,
+        http_debug: Option<&HttpDebug>,
+    ) -> Result<Vec<u8>, Error> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "call",
+            "params": [session.expose(), self.object(), "status", {}],
+        });
+        let request = client
+            .post(url.as_ref())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .timeout(self.timeout)
+            .build()?;
+        let (_, _, body) = http_debug::execute(client, request, http_debug)
+            .await
+            .map_err(|err| {
+                request_error(
+                    self.name(),
+                    self.timeout,
+                    format!("访问 ubus 接口 {}", url),
+                    err,
+                )
+            })?;
+
+        Ok(body.to_vec())
+    }
+
+    async fn query(&self) -> Result<IpAddr, Error> {
+        let result = match &self.transport {
+            UbusTransport::Local => {
+                let output = self.call_local().await?;
+                serde_json::from_slice::<Value>(&output)
+                    .map_err(|err| format!("响应消息解析失败：{}", err))
+                    .and_then(|status| parse_status(&status, self.family))
+            }
+            UbusTransport::Remote {
+                url,
+                session,
+                client,
+                http_debug,
+            } => {
+                let body = self
+                    .call_remote(url, session, client, http_debug.as_ref())
+                    .await?;
+                parse_rpc(&body, self.family)
+            }
+        };
+
+        result.map_err(|message| {
+            Error::ip_source(self.name(), format!("接口 {} {}", self.interface, message))
+        })
+    }
+}
+
+/// 从接口状态中读取对应地址族的首个地址
+///
+/// 状态形如：
+///
+/// ```json
+/// {"up":true,"interface":"wan","ipv4-address":[{"address":"203.0.113.5","mask":32}],"ipv6-address":[]}
+/// ```
+fn parse_status(status: &Value, family: IpFamily) -> Result<IpAddr, String> {
+    let key = match family {
+        IpFamily::V4 => "ipv4-address",
+        IpFamily::V6 => "ipv6-address",
+    };
+    if status.get("up").and_then(Value::as_bool) == Some(false) {
+        return Err("未启用".to_string());
+    }
+    let address = status
+        .get(key)
+        .and_then(|addresses| addresses.get(0))
+        .and_then(|address| address.get("address"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("的状态中没有 {} 地址", family.as_str()))?;
+    match parse_ip(address, false) {
+        Some(ip) if family.matches(&ip) => Ok(ip),
+        _ => Err(format!(
+            "的地址 {} 并非合法 {} 地址",
+            address,
+            family.as_str()
+        )),
+    }
+}
+
+/// 解析 JSON-RPC 响应，`result` 首个元素为 ubus 状态码，为 `0` 时第二个元素即接口状态
+///
+/// 响应形如：
+///
+/// ```json
+/// {"jsonrpc":"2.0","id":1,"result":[0,{"up":true,"ipv4-address":[{"address":"203.0.113.5","mask":32}]}]}
+/// ```
+///
+/// 会话过期或无权访问时 `result` 为 `[6]`，会话 ID 无效时返回 `error` 字段。
+fn parse_rpc(body: &[u8], family: IpFamily) -> Result<IpAddr, String> {
+    /// ubus 的权限不足状态码
+    const UBUS_STATUS_PERMISSION_DENIED: i64 = 6;
+
+    let response = serde_json::from_slice::<Value>(body)
+        .map_err(|err| format!("响应消息解析失败：{}", err))?;
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("未知错误");
+        return Err(format!("调用失败：{}，请检查会话 ID 是否有效", message));
+    }
+
+    let result = response.get("result").and_then(Value::as_array);
+    match result
+        .and_then(|result| result.first())
+        .and_then(Value::as_i64)
+    {
+        Some(0) => {}
+        Some(UBUS_STATUS_PERMISSION_DENIED) => {
+            return Err("调用被拒绝，会话已过期或无权访问该接口".to_string())
+        }
+        Some(code) => return Err(format!("调用失败，ubus 状态码 {}", code)),
+        None => return Err("响应消息中缺少 result 字段".to_string()),
+    }
+    let status = result
+        .and_then(|result| result.get(1))
+        .ok_or("响应消息中缺少接口状态")?;
+    parse_status(status, family)
+}
+
+#[async_trait]
+impl IpSource for Ubus {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.query().await
+    }
+
+    fn name(&self) -> &'static str {
+        "ubus"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        let info = match &self.transport {
+            UbusTransport::Local => format!("{} {}", self.interface, self.family.as_str()),
+            UbusTransport::Remote { url, .. } => {
+                format!("{} {} {}", url, self.interface, self.family.as_str())
+            }
+        };
+        Some(Cow::Owned(info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use serde_json::Value;
+
+    use crate::libs::source::IpFamily;
+
+    use super::{parse_rpc, parse_status};
+
+    /// `ubus call network.interface.wan status` 的输出（节选）
+    const STATUS: &str = r#"{
+        "up": true,
+        "pending": false,
+        "available": true,
+        "autostart": true,
+        "uptime": 86400,
+        "l3_device": "pppoe-wan",
+        "proto": "pppoe",
+        "device": "eth1",
+        "ipv4-address": [{ "address": "203.0.113.5", "mask": 32, "ptpaddress": "198.51.100.1" }],
+        "ipv6-address": [{ "address": "2001:db8::5", "mask": 64 }],
+        "ipv6-prefix": [],
+        "route": [],
+        "dns-server": ["198.51.100.53"]
+    }"#;
+
+    #[test]
+    fn test_parse_status() {
+        let status = serde_json::from_str::<Value>(STATUS).unwrap();
+        assert_eq!(
+            parse_status(&status, IpFamily::V4),
+            Ok("203.0.113.5".parse::<IpAddr>().unwrap())
+        );
+        assert_eq!(
+            parse_status(&status, IpFamily::V6),
+            Ok("2001:db8::5".parse::<IpAddr>().unwrap())
+        );
+
+        let down = serde_json::json!({ "up": false, "ipv4-address": [] });
+        assert!(parse_status(&down, IpFamily::V4)
+            .unwrap_err()
+            .contains("未启用"));
+        let empty = serde_json::json!({ "up": true, "ipv4-address": [] });
+        assert!(parse_status(&empty, IpFamily::V4).is_err());
+        let mismatched = serde_json::json!({ "ipv4-address": [{ "address": "2001:db8::1" }] });
+        assert!(parse_status(&mismatched, IpFamily::V4).is_err());
+    }
+
+    #[test]
+    fn test_parse_rpc() {
+        let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":[0,{}]}}"#, STATUS);
+        assert_eq!(
+            parse_rpc(response.as_bytes(), IpFamily::V4),
+            Ok("203.0.113.5".parse::<IpAddr>().unwrap())
+        );
+
+        assert!(
+            parse_rpc(br#"{"jsonrpc":"2.0","id":1,"result":[6]}"#, IpFamily::V4)
+                .unwrap_err()
+                .contains("会话已过期")
+        );
+        assert!(
+            parse_rpc(br#"{"jsonrpc":"2.0","id":1,"result":[4]}"#, IpFamily::V4)
+                .unwrap_err()
+                .contains("状态码 4")
+        );
+        assert!(parse_rpc(
+            br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"Access denied"}}"#,
+            IpFamily::V4
+        )
+        .unwrap_err()
+        .contains("Access denied"));
+        assert!(parse_rpc(b"<html>", IpFamily::V4).is_err());
+    }
+}
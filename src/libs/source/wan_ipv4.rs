@@ -0,0 +1,246 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+    net::{IpAddr, Ipv4Addr},
+    process::Stdio,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use log::warn;
+use tokio::process::Command;
+
+use crate::libs::error::Error;
+
+use super::IpSource;
+
+/// Linux 专用，读取指定网卡（通常为 PPPoE 拨号的 `ppp0`）上的 IPv4 地址
+///
+/// 通过 `ip -4 -j addr show dev <interface>` 命令获取，无需访问外部服务。
+/// 网卡的 `flags` 须包含 `UP` 及 `LOWER_UP`，PPP 网卡的 `operstate` 通常为 `UNKNOWN`，因此不使用该字段。
+/// 将会使用首个 `scope` 为 `global` 的公网地址，私有、回环、链路本地等地址均被跳过。
+///
+/// 运营商级 NAT 地址（`100.64.0.0/10`）默认视为获取失败并输出警告，可通过 `allow_cgnat` 允许。
+#[derive(Debug)]
+pub struct WanIpv4 {
+    interface: String,
+    allow_cgnat: bool,
+    /// 命令最长执行时间
+    timeout: Duration,
+}
+
+/// 读取网卡地址时的错误
+///
+/// PPP 网卡在重新拨号期间会被删除，网卡不存在与网卡未启用需要区分。
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum InterfaceError {
+    /// 网卡不存在
+    NotFound,
+    /// 网卡存在但未启用或链路未连接
+    Down,
+    /// 网卡上没有可用的公网地址
+    NoAddress,
+    /// 网卡上仅有运营商级 NAT 地址
+    Cgnat(Ipv4Addr),
+    /// `ip` 命令输出无法解析
+    Parse(String),
+}
+
+impl Display for InterfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterfaceError::NotFound => f.write_str("网卡不存在"),
+            InterfaceError::Down => f.write_str("网卡未启用或链路未连接"),
+            InterfaceError::NoAddress => f.write_str("网卡上没有可用的公网 IPv4 地址"),
+            InterfaceError::Cgnat(ip) => write!(
+                f,
+                "网卡地址 {} 属于运营商级 NAT 地址段 100.64.0.0/10，如确需使用请指定 allow_cgnat",
+                ip
+            ),
+            InterfaceError::Parse(err) => write!(f, "解析 JSON 时发生错误：{}", err),
+        }
+    }
+}
+
+impl WanIpv4 {
+    pub fn new(interface: String, allow_cgnat: bool, timeout: Duration) -> Self {
+        Self {
+            interface,
+            allow_cgnat,
+            timeout,
+        }
+    }
+
+    async fn execute(&self) -> Result<IpAddr, Error> {
+        let child = Command::new("ip")
+            .args(["-4", "-j", "addr", "show", "dev", &self.interface])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| Error::io(format!("执行命令时发生错误：{err}"), err))?;
+
+        let output = match tokio::time::timeout(self.timeout, child.wait_with_output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(err)) => return Err(Error::io(format!("等待命令结束失败：{err}"), err)),
+            Err(_) => {
+                return Err(Error::ip_source(
+                    self.name(),
+                    format!("ip 命令执行超过 {} 秒，已终止", self.timeout.as_secs_f64()),
+                ))
+            }
+        };
+
+        let result = if output.status.success() {
+            select(&output.stdout, self.allow_cgnat)
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            // 网卡不存在时输出 `Device "ppp0" does not exist.`
+            if stderr.contains("does not exist") {
+                Err(InterfaceError::NotFound)
+            } else {
+                return Err(Error::ip_source(
+                    self.name(),
+                    format!("ip 命令执行失败：{}", stderr.trim()),
+                ));
+            }
+        };
+
+        result.map(IpAddr::V4).map_err(|err| {
+            if let InterfaceError::Cgnat(_) = err {
+                warn!("网卡 {} {}", self.interface, err);
+            }
+            Error::ip_source(self.name(), format!("网卡 {} {}", self.interface, err))
+        })
+    }
+}
+
+/// 是否为运营商级 NAT 地址，即 `100.64.0.0/10`
+pub(super) fn is_cgnat(ip: &Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    first == 100 && second & 0xc0 == 64
+}
+
+/// 是否为公网地址，运营商级 NAT 地址另行处理
+fn is_public(ip: &Ipv4Addr) -> bool {
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast())
+}
+
+/// 解析 `ip -4 -j addr show dev <interface>` 命令的输出并选取地址
+///
+/// 优先使用非运营商级 NAT 的公网地址，仅有运营商级 NAT 地址时按 `allow_cgnat` 决定是否使用。
+fn select(output: &[u8], allow_cgnat: bool) -> Result<Ipv4Addr, InterfaceError> {
+    #[derive(serde::Deserialize)]
+    struct CommandInterface {
+        #[serde(default)]
+        flags: Vec<String>,
+        #[serde(default)]
+        addr_info: Vec<AddrInfo>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct AddrInfo {
+        local: Ipv4Addr,
+        scope: String,
+    }
+
+    let interfaces = serde_json::from_slice::<Vec<CommandInterface>>(output)
+        .map_err(|err| InterfaceError::Parse(err.to_string()))?;
+    let Some(interface) = interfaces.into_iter().next() else {
+        return Err(InterfaceError::NotFound);
+    };
+    let flag = |name: &str| interface.flags.iter().any(|flag| flag == name);
+    if !flag("UP") || !flag("LOWER_UP") {
+        return Err(InterfaceError::Down);
+    }
+
+    let mut cgnat = None;
+    for info in interface.addr_info {
+        if info.scope != "global" || !is_public(&info.local) {
+            continue;
+        }
+        if !is_cgnat(&info.local) {
+            return Ok(info.local);
+        }
+        cgnat.get_or_insert(info.local);
+    }
+    match cgnat {
+        Some(ip) if allow_cgnat => Ok(ip),
+        Some(ip) => Err(InterfaceError::Cgnat(ip)),
+        None => Err(InterfaceError::NoAddress),
+    }
+}
+
+#[async_trait]
+impl IpSource for WanIpv4 {
+    async fn ip(&self) -> Result<IpAddr, Error> {
+        self.execute().await
+    }
+
+    fn name(&self) -> &'static str {
+        "WAN IPv4"
+    }
+
+    fn info(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(format!("指定网卡接口 {}", self.interface)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_cgnat, select, InterfaceError};
+
+    const PPP0: &str = r#"[{"ifindex":7,"ifname":"ppp0","flags":["POINTOPOINT","MULTICAST","NOARP","UP","LOWER_UP"],"mtu":1492,"qdisc":"fq_codel","operstate":"UNKNOWN","group":"default","qlen":3,"addr_info":[{"family":"inet","local":"203.0.113.5","address":"198.51.100.1","prefixlen":32,"scope":"global","dynamic":true,"label":"ppp0","valid_life_time":4294967295,"preferred_life_time":4294967295}]}]"#;
+
+    #[test]
+    fn test_is_cgnat() {
+        assert!(is_cgnat(&"100.64.0.1".parse().unwrap()));
+        assert!(is_cgnat(&"100.127.255.254".parse().unwrap()));
+        assert!(!is_cgnat(&"100.128.0.1".parse().unwrap()));
+        assert!(!is_cgnat(&"100.63.255.255".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(
+            select(PPP0.as_bytes(), false),
+            Ok("203.0.113.5".parse().unwrap())
+        );
+
+        // 链路未连接
+        let down = PPP0.replace(r#","LOWER_UP""#, "");
+        assert_eq!(select(down.as_bytes(), false), Err(InterfaceError::Down));
+        let down = PPP0.replace(r#","UP","LOWER_UP""#, "");
+        assert_eq!(select(down.as_bytes(), false), Err(InterfaceError::Down));
+
+        // 部分版本的 ip 命令在网卡不存在时输出空数组
+        assert_eq!(select(b"[]", false), Err(InterfaceError::NotFound));
+
+        let cgnat = PPP0.replace("203.0.113.5", "100.72.1.2");
+        assert_eq!(
+            select(cgnat.as_bytes(), false),
+            Err(InterfaceError::Cgnat("100.72.1.2".parse().unwrap()))
+        );
+        assert_eq!(
+            select(cgnat.as_bytes(), true),
+            Ok("100.72.1.2".parse().unwrap())
+        );
+
+        let private = PPP0.replace("203.0.113.5", "192.168.1.2");
+        assert_eq!(
+            select(private.as_bytes(), true),
+            Err(InterfaceError::NoAddress)
+        );
+
+        assert!(matches!(
+            select(b"Device", false),
+            Err(InterfaceError::Parse(_))
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, Local};
+use log::{debug, warn};
+
+use super::{atomic_write::write_atomic, cloudflare::CloudflareRecordDetails, history::IpHistory};
+
+/// 默认状态有效期，单位秒
+pub const DEFAULT_STATE_MAX_AGE_SECONDS: u64 = 60 * 60;
+
+/// 单条记录的状态
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RecordState {
+    /// 记录在配置中的标识，配置变化后标识随之变化，已保存的状态不再使用
+    pub key: String,
+    /// 记录所在 zone 的 id
+    pub zone_id: Arc<str>,
+    /// 记录 id
+    pub record_id: Arc<str>,
+    /// 最近一次获取或更新后的记录详情
+    pub details: CloudflareRecordDetails,
+}
+
+/// 单个更新器的状态
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UpdaterState {
+    /// 保存时间
+    pub saved_at: DateTime<Local>,
+    /// 最近一次更新成功的时间
+    #[serde(default)]
+    pub last_success: Option<DateTime<Local>>,
+    /// 最近一次强制更新的时间
+    #[serde(default)]
+    pub last_forced_update: Option<DateTime<Local>>,
+    /// IP 地址变化历史
+    #[serde(default)]
+    pub history: IpHistory,
+    /// 更新器管理的记录，顺序与配置一致
+    pub records: Vec<RecordState>,
+}
+
+/// 更新器状态文件，由所有更新器共享，以更新器昵称区分
+///
+/// 重启后可直接使用未过期的记录详情，无需再次访问 Cloudflare。
+/// 文件不存在或内容损坏时以空状态开始，写入时先写入临时文件再重命名，避免留下不完整的文件。
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+    max_age: Duration,
+    states: Mutex<BTreeMap<String, UpdaterState>>,
+    /// 最近一次序列化的版本号，与 `states` 在同一锁内递增
+    version: Mutex<u64>,
+    /// 已写入文件的版本号，较旧的版本不再覆盖较新的版本
+    written: Arc<Mutex<u64>>,
+}
+
+impl StateFile {
+    /// 读取状态文件，超过 `max_age` 的状态不会被使用
+    pub fn load(path: PathBuf, max_age: std::time::Duration) -> Self {
+        let states = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                warn!(
+                    "状态文件 {} 内容无法解析，将重新获取记录详情：{}",
+                    path.display(),
+                    err
+                );
+                BTreeMap::new()
+            }),
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!(
+                        "读取状态文件 {} 失败，将重新获取记录详情：{}",
+                        path.display(),
+                        err
+                    );
+                }
+                BTreeMap::new()
+            }
+        };
+
+        Self {
+            path,
+            max_age: Duration::from_std(max_age).unwrap_or(Duration::MAX),
+            states: Mutex::new(states),
+            version: Mutex::new(0),
+            written: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// 获取更新器 `nickname` 未过期的状态
+    pub fn fresh(&self, nickname: &str) -> Option<UpdaterState> {
+        let state = self.states.lock().unwrap().get(nickname).cloned()?;
+        let age = Local::now() - state.saved_at;
+        if age < Duration::zero() || age > self.max_age {
+            debug!(
+                "[{}] 状态文件中的记录详情已过期（保存于 {}），将重新获取",
+                nickname, state.saved_at
+            );
+            return None;
+        }
+        Some(state)
+    }
+
+    /// 获取更新器 `nickname` 最近一次强制更新的时间，不受状态有效期限制
+    pub fn last_forced_update(&self, nickname: &str) -> Option<DateTime<Local>> {
+        self.states
+            .lock()
+            .unwrap()
+            .get(nickname)
+            .and_then(|state| state.last_forced_update)
+    }
+
+    /// 获取更新器 `nickname` 的 IP 地址变化历史，不受状态有效期限制
+    pub fn history(&self, nickname: &str) -> Option<IpHistory> {
+        self.states
+            .lock()
+            .unwrap()
+            .get(nickname)
+            .map(|state| state.history.clone())
+    }
+
+    /// 保存更新器 `nickname` 的状态并写入文件，写入失败仅输出警告
+    ///
+    /// 持有锁时仅序列化，写入文件在阻塞线程中进行，不占用运行时的工作线程。
+    pub async fn save(&self, nickname: &str, state: UpdaterState) {
+        let (version, contents) = {
+            let mut states = self.states.lock().unwrap();
+            states.insert(nickname.to_string(), state);
+            let mut version = self.version.lock().unwrap();
+            *version += 1;
+            (*version, serde_json::to_vec_pretty(&*states).unwrap())
+        };
+
+        let path = self.path.clone();
+        let written = self.written.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let mut written = written.lock().unwrap();
+            if *written > version {
+                return Ok(());
+            }
+            write_atomic(&path, contents)?;
+            *written = version;
+            Ok(())
+        })
+        .await
+        .unwrap_or_else(|err| Err(io::Error::other(err)));
+        if let Err(err) = result {
+            warn!(
+                "[{}] 写入状态文件 {} 失败：{}",
+                nickname,
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc, time::Duration};
+
+    use chrono::Local;
+
+    use super::{RecordState, StateFile, UpdaterState};
+    use crate::libs::{
+        history::{IpChange, IpHistory},
+        updater::tests::record,
+    };
+
+    fn state(saved_at: chrono::DateTime<Local>) -> UpdaterState {
+        let mut history = IpHistory::default();
+        history.push(IpChange {
+            time: saved_at - chrono::Duration::days(3),
+            old_ip: "2.2.2.2".parse().unwrap(),
+            new_ip: "1.1.1.1".parse().unwrap(),
+        });
+        UpdaterState {
+            saved_at,
+            last_success: Some(saved_at),
+            last_forced_update: Some(saved_at),
+            history,
+            records: vec![RecordState {
+                key: "zone_id:z|id:1".to_string(),
+                zone_id: Arc::from("z"),
+                record_id: Arc::from("1"),
+                details: record("1.1.1.1"),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_state_file() {
+        let dir = std::env::temp_dir().join(format!("ddns4cf-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        let _ = fs::remove_file(&path);
+
+        // 文件不存在时以空状态开始
+        let file = StateFile::load(path.clone(), Duration::from_secs(3600));
+        assert!(file.fresh("home").is_none());
+
+        let saved = state(Local::now());
+        file.save("home", saved.clone()).await;
+        file.save("old", state(Local::now() - chrono::Duration::hours(2)))
+            .await;
+        // 不应残留临时文件
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // 较旧的版本不覆盖已写入的较新版本
+        let contents = fs::read(&path).unwrap();
+        *file.written.lock().unwrap() = u64::MAX;
+        file.save("stale", state(Local::now())).await;
+        assert_eq!(fs::read(&path).unwrap(), contents);
+
+        // 重新读取后仅使用未过期的状态
+        let file = StateFile::load(path.clone(), Duration::from_secs(3600));
+        assert_eq!(file.fresh("home"), Some(saved));
+        assert!(file.fresh("old").is_none());
+        // 强制更新时间及变化历史不受有效期限制
+        assert!(file.last_forced_update("old").is_some());
+        assert!(file.last_forced_update("missing").is_none());
+        assert_eq!(
+            file.history("old")
+                .unwrap()
+                .last()
+                .map(|change| change.new_ip),
+            Some("1.1.1.1".parse().unwrap())
+        );
+        assert!(file.history("missing").is_none());
+
+        // 旧版本保存的状态不含变化历史
+        fs::write(
+            &path,
+            r#"{"home":{"saved_at":"2024-05-01T08:00:00+08:00","records":[]}}"#,
+        )
+        .unwrap();
+        let file = StateFile::load(path.clone(), Duration::from_secs(3600));
+        assert_eq!(file.history("home"), Some(IpHistory::default()));
+
+        // 内容损坏时以空状态开始
+        fs::write(&path, "{ broken").unwrap();
+        let file = StateFile::load(path.clone(), Duration::from_secs(3600));
+        assert!(file.fresh("home").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+/// 故障事件状态
+///
+/// 状态变化顺序为 `Ok → Failing → Alerted → Recovered`，恢复后再次失败将重新进入 `Failing`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IncidentState {
+    /// 正常
+    #[default]
+    Ok,
+    /// 出现失败，但连续失败次数尚未达到告警阈值
+    Failing,
+    /// 已发送告警，同一次故障内不再重复告警
+    Alerted,
+    /// 告警后已恢复
+    Recovered,
+}
+
+/// 故障事件状态变化时需要发出的通知
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IncidentTransition {
+    /// 连续失败次数达到阈值，发送告警
+    Alert,
+    /// 告警后首次成功，发送恢复通知
+    Recover,
+}
+
+/// 故障事件状态机
+///
+/// 每个通知渠道针对每个域名维护一个独立的状态机，以便使用不同的告警阈值。
+#[derive(Debug, Clone, Default)]
+pub struct Incident {
+    state: IncidentState,
+}
+
+impl Incident {
+    /// 获取当前状态
+    #[cfg(test)]
+    pub fn state(&self) -> IncidentState {
+        self.state
+    }
+
+    /// 记录一次失败，`consecutive_failures` 为包含本次在内的连续失败次数。
+    ///
+    /// 仅在首次达到 `threshold` 时返回 [`IncidentTransition::Alert`]，同一次故障内只告警一次。
+    pub fn on_failure(
+        &mut self,
+        consecutive_failures: u32,
+        threshold: u32,
+    ) -> Option<IncidentTransition> {
+        match self.state {
+            IncidentState::Alerted => None,
+            _ if consecutive_failures >= threshold.max(1) => {
+                self.state = IncidentState::Alerted;
+                Some(IncidentTransition::Alert)
+            }
+            _ => {
+                self.state = IncidentState::Failing;
+                None
+            }
+        }
+    }
+
+    /// 记录一次成功。
+    ///
+    /// 仅在已告警的情况下返回 [`IncidentTransition::Recover`]，未达到告警阈值的失败将被静默忽略。
+    pub fn on_success(&mut self) -> Option<IncidentTransition> {
+        match self.state {
+            IncidentState::Alerted => {
+                self.state = IncidentState::Recovered;
+                Some(IncidentTransition::Recover)
+            }
+            _ => {
+                self.state = IncidentState::Ok;
+                None
+            }
+        }
+    }
+}
+
+/// 更新器运行统计
+#[derive(Debug, Clone, Default)]
+pub struct UpdaterStats {
+    /// 连续失败次数，成功后清零
+    consecutive_failures: u32,
+}
+
+impl UpdaterStats {
+    /// 记录一次成功
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// 记录一次失败，返回包含本次在内的连续失败次数
+    pub fn record_failure(&mut self) -> u32 {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        self.consecutive_failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Incident, IncidentState, IncidentTransition, UpdaterStats};
+
+    /// 按顺序输入成功(`true`)或失败(`false`)，返回每一步产生的通知
+    fn run(sequence: &[bool], threshold: u32) -> Vec<Option<IncidentTransition>> {
+        let mut stats = UpdaterStats::default();
+        let mut incident = Incident::default();
+        sequence
+            .iter()
+            .map(|success| {
+                if *success {
+                    stats.record_success();
+                    incident.on_success()
+                } else {
+                    let failures = stats.record_failure();
+                    incident.on_failure(failures, threshold)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_alert_once_per_incident() {
+        let transitions = run(&[false, false, false, false, false], 3);
+        assert_eq!(
+            transitions,
+            vec![None, None, Some(IncidentTransition::Alert), None, None]
+        );
+    }
+
+    #[test]
+    fn test_flapping_below_threshold_is_silent() {
+        let transitions = run(&[false, false, true, false, false, true, false, true], 3);
+        assert!(transitions.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_recover_after_alert() {
+        let transitions = run(&[false, false, true, true], 2);
+        assert_eq!(
+            transitions,
+            vec![
+                None,
+                Some(IncidentTransition::Alert),
+                Some(IncidentTransition::Recover),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flapping_after_recovery_starts_new_incident() {
+        let transitions = run(&[false, true, false, false, true, false], 1);
+        assert_eq!(
+            transitions,
+            vec![
+                Some(IncidentTransition::Alert),
+                Some(IncidentTransition::Recover),
+                Some(IncidentTransition::Alert),
+                None,
+                Some(IncidentTransition::Recover),
+                Some(IncidentTransition::Alert),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_threshold_behaves_as_one() {
+        assert_eq!(run(&[false], 0), vec![Some(IncidentTransition::Alert)]);
+    }
+
+    #[test]
+    fn test_states() {
+        let mut incident = Incident::default();
+        assert_eq!(incident.state(), IncidentState::Ok);
+        incident.on_failure(1, 2);
+        assert_eq!(incident.state(), IncidentState::Failing);
+        incident.on_failure(2, 2);
+        assert_eq!(incident.state(), IncidentState::Alerted);
+        incident.on_success();
+        assert_eq!(incident.state(), IncidentState::Recovered);
+        incident.on_success();
+        assert_eq!(incident.state(), IncidentState::Ok);
+    }
+}
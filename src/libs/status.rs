@@ -0,0 +1,406 @@
+use std::{net::IpAddr, sync::Arc};
+
+use chrono::{DateTime, Local};
+use tokio::sync::watch;
+
+use super::history::{format_duration, IpChange, IpHistory};
+
+/// 状态报告中的时间格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+fn format_time(time: Option<DateTime<Local>>) -> String {
+    time.map(|time| time.format(TIME_FORMAT).to_string())
+        .unwrap_or("未知".to_string())
+}
+
+/// 更新器状态快照
+///
+/// 状态查询、健康检查等功能均通过该结构获取更新器的运行状态。
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct UpdaterStatus {
+    /// 域名昵称
+    pub nickname: String,
+    /// DNS 记录名称，获取 DNS 记录详情前为空
+    pub record_name: Option<String>,
+    /// DNS 记录类型，获取 DNS 记录详情前为空
+    pub record_type: Option<String>,
+    /// DNS 记录当前的 IP 地址
+    pub content: Option<IpAddr>,
+    /// 最近一次尝试更新的时间
+    pub last_attempt: Option<DateTime<Local>>,
+    /// 最近一次更新成功的时间
+    pub last_success: Option<DateTime<Local>>,
+    /// 最近一次更新失败的原因，更新成功后清空
+    pub last_error: Option<String>,
+    /// 连续失败次数
+    pub consecutive_failures: u32,
+    /// 调度器计划的下次检查时间
+    pub next_check: Option<DateTime<Local>>,
+    /// 是否已暂停自动更新
+    pub paused: bool,
+    /// 是否因系统挂起暂停更新
+    pub suspended: bool,
+    /// 是否因初始化失败、连续失败或异常次数过多已永久停止更新
+    pub failed: bool,
+    /// 检查次数
+    pub checks: u64,
+    /// IP 地址变化次数
+    pub changes: u64,
+    /// 失败次数
+    pub failures: u64,
+    /// IP 地址变化历史
+    pub history: IpHistory,
+}
+
+impl UpdaterStatus {
+    pub fn new(nickname: &str, history_depth: usize) -> Self {
+        Self {
+            nickname: nickname.to_string(),
+            history: IpHistory::new(history_depth),
+            ..Default::default()
+        }
+    }
+
+    /// 获取 DNS 记录详情成功
+    pub fn record_details(&mut self, name: &str, r#type: &str, content: IpAddr) {
+        self.record_name = Some(name.to_string());
+        self.record_type = Some(r#type.to_string());
+        self.content = Some(content);
+    }
+
+    /// 开始一次更新
+    pub fn record_attempt(&mut self, time: DateTime<Local>) {
+        self.last_attempt = Some(time);
+    }
+
+    /// 更新成功
+    pub fn record_success(&mut self, time: DateTime<Local>, content: IpAddr) {
+        self.checks += 1;
+        self.content = Some(content);
+        self.last_success = Some(time);
+        self.last_error = None;
+        self.consecutive_failures = 0;
+    }
+
+    /// IP 地址发生变化，且已成功更新 DNS 记录
+    pub fn record_change(&mut self, time: DateTime<Local>, old_ip: IpAddr, new_ip: IpAddr) {
+        self.changes += 1;
+        self.history.push(IpChange {
+            time,
+            old_ip,
+            new_ip,
+        });
+    }
+
+    /// 运行摘要，用于停止时输出
+    pub fn summary(&self) -> String {
+        format!(
+            "[{}] 记录 {}：检查 {} 次，IP 地址变化 {} 次，失败 {} 次，最终 IP 地址：{}",
+            self.nickname,
+            self.record_name.as_deref().unwrap_or("未知"),
+            self.checks,
+            self.changes,
+            self.failures,
+            self.content
+                .map(|ip| ip.to_string())
+                .unwrap_or("未知".to_string())
+        )
+    }
+
+    /// 当前状态报告，用于按需输出
+    pub fn report(&self) -> String {
+        self.report_at(Local::now())
+    }
+
+    /// `now` 时的状态报告，变化统计以 `now` 为准
+    fn report_at(&self, now: DateTime<Local>) -> String {
+        let result = match (&self.last_error, self.last_attempt) {
+            (Some(err), _) => format!(
+                "更新失败（已连续失败 {} 次）：{}",
+                self.consecutive_failures, err
+            ),
+            (None, Some(_)) => "更新成功".to_string(),
+            (None, None) => "尚未更新".to_string(),
+        };
+        let state = if self.failed {
+            "，已永久停止更新"
+        } else if self.suspended {
+            "，因系统挂起暂停更新"
+        } else if self.paused {
+            "，已暂停自动更新"
+        } else {
+            ""
+        };
+        let statistics = self.history.statistics(now);
+        format!(
+            "[{}] 记录 {}：最近一次{}，当前 IP 地址：{}，最近变化时间：{}，最近 24 小时内变化 {} 次，最近 7 天内变化 {} 次，变化间隔中位数：{}，下次检查时间：{}{}",
+            self.nickname,
+            self.record_name.as_deref().unwrap_or("未知"),
+            result,
+            self.content
+                .map(|ip| ip.to_string())
+                .unwrap_or("未知".to_string()),
+            format_time(self.history.last().map(|change| change.time)),
+            statistics.last_24h,
+            statistics.last_7d,
+            statistics
+                .median_interval
+                .map(format_duration)
+                .unwrap_or("无记录".to_string()),
+            format_time(self.next_check),
+            state
+        )
+    }
+
+    /// 更新失败，`consecutive_failures` 为包含本次在内的连续失败次数
+    pub fn record_failure(&mut self, error: String, consecutive_failures: u32) {
+        self.checks += 1;
+        self.failures += 1;
+        self.last_error = Some(error);
+        self.consecutive_failures = consecutive_failures;
+    }
+}
+
+/// 单个更新器的状态发布端
+///
+/// 由更新器及调度器在固定的时间点写入，通过 [`StatusRegistry`] 供其他功能读取或订阅变化。
+#[derive(Debug, Clone)]
+pub struct StatusHandle(Arc<watch::Sender<UpdaterStatus>>);
+
+impl StatusHandle {
+    pub fn new(nickname: &str, history_depth: usize) -> Self {
+        let (tx, _) = watch::channel(UpdaterStatus::new(nickname, history_depth));
+        Self(Arc::new(tx))
+    }
+
+    /// 修改状态并通知所有订阅者
+    pub fn update<F>(&self, f: F)
+    where
+        F: FnOnce(&mut UpdaterStatus),
+    {
+        self.0.send_modify(f);
+    }
+
+    /// 获取当前状态快照
+    pub fn get(&self) -> UpdaterStatus {
+        self.0.borrow().clone()
+    }
+
+    /// 最近一次 IP 地址变化的时间
+    pub fn last_change(&self) -> Option<DateTime<Local>> {
+        self.0.borrow().history.last().map(|change| change.time)
+    }
+
+    /// 订阅状态变化
+    pub fn subscribe(&self) -> watch::Receiver<UpdaterStatus> {
+        self.0.subscribe()
+    }
+}
+
+/// 所有更新器的状态注册表
+#[derive(Debug, Clone, Default)]
+pub struct StatusRegistry {
+    receivers: Vec<watch::Receiver<UpdaterStatus>>,
+}
+
+impl StatusRegistry {
+    pub fn new<'a, I>(handles: I) -> Self
+    where
+        I: IntoIterator<Item = &'a StatusHandle>,
+    {
+        let mut registry = Self::default();
+        handles
+            .into_iter()
+            .for_each(|handle| registry.register(handle));
+        registry
+    }
+
+    /// 注册更新器状态
+    pub fn register(&mut self, handle: &StatusHandle) {
+        self.receivers.push(handle.subscribe());
+    }
+
+    /// 获取所有更新器的状态快照，顺序与注册顺序一致
+    pub fn snapshot(&self) -> Vec<UpdaterStatus> {
+        self.receivers
+            .iter()
+            .map(|receiver| receiver.borrow().clone())
+            .collect()
+    }
+
+    /// 所有更新器的状态报告，每个更新器一行
+    pub fn report(&self) -> Vec<String> {
+        self.snapshot()
+            .iter()
+            .map(|status| status.report())
+            .collect()
+    }
+
+    /// 通过域名昵称获取状态快照
+    #[cfg(test)]
+    pub fn get(&self, nickname: &str) -> Option<UpdaterStatus> {
+        self.receivers
+            .iter()
+            .map(|receiver| receiver.borrow())
+            .find(|status| status.nickname == nickname)
+            .map(|status| status.clone())
+    }
+
+    /// 订阅所有更新器的状态变化
+    #[cfg(test)]
+    pub fn subscribe(&self) -> Vec<watch::Receiver<UpdaterStatus>> {
+        self.receivers.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{Local, TimeZone};
+
+    use crate::libs::history::DEFAULT_HISTORY_DEPTH;
+
+    use super::{StatusHandle, StatusRegistry, UpdaterStatus};
+
+    #[test]
+    fn test_status_transitions() {
+        let now = Local::now();
+        let mut status = UpdaterStatus::new("home", DEFAULT_HISTORY_DEPTH);
+        status.record_details("home.example.com", "A", "1.1.1.1".parse().unwrap());
+
+        status.record_attempt(now);
+        status.record_failure("timeout".to_string(), 1);
+        status.record_attempt(now);
+        status.record_failure("timeout".to_string(), 2);
+        assert_eq!(status.last_error.as_deref(), Some("timeout"));
+        assert_eq!(status.consecutive_failures, 2);
+        assert_eq!(status.last_success, None);
+
+        status.record_attempt(now);
+        status.record_success(now, "2.2.2.2".parse().unwrap());
+        status.record_change(now, "1.1.1.1".parse().unwrap(), "2.2.2.2".parse().unwrap());
+        assert_eq!(status.checks, 3);
+        assert_eq!(status.failures, 2);
+        assert_eq!(status.changes, 1);
+        assert_eq!(status.content, Some("2.2.2.2".parse().unwrap()));
+        assert_eq!(status.last_error, None);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.last_attempt, Some(now));
+        assert_eq!(status.last_success, Some(now));
+        assert_eq!(status.record_name.as_deref(), Some("home.example.com"));
+        assert_eq!(
+            status.history.last().map(|change| change.new_ip),
+            Some("2.2.2.2".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut status = UpdaterStatus::new("home", DEFAULT_HISTORY_DEPTH);
+        assert_eq!(
+            status.summary(),
+            "[home] 记录 未知：检查 0 次，IP 地址变化 0 次，失败 0 次，最终 IP 地址：未知"
+        );
+
+        status.record_details("home.example.com", "A", "1.1.1.1".parse().unwrap());
+        status.record_failure("timeout".to_string(), 1);
+        status.record_success(Local::now(), "2.2.2.2".parse().unwrap());
+        status.record_change(
+            Local::now(),
+            "1.1.1.1".parse().unwrap(),
+            "2.2.2.2".parse().unwrap(),
+        );
+        assert_eq!(
+            status.summary(),
+            "[home] 记录 home.example.com：检查 2 次，IP 地址变化 1 次，失败 1 次，最终 IP 地址：2.2.2.2"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let home = StatusHandle::new("home", DEFAULT_HISTORY_DEPTH);
+        let office = StatusHandle::new("office", DEFAULT_HISTORY_DEPTH);
+        let registry = StatusRegistry::new([&home, &office]);
+        assert_eq!(
+            registry.report()[0],
+            "[home] 记录 未知：最近一次尚未更新，当前 IP 地址：未知，最近变化时间：未知，最近 24 小时内变化 0 次，最近 7 天内变化 0 次，变化间隔中位数：无记录，下次检查时间：未知"
+        );
+
+        let now = Local.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        home.update(|status| {
+            status.record_details("home.example.com", "A", "1.1.1.1".parse().unwrap());
+            status.record_attempt(now);
+            status.record_success(now, "2.2.2.2".parse().unwrap());
+            status.record_change(
+                now - chrono::Duration::days(2),
+                "3.3.3.3".parse().unwrap(),
+                "1.1.1.1".parse().unwrap(),
+            );
+            status.record_change(now, "1.1.1.1".parse().unwrap(), "2.2.2.2".parse().unwrap());
+            status.next_check = Some(now + chrono::Duration::minutes(15));
+        });
+        office.update(|status| {
+            status.record_attempt(now);
+            status.record_failure("timeout".to_string(), 3);
+            status.failed = true;
+        });
+        assert_eq!(
+            registry
+                .snapshot()
+                .iter()
+                .map(|status| status.report_at(now + chrono::Duration::hours(1)))
+                .collect::<Vec<_>>(),
+            vec![
+                "[home] 记录 home.example.com：最近一次更新成功，当前 IP 地址：2.2.2.2，最近变化时间：2024-05-01 08:00:00，最近 24 小时内变化 1 次，最近 7 天内变化 2 次，变化间隔中位数：2 天 0 小时 0 分 0 秒，下次检查时间：2024-05-01 08:15:00",
+                "[office] 记录 未知：最近一次更新失败（已连续失败 3 次）：timeout，当前 IP 地址：未知，最近变化时间：未知，最近 24 小时内变化 0 次，最近 7 天内变化 0 次，变化间隔中位数：无记录，下次检查时间：未知，已永久停止更新",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_registry_snapshot() {
+        let home = StatusHandle::new("home", DEFAULT_HISTORY_DEPTH);
+        let office = StatusHandle::new("office", DEFAULT_HISTORY_DEPTH);
+        let registry = StatusRegistry::new([&home, &office]);
+
+        home.update(|status| status.record_failure("timeout".to_string(), 1));
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].nickname, "home");
+        assert_eq!(snapshot[0].failures, 1);
+        assert_eq!(
+            snapshot[1],
+            UpdaterStatus::new("office", DEFAULT_HISTORY_DEPTH)
+        );
+
+        assert_eq!(registry.get("home").unwrap().failures, 1);
+        assert!(registry.get("missing").is_none());
+
+        // 快照为独立副本，不受后续修改影响
+        home.update(|status| status.paused = true);
+        assert!(!snapshot[0].paused);
+        assert!(registry.get("home").unwrap().paused);
+    }
+
+    #[tokio::test]
+    async fn test_registry_subscribe() {
+        let home = StatusHandle::new("home", DEFAULT_HISTORY_DEPTH);
+        let registry = StatusRegistry::new([&home]);
+        let mut receivers = registry.subscribe();
+        let mut receiver = receivers.pop().unwrap();
+        receiver.mark_unchanged();
+
+        let writer = home.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            writer.update(|status| status.checks += 1);
+        });
+
+        tokio::time::timeout(Duration::from_secs(1), receiver.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(receiver.borrow().checks, 1);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{any::Any, collections::VecDeque, future::Future, time::Duration};
+
+use log::error;
+use tokio::time::{sleep, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// 默认时间窗口内允许的最大重启次数
+const DEFAULT_MAX_RESTARTS: usize = 5;
+/// 默认统计重启次数的时间窗口
+const DEFAULT_RESTART_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// 默认首次重启前的等待时间，此后每次重启等待时间翻倍
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// 默认重启前的最长等待时间
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 任务重启策略
+///
+/// `window` 时间内重启次数超过 `max_restarts` 时放弃重启。
+#[derive(Debug, Clone, Copy)]
+pub struct RestartPolicy {
+    pub max_restarts: usize,
+    pub window: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            window: DEFAULT_RESTART_WINDOW,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// 第 `restarts` 次重启前的等待时间
+    fn backoff(&self, restarts: usize) -> Duration {
+        let factor = 1u32 << restarts.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// 按照重启策略统计时间窗口内的重启次数
+#[derive(Debug)]
+pub struct RestartWindow {
+    policy: RestartPolicy,
+    recent: VecDeque<Instant>,
+    restarts: usize,
+}
+
+impl RestartWindow {
+    pub fn new(policy: RestartPolicy) -> Self {
+        Self {
+            policy,
+            recent: VecDeque::with_capacity(policy.max_restarts + 1),
+            restarts: 0,
+        }
+    }
+
+    /// 记录一次异常退出，返回重启前的等待时间，时间窗口内重启次数已达上限时返回 `None`
+    pub fn record(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        while self
+            .recent
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= self.policy.window)
+        {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= self.policy.max_restarts {
+            return None;
+        }
+
+        self.recent.push_back(now);
+        self.restarts += 1;
+        Some(self.policy.backoff(self.restarts))
+    }
+
+    /// 时间窗口内的重启次数
+    pub fn recent(&self) -> usize {
+        self.recent.len()
+    }
+
+    /// 累计重启次数
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    pub fn policy(&self) -> &RestartPolicy {
+        &self.policy
+    }
+}
+
+/// 获取 panic 消息
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "未知原因"
+    }
+}
+
+/// 监督任务运行，任务 panic 后按照重启策略重新启动
+///
+/// 每次启动均通过 `task` 创建新的任务。任务正常结束、被取消或收到终止消息后停止监督，
+/// 返回任务累计重启次数。
+pub async fn supervise<F, Fut>(
+    name: &str,
+    policy: RestartPolicy,
+    termination: &CancellationToken,
+    mut task: F,
+) -> usize
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut window = RestartWindow::new(policy);
+    loop {
+        let err = match tokio::spawn(task()).await {
+            Ok(()) => break,
+            Err(err) if err.is_panic() => err.into_panic(),
+            Err(_) => break,
+        };
+
+        let Some(backoff) = window.record() else {
+            error!(
+                "[{}] 任务异常退出：{}。{} 秒内已重启 {} 次，不再重启，请检查程序日志",
+                name,
+                panic_message(err.as_ref()),
+                policy.window.as_secs(),
+                window.recent()
+            );
+            break;
+        };
+        error!(
+            "[{}] 任务异常退出：{}。将在 {} 秒后进行第 {} 次重启",
+            name,
+            panic_message(err.as_ref()),
+            backoff.as_secs(),
+            window.restarts()
+        );
+
+        let abort = tokio::select! {
+            _ = termination.cancelled() => true,
+            _ = sleep(backoff) => false,
+        };
+        if abort {
+            break;
+        }
+    }
+
+    window.restarts()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use super::{supervise, RestartPolicy};
+
+    fn policy() -> RestartPolicy {
+        RestartPolicy {
+            max_restarts: 3,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_millis(1));
+        assert_eq!(policy.backoff(2), Duration::from_millis(2));
+        assert_eq!(policy.backoff(10), Duration::from_millis(4));
+    }
+
+    #[tokio::test]
+    async fn test_restart_after_panic() {
+        let termination = CancellationToken::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        // 前两次运行 panic，第三次正常结束
+        let counter = runs.clone();
+        let restarts = supervise("test", policy(), &termination, move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("boom");
+                }
+            }
+        })
+        .await;
+        assert_eq!(restarts, 2);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_give_up() {
+        let termination = CancellationToken::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let counter = runs.clone();
+        let restarts = supervise("test", policy(), &termination, move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                panic!("boom");
+            }
+        })
+        .await;
+        assert_eq!(restarts, 3);
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{de, Deserialize};
+
+/// 自动 TTL 的默认较低值，单位秒
+pub const DEFAULT_LOW_TTL: usize = 60;
+/// 自动 TTL 的默认较高值，单位秒
+pub const DEFAULT_HIGH_TTL: usize = 3600;
+/// 默认 IP 地址保持不变多久后提高 TTL，单位秒
+pub const DEFAULT_STABLE_AFTER_SECONDS: u64 = 7 * 24 * 60 * 60;
+/// Cloudflare 允许的最低 TTL，单位秒
+pub const MIN_TTL: usize = 60;
+/// Cloudflare 允许的最高 TTL，单位秒
+pub const MAX_TTL: usize = 86400;
+/// 由 Cloudflare 自动决定的 TTL
+pub const AUTO_TTL: usize = 1;
+
+/// Cloudflare 接受的记录 TTL，为 `1`（自动）或介于 [`MIN_TTL`] 与 [`MAX_TTL`] 之间，解析配置时校验
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ttl(usize);
+
+impl Ttl {
+    /// TTL，单位秒
+    pub fn seconds(&self) -> usize {
+        self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Ttl {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let ttl = usize::deserialize(deserializer)?;
+        if ttl == AUTO_TTL || (MIN_TTL..=MAX_TTL).contains(&ttl) {
+            Ok(Self(ttl))
+        } else {
+            Err(de::Error::custom(format!(
+                "TTL 必须为 {}（自动）或介于 {} 与 {} 之间，当前为 {}",
+                AUTO_TTL, MIN_TTL, MAX_TTL, ttl
+            )))
+        }
+    }
+}
+
+/// 根据 IP 地址变化频率自动调整记录 TTL
+///
+/// IP 地址保持不变超过 `stable_after` 后使用较高的 TTL 以提高解析器缓存命中率，
+/// 发生变化后立即改用较低的 TTL，并保持至再次稳定超过 `stable_after`。
+/// 提高 TTL 需要长期稳定，而降低 TTL 需要实际发生变化，两档之间不会反复切换。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoTtl {
+    low: usize,
+    high: usize,
+    stable_after: Duration,
+}
+
+impl AutoTtl {
+    pub fn new(low: usize, high: usize, stable_after: Duration) -> Self {
+        Self {
+            low,
+            high,
+            stable_after,
+        }
+    }
+
+    /// 根据最近一次 IP 地址变化时间选择 TTL
+    pub fn ttl(&self, last_change: DateTime<Local>, now: DateTime<Local>) -> usize {
+        if now - last_change >= self.stable_after {
+            self.high
+        } else {
+            self.low
+        }
+    }
+
+    /// 缺少 IP 地址变化历史（如重启后）时选择 TTL
+    ///
+    /// 记录已使用较高的 TTL 说明此前已稳定，降低 TTL 必须以实际发生变化为前提，因此保持较高的 TTL；
+    /// 否则以 `since`（通常为记录最近一次修改的时间）作为最近一次变化时间。
+    pub fn ttl_without_history(
+        &self,
+        current: usize,
+        since: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> usize {
+        if current >= self.high {
+            self.high
+        } else {
+            self.ttl(since, now)
+        }
+    }
+
+    /// TTL 档位名称，用于输出日志
+    pub fn tier(&self, ttl: usize) -> &'static str {
+        if ttl >= self.high {
+            "稳定"
+        } else {
+            "频繁变化"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local};
+
+    use super::{AutoTtl, Ttl};
+    use crate::libs::history::{IpChange, IpHistory};
+
+    #[test]
+    fn test_auto_ttl() {
+        let auto_ttl = AutoTtl::new(60, 3600, Duration::days(7));
+        let start = Local::now();
+        let mut history = IpHistory::new(10);
+        let ip = |text: &str| text.parse().unwrap();
+
+        // 模拟 IP 地址变化历史，返回 `now` 时选择的 TTL
+        let ttl = |history: &IpHistory, now| {
+            let last_change = history.last().map_or(start, |change| change.time);
+            auto_ttl.ttl(last_change, now)
+        };
+
+        // 启动后尚未稳定足够长的时间
+        assert_eq!(ttl(&history, start + Duration::days(1)), 60);
+        assert_eq!(ttl(&history, start + Duration::days(7)), 3600);
+
+        // 发生变化后立即降低，此后频繁变化期间保持较低
+        for day in [8, 10, 13] {
+            history.push(IpChange {
+                time: start + Duration::days(day),
+                old_ip: ip("1.1.1.1"),
+                new_ip: ip("2.2.2.2"),
+            });
+            assert_eq!(ttl(&history, start + Duration::days(day)), 60);
+            assert_eq!(ttl(&history, start + Duration::days(day + 2)), 60);
+        }
+
+        // 再次稳定 7 天后提高
+        assert_eq!(ttl(&history, start + Duration::days(19)), 60);
+        assert_eq!(ttl(&history, start + Duration::days(20)), 3600);
+        assert_eq!(auto_ttl.tier(3600), "稳定");
+        assert_eq!(auto_ttl.tier(60), "频繁变化");
+    }
+
+    #[test]
+    fn test_ttl_without_history() {
+        let auto_ttl = AutoTtl::new(60, 3600, Duration::days(7));
+        let now = Local::now();
+
+        // 已使用较高的 TTL 时即使最近修改过记录也保持较高
+        assert_eq!(auto_ttl.ttl_without_history(3600, now, now), 3600);
+        assert_eq!(auto_ttl.ttl_without_history(7200, now, now), 3600);
+        // 否则按最近一次修改的时间判断是否稳定
+        assert_eq!(
+            auto_ttl.ttl_without_history(60, now - Duration::days(1), now),
+            60
+        );
+        assert_eq!(
+            auto_ttl.ttl_without_history(60, now - Duration::days(30), now),
+            3600
+        );
+    }
+
+    #[test]
+    fn test_ttl_range() {
+        let ttl = |text: &str| json5::from_str::<Ttl>(text).map(|ttl| ttl.seconds());
+        assert_eq!(ttl("1").unwrap(), 1);
+        assert_eq!(ttl("60").unwrap(), 60);
+        assert_eq!(ttl("86400").unwrap(), 86400);
+        assert!(ttl("0").is_err());
+        assert!(ttl("30").is_err());
+        assert!(ttl("86401").is_err());
+    }
+}
@@ -1,102 +1,350 @@
-use std::{borrow::Cow, fmt::Display, net::IpAddr, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::hash_map::RandomState,
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use bytes::Buf;
-use log::{error, info};
-use reqwest::{header, Client};
+use chrono::{DateTime, Local};
+use log::{debug, error, info, warn};
 use tokio::time::sleep;
 
-use super::{error::Error, source::IpSource};
+use super::{
+    budget::RetryBudget,
+    cloudflare::{CfApi, CloudflareRecordDetails},
+    error::{Error, ErrorKind, Phase},
+    history::format_duration,
+    metrics::Metrics,
+    netns::Netns,
+    notification::NotificationDispatcher,
+    source::{non_public_kind, IpSource},
+    state::{RecordState, StateFile, UpdaterState},
+    stats::UpdaterStats,
+    status::StatusHandle,
+    ttl::AutoTtl,
+};
 
-/// Cloudflare API 响应
-#[derive(serde::Deserialize, Debug)]
-struct CloudflareResponse<T> {
-    success: bool,
-    errors: Option<Vec<CloudflareMessage>>,
-    result: Option<T>,
+/// Cloudflare API 返回服务器错误时的最长重试间隔，单位秒
+const CLOUDFLARE_SERVER_ERROR_RETRY_SECONDS: u64 = 60;
+
+/// 在 `seconds` 的 50% 至 100% 之间随机取值
+fn jitter(seconds: u64) -> u64 {
+    let random = RandomState::new().build_hasher().finish();
+    let half = seconds / 2;
+    half + random % (seconds - half + 1)
 }
 
-/// Cloudflare API 消息
-#[derive(serde::Deserialize, Debug)]
-struct CloudflareMessage {
-    code: u32,
-    message: String,
+/// 初始化失败后的指数退避
+///
+/// 首次重试等待 `base` 秒，此后每次翻倍，最长不超过出现错误时的重试间隔。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// 首次重试的等待时间，单位秒
+    pub base: u64,
+    /// 是否加入随机抖动，避免多个域名同时重试
+    pub jitter: bool,
 }
 
-impl Display for CloudflareMessage {
+impl Backoff {
+    /// 第 `attempt` 次失败后的等待时间，不超过 `cap`
+    fn delay(&self, attempt: u32, cap: u64) -> u64 {
+        let delay = self
+            .base
+            .saturating_mul(1u64 << attempt.saturating_sub(1).min(32))
+            .min(cap)
+            .max(1);
+        if self.jitter {
+            jitter(delay)
+        } else {
+            delay
+        }
+    }
+}
+
+/// 单次更新结果
+#[derive(Debug, Clone, Copy)]
+pub enum UpdateOutcome {
+    /// IP 地址未发生变化
+    Unchanged(IpAddr),
+    /// IP 地址发生变化，且已成功更新 DNS 记录
+    Changed { old: IpAddr, new: IpAddr },
+    /// 模拟运行，记录需要更新但未实际更新
+    DryRun { old: IpAddr, new: IpAddr },
+}
+
+impl Display for UpdateOutcome {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "Cloudflare 响应代码 {}：{}",
-            self.code, self.message
-        ))
+        match self {
+            UpdateOutcome::Unchanged(ip) => {
+                write!(f, "IP 地址未发生变化，当前地址为：{}", ip)
+            }
+            UpdateOutcome::Changed { old, new } => write!(
+                f,
+                "Cloudflare DNS 记录更新成功，IP 地址更新为：{}（更新前为：{}）",
+                new, old
+            ),
+            UpdateOutcome::DryRun { old, new } => write!(
+                f,
+                "[模拟运行] DNS 记录需要更新，但未实际更新，当前地址为：{}（获取的地址为：{}）",
+                old, new
+            ),
+        }
     }
 }
 
-/// Cloudflare API 域名详情
-#[derive(serde::Deserialize, Debug)]
-struct CloudflareRecordDetails {
-    r#type: String,
-    name: String,
-    content: IpAddr,
-    ttl: usize,
-    proxied: bool,
+/// 更新器管理的单条 DNS 记录
+#[derive(Debug)]
+struct RecordSlot {
+    cf_api: Box<dyn CfApi>,
+    details: Option<CloudflareRecordDetails>,
 }
 
-/// Cloudflare API 更新域名发送的消息负载
-#[derive(serde::Serialize, Debug)]
-struct CloudflareUpdateDNSBody<'a> {
-    r#type: &'a str,
-    ttl: usize,
-    name: &'a str,
-    content: &'a IpAddr,
-    proxied: bool,
+impl RecordSlot {
+    fn new(cf_api: Box<dyn CfApi>) -> Self {
+        Self {
+            cf_api,
+            details: None,
+        }
+    }
 }
 
 /// Cloudflare 域名更新器，所有更新相关的操作均由该结构负责完成。
+///
+/// 一个更新器可管理同一 IP 地址的多条记录，每次更新仅获取一次 IP 地址，再逐条比较及更新。
 #[derive(Debug)]
 pub struct Updater {
     pub bind_address: Option<IpAddr>,
     pub refresh_interval: u64,
     pub retry_interval: u64,
-    pub nickname: String,
-    pub token: String,
-    pub id: String,
-    pub zone_id: String,
-    cf_http_client: Client,
+    pub nickname: Arc<str>,
+    /// 管理的记录，首条记录的详情用于状态及通知
+    records: Vec<RecordSlot>,
+    /// 定期重新获取记录详情的间隔，为 `None` 时仅在缓存的详情可能有误时重新获取
+    details_refresh: Option<Duration>,
+    /// 最近一次获取记录详情的时间，为 `None` 时下次更新前重新获取
+    details_fetched_at: Option<Instant>,
     ip_source: Box<dyn IpSource>,
-    details: Option<CloudflareRecordDetails>,
+    /// 初始化时获取的 IP 地址，由初始化后的首次更新使用
+    initial_ip: Option<IpAddr>,
+    notifications: Option<Arc<NotificationDispatcher>>,
+    metrics: Option<Arc<Metrics>>,
+    stats: UpdaterStats,
+    status: StatusHandle,
+    /// 获取 IP 地址的超时时间
+    source_timeout: Duration,
+    /// 单次更新的最长时间
+    update_deadline: Duration,
+    /// 当前更新所处阶段
+    phase: Phase,
+    /// 全局重试预算
+    retry_budget: Arc<RetryBudget>,
+    /// 获取 IP 地址失败后的快速重试次数，用尽后才等待重试间隔
+    source_retries: u32,
+    /// 获取 IP 地址快速重试的间隔
+    source_retry_delay: Duration,
+    /// 自动 TTL，未启用时保持记录原有的 TTL
+    auto_ttl: Option<AutoTtl>,
+    /// 固定 TTL，与自动 TTL 不会同时启用
+    ttl: Option<usize>,
+    /// 固定的 Cloudflare 代理状态，未指定时保持记录原有的代理状态
+    proxied: Option<bool>,
+    /// 更新器创建时间，尚无 IP 地址变化记录时视为最近一次变化时间
+    started_at: DateTime<Local>,
+    /// 初始化时是否先校验 IP 地址来源
+    validate_source: bool,
+    /// 记录不存在时是否创建记录
+    create_if_missing: bool,
+    /// API token 无效时是否停止更新
+    stop_on_invalid_token: bool,
+    /// 状态文件，用于重启后恢复记录详情
+    state: Option<Arc<StateFile>>,
+    /// 初始化失败后的指数退避，为 `None` 时固定按重试间隔重试
+    backoff: Option<Backoff>,
+    /// 连续失败次数上限，达到后永久停止更新，为 `None` 时不限制
+    max_consecutive_failures: Option<u32>,
+    /// 是否模拟运行，仅输出将要进行的更新
+    dry_run: bool,
+    /// 强制更新周期，IP 地址未变化时也至多每隔该时长更新一次记录，为 `None` 时不强制更新
+    force_update: Option<Duration>,
+    /// 最近一次强制更新的时间
+    last_forced_update: Option<DateTime<Local>>,
+    /// 是否允许发布私有地址、环回地址等不可公开访问的地址
+    allow_private: bool,
+    /// 执行网络操作的网络命名空间，为 `None` 时使用进程所在的命名空间
+    netns: Option<Arc<Netns>>,
 }
 
 impl Updater {
     /// 创建新更新器
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         bind_address: Option<IpAddr>,
         ip_source: Box<dyn IpSource>,
-        nickname: &str,
-        token: &str,
-        id: &str,
-        zone_id: &str,
+        nickname: Arc<str>,
+        cf_api: Box<dyn CfApi>,
         refresh_interval: u64,
         retry_interval: u64,
-        cf_http_client: Client,
+        notifications: Option<Arc<NotificationDispatcher>>,
+        metrics: Option<Arc<Metrics>>,
+        history_depth: usize,
+        source_timeout: Duration,
+        update_deadline: Duration,
+        retry_budget: Arc<RetryBudget>,
     ) -> Self {
         Self {
             bind_address,
             ip_source,
-            nickname: nickname.to_string(),
-            token: token.to_string(),
-            id: id.to_string(),
-            zone_id: zone_id.to_string(),
+            status: StatusHandle::new(&nickname, history_depth),
+            nickname,
+            records: vec![RecordSlot::new(cf_api)],
+            details_refresh: None,
+            details_fetched_at: None,
             refresh_interval,
             retry_interval,
-            cf_http_client,
-            details: None,
+            initial_ip: None,
+            notifications,
+            metrics,
+            stats: UpdaterStats::default(),
+            source_timeout,
+            update_deadline,
+            phase: Phase::Source,
+            retry_budget,
+            source_retries: 0,
+            source_retry_delay: Duration::ZERO,
+            auto_ttl: None,
+            ttl: None,
+            proxied: None,
+            started_at: Local::now(),
+            validate_source: false,
+            create_if_missing: false,
+            stop_on_invalid_token: false,
+            state: None,
+            backoff: None,
+            max_consecutive_failures: None,
+            dry_run: false,
+            force_update: None,
+            last_forced_update: None,
+            allow_private: false,
+            netns: None,
+        }
+    }
+
+    /// 启用自动 TTL
+    pub fn with_auto_ttl(mut self, auto_ttl: Option<AutoTtl>) -> Self {
+        self.auto_ttl = auto_ttl;
+        self
+    }
+
+    /// 设置固定 TTL，记录的 TTL 与其不一致时即使 IP 地址未变化也更新记录
+    pub fn with_ttl(mut self, ttl: Option<usize>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 设置固定的 Cloudflare 代理状态，记录的代理状态与其不一致时即使 IP 地址未变化也更新记录
+    pub fn with_proxied(mut self, proxied: Option<bool>) -> Self {
+        self.proxied = proxied;
+        self
+    }
+
+    /// 设置获取 IP 地址失败后的快速重试次数及间隔
+    pub fn with_source_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.source_retries = retries;
+        self.source_retry_delay = delay;
+        self
+    }
+
+    /// 设置初始化时是否先校验 IP 地址来源
+    pub fn with_source_validation(mut self, validate_source: bool) -> Self {
+        self.validate_source = validate_source;
+        self
+    }
+
+    /// 设置 API token 无效时是否停止更新，不再重试初始化
+    pub fn with_invalid_token_stop(mut self, stop_on_invalid_token: bool) -> Self {
+        self.stop_on_invalid_token = stop_on_invalid_token;
+        self
+    }
+
+    /// 设置定期重新获取记录详情的间隔，用于发现在 Cloudflare 中对记录的修改
+    pub fn with_details_refresh(mut self, details_refresh: Option<Duration>) -> Self {
+        self.details_refresh = details_refresh;
+        self
+    }
+
+    /// 设置初始化失败后的指数退避
+    pub fn with_backoff(mut self, backoff: Option<Backoff>) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// 设置连续失败次数上限，达到后永久停止更新
+    pub fn with_max_consecutive_failures(mut self, max_consecutive_failures: Option<u32>) -> Self {
+        self.max_consecutive_failures = max_consecutive_failures;
+        self
+    }
+
+    /// 设置是否模拟运行，模拟运行时照常获取及比较，但不更新或创建记录
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// 在指定的网络命名空间中执行网络操作，IP 地址来源及 Cloudflare API 须使用该命名空间专用的客户端
+    pub fn with_netns(mut self, netns: Option<Arc<Netns>>) -> Self {
+        self.netns = netns;
+        self
+    }
+
+    /// 设置强制更新周期，IP 地址未变化时也至多每隔该时长更新一次记录
+    pub fn with_force_update(mut self, force_update: Option<Duration>) -> Self {
+        self.force_update = force_update;
+        self
+    }
+
+    /// 设置是否允许发布私有地址、环回地址等不可公开访问的地址，用于内外网使用不同解析的场景
+    pub fn with_private_addresses(mut self, allow_private: bool) -> Self {
+        self.allow_private = allow_private;
+        self
+    }
+
+    /// 设置状态文件，并恢复最近一次强制更新的时间及 IP 地址变化历史
+    pub fn with_state(mut self, state: Option<Arc<StateFile>>) -> Self {
+        self.last_forced_update = state
+            .as_ref()
+            .and_then(|state| state.last_forced_update(&self.nickname));
+        if let Some(history) = state
+            .as_ref()
+            .and_then(|state| state.history(&self.nickname))
+        {
+            self.status.update(|status| status.history.restore(history));
         }
+        self.state = state;
+        self
+    }
+
+    /// 添加同一 IP 地址的其他记录，与首条记录一同更新
+    pub fn with_records(mut self, cf_apis: Vec<Box<dyn CfApi>>) -> Self {
+        self.records
+            .extend(cf_apis.into_iter().map(RecordSlot::new));
+        self
+    }
+
+    /// 设置记录不存在时是否以获取的 IP 地址创建记录
+    pub fn with_record_creation(mut self, create_if_missing: bool) -> Self {
+        self.create_if_missing = create_if_missing;
+        self
     }
 }
 
 impl Updater {
     /// 初始化
-    pub async fn init(&mut self) {
+    ///
+    /// 出现可重试的错误时将持续重试，出现不可重试的错误时直接返回错误。
+    pub async fn init(&mut self) -> Result<(), Error> {
         if let Some(bind_address) = self.bind_address {
             info!(
                 "[{}] 正在使用手动绑定的本地地址：{}",
@@ -111,142 +359,1557 @@ impl Updater {
             self.ip_source.info().unwrap_or(Cow::Borrowed(""))
         );
 
+        // token 无效时获取记录必然失败，继续重试只会持续消耗 Cloudflare API 请求
+        if let Err(err) = self.records[0].cf_api.verify_token().await {
+            if self.stop_on_invalid_token {
+                error!(
+                    "[{}] API token 无效，将不再重试，请检查账号 token 是否正确、是否已过期或被禁用",
+                    self.nickname
+                );
+                return Err(err);
+            }
+            error!("{}。将继续尝试初始化", err);
+        }
+
+        if self.validate_source {
+            self.validate_source().await;
+        }
+
         info!("[{}] 初始化中...", self.nickname);
-        self.prepare().await;
-        info!("[{}] 初始化完毕", self.nickname);
+        self.prepare().await
+    }
+
+    /// 校验 IP 地址来源，仅尝试一次，失败时输出错误但不中止初始化
+    ///
+    /// 使配置有误的来源（如地址拼写错误、缺少命令）在启动时即可发现。校验获取的地址作为初始化时获取的 IP 地址，不再重复获取。
+    async fn validate_source(&mut self) {
+        let source = match self.ip_source.info() {
+            Some(info) => format!("{} {}", self.ip_source.name(), info),
+            None => self.ip_source.name().to_string(),
+        };
+        match self.source_ip_once().await {
+            Ok(ip) => {
+                info!(
+                    "[{}] IP 地址来源 {} 校验通过，获取到 IP 地址：{}",
+                    self.nickname, source, ip
+                );
+                self.initial_ip = Some(ip);
+            }
+            Err(err) => error!(
+                "[{}] IP 地址来源 {} 校验失败，请检查来源配置：{}",
+                self.nickname, source, err
+            ),
+        }
     }
 
     /// 启动前预处理
     ///
-    /// 将会访问 Cloudflare API 接口获取当前域名的详细信息
-    async fn prepare(&mut self) {
+    /// 先从 IP 地址来源获取 IP 地址，成功后才访问 Cloudflare API 接口获取当前域名的详细信息，
+    /// 避免 IP 地址来源尚不可用时消耗 Cloudflare API 请求。
+    async fn prepare(&mut self) -> Result<(), Error> {
+        if self.restore_state() {
+            return Ok(());
+        }
+
+        let mut attempt = 0;
         loop {
-            match self.retrieve_dns_details().await {
-                Ok(details) => {
-                    self.details = Some(details);
-                    break;
+            if self.initial_ip.is_none() {
+                info!("[{}] 正在获取 IP 地址...", self.nickname);
+                match self.source_ip().await {
+                    Ok(ip) => {
+                        info!("[{}] 成功获取 IP 地址：{}", self.nickname, ip);
+                        self.initial_ip = Some(ip);
+                    }
+                    Err(err) if !err.is_retryable() => {
+                        return Err(err.context(&self.nickname, Phase::Source));
+                    }
+                    Err(err) => {
+                        let err = err.context(&self.nickname, Phase::Source);
+                        attempt += 1;
+                        let delay = self.prepare_delay(&err, attempt);
+                        error!(
+                            "{}。第 {} 次初始化尝试失败，将在 {} 秒后重试",
+                            err, attempt, delay
+                        );
+                        sleep(Duration::from_secs(delay)).await;
+                        self.retry_budget.acquire().await;
+                        continue;
+                    }
                 }
-                Err(err) => {
+            }
+
+            info!("[{}] 正在获取 Cloudflare DNS 记录详情...", self.nickname);
+            // 已获取详情的记录在重试时不再重复获取
+            let mut failure = None;
+            for index in 0..self.records.len() {
+                if self.records[index].details.is_some() {
+                    continue;
+                }
+                match self.fetch_record(index, self.initial_ip).await {
+                    Ok(details) => self.set_details(index, details),
+                    Err(err) => {
+                        failure = Some(err);
+                        break;
+                    }
+                }
+            }
+
+            match failure {
+                None => {
+                    self.details_fetched_at = Some(Instant::now());
+                    self.save_state().await;
+                    return Ok(());
+                }
+                Some(err) if !err.is_retryable() => {
+                    warn!(
+                        "[{}] 该错误通常由配置有误导致，请检查 token、zone_id（或 zone_name）及 id（或 name 及 type）是否正确",
+                        self.nickname
+                    );
+                    return Err(err);
+                }
+                Some(err) => {
+                    attempt += 1;
+                    let delay = self.prepare_delay(&err, attempt);
                     error!(
-                        "[{}] {}。将在 {} 秒后重试",
-                        self.nickname, err, self.retry_interval
+                        "{}。第 {} 次初始化尝试失败，将在 {} 秒后重试",
+                        err, attempt, delay
                     );
-                    sleep(Duration::from_secs(self.retry_interval)).await;
+                    sleep(Duration::from_secs(delay)).await;
+                    self.retry_budget.acquire().await;
                 }
             };
         }
     }
 
-    /// 触发更新
+    /// 从状态文件恢复记录详情，状态不存在、已过期或与配置不符时返回 `false`
+    fn restore_state(&mut self) -> bool {
+        let Some(state) = self
+            .state
+            .as_ref()
+            .and_then(|state| state.fresh(&self.nickname))
+        else {
+            return false;
+        };
+        let matches = state.records.len() == self.records.len()
+            && state
+                .records
+                .iter()
+                .zip(&self.records)
+                .all(|(record, slot)| record.key == slot.cf_api.state_key());
+        if !matches {
+            debug!(
+                "[{}] 状态文件中的记录与配置不符，将重新获取记录详情",
+                self.nickname
+            );
+            return false;
+        }
+
+        for (index, record) in state.records.into_iter().enumerate() {
+            self.records[index]
+                .cf_api
+                .restore_ids(record.zone_id, record.record_id);
+            self.set_details(index, record.details);
+        }
+        let age = (Local::now() - state.saved_at)
+            .to_std()
+            .unwrap_or(Duration::ZERO);
+        // 以保存时间作为获取时间，定期重新获取的间隔从保存时开始计算
+        self.details_fetched_at = Instant::now().checked_sub(age).or(Some(Instant::now()));
+        self.status
+            .update(|status| status.last_success = state.last_success);
+        info!(
+            "[{}] 已从状态文件恢复记录详情（保存于 {}），跳过获取记录详情",
+            self.nickname,
+            state.saved_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        true
+    }
+
+    /// 将记录详情保存至状态文件，尚未确定 zone id 或记录 id 时不保存
+    async fn save_state(&self) {
+        let Some(state) = &self.state else {
+            return;
+        };
+        let records = self
+            .records
+            .iter()
+            .map(|slot| {
+                let (zone_id, record_id) = slot.cf_api.resolved_ids()?;
+                Some(RecordState {
+                    key: slot.cf_api.state_key(),
+                    zone_id,
+                    record_id,
+                    details: slot.details.clone()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(records) = records {
+            let status = self.status.get();
+            state
+                .save(
+                    &self.nickname,
+                    UpdaterState {
+                        saved_at: Local::now(),
+                        last_success: status.last_success,
+                        last_forced_update: self.last_forced_update,
+                        history: status.history,
+                        records,
+                    },
+                )
+                .await;
+        }
+    }
+
+    /// 获取第 `index` 条记录的详情，记录不存在且启用 create_if_missing 时以 `ip` 创建记录
+    async fn fetch_record(
+        &self,
+        index: usize,
+        ip: Option<IpAddr>,
+    ) -> Result<CloudflareRecordDetails, Error> {
+        let cf_api = &self.records[index].cf_api;
+        let start = Instant::now();
+        let result = cf_api
+            .get_record()
+            .await
+            .map_err(|err| err.context(&self.nickname, Phase::Fetch));
+        // 记录不存在时以获取的 IP 地址创建记录，创建后的记录详情直接用于本次更新
+        let result = match (result, ip) {
+            (Err(err), Some(ip))
+                if self.dry_run && self.create_if_missing && err.is_record_missing() =>
+            {
+                warn!(
+                    "[{}][模拟运行] {}，将以 IP 地址 {} 创建记录（未实际创建）",
+                    self.nickname, err, ip
+                );
+                Err(err)
+            }
+            (Err(err), Some(ip)) if self.create_if_missing && err.is_record_missing() => {
+                info!("[{}] {}，正在创建记录...", self.nickname, err);
+                cf_api
+                    .create_record(ip)
+                    .await
+                    .map_err(|err| err.context(&self.nickname, Phase::Fetch))
+            }
+            (result, _) => result,
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.cloudflare_latency(&self.nickname, start.elapsed());
+        }
+        result
+    }
+
+    /// 保存第 `index` 条记录的详情，首条记录同时更新状态
+    fn set_details(&mut self, index: usize, details: CloudflareRecordDetails) {
+        if index == 0 {
+            self.status.update(|status| {
+                status.record_details(&details.name, &details.r#type, details.content)
+            });
+        }
+        self.records[index].details = Some(details);
+    }
+
+    /// 缓存的记录详情是否需要重新获取
+    fn details_stale(&self) -> bool {
+        match self.details_fetched_at {
+            None => true,
+            Some(fetched_at) => self
+                .details_refresh
+                .is_some_and(|interval| fetched_at.elapsed() >= interval),
+        }
+    }
+
+    /// 重新获取所有记录的详情，记录已在 Cloudflare 中被修改时输出日志
+    ///
+    /// 记录已在 Cloudflare 中被删除且启用 create_if_missing 时以 `ip` 重新创建记录。
+    async fn refresh_details(&mut self, ip: IpAddr) -> Result<(), Error> {
+        debug!(
+            "[{}] 正在重新获取 Cloudflare DNS 记录详情...",
+            self.nickname
+        );
+        for index in 0..self.records.len() {
+            let details = self.fetch_record(index, Some(ip)).await?;
+            let modified = self.records[index].details.as_ref().is_some_and(|old| {
+                old.r#type != details.r#type
+                    || old.content != details.content
+                    || old.ttl != details.ttl
+                    || old.proxied != details.proxied
+            });
+            if modified {
+                warn!(
+                    "[{}] 记录 {} 已在 Cloudflare 中被修改（类型：{}，内容：{}，TTL：{}，代理：{}），将以最新的记录详情比较",
+                    self.nickname,
+                    details.name,
+                    details.r#type,
+                    details.content,
+                    details.ttl,
+                    details.proxied
+                );
+            }
+            self.set_details(index, details);
+        }
+        self.details_fetched_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// 首条记录的详情
+    fn details(&self) -> Option<&CloudflareRecordDetails> {
+        self.records[0].details.as_ref()
+    }
+
+    /// 管理的记录名称，用于通知，多条记录以顿号分隔
+    fn record_names(&self) -> Option<String> {
+        self.records
+            .iter()
+            .map(|record| record.details.as_ref().map(|details| details.name.as_str()))
+            .collect::<Option<Vec<_>>>()
+            .map(|names| names.join("、"))
+    }
+
+    /// 出现错误后距下次重试的时间，单位秒
+    ///
+    /// Cloudflare 服务器错误通常很快恢复，使用较短的重试间隔并加入随机抖动，避免所有客户端同时重试。
+    /// 请求过于频繁时按 Cloudflare 要求的时间等待，未指定时使用重试间隔。
+    pub fn retry_delay(&self, err: &Error) -> u64 {
+        match err.kind() {
+            ErrorKind::RateLimited => err.retry_after().unwrap_or(self.retry_interval),
+            ErrorKind::CloudflareServer => jitter(
+                self.retry_interval
+                    .min(CLOUDFLARE_SERVER_ERROR_RETRY_SECONDS),
+            ),
+            _ => self.retry_interval,
+        }
+    }
+
+    /// 初始化第 `attempt` 次失败后距下次重试的时间，单位秒
+    ///
+    /// 启用指数退避时从较短的间隔开始逐步延长，最长不超过 [`Updater::retry_delay`]；
+    /// Cloudflare 要求等待的时间始终优先。
+    fn prepare_delay(&self, err: &Error, attempt: u32) -> u64 {
+        let delay = self.retry_delay(err);
+        match (&self.backoff, err.retry_after()) {
+            (Some(backoff), None) => backoff.delay(attempt, delay),
+            _ => delay,
+        }
+    }
+
+    /// 连续失败次数是否已达到上限
+    pub fn failure_limit_reached(&self) -> bool {
+        self.max_consecutive_failures
+            .is_some_and(|max| self.status.get().consecutive_failures >= max)
+    }
+
+    /// 获取全局重试预算
+    pub fn retry_budget(&self) -> &Arc<RetryBudget> {
+        &self.retry_budget
+    }
+
+    /// 获取状态发布端
+    pub fn status(&self) -> &StatusHandle {
+        &self.status
+    }
+
+    /// 获取执行网络操作的网络命名空间
+    pub fn netns(&self) -> Option<&Arc<Netns>> {
+        self.netns.as_ref()
+    }
+
+    /// 获取通知分发器
+    pub fn notifications(&self) -> Option<&Arc<NotificationDispatcher>> {
+        self.notifications.as_ref()
+    }
+
+    /// 记录调度器计划的下次检查时间，返回 `delay` 后的本地时间
+    pub fn schedule_next(&self, delay: Duration) -> DateTime<Local> {
+        let next_check =
+            Local::now() + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero());
+        self.status
+            .update(|status| status.next_check = Some(next_check));
+        next_check
+    }
+
+    /// 触发更新，并记录运行统计及发送通知
     pub async fn update(&mut self) -> Result<String, Error> {
-        let Some(old_details) = self.details.as_ref() else {
-            return Err(Error::uninitialized());
+        if let Some(metrics) = &self.metrics {
+            metrics.attempt(&self.nickname);
+        }
+
+        self.status
+            .update(|status| status.record_attempt(Local::now()));
+
+        // 超时后 try_update 将被直接丢弃，由于 DNS 记录详情仅在最后一步替换，超时不会留下不完整的状态
+        let result = match tokio::time::timeout(self.update_deadline, self.try_update()).await {
+            Ok(result) => result,
+            Err(err) => Err(Error::network(
+                format!("更新超时（{} 秒）", self.update_deadline.as_secs()),
+                err,
+            )),
         };
+        // 所有错误均附加域名及所处阶段
+        let result = result.map_err(|err| err.context(&self.nickname, self.phase));
 
-        let new_ip = self.ip_source.ip().await?;
-        if new_ip == old_details.content {
-            Ok(format!("IP 地址未发生变化，当前地址为：{}", new_ip))
-        } else {
-            info!("[{}] 成功获取最新 IP 地址：{}", self.nickname, new_ip);
+        let record = self.record_names();
+        let record = record.as_deref();
+        match &result {
+            Ok(outcome) => {
+                self.stats.record_success();
+                self.record_status(outcome);
+                self.save_state().await;
+                if let Some(metrics) = &self.metrics {
+                    metrics.success(&self.nickname);
+                    if let UpdateOutcome::Changed { .. } = outcome {
+                        metrics.ip_changed(&self.nickname);
+                    }
+                }
+                if let Some(notifications) = &self.notifications {
+                    let (old_ip, new_ip) = match *outcome {
+                        UpdateOutcome::Unchanged(ip) | UpdateOutcome::DryRun { old: ip, .. } => {
+                            (None, ip)
+                        }
+                        UpdateOutcome::Changed { old, new } => (Some(old), new),
+                    };
+                    notifications.on_success(&self.nickname, record, old_ip, new_ip);
+                }
+            }
+            Err(err) => {
+                let consecutive_failures = self.stats.record_failure();
+                self.status
+                    .update(|status| status.record_failure(err.to_string(), consecutive_failures));
+                if let Some(metrics) = &self.metrics {
+                    metrics.failure(&self.nickname);
+                }
+                if let Some(notifications) = &self.notifications {
+                    notifications.on_failure(
+                        &self.nickname,
+                        record,
+                        self.details().map(|details| details.content),
+                        err,
+                        consecutive_failures,
+                    );
+                }
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.cycle_completed();
+        }
 
-            let new_details = self.update_dns_record(&new_ip).await?;
+        result.map(|outcome| outcome.to_string())
+    }
 
-            let msg = format!(
-                "Cloudflare DNS 记录更新成功，IP 地址更新为：{}（更新前为：{}）",
-                new_details.content, old_details.content
+    /// 记录更新成功后的状态，IP 地址发生变化时输出变化统计
+    fn record_status(&self, outcome: &UpdateOutcome) {
+        let now = Local::now();
+        let (old_ip, new_ip) = match *outcome {
+            UpdateOutcome::Unchanged(ip) | UpdateOutcome::DryRun { old: ip, .. } => (None, ip),
+            UpdateOutcome::Changed { old, new } => (Some(old), new),
+        };
+
+        let mut previous_change = None;
+        let mut statistics = None;
+        self.status.update(|status| {
+            status.record_success(now, new_ip);
+            if let Some(old_ip) = old_ip {
+                previous_change = status.history.last().map(|change| change.time);
+                status.record_change(now, old_ip, new_ip);
+                statistics = Some(status.history.statistics(now));
+            }
+        });
+
+        if let Some(statistics) = statistics {
+            info!(
+                "[{}] 距上次 IP 地址变化：{}，最近 24 小时内变化 {} 次，最近 7 天内变化 {} 次，变化间隔中位数：{}",
+                self.nickname,
+                previous_change
+                    .map(|time| format_duration(now - time))
+                    .unwrap_or("无记录".to_string()),
+                statistics.last_24h,
+                statistics.last_7d,
+                statistics
+                    .median_interval
+                    .map(format_duration)
+                    .unwrap_or("无记录".to_string()),
             );
-            self.details.replace(new_details);
-            Ok(msg)
         }
     }
 
-    /// 尝试获取 Cloudflare DNS 记录详情
-    async fn retrieve_dns_details(&self) -> Result<CloudflareRecordDetails, Error> {
-        // 访问 Cloudflare 获取当前 DNS 记录配置
-        let bytes = self
-            .cf_http_client
-            .get(format!(
-                "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-                self.zone_id, self.id
-            ))
-            .header(header::CONTENT_TYPE, "application/json")
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.token))
-            .send()
-            .await
-            .or_else(|err| Err(Error::cloudflare_network_failure(err)))?
-            .bytes()
-            .await
-            .or_else(|err| Err(Error::cloudflare_deserialized_failure(err)))?
-            .reader();
+    /// 从 IP 地址来源获取 IP 地址，失败后按快速重试次数重试，均失败时返回最后一次的错误
+    async fn source_ip(&self) -> Result<IpAddr, Error> {
+        let mut attempts = 1;
+        loop {
+            match self.source_ip_once().await {
+                Ok(ip) => return Ok(ip),
+                Err(err) if attempts <= self.source_retries && err.is_retryable() => {
+                    debug!(
+                        "[{}] 第 {} 次获取 IP 地址失败，{} 秒后重试：{}",
+                        self.nickname,
+                        attempts,
+                        self.source_retry_delay.as_secs(),
+                        err
+                    );
+                    sleep(self.source_retry_delay).await;
+                    attempts += 1;
+                }
+                Err(err) if attempts > 1 => {
+                    return Err(err.with_prefix(&format!("已尝试 {} 次获取 IP 地址：", attempts)))
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// 从 IP 地址来源获取一次 IP 地址，超过超时时间或获取的地址不可公开发布时返回错误
+    async fn source_ip_once(&self) -> Result<IpAddr, Error> {
+        let start = Instant::now();
+        let ip = tokio::time::timeout(self.source_timeout, self.ip_source.ip()).await;
+        if let Some(metrics) = &self.metrics {
+            metrics.source_latency(&self.nickname, start.elapsed());
+        }
+        ip.map_err(|err| {
+            Error::network(
+                format!(
+                    "IP 地址来源 {} 获取 IP 地址超时（{} 秒）",
+                    self.ip_source.name(),
+                    self.source_timeout.as_secs()
+                ),
+                err,
+            )
+        })?
+        .and_then(|ip| self.check_public(ip))
+    }
 
-        let details: CloudflareResponse<CloudflareRecordDetails> = simd_json::from_reader(bytes)
-            .or_else(|err| Err(Error::cloudflare_deserialized_failure(err)))?;
+    /// 拒绝发布私有地址、环回地址等不可公开访问的地址，允许发布时直接返回
+    fn check_public(&self, ip: IpAddr) -> Result<IpAddr, Error> {
+        match non_public_kind(&ip) {
+            Some(kind) if !self.allow_private => Err(Error::ip_source_rejected(
+                self.ip_source.name(),
+                format!(
+                    "获取的 IP 地址 {} 为{}，已拒绝发布。如确需发布该地址，请为域名指定 allow_private",
+                    ip, kind
+                ),
+            )),
+            _ => Ok(ip),
+        }
+    }
 
-        match (details.success, details.result) {
-            (true, Some(details)) => Ok(details),
-            (false, _) | (true, None) => {
-                let message = details.errors.and_then(|errors| {
-                    let message = errors
-                        .into_iter()
-                        .map(|error| error.to_string())
-                        .collect::<Vec<_>>()
-                        .join("；");
-                    Some(Cow::Owned(message))
-                });
-                Err(Error::cloudflare_record_failure(message))
+    /// 获取最新 IP 地址，若发生变化则更新 DNS 记录
+    ///
+    /// 管理多条记录时逐条更新，单条记录失败不影响其余记录，返回首个失败记录的错误。
+    async fn try_update(&mut self) -> Result<UpdateOutcome, Error> {
+        if self.records.iter().any(|record| record.details.is_none()) {
+            return Err(Error::uninitialized());
+        }
+
+        self.phase = Phase::Source;
+        // 初始化后的首次更新直接使用初始化时获取的 IP 地址
+        let new_ip = match self.initial_ip.take() {
+            Some(ip) => ip,
+            None => self.source_ip().await?,
+        };
+
+        // 缓存的记录详情过旧或可能有误时，先重新获取再比较
+        if self.details_stale() {
+            self.phase = Phase::Fetch;
+            self.refresh_details(new_ip).await?;
+        }
+        if self
+            .details()
+            .is_some_and(|details| details.content != new_ip)
+        {
+            info!("[{}] 成功获取最新 IP 地址：{}", self.nickname, new_ip);
+        }
+
+        let force = self.force_update_due(Local::now());
+        if self.records.len() == 1 {
+            return self.update_record(0, new_ip, force).await;
+        }
+
+        let mut outcome: Option<UpdateOutcome> = None;
+        let mut failure = None;
+        for index in 0..self.records.len() {
+            let result = self.update_record(index, new_ip, force).await;
+            let name = self.records[index]
+                .details
+                .as_ref()
+                .map(|details| details.name.clone())
+                .unwrap_or_default();
+            match result {
+                Ok(result) => {
+                    info!("[{}] 记录 {}：{}", self.nickname, name, result);
+                    if !matches!(outcome, Some(UpdateOutcome::Changed { .. })) {
+                        outcome = Some(result);
+                    }
+                }
+                Err(err) => {
+                    error!("[{}] 记录 {} 更新失败：{}", self.nickname, name, err);
+                    if failure.is_none() {
+                        failure = Some((err.with_prefix(&format!("记录 {}：", name)), self.phase));
+                    }
+                }
+            }
+        }
+
+        match (failure, outcome) {
+            (Some((err, phase)), _) => {
+                self.phase = phase;
+                Err(err)
             }
+            (None, Some(outcome)) => Ok(outcome),
+            (None, None) => Err(Error::uninitialized()),
         }
     }
 
-    /// 更新 Cloudflare DNS 记录
-    async fn update_dns_record(&self, new_ip: &IpAddr) -> Result<CloudflareRecordDetails, Error> {
-        let Some(details) = self.details.as_ref() else {
+    /// 是否已到强制更新时间
+    ///
+    /// 周期从最近一次强制更新或 IP 地址变化时开始计算，均无记录时从更新器创建时开始计算。
+    fn force_update_due(&self, now: DateTime<Local>) -> bool {
+        let Some(period) = self
+            .force_update
+            .and_then(|period| chrono::Duration::from_std(period).ok())
+        else {
+            return false;
+        };
+        let since = self
+            .last_forced_update
+            .max(self.status.last_change())
+            .unwrap_or(self.started_at);
+        now - since >= period
+    }
+
+    /// 将第 `index` 条记录更新为 `new_ip`，记录已符合预期且无需强制更新时不访问 Cloudflare
+    async fn update_record(
+        &mut self,
+        index: usize,
+        new_ip: IpAddr,
+        force: bool,
+    ) -> Result<UpdateOutcome, Error> {
+        let Some(old_details) = self.records[index].details.as_ref() else {
             return Err(Error::uninitialized());
         };
+        let old_ip = old_details.content;
+
+        self.phase = Phase::Source;
+        // 地址族与记录类型不符时 Cloudflare 将拒绝更新，提前给出明确的错误
+        if !record_type_matches(&old_details.r#type, &new_ip) {
+            let err = Error::ip_source(
+                self.ip_source.name(),
+                format!(
+                    "获取的 IP 地址 {} 与 DNS 记录类型 {} 不符",
+                    new_ip, old_details.r#type
+                ),
+            );
+            // 记录类型可能已在 Cloudflare 中被修改，下次更新前重新获取记录详情
+            self.details_fetched_at = None;
+            return Err(err);
+        }
 
-        // 访问 Cloudflare 更新当前 DNS 记录配置
-        let body = CloudflareUpdateDNSBody {
-            r#type: &details.r#type,
-            ttl: details.ttl,
-            name: &details.name,
-            content: new_ip,
-            proxied: details.proxied,
+        // IP 地址发生变化时立即降低 TTL，本次变化尚未记录至变化历史；
+        // 缺少变化历史时以记录的修改时间代替，避免重启后将长期稳定的记录误判为频繁变化
+        let ttl = self
+            .auto_ttl
+            .map(|auto_ttl| {
+                let now = Local::now();
+                match self.status.last_change() {
+                    _ if new_ip != old_ip => auto_ttl.ttl(now, now),
+                    Some(last_change) => auto_ttl.ttl(last_change, now),
+                    None => auto_ttl.ttl_without_history(
+                        old_details.ttl,
+                        old_details.modified_on.unwrap_or(self.started_at),
+                        now,
+                    ),
+                }
+            })
+            .or(self.ttl);
+        let old_ttl = old_details.ttl;
+        let ttl_changed = ttl.is_some_and(|ttl| ttl != old_ttl);
+        let old_proxied = old_details.proxied;
+        let proxied_changed = self.proxied.is_some_and(|proxied| proxied != old_proxied);
+
+        // 记录已符合预期时仅在到达强制更新时间后更新
+        let up_to_date = new_ip == old_ip && !ttl_changed && !proxied_changed;
+        if up_to_date && !force {
+            return Ok(UpdateOutcome::Unchanged(new_ip));
+        }
+
+        let mut target = old_details.clone();
+        if let Some(ttl) = ttl {
+            target.ttl = ttl;
+        }
+        if let Some(proxied) = self.proxied {
+            target.proxied = proxied;
+        }
+
+        if self.dry_run {
+            warn!(
+                "[{}][模拟运行] 将更新记录 {}：IP 地址 {} -> {}，TTL {} -> {}，代理状态 {} -> {}（未实际更新）",
+                self.nickname,
+                target.name,
+                old_ip,
+                new_ip,
+                old_ttl,
+                target.ttl,
+                old_proxied,
+                target.proxied
+            );
+            return Ok(UpdateOutcome::DryRun {
+                old: old_ip,
+                new: new_ip,
+            });
+        }
+
+        self.phase = Phase::Update;
+        let start = Instant::now();
+        let new_details = self.records[index]
+            .cf_api
+            .update_record(&target, new_ip)
+            .await;
+        if let Some(metrics) = &self.metrics {
+            metrics.cloudflare_latency(&self.nickname, start.elapsed());
+        }
+        let new_details = match new_details {
+            Ok(new_details) => new_details,
+            Err(err) => {
+                // 记录可能已在 Cloudflare 中被删除，下次更新前重新获取记录详情
+                if err.is_record_missing() {
+                    self.details_fetched_at = None;
+                }
+                return Err(err);
+            }
+        };
+        match (ttl_changed, &self.auto_ttl) {
+            (true, Some(auto_ttl)) => info!(
+                "[{}] 记录 TTL 已由 {} 秒调整为 {} 秒（IP 地址{}）",
+                self.nickname,
+                old_ttl,
+                new_details.ttl,
+                auto_ttl.tier(new_details.ttl)
+            ),
+            (true, None) => info!(
+                "[{}] 记录 TTL 已由 {} 秒恢复为配置的 {} 秒",
+                self.nickname, old_ttl, new_details.ttl
+            ),
+            (false, _) => {}
+        }
+        // 记录已符合预期时本次更新仅因到达强制更新时间而提交
+        if up_to_date {
+            self.last_forced_update = Some(Local::now());
+            info!(
+                "[{}] IP 地址未发生变化，已按强制更新周期重新提交记录 {}",
+                self.nickname, new_details.name
+            );
+        }
+        if proxied_changed {
+            info!(
+                "[{}] 记录的 Cloudflare 代理状态已由 {} 恢复为配置的 {}",
+                self.nickname, old_proxied, new_details.proxied
+            );
+        }
+        let outcome = if new_ip == old_ip {
+            UpdateOutcome::Unchanged(new_ip)
+        } else {
+            UpdateOutcome::Changed {
+                old: old_ip,
+                new: new_details.content,
+            }
         };
 
-        let bytes = self
-            .cf_http_client
-            .put(format!(
-                "https://api.cloudflare.com/client/v4/zones/{}/dns_records/{}",
-                self.zone_id, self.id
-            ))
-            .header(header::CONTENT_TYPE, "application/json")
-            .header(header::AUTHORIZATION, format!("Bearer {}", self.token))
-            // 由于需要序列化，所以此处使用 body
-            .body(simd_json::to_string::<CloudflareUpdateDNSBody>(&body).unwrap())
-            .send()
-            .await
-            .or_else(|err| Err(Error::cloudflare_network_failure(err)))?
-            .bytes()
-            .await
-            .or_else(|err| Err(Error::cloudflare_deserialized_failure(err)))?
-            .reader();
-
-        let details: CloudflareResponse<CloudflareRecordDetails> = simd_json::from_reader(bytes)
-            .or_else(|err| Err(Error::cloudflare_deserialized_failure(err)))?;
-
-        match (details.success, details.result) {
-            (true, Some(details)) => Ok(details),
-            (false, _) | (true, None) => {
-                let message = details.errors.and_then(|errors| {
-                    let message = errors
-                        .into_iter()
-                        .map(|error| error.to_string())
-                        .collect::<Vec<_>>()
-                        .join("；");
-                    Some(Cow::Owned(message))
-                });
-                Err(Error::cloudflare_update_failure(message))
+        // 所有异步操作完成后才替换 DNS 记录详情，此后不再出现 await
+        self.records[index].details.replace(new_details);
+        Ok(outcome)
+    }
+}
+
+/// IP 地址的地址族是否与 DNS 记录类型相符，非 A 及 AAAA 记录不做限制
+fn record_type_matches(record_type: &str, ip: &IpAddr) -> bool {
+    match record_type {
+        "A" => ip.is_ipv4(),
+        "AAAA" => ip.is_ipv6(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        borrow::Cow,
+        collections::VecDeque,
+        net::IpAddr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use reqwest::StatusCode;
+
+    use chrono::Local;
+
+    use super::{Backoff, Updater};
+    use crate::libs::{
+        budget::RetryBudget,
+        cloudflare::{CfApi, CloudflareRecordDetails},
+        error::{Error, ErrorKind, Phase},
+        history::IpChange,
+        source::IpSource,
+        state::StateFile,
+        ttl::AutoTtl,
+    };
+
+    pub(crate) fn record(ip: &str) -> CloudflareRecordDetails {
+        CloudflareRecordDetails {
+            r#type: "A".to_string(),
+            name: "home.example.com".to_string(),
+            content: ip.parse().unwrap(),
+            ttl: 1,
+            proxied: false,
+            modified_on: None,
+        }
+    }
+
+    /// 按顺序返回预设结果的 Cloudflare API
+    #[derive(Debug, Default)]
+    pub(crate) struct MockCfApi {
+        records: Mutex<VecDeque<Result<CloudflareRecordDetails, Error>>>,
+        updates: Mutex<VecDeque<Result<CloudflareRecordDetails, Error>>>,
+        /// 收到的更新请求中的 IP 地址
+        updated: Mutex<Vec<IpAddr>>,
+        /// 收到的更新请求中的 TTL
+        ttls: Mutex<Vec<usize>>,
+        /// 收到的更新请求中的代理状态
+        proxied: Mutex<Vec<bool>>,
+        /// 收到的创建请求中的 IP 地址
+        created: Mutex<Vec<IpAddr>>,
+        /// 验证 token 的结果
+        token: Mutex<Option<Error>>,
+    }
+
+    impl MockCfApi {
+        pub(crate) fn new<R, U>(records: R, updates: U) -> Arc<Self>
+        where
+            R: IntoIterator<Item = Result<CloudflareRecordDetails, Error>>,
+            U: IntoIterator<Item = Result<CloudflareRecordDetails, Error>>,
+        {
+            Arc::new(Self {
+                records: Mutex::new(records.into_iter().collect()),
+                updates: Mutex::new(updates.into_iter().collect()),
+                updated: Mutex::default(),
+                ttls: Mutex::default(),
+                proxied: Mutex::default(),
+                created: Mutex::default(),
+                token: Mutex::default(),
+            })
+        }
+
+        fn updated(&self) -> Vec<IpAddr> {
+            self.updated.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl CfApi for Arc<MockCfApi> {
+        async fn get_record(&self) -> Result<CloudflareRecordDetails, Error> {
+            self.records
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected get_record call")
+        }
+
+        async fn update_record(
+            &self,
+            details: &CloudflareRecordDetails,
+            new_ip: IpAddr,
+        ) -> Result<CloudflareRecordDetails, Error> {
+            self.updated.lock().unwrap().push(new_ip);
+            self.ttls.lock().unwrap().push(details.ttl);
+            self.proxied.lock().unwrap().push(details.proxied);
+            self.updates
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected update_record call")
+        }
+
+        async fn create_record(&self, ip: IpAddr) -> Result<CloudflareRecordDetails, Error> {
+            self.created.lock().unwrap().push(ip);
+            Ok(record(&ip.to_string()))
+        }
+
+        async fn verify_token(&self) -> Result<(), Error> {
+            match self.token.lock().unwrap().clone() {
+                Some(err) => Err(err),
+                None => Ok(()),
             }
         }
+
+        fn state_key(&self) -> String {
+            "zone_id:z|id:1".to_string()
+        }
+
+        fn resolved_ids(&self) -> Option<(Arc<str>, Arc<str>)> {
+            Some((Arc::from("z"), Arc::from("1")))
+        }
+
+        fn restore_ids(&self, _zone_id: Arc<str>, _record_id: Arc<str>) {}
+    }
+
+    /// 按顺序返回预设结果的 IP 地址来源
+    #[derive(Debug)]
+    struct MockSource(Mutex<VecDeque<Result<IpAddr, Error>>>);
+
+    #[async_trait]
+    impl IpSource for MockSource {
+        fn name(&self) -> &'static str {
+            "Mock"
+        }
+
+        fn info(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        async fn ip(&self) -> Result<IpAddr, Error> {
+            self.0
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected ip call")
+        }
+    }
+
+    pub(crate) fn updater<I>(api: &Arc<MockCfApi>, ips: I) -> Updater
+    where
+        I: IntoIterator<Item = Result<IpAddr, Error>>,
+    {
+        Updater::new(
+            None,
+            Box::new(MockSource(Mutex::new(ips.into_iter().collect()))),
+            "home".into(),
+            Box::new(api.clone()),
+            900,
+            300,
+            None,
+            None,
+            10,
+            Duration::from_secs(1),
+            Duration::from_secs(5),
+            Arc::new(RetryBudget::new(60)),
+        )
+    }
+
+    pub(crate) fn ip(ip: &str) -> Result<IpAddr, Error> {
+        Ok(ip.parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let mut dry_run = updater(&api, [ip("1.1.1.1"), ip("2.2.2.2"), ip("2.2.2.2")])
+            .with_ttl(Some(300))
+            .with_dry_run(true);
+        dry_run.init().await.unwrap();
+
+        // 需要更新时仅输出将要进行的更新，不访问 Cloudflare
+        assert!(dry_run.update().await.unwrap().contains("模拟运行"));
+        assert!(dry_run.update().await.unwrap().contains("2.2.2.2"));
+        assert!(api.updated().is_empty());
+
+        // 记录详情及状态保持不变，不记录 IP 地址变化
+        assert_eq!(dry_run.details().unwrap().content, ip("1.1.1.1").unwrap());
+        let status = dry_run.status().get();
+        assert_eq!(status.content, Some(ip("1.1.1.1").unwrap()));
+        assert_eq!(status.changes, 0);
+        assert_eq!(status.failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_force_update() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("1.1.1.1"))]);
+        let mut forced = updater(
+            &api,
+            [ip("1.1.1.1"), ip("1.1.1.1"), ip("1.1.1.1"), ip("1.1.1.1")],
+        )
+        .with_force_update(Some(Duration::from_secs(3600)));
+        forced.init().await.unwrap();
+
+        // 未到强制更新时间时不访问 Cloudflare
+        assert!(forced.update().await.unwrap().contains("未发生变化"));
+        assert!(api.updated().is_empty());
+
+        // 超过强制更新周期后即使 IP 地址未变化也更新记录，且不记为 IP 地址变化
+        forced.started_at = Local::now() - chrono::Duration::hours(2);
+        assert!(forced.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.updated(), vec![ip("1.1.1.1").unwrap()]);
+        assert!(forced.last_forced_update.is_some());
+        assert_eq!(forced.status().get().changes, 0);
+
+        // 周期从最近一次强制更新开始计算
+        assert!(forced.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.updated().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_private_address() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("100.64.0.2"))]);
+        let mut private = updater(
+            &api,
+            [
+                ip("1.1.1.1"),
+                ip("10.0.0.2"),
+                ip("100.64.0.2"),
+                ip("100.64.0.2"),
+            ],
+        );
+        private.init().await.unwrap();
+        assert!(private.update().await.is_ok());
+
+        // 默认拒绝发布不可公开访问的地址，且不访问 Cloudflare
+        for _ in 0..2 {
+            let err = private.update().await.unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Source);
+            assert!(err.to_string().contains("已拒绝发布"));
+        }
+        assert!(api.updated().is_empty());
+        assert_eq!(private.details().unwrap().content, ip("1.1.1.1").unwrap());
+
+        // 允许发布时照常更新
+        private.allow_private = true;
+        assert!(private.update().await.unwrap().contains("100.64.0.2"));
+        assert_eq!(api.updated(), vec![ip("100.64.0.2").unwrap()]);
+
+        // 初始化时获取的地址被拒绝发布，不进行快速重试且不再重试初始化
+        let api = MockCfApi::new([], []);
+        let mut rejected = updater(&api, [ip("10.0.0.2")]).with_source_retries(2, Duration::ZERO);
+        let err = rejected.init().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Source);
+        assert!(!err.is_retryable());
+        assert!(
+            err.to_string()
+                .starts_with("[home][source] 获取的 IP 地址 10.0.0.2 为私有地址，已拒绝发布"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("2.2.2.2"))]);
+        let mut updater = updater(&api, [ip("1.1.1.1"), ip("2.2.2.2"), ip("2.2.2.2")]);
+        updater.init().await.unwrap();
+
+        // IP 地址未变化时不访问 Cloudflare
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert!(api.updated().is_empty());
+
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(api.updated(), vec!["2.2.2.2".parse::<IpAddr>().unwrap()]);
+
+        // 更新后以新的记录详情比较
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.updated().len(), 1);
+        assert_eq!(
+            updater.status().get().content,
+            Some("2.2.2.2".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_type_mismatch() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1")), Ok(record("1.1.1.1"))], []);
+        let mut updater = updater(&api, [ip("2001:db8::1"), ip("1.1.1.1")]);
+        updater.init().await.unwrap();
+
+        // A 记录获取到 IPv6 地址时不发送更新请求
+        let err = updater.update().await.unwrap_err();
+        assert!(err.to_string().contains("DNS 记录类型 A"), "{}", err);
+        assert!(err.is_retryable());
+        assert!(api.updated().is_empty());
+
+        // 记录类型可能已被修改，下次更新前重新获取记录详情
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert!(api.records.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_details_refresh() {
+        // 记录在 Cloudflare 中被修改为 3.3.3.3，重新获取后改回当前 IP 地址
+        let api = MockCfApi::new(
+            [Ok(record("1.1.1.1")), Ok(record("3.3.3.3"))],
+            [Ok(record("1.1.1.1"))],
+        );
+        let mut refreshed = updater(&api, [ip("1.1.1.1"), ip("1.1.1.1")])
+            .with_details_refresh(Some(Duration::ZERO));
+        refreshed.init().await.unwrap();
+        assert!(refreshed.update().await.unwrap().contains("1.1.1.1"));
+        assert_eq!(api.updated(), vec!["1.1.1.1".parse::<IpAddr>().unwrap()]);
+
+        // 未到重新获取时间时以缓存的记录详情比较
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let mut cached = updater(&api, [ip("1.1.1.1"), ip("1.1.1.1")])
+            .with_details_refresh(Some(Duration::from_secs(3600)));
+        cached.init().await.unwrap();
+        assert!(cached.update().await.unwrap().contains("未发生变化"));
+        assert!(cached.update().await.unwrap().contains("未发生变化"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_ttl() {
+        let ttl_record = |ip: &str, ttl| CloudflareRecordDetails { ttl, ..record(ip) };
+        let api = MockCfApi::new(
+            [Ok(record("1.1.1.1"))],
+            [
+                Ok(ttl_record("1.1.1.1", 3600)),
+                Ok(ttl_record("2.2.2.2", 60)),
+            ],
+        );
+        let mut updater = updater(&api, [ip("1.1.1.1"), ip("1.1.1.1"), ip("2.2.2.2")])
+            .with_auto_ttl(Some(AutoTtl::new(60, 3600, chrono::Duration::hours(1))));
+        updater.started_at = Local::now() - chrono::Duration::hours(2);
+        updater.init().await.unwrap();
+
+        // IP 地址稳定时仅提高 TTL
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(*api.ttls.lock().unwrap(), vec![3600]);
+
+        // TTL 已符合预期时不访问 Cloudflare
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.ttls.lock().unwrap().len(), 1);
+
+        // IP 地址变化时立即降低 TTL
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(*api.ttls.lock().unwrap(), vec![3600, 60]);
+    }
+
+    #[tokio::test]
+    async fn test_auto_ttl_after_restart() {
+        let auto_ttl = Some(AutoTtl::new(60, 3600, chrono::Duration::days(7)));
+        let now = Local::now();
+        let modified = |ttl, days| CloudflareRecordDetails {
+            ttl,
+            modified_on: Some(now - chrono::Duration::days(days)),
+            ..record("1.1.1.1")
+        };
+
+        // 重启后缺少变化历史，记录已使用较高的 TTL 时保持不变，即使最近修改过记录
+        let api = MockCfApi::new([Ok(modified(3600, 0))], []);
+        let mut restarted = updater(&api, [ip("1.1.1.1"), ip("1.1.1.1")]).with_auto_ttl(auto_ttl);
+        restarted.init().await.unwrap();
+        assert!(restarted.update().await.unwrap().contains("未发生变化"));
+        assert!(restarted.update().await.unwrap().contains("未发生变化"));
+        assert!(api.ttls.lock().unwrap().is_empty());
+
+        // 以记录的修改时间判断是否稳定，而非程序启动时间
+        let api = MockCfApi::new([Ok(modified(60, 30))], [Ok(modified(3600, 0))]);
+        let mut restarted = updater(&api, [ip("1.1.1.1")]).with_auto_ttl(auto_ttl);
+        restarted.init().await.unwrap();
+        assert!(restarted.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(*api.ttls.lock().unwrap(), vec![3600]);
+
+        let api = MockCfApi::new([Ok(modified(60, 1))], []);
+        let mut restarted = updater(&api, [ip("1.1.1.1")]).with_auto_ttl(auto_ttl);
+        restarted.init().await.unwrap();
+        assert!(restarted.update().await.unwrap().contains("未发生变化"));
+        assert!(api.ttls.lock().unwrap().is_empty());
+
+        // 从状态文件恢复的变化历史优先于记录的修改时间
+        let api = MockCfApi::new([Ok(modified(60, 0))], [Ok(modified(3600, 0))]);
+        let mut restarted = updater(&api, [ip("1.1.1.1")]).with_auto_ttl(auto_ttl);
+        restarted.status().update(|status| {
+            status.history.push(IpChange {
+                time: now - chrono::Duration::days(30),
+                old_ip: "2.2.2.2".parse().unwrap(),
+                new_ip: "1.1.1.1".parse().unwrap(),
+            })
+        });
+        restarted.init().await.unwrap();
+        assert!(restarted.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(*api.ttls.lock().unwrap(), vec![3600]);
+    }
+
+    #[tokio::test]
+    async fn test_fixed_ttl() {
+        let ttl_record = |ip: &str, ttl| CloudflareRecordDetails { ttl, ..record(ip) };
+        let api = MockCfApi::new(
+            [Ok(ttl_record("1.1.1.1", 300))],
+            [Ok(ttl_record("1.1.1.1", 60)), Ok(ttl_record("2.2.2.2", 60))],
+        );
+        let mut updater =
+            updater(&api, [ip("1.1.1.1"), ip("1.1.1.1"), ip("2.2.2.2")]).with_ttl(Some(60));
+        updater.init().await.unwrap();
+
+        // IP 地址未变化但 TTL 与配置不一致时更新记录
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(*api.ttls.lock().unwrap(), vec![60]);
+        assert_eq!(api.updated().len(), 1);
+
+        // TTL 一致时不访问 Cloudflare
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.ttls.lock().unwrap().len(), 1);
+
+        // IP 地址变化时使用配置的 TTL
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(*api.ttls.lock().unwrap(), vec![60, 60]);
+    }
+
+    #[tokio::test]
+    async fn test_fixed_proxied() {
+        let proxied_record = |proxied| CloudflareRecordDetails {
+            proxied,
+            ..record("1.1.1.1")
+        };
+        let api = MockCfApi::new([Ok(proxied_record(true))], [Ok(proxied_record(false))]);
+        let mut pinned = updater(&api, [ip("1.1.1.1"), ip("1.1.1.1")]).with_proxied(Some(false));
+        pinned.init().await.unwrap();
+
+        // IP 地址未变化但代理状态与配置不一致时更新记录
+        assert!(pinned.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.updated().len(), 1);
+        assert_eq!(*api.proxied.lock().unwrap(), vec![false]);
+
+        // 代理状态一致时不访问 Cloudflare
+        assert!(pinned.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(api.updated().len(), 1);
+
+        // 未指定时保持记录原有的代理状态
+        let api = MockCfApi::new([Ok(proxied_record(true))], [Ok(record("2.2.2.2"))]);
+        let mut updater = updater(&api, [ip("1.1.1.1"), ip("2.2.2.2")]);
+        updater.init().await.unwrap();
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+        assert!(api.updated().is_empty());
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(*api.proxied.lock().unwrap(), vec![true]);
+    }
+
+    #[tokio::test]
+    async fn test_init_reuses_ip() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("2.2.2.2"))]);
+        let mut updater = updater(&api, [ip("2.2.2.2")]);
+        updater.init().await.unwrap();
+
+        // 首次更新直接使用初始化时获取的 IP 地址，不再访问 IP 地址来源
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(api.updated(), vec!["2.2.2.2".parse::<IpAddr>().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn test_create_if_missing() {
+        let missing = || {
+            Err(Error::cloudflare_record_failure(
+                vec![81044],
+                vec!["Record does not exist.".to_string()],
+            ))
+        };
+
+        // 创建后的记录详情直接用于首次更新，不再重复获取
+        let api = MockCfApi::new([missing()], []);
+        let mut created = updater(&api, [ip("1.1.1.1")]).with_record_creation(true);
+        created.init().await.unwrap();
+        assert_eq!(
+            *api.created.lock().unwrap(),
+            vec!["1.1.1.1".parse::<IpAddr>().unwrap()]
+        );
+        assert!(created.update().await.unwrap().contains("未发生变化"));
+        assert!(api.updated().is_empty());
+
+        // 未启用时不创建记录
+        let api = MockCfApi::new([missing()], []);
+        let mut disabled = updater(&api, [ip("1.1.1.1")]);
+        assert!(disabled.init().await.is_err());
+        assert!(api.created.lock().unwrap().is_empty());
+
+        // zone id 有误时同样返回 404，不创建记录
+        let api = MockCfApi::new(
+            [Err(Error::cloudflare_record_failure(
+                vec![7003],
+                vec!["Could not route to /zones/z/dns_records/1".to_string()],
+            )
+            .context("home", Phase::Fetch)
+            .with_http_status(StatusCode::NOT_FOUND))],
+            [],
+        );
+        let mut wrong_zone = updater(&api, [ip("1.1.1.1")]).with_record_creation(true);
+        assert!(wrong_zone.init().await.is_err());
+        assert!(api.created.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recreate_deleted_record() {
+        let missing = || {
+            Err(Error::cloudflare_record_failure(
+                vec![81044],
+                vec!["Record does not exist.".to_string()],
+            ))
+        };
+        let api = MockCfApi::new(
+            [Ok(record("1.1.1.1")), missing()],
+            [missing(), Ok(record("3.3.3.3"))],
+        );
+        let mut recreated = updater(
+            &api,
+            [ip("1.1.1.1"), ip("2.2.2.2"), ip("2.2.2.2"), ip("3.3.3.3")],
+        )
+        .with_record_creation(true);
+        recreated.init().await.unwrap();
+        assert!(recreated.update().await.is_ok());
+
+        // 记录在运行期间被删除，本次更新失败
+        let err = recreated.update().await.unwrap_err();
+        assert!(err.is_record_missing());
+        assert!(api.created.lock().unwrap().is_empty());
+
+        // 下次更新重新获取记录详情时以最新的 IP 地址重新创建记录
+        assert!(recreated.update().await.unwrap().contains("未发生变化"));
+        assert_eq!(
+            *api.created.lock().unwrap(),
+            vec!["2.2.2.2".parse::<IpAddr>().unwrap()]
+        );
+
+        // 此后照常更新重新创建的记录
+        assert!(recreated.update().await.unwrap().contains("3.3.3.3"));
+        assert_eq!(
+            api.updated(),
+            vec![ip("2.2.2.2").unwrap(), ip("3.3.3.3").unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multiple_records() {
+        let first = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("2.2.2.2"))]);
+        let second = MockCfApi::new(
+            [Ok(record("1.1.1.1"))],
+            [
+                Err(Error::cloudflare_record_failure(
+                    vec![1004],
+                    vec!["DNS Validation Error".to_string()],
+                )),
+                Ok(record("2.2.2.2")),
+            ],
+        );
+        let mut multiple = updater(&first, [ip("2.2.2.2"), ip("2.2.2.2")])
+            .with_records(vec![Box::new(second.clone())]);
+        multiple.init().await.unwrap();
+
+        // 单条记录更新失败不影响其余记录，IP 地址仅获取一次
+        assert!(multiple.update().await.is_err());
+        assert_eq!(first.updated(), vec!["2.2.2.2".parse::<IpAddr>().unwrap()]);
+        assert_eq!(second.updated(), vec!["2.2.2.2".parse::<IpAddr>().unwrap()]);
+
+        // 已更新的记录不再重复更新，失败的记录在下次更新时重试
+        assert!(multiple.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(first.updated().len(), 1);
+        assert_eq!(second.updated().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_token() {
+        let invalid = || {
+            Some(Error::cloudflare_token_failure(
+                vec![1000],
+                vec!["Invalid API Token".to_string()],
+            ))
+        };
+
+        // token 无效时直接停止，不获取 IP 地址及记录详情
+        let api = MockCfApi::new([], []);
+        *api.token.lock().unwrap() = invalid();
+        let mut stopped = updater(&api, []).with_invalid_token_stop(true);
+        let err = stopped.init().await.unwrap_err();
+        assert!(err.to_string().contains("Invalid API Token"), "{}", err);
+
+        // 未启用时继续初始化
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        *api.token.lock().unwrap() = invalid();
+        let mut updater = updater(&api, [ip("1.1.1.1")]);
+        updater.init().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_state_file() {
+        let dir = std::env::temp_dir().join(format!("ddns4cf-updater-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.json");
+        let state = || {
+            Some(Arc::new(StateFile::load(
+                path.clone(),
+                Duration::from_secs(3600),
+            )))
+        };
+
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("2.2.2.2"))]);
+        let mut saved = updater(&api, [ip("1.1.1.1"), ip("2.2.2.2")]).with_state(state());
+        saved.init().await.unwrap();
+        assert!(saved.update().await.is_ok());
+        assert!(saved.update().await.unwrap().contains("2.2.2.2"));
+
+        // 重启后直接使用保存的记录详情，不再获取
+        let api = MockCfApi::new([], []);
+        let mut restored = updater(&api, [ip("2.2.2.2")]).with_state(state());
+        restored.init().await.unwrap();
+        let status = restored.status().get();
+        assert_eq!(status.content, Some("2.2.2.2".parse().unwrap()));
+        // 变化历史随状态一同恢复
+        assert_eq!(status.history, saved.status().get().history);
+        assert_eq!(
+            status.history.last().map(|change| change.new_ip),
+            Some("2.2.2.2".parse().unwrap())
+        );
+        assert!(restored.update().await.unwrap().contains("未发生变化"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_backoff() {
+        let api = MockCfApi::new([], []);
+        let err = Error::ip_source("Mock", "unavailable");
+        let backoff = Backoff {
+            base: 5,
+            jitter: false,
+        };
+
+        // 从 base 开始翻倍，最长不超过重试间隔
+        let exponential = updater(&api, []).with_backoff(Some(backoff));
+        assert_eq!(
+            (1..=8)
+                .map(|attempt| exponential.prepare_delay(&err, attempt))
+                .collect::<Vec<_>>(),
+            [5, 10, 20, 40, 80, 160, 300, 300]
+        );
+        assert_eq!(exponential.prepare_delay(&err, u32::MAX), 300);
+        // Cloudflare 要求等待的时间优先
+        assert_eq!(
+            exponential.prepare_delay(&Error::cloudflare_rate_limited(Some(600)), 1),
+            600
+        );
+
+        let jittered = updater(&api, []).with_backoff(Some(Backoff {
+            jitter: true,
+            ..backoff
+        }));
+        assert!((20..=40).contains(&jittered.prepare_delay(&err, 4)));
+
+        // 未启用时固定按重试间隔重试
+        let fixed = updater(&api, []);
+        assert_eq!(fixed.prepare_delay(&err, 1), 300);
+    }
+
+    #[tokio::test]
+    async fn test_validate_source() {
+        // 校验获取的地址直接用于初始化，仅访问一次 IP 地址来源
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("2.2.2.2"))]);
+        let mut validated = updater(&api, [ip("2.2.2.2")]).with_source_validation(true);
+        validated.init().await.unwrap();
+        assert!(validated.update().await.unwrap().contains("2.2.2.2"));
+
+        // 校验失败不中止初始化
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let mut updater = updater(
+            &api,
+            [Err(Error::ip_source("Mock", "bad url")), ip("1.1.1.1")],
+        )
+        .with_source_validation(true);
+        updater.init().await.unwrap();
+        assert!(updater.update().await.unwrap().contains("未发生变化"));
+    }
+
+    #[tokio::test]
+    async fn test_update_failure() {
+        let api = MockCfApi::new(
+            [Ok(record("1.1.1.1"))],
+            [
+                Err(Error::cloudflare_server_failure(
+                    StatusCode::BAD_GATEWAY,
+                    None,
+                )),
+                Ok(record("2.2.2.2")),
+            ],
+        );
+        let mut updater = updater(&api, [ip("2.2.2.2"), ip("2.2.2.2")]);
+        updater.init().await.unwrap();
+
+        let err = updater.update().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CloudflareServer);
+        assert!(err.is_retryable());
+        assert!(err.to_string().starts_with("[home][update]"));
+        assert!((30..=60).contains(&updater.retry_delay(&err)));
+        assert_eq!(updater.status().get().consecutive_failures, 1);
+
+        // 请求过于频繁时按 Cloudflare 要求的时间等待
+        assert_eq!(
+            updater.retry_delay(&Error::cloudflare_rate_limited(Some(900))),
+            900
+        );
+        assert_eq!(
+            updater.retry_delay(&Error::cloudflare_rate_limited(None)),
+            300
+        );
+
+        // 更新失败不替换记录详情，下次更新时重新提交
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+        assert_eq!(api.updated().len(), 2);
+        assert_eq!(updater.status().get().consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_source_failure() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], []);
+        let mut updater = updater(
+            &api,
+            [ip("1.1.1.1"), Err(Error::ip_source("Mock", "无可用地址"))],
+        );
+        updater.init().await.unwrap();
+
+        assert!(updater.update().await.is_ok());
+        let err = updater.update().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Source);
+        assert!(err.to_string().starts_with("[home][source]"));
+        assert!(api.updated().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_source_retries() {
+        let api = MockCfApi::new([Ok(record("1.1.1.1"))], [Ok(record("2.2.2.2"))]);
+        let failure = || Err(Error::ip_source("Mock", "无可用地址"));
+        let mut updater = updater(
+            &api,
+            [
+                failure(),
+                ip("1.1.1.1"),
+                failure(),
+                failure(),
+                ip("2.2.2.2"),
+                failure(),
+                failure(),
+                failure(),
+            ],
+        )
+        .with_source_retries(2, Duration::ZERO);
+
+        // 快速重试成功时不进入重试间隔
+        updater.init().await.unwrap();
+        assert!(updater.update().await.is_ok());
+        assert!(updater.update().await.unwrap().contains("2.2.2.2"));
+
+        // 重试次数用尽后返回最后一次的错误
+        let err = updater.update().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Source);
+        assert_eq!(
+            err.to_string(),
+            "[home][source] 已尝试 3 次获取 IP 地址：无可用地址"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_init_fatal_failure() {
+        let api = MockCfApi::new(
+            [Err(Error::cloudflare_record_failure(
+                vec![9109],
+                vec!["Invalid access token".to_string()],
+            ))],
+            [],
+        );
+        let mut updater = updater(&api, [ip("1.1.1.1")]);
+
+        let err = updater.init().await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CloudflareApi);
+        assert!(!err.is_retryable());
+        assert!(err.to_string().starts_with("[home][fetch]"));
     }
 }
@@ -1,10 +1,25 @@
-use std::sync::{atomic::AtomicPtr, Arc};
+#[cfg(target_os = "windows")]
+use std::sync::atomic::AtomicPtr;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use futures::future::join_all;
+use futures::future::BoxFuture;
 use libs::{
+    args,
+    cloudflare::CfKeepalive,
     config,
+    connectivity::ConnectivityProbe,
     error::Error,
-    scheduler::{LoopingScheduler, NotifyKind, NotifyScheduler},
+    history::format_duration,
+    launchd::{self, LaunchdKind},
+    scheduler::{LoopingScheduler, NotifyKind, NotifyScheduler, SleepSentinel, UpdaterTask},
+    status::StatusRegistry,
+    supervisor::{supervise, RestartPolicy},
     updater::Updater,
 };
 #[allow(unused_imports)]
@@ -12,28 +27,43 @@ use log::{error, info, warn};
 use smallvec::SmallVec;
 use tokio::{
     signal,
-    sync::{
-        broadcast::{self, error::SendError, Sender},
-        Mutex,
-    },
+    sync::broadcast::{self, Sender},
+    task::JoinSet,
+    time::sleep,
 };
+use tokio_util::sync::CancellationToken;
 #[cfg(target_os = "windows")]
 use windows::Win32::{
-    Foundation::{ERROR_SUCCESS, ERROR_UNHANDLED_EXCEPTION, HANDLE},
+    Foundation::{ERROR_SUCCESS, HANDLE},
     System::Power::{
         PowerRegisterSuspendResumeNotification, PowerUnregisterSuspendResumeNotification,
         DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, HPOWERNOTIFY,
     },
-    UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC},
+    UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND},
 };
 
 mod libs;
 
+/// 系统事件监听注册失败后的首次重试间隔，此后每次翻倍
+const OS_LISTENER_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+/// 系统事件监听最多注册次数
+const OS_LISTENER_MAX_ATTEMPTS: u32 = 5;
+/// 运行时线程名称，便于在 `ps -L` 及调试器中识别
+const RUNTIME_THREAD_NAME: &str = "ddns4cf-worker";
+/// 收到终止消息后等待正在进行的更新完成的最长时间
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
 fn main() {
     setup_logger();
-    match start() {
-        Ok(_) => {}
-        Err(err) => error!("{}", err),
+    let matches = args::arguments();
+    let result = match matches.subcommand_name() {
+        Some(args::TEST_NOTIFICATION) => test_notification(&matches),
+        Some(args::LAUNCHD_PLIST) => launchd_plist(&matches),
+        _ => start(&matches),
+    };
+    if let Err(err) = result {
+        error!("{}", err);
+        std::process::exit(1);
     }
 }
 
@@ -59,8 +89,18 @@ fn setup_logger() {
         .unwrap();
 }
 
+/// Windows 电源通知回调上下文
+///
+/// 注册期间系统会持有该结构的地址，因此必须分配在堆上，直至注销后才能释放。
+#[cfg(target_os = "windows")]
+struct PowerNotifyContext {
+    tx: Sender<NotifyKind>,
+    parameters: DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+}
+
 struct OsSuspendResumeUnregister {
-    tx_ptr: AtomicPtr<Sender<NotifyKind>>,
+    #[cfg(target_os = "windows")]
+    context: AtomicPtr<PowerNotifyContext>,
     #[cfg(target_os = "windows")]
     handle: HPOWERNOTIFY,
 }
@@ -71,64 +111,79 @@ impl OsSuspendResumeUnregister {
         unsafe {
             let error = PowerUnregisterSuspendResumeNotification(self.handle);
             if error.is_err() {
+                // 注销失败时系统仍可能调用回调，此时不能释放上下文
                 warn!(
                     "注销 Windows Suspend Resume 通知事件失败。错误代码: {}",
                     error.to_hresult()
                 );
             } else {
                 info!("注销 Windows Suspend Resume 通知事件成功");
+                drop(Box::from_raw(self.context.into_inner()));
             }
         }
+    }
+}
 
-        unsafe {
-            drop(Box::from_raw(self.tx_ptr.into_inner()));
-        }
+/// Windows 电源通知回调
+///
+/// 由系统线程调用，panic 不得跨越 FFI 边界，因此所有逻辑均在 `catch_unwind` 中执行，且始终返回成功。
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn power_notify_callback(
+    context: *const core::ffi::c_void,
+    r#type: u32,
+    _: *const core::ffi::c_void,
+) -> u32 {
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let kind = match r#type {
+            PBT_APMSUSPEND => NotifyKind::OsSuspending,
+            PBT_APMRESUMEAUTOMATIC => NotifyKind::OsSuspendResume,
+            _ => return,
+        };
+        let context = &*(context as *const PowerNotifyContext);
+        // 所有接收端均已释放说明程序正在停止，忽略发送失败
+        let _ = context.tx.send(kind);
+    }));
+    if result.is_err() {
+        // 输出日志本身也可能 panic，同样需要捕获
+        let _ =
+            std::panic::catch_unwind(|| warn!("处理 Windows Suspend Resume 通知事件时出现异常"));
     }
+
+    ERROR_SUCCESS.0
 }
 
-/// 注册系统挂起后恢复事件。当前仅支持 Windows 系统，其他系统不会接收到消息。
-fn listen_os_suspend_resume() -> Option<(Sender<NotifyKind>, OsSuspendResumeUnregister)> {
+/// 注册系统挂起后恢复事件，事件将发送至 `tx`。当前仅支持 Windows 系统，其他系统不会接收到消息。
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+fn listen_os_suspend_resume(tx: &Sender<NotifyKind>) -> Option<OsSuspendResumeUnregister> {
     #[cfg(target_os = "windows")]
     unsafe {
-        let (tx, _) = broadcast::channel(1);
-        let tx_ptr = AtomicPtr::new(Box::leak(Box::new(tx.clone())));
-
-        unsafe extern "system" fn callback(
-            context: *const core::ffi::c_void,
-            r#type: u32,
-            _: *const core::ffi::c_void,
-        ) -> u32 {
-            let tx = &*(context as *mut Sender<NotifyKind>);
-            match r#type {
-                PBT_APMRESUMEAUTOMATIC => match tx.send(NotifyKind::OsSuspendResume) {
-                    Ok(_) => ERROR_SUCCESS.0,
-                    Err(_) => ERROR_UNHANDLED_EXCEPTION.0,
-                },
-                _ => ERROR_SUCCESS.0,
-            }
-        }
-        let mut recipient = DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
-            Callback: Some(callback),
-            Context: *tx_ptr.as_ptr() as *mut _ as *mut core::ffi::c_void,
-        };
+        let context = Box::into_raw(Box::new(PowerNotifyContext {
+            tx: tx.clone(),
+            parameters: DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+                Callback: Some(power_notify_callback),
+                Context: std::ptr::null_mut(),
+            },
+        }));
+        (*context).parameters.Context = context as *mut core::ffi::c_void;
 
         let mut handle = std::ptr::null_mut();
         let error = PowerRegisterSuspendResumeNotification(
             DEVICE_NOTIFY_CALLBACK,
-            HANDLE(&mut recipient as *mut _ as *mut core::ffi::c_void),
+            HANDLE(std::ptr::addr_of_mut!((*context).parameters) as *mut core::ffi::c_void),
             &mut handle,
         );
         match error {
             ERROR_SUCCESS => {
                 info!("注册 Windows Suspend Resume 通知事件成功");
                 let unregister = OsSuspendResumeUnregister {
-                    tx_ptr,
+                    context: AtomicPtr::new(context),
                     handle: HPOWERNOTIFY(handle as isize),
                 };
 
-                return Some((tx, unregister));
+                return Some(unregister);
             }
             _ => {
+                drop(Box::from_raw(context));
                 warn!(
                     "注册 Windows Suspend Resume 通知事件失败。错误代码: {}",
                     error.to_hresult()
@@ -142,109 +197,422 @@ fn listen_os_suspend_resume() -> Option<(Sender<NotifyKind>, OsSuspendResumeUnre
     None
 }
 
-fn send_terminate(termination_tx: Sender<()>) -> Result<(), SendError<()>> {
-    termination_tx.send(())?;
-    info!("正在停止所有 Schedulers...");
-    Ok(())
+/// 注册系统挂起恢复事件监听，并在收到终止消息后注销
+///
+/// 部分会话环境下启动时注册可能失败，此时按指数退避重试，多次失败后放弃。
+async fn run_os_suspend_resume_listener(
+    notify_tx: Sender<NotifyKind>,
+    listener_active: Arc<AtomicBool>,
+    termination: CancellationToken,
+) {
+    let mut attempts = 1;
+    let unregister = loop {
+        if let Some(unregister) = listen_os_suspend_resume(&notify_tx) {
+            break unregister;
+        }
+        if attempts >= OS_LISTENER_MAX_ATTEMPTS {
+            warn!(
+                "注册系统挂起恢复事件监听失败 {} 次，已放弃，系统唤醒后将不会立即更新域名",
+                attempts
+            );
+            return;
+        }
+
+        let delay = OS_LISTENER_RETRY_INTERVAL * (1 << (attempts - 1));
+        info!("将在 {} 秒后重新注册系统挂起恢复事件监听", delay.as_secs());
+        attempts += 1;
+        tokio::select! {
+            _ = termination.cancelled() => return,
+            _ = sleep(delay) => {}
+        }
+    };
+
+    listener_active.store(true, Ordering::Relaxed);
+    termination.cancelled().await;
+    listener_active.store(false, Ordering::Relaxed);
+    unregister.unregister();
 }
 
-fn listen_ctrl_c(termination_tx: Sender<()>) {
+fn send_terminate(termination: &CancellationToken) {
+    if !termination.is_cancelled() {
+        termination.cancel();
+        info!("正在停止所有 Schedulers...");
+    }
+}
+
+/// 监听 Ctrl+C，Unix 系统中由 [`listen_signal`] 通过 SIGINT 处理
+#[cfg_attr(unix, allow(unused_variables))]
+fn listen_ctrl_c(termination: CancellationToken) {
+    #[cfg(not(unix))]
     tokio::spawn(async move {
         signal::ctrl_c().await.unwrap();
-        send_terminate(termination_tx).unwrap();
+        send_terminate(&termination);
     });
 }
 
-fn listen_signal(termination_tx: Sender<()>) {
-    #[cfg(target_os = "linux")]
-    tokio::spawn(async move {
-        let mut stream =
-            signal::unix::signal(tokio::signal::unix::SignalKind::terminate()).unwrap();
-        stream.recv().await;
-        send_terminate(termination_tx).unwrap();
-    });
+/// 触发正常停止的 Unix 信号，systemd、rc.d 及 launchd 停止服务时均发送 SIGTERM
+#[cfg(unix)]
+const TERMINATION_SIGNALS: [(signal::unix::SignalKind, &str); 3] = [
+    (signal::unix::SignalKind::terminate(), "SIGTERM"),
+    (signal::unix::SignalKind::interrupt(), "SIGINT"),
+    (signal::unix::SignalKind::quit(), "SIGQUIT"),
+];
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn listen_signal(termination: CancellationToken) {
+    #[cfg(unix)]
+    for (kind, name) in TERMINATION_SIGNALS {
+        let mut stream = match signal::unix::signal(kind) {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("注册 {} 信号监听失败：{}", name, err);
+                continue;
+            }
+        };
+        let termination = termination.clone();
+        tokio::spawn(async move {
+            stream.recv().await;
+            info!("收到 {} 信号", name);
+            send_terminate(&termination);
+        });
+    }
 }
 
-async fn init_updaters(updaters: &[Arc<Mutex<Updater>>]) {
-    join_all(updaters.iter().map(|updater| async move {
-        updater.lock().await.init().await;
-    }))
-    .await;
+/// 收到 SIGUSR1 信号时输出所有域名的当前状态
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn listen_status_dump(registry: StatusRegistry) {
+    #[cfg(unix)]
+    {
+        let mut stream = match signal::unix::signal(signal::unix::SignalKind::user_defined1()) {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("注册 SIGUSR1 信号监听失败：{}", err);
+                return;
+            }
+        };
+        tokio::spawn(async move {
+            while stream.recv().await.is_some() {
+                info!("当前域名状态：");
+                registry
+                    .report()
+                    .iter()
+                    .for_each(|report| info!("{}", report));
+            }
+        });
+    }
 }
 
+/// 启动所有调度器，直至全部退出
+///
+/// 必要任务（循环更新调度器）异常退出或在收到终止消息前退出时，发出终止消息并返回错误，
+/// 以便进程以非零状态码退出，由外部进程管理器重新启动；非必要任务 panic 后按照重启策略重新启动，
+/// 超出重启次数或退出时仅输出日志，不影响程序运行。
 async fn start_schedulers(
-    updaters: SmallVec<[Arc<Mutex<Updater>>; 4]>,
-    termination_tx: Sender<()>,
-) {
-    let mut handlers = Vec::new();
+    updaters: SmallVec<[Updater; 4]>,
+    init_concurrency: usize,
+    trigger_quiescence: Duration,
+    cf_keepalive: Option<CfKeepalive>,
+    connectivity: Option<Arc<ConnectivityProbe>>,
+    termination: CancellationToken,
+) -> Result<(), Error> {
+    let mut tasks = JoinSet::new();
+    let mut spawn = |name: &'static str, essential: bool, task: BoxFuture<'static, ()>| {
+        // 外层任务不会 panic，保证每个任务结束时均能获取名称
+        tasks.spawn(async move { (name, essential, tokio::spawn(task).await) });
+    };
+
+    // 每个更新器由独立的任务持有，事件调度器通过句柄触发更新
+    let (updater_tasks, handles): (Vec<_>, SmallVec<[_; 4]>) = updaters
+        .into_iter()
+        .map(|updater| {
+            // 连通性检查在进程所在的网络命名空间中进行，不适用于其他网络命名空间中的域名
+            let connectivity = connectivity.clone().filter(|_| updater.netns().is_none());
+            UpdaterTask::new(updater, trigger_quiescence, connectivity)
+        })
+        .unzip();
 
     // 启动循环更新器
     {
-        let scheduler = LoopingScheduler::new(updaters.clone(), &termination_tx);
-        handlers.push(tokio::spawn(async move {
-            scheduler.start().await;
-        }));
+        let scheduler = LoopingScheduler::new(updater_tasks, init_concurrency, &termination);
+        spawn("LoopingScheduler", true, Box::pin(scheduler.start()));
+    }
+
+    // 启动事件消息调度器，通知渠道独立于系统事件监听存在，以便监听延迟注册成功后仍能接收事件
+    let (notify_tx, _) = broadcast::channel(1);
+    {
+        let notify_tx = notify_tx.clone();
+        let termination = termination.clone();
+        spawn(
+            "NotifyScheduler",
+            false,
+            Box::pin(async move {
+                supervise(
+                    "NotifyScheduler",
+                    RestartPolicy::default(),
+                    &termination,
+                    || {
+                        let scheduler = NotifyScheduler::new(
+                            handles.clone(),
+                            notify_tx.subscribe(),
+                            &termination,
+                        );
+                        scheduler.start()
+                    },
+                )
+                .await;
+            }),
+        );
     }
 
     // 启动系统挂起恢复事件监听
-    if let Some((notify_tx, unregister)) = listen_os_suspend_resume() {
-        let scheduler =
-            NotifyScheduler::new(updaters.clone(), notify_tx.subscribe(), &termination_tx);
-        let handler = tokio::spawn(async move {
-            scheduler.start().await;
-            unregister.unregister();
-        });
-        handlers.push(handler);
+    let listener_active = Arc::new(AtomicBool::new(false));
+    if cfg!(target_os = "windows") {
+        let notify_tx = notify_tx.clone();
+        let listener_active = listener_active.clone();
+        let termination = termination.clone();
+        spawn(
+            "OsSuspendResumeListener",
+            false,
+            Box::pin(async move {
+                supervise(
+                    "OsSuspendResumeListener",
+                    RestartPolicy::default(),
+                    &termination,
+                    || {
+                        run_os_suspend_resume_listener(
+                            notify_tx.clone(),
+                            listener_active.clone(),
+                            termination.clone(),
+                        )
+                    },
+                )
+                .await;
+            }),
+        );
+    }
+
+    // 启动系统休眠检测，系统挂起恢复事件监听生效后自动跳过检测
+    {
+        let termination = termination.clone();
+        spawn(
+            "SleepSentinel",
+            false,
+            Box::pin(async move {
+                supervise(
+                    "SleepSentinel",
+                    RestartPolicy::default(),
+                    &termination,
+                    || {
+                        SleepSentinel::run(
+                            notify_tx.clone(),
+                            listener_active.clone(),
+                            termination.clone(),
+                        )
+                    },
+                )
+                .await;
+            }),
+        );
+    }
+
+    // 启动 Cloudflare API 连接保活
+    if let Some(cf_keepalive) = cf_keepalive {
+        let termination = termination.clone();
+        spawn(
+            "CfKeepalive",
+            false,
+            Box::pin(async move {
+                supervise(
+                    "CfKeepalive",
+                    RestartPolicy::default(),
+                    &termination,
+                    || cf_keepalive.clone().run(termination.clone()),
+                )
+                .await;
+            }),
+        );
+    }
+
+    let mut terminating = false;
+    let mut result = Ok(());
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((name, essential, joined)) = joined else {
+            continue;
+        };
+        terminating |= termination.is_cancelled();
+        let reason = match joined {
+            Ok(()) if terminating => continue,
+            Ok(()) => "任务提前退出".to_string(),
+            Err(err) => err.to_string(),
+        };
+
+        if essential {
+            error!("必要任务 {} 异常退出：{}，正在停止程序", name, reason);
+            if !terminating {
+                terminating = true;
+                send_terminate(&termination);
+            }
+            result = Err(Error::task_failure(name));
+        } else {
+            warn!("任务 {} 异常退出：{}", name, reason);
+        }
+    }
+
+    result
+}
+
+/// 输出本次运行摘要，`clean` 为是否在宽限时间内正常停止
+fn log_shutdown_summary(registry: &StatusRegistry, started_at: Instant, clean: bool) {
+    info!(
+        "运行摘要：运行时长 {}，{}",
+        format_duration(chrono::Duration::from_std(started_at.elapsed()).unwrap_or_default()),
+        if clean {
+            "已正常停止"
+        } else {
+            "等待更新完成超时，已强制停止"
+        }
+    );
+    registry
+        .snapshot()
+        .iter()
+        .for_each(|status| info!("{}", status.summary()));
+}
+
+/// 向所有已配置的通知渠道发送测试消息
+fn test_notification(matches: &clap::ArgMatches) -> Result<(), Error> {
+    let Some(dispatcher) = config::configuration(matches)?.create_notification_dispatcher()? else {
+        return Err(Error::config("未配置任何通知渠道"));
+    };
+
+    let failures = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(dispatcher.send_test());
+    if failures == 0 {
+        info!("测试消息发送完毕");
+        Ok(())
+    } else {
+        Err(Error::notification_test_failure(failures))
     }
+}
+
+/// 输出 launchd 任务配置，程序及配置文件均使用绝对路径
+fn launchd_plist(matches: &clap::ArgMatches) -> Result<(), Error> {
+    let subcommand = matches.subcommand_matches(args::LAUNCHD_PLIST).unwrap();
+    let kind = if subcommand.is_present("agent") {
+        LaunchdKind::Agent
+    } else {
+        LaunchdKind::Daemon
+    };
+
+    let program = std::env::current_exe()
+        .and_then(std::fs::canonicalize)
+        .or(Err(Error::config("无法获取当前程序路径")))?;
+    let config = config::configuration_path(matches)?;
+    let config = std::fs::canonicalize(&config)
+        .map_err(|err| Error::read_configuration_failure(err, &config))?;
+    let home = std::env::var_os("HOME").unwrap_or_default();
 
-    join_all(handlers).await;
+    print!(
+        "{}",
+        launchd::plist(
+            subcommand
+                .value_of("label")
+                .unwrap_or(launchd::DEFAULT_LABEL),
+            &program,
+            &config,
+            &kind.log_path(home.as_ref()),
+        )
+    );
+    Ok(())
 }
 
-fn start() -> Result<(), Error> {
+fn start(matches: &clap::ArgMatches) -> Result<(), Error> {
     info!("启动 ddns4cf，版本: {}", env!("CARGO_PKG_VERSION"));
     info!("程序运行 pid：{}", std::process::id());
 
-    let updaters = config::configuration()?.create_updaters()?;
+    let configuration = config::configuration(matches)?;
+    if configuration.dry_run() {
+        warn!("[模拟运行] 已启用模拟运行模式，仅输出将要进行的更新，不会修改任何 DNS 记录");
+    }
+    let updaters = configuration.create_updaters()?;
+    let network_gate = configuration.create_network_gate();
+    let init_concurrency = configuration.init_concurrency();
+    let trigger_quiescence = configuration.trigger_quiescence();
+    let cf_keepalive = configuration.create_cf_keepalive()?;
+    let connectivity = configuration.create_connectivity_probe()?;
+    let runtime_threads = configuration.runtime_threads(updaters.len());
 
-    if updaters.len() == 0 {
+    if updaters.is_empty() {
         info!("未设置需要更新的域名信息，ddns4cf 已中止");
+        Ok(())
     } else {
-        let updater_len = updaters.len();
+        let started_at = Instant::now();
+        let handles = updaters
+            .iter()
+            .map(|updater| updater.status().clone())
+            .collect::<Vec<_>>();
+        let registry = StatusRegistry::new(&handles);
 
         let main = async move {
-            let (termination_tx, mut termination_rx) = broadcast::channel::<()>(1);
-            listen_ctrl_c(termination_tx.clone());
-            listen_signal(termination_tx.clone());
-
-            // 初始化
-            tokio::select! {
-                _ = init_updaters(&updaters) => {}
-                _ = termination_rx.recv() => return,
+            let termination = CancellationToken::new();
+            listen_ctrl_c(termination.clone());
+            listen_signal(termination.clone());
+            listen_status_dump(registry.clone());
+
+            // 等待网络就绪
+            if let Some(network_gate) = network_gate {
+                tokio::select! {
+                    _ = network_gate.wait() => {}
+                    _ = termination.cancelled() => {
+                        log_shutdown_summary(&registry, started_at, true);
+                        return Ok(());
+                    }
+                }
             }
 
-            // 启动调度器
-            start_schedulers(updaters, termination_tx).await;
+            // 启动调度器，各域名在各自的任务中初始化，收到终止消息后最多等待宽限时间
+            let schedulers = start_schedulers(
+                updaters,
+                init_concurrency,
+                trigger_quiescence,
+                cf_keepalive,
+                connectivity,
+                termination.clone(),
+            );
+            tokio::pin!(schedulers);
+            let (clean, result) = tokio::select! {
+                result = &mut schedulers => (true, result),
+                _ = async {
+                    termination.cancelled().await;
+                    sleep(SHUTDOWN_GRACE_PERIOD).await;
+                } => (false, Ok(())),
+            };
+            log_shutdown_summary(&registry, started_at, clean);
+            result
         };
 
-        if updater_len == 1 {
+        if runtime_threads == 1 {
             info!("正在使用单线程模式运行");
 
-            // 如果只有一个 Updater，使用单线程运行时
+            // 只有一个域名或配置为单个线程时，使用单线程运行时
             tokio::runtime::Builder::new_current_thread()
                 .enable_all()
+                .thread_name(RUNTIME_THREAD_NAME)
                 .build()
                 .unwrap()
-                .block_on(main);
+                .block_on(main)
         } else {
-            info!("正在使用多线程模式运行");
+            info!("正在使用多线程模式运行，工作线程数量：{}", runtime_threads);
 
             tokio::runtime::Builder::new_multi_thread()
                 .enable_all()
+                .worker_threads(runtime_threads)
+                .thread_name(RUNTIME_THREAD_NAME)
                 .build()
                 .unwrap()
-                .block_on(main);
+                .block_on(main)
         }
     }
-
-    Ok(())
 }
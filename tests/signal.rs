@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    process::{Command, Stdio},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// 收到终止信号后允许的最长退出时间，与程序的停止等待时间一致
+const GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// IP 地址来源不可用，程序持续重试初始化，直至收到终止信号
+const CONFIGURATION: &str = r#"{
+    ip_source: { type: 1, server: "http://127.0.0.1:9/ip" },
+    retry_interval: 10,
+    wait_for_network: false,
+    accounts: [{ token: "token", domains: [{ nickname: "home", id: "1", zone_id: "z" }] }],
+}"#;
+
+fn assert_clean_exit(signal: &str) {
+    let path = env::temp_dir().join(format!(
+        "ddns4cf-signal-{}-{}.json5",
+        signal,
+        std::process::id()
+    ));
+    fs::write(&path, CONFIGURATION).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ddns4cf"))
+        .arg("-c")
+        .arg(&path)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    // 等待信号监听注册完成
+    sleep(Duration::from_secs(1));
+
+    let status = Command::new("kill")
+        .arg(format!("-{}", signal))
+        .arg(child.id().to_string())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() > GRACE_PERIOD {
+            child.kill().unwrap();
+            panic!("收到 {} 信号后未在 {:?} 内退出", signal, GRACE_PERIOD);
+        }
+        sleep(Duration::from_millis(50));
+    };
+    fs::remove_file(&path).unwrap();
+
+    assert!(status.success(), "{}: {}", signal, status);
+}
+
+#[test]
+fn test_sigterm() {
+    assert_clean_exit("TERM");
+}
+
+#[test]
+fn test_sigint_sigquit() {
+    assert_clean_exit("INT");
+    assert_clean_exit("QUIT");
+}